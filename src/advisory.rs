@@ -0,0 +1,388 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use cargo_metadata::Metadata;
+use mcp_attr::Result;
+use rustsec::database::Query;
+use rustsec::{Collection, Database};
+use serde::Serialize;
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::findings::{self, Finding, FindingSet, Level};
+use crate::graph::Graph;
+use crate::{git, index};
+
+const ADVISORY_DB_URL: &str = "https://github.com/rustsec/advisory-db";
+
+#[derive(Serialize, Clone)]
+pub struct AdvisoryFinding {
+    pub id: String,
+    /// `vulnerability` または informational advisory の種類 (`unmaintained`, `unsound`, `notice` など)
+    pub kind: String,
+    pub package: String,
+    pub version: String,
+    pub package_id: String,
+    pub title: String,
+    pub description: String,
+    pub date: String,
+    pub severity: Option<String>,
+    pub cvss: Option<String>,
+    pub cvss_score: Option<f64>,
+    pub aliases: Vec<String>,
+    pub url: Option<String>,
+    pub patched_versions: Vec<String>,
+    pub unaffected_versions: Vec<String>,
+    pub affected_os: Vec<String>,
+    pub affected_arch: Vec<String>,
+    /// ワークスペースメンバーからこのパッケージに至る依存パス
+    pub dependency_path: Vec<String>,
+    /// dev-dependencies 経由でのみ依存しているかどうか
+    pub dev_only: bool,
+}
+
+/// cargo-audit と共有するアドバイザリデータベースの場所
+pub fn database_path() -> PathBuf {
+    index::cargo_home().join("advisory-db")
+}
+
+/// 前回の取得からこの期間が過ぎたデータベースは、読み込む前に取得し直す
+const MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// 読み込んだアドバイザリデータベース
+pub struct AdvisoryDatabase {
+    pub db: Database,
+    pub info: DatabaseInfo,
+}
+
+/// 検査に使用したアドバイザリデータベースの情報
+#[derive(Serialize, Clone)]
+pub struct DatabaseInfo {
+    pub path: String,
+    /// データベースの最新のコミットの日時 (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<String>,
+    /// 取得し直せず、以前に取得したデータベースを使用した場合のエラー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_error: Option<String>,
+}
+
+/// アドバイザリデータベースを読み込む
+///
+/// データベースが存在しない場合は GitHub から取得し、前回の取得から [`MAX_AGE`] が過ぎている場合は取得し直す。
+/// 取得し直せなかった場合は、以前に取得したデータベースを使用する。
+/// git を実行してブロッキングするため、ツールからは `spawn_blocking` の中で呼び出す。
+pub fn load_database() -> Result<AdvisoryDatabase> {
+    let path = database_path();
+    let mut update_error = None;
+    if !path.exists() {
+        if let Err(e) = fetch(ADVISORY_DB_URL, &path) {
+            bail_kind!(
+                ErrorKind::AdvisoryFetchFailed,
+                ADVISORY_DB_URL,
+                e.to_error_object(false).message
+            );
+        }
+    } else if is_stale(&path)
+        && let Err(e) = fetch(ADVISORY_DB_URL, &path)
+    {
+        update_error = Some(
+            ErrorKind::AdvisoryFetchFailed
+                .error(&[
+                    ADVISORY_DB_URL.to_string(),
+                    e.to_error_object(false).message,
+                ])
+                .to_error_object(false)
+                .message,
+        );
+    }
+    let db = match Database::open(&path) {
+        Ok(db) => db,
+        Err(e) => bail_kind!(ErrorKind::AdvisoryLoadFailed, path.display(), e),
+    };
+    Ok(AdvisoryDatabase {
+        db,
+        info: DatabaseInfo {
+            path: path.display().to_string(),
+            last_updated: last_updated(&path),
+            update_error,
+        },
+    })
+}
+
+/// データベースを `url` から取得する (既に存在する場合は最新のコミットに更新する)
+fn fetch(url: &str, path: &Path) -> Result<()> {
+    if !path.exists() {
+        let parent = path.parent().unwrap_or(path);
+        git::git(
+            parent,
+            &["clone", "--depth", "1", url, &path.display().to_string()],
+        )?;
+        return Ok(());
+    }
+    git::git(path, &["fetch", "--depth", "1", url, "HEAD"])?;
+    git::git(path, &["reset", "--hard", "FETCH_HEAD"])?;
+    Ok(())
+}
+
+/// 前回の取得 (`git fetch` がなければ `git clone`) から [`MAX_AGE`] が過ぎているか
+fn is_stale(path: &Path) -> bool {
+    let git_dir = path.join(".git");
+    let fetched = ["FETCH_HEAD", "HEAD"].iter().find_map(|file| {
+        std::fs::metadata(git_dir.join(file))
+            .and_then(|m| m.modified())
+            .ok()
+    });
+    fetched.is_none_or(|time| time.elapsed().unwrap_or_default() > MAX_AGE)
+}
+
+/// データベースの最新のコミットの日時
+fn last_updated(path: &Path) -> Option<String> {
+    let date = git::git(path, &["log", "-1", "--format=%cI"]).ok()?;
+    Some(date.trim().to_string()).filter(|date| !date.is_empty())
+}
+
+/// 依存グラフ内の crates.io パッケージに該当するアドバイザリを検索する
+pub fn find_advisories(metadata: &Metadata, db: &Database) -> Vec<AdvisoryFinding> {
+    let graph = Graph::new(metadata);
+    let mut findings = Vec::new();
+    for package in &metadata.packages {
+        if !package.source.as_ref().is_some_and(|s| s.is_crates_io()) {
+            continue;
+        }
+        let Ok(name) = package.name.parse() else {
+            continue;
+        };
+        // informational advisory も含めるため crate_scope() は使用しない
+        let query = Query::new()
+            .collection(Collection::Crates)
+            .withdrawn(false)
+            .package_name(name)
+            .package_version(package.version.clone());
+        for advisory in db.query(&query) {
+            let meta = &advisory.metadata;
+            let affected = advisory.affected.as_ref();
+            let path = graph.path_labels(&package.id, false);
+            let dev_only = path.is_empty();
+            let dependency_path = if dev_only {
+                graph.path_labels(&package.id, true)
+            } else {
+                path
+            };
+            findings.push(AdvisoryFinding {
+                id: meta.id.to_string(),
+                kind: meta
+                    .informational
+                    .as_ref()
+                    .map_or("vulnerability", |i| i.as_str())
+                    .to_string(),
+                package: package.name.clone(),
+                version: package.version.to_string(),
+                package_id: package.id.repr.clone(),
+                title: meta.title.clone(),
+                description: meta.description.clone(),
+                date: meta.date.to_string(),
+                severity: advisory.severity().map(|s| s.as_str().to_string()),
+                cvss: meta.cvss.as_ref().map(|c| c.to_string()),
+                cvss_score: meta.cvss.as_ref().map(|c| c.score()),
+                aliases: meta.aliases.iter().map(|a| a.to_string()).collect(),
+                url: meta
+                    .url
+                    .as_ref()
+                    .map(|u| u.to_string())
+                    .or_else(|| meta.id.url()),
+                patched_versions: advisory
+                    .versions
+                    .patched()
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+                unaffected_versions: advisory
+                    .versions
+                    .unaffected()
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+                affected_os: affected
+                    .map(|a| a.os.iter().map(|os| os.as_str().to_string()).collect())
+                    .unwrap_or_default(),
+                affected_arch: affected
+                    .map(|a| {
+                        a.arch
+                            .iter()
+                            .map(|arch| arch.as_str().to_string())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                dependency_path,
+                dev_only,
+            });
+        }
+    }
+    findings.sort_by(|a, b| (&a.id, &a.package_id).cmp(&(&b.id, &b.package_id)));
+    findings
+}
+
+impl AdvisoryFinding {
+    /// サーバーが動作しているプラットフォームがこのアドバイザリの対象外かどうか
+    pub fn excludes_host_platform(&self) -> bool {
+        (!self.affected_os.is_empty()
+            && !self.affected_os.iter().any(|os| os == std::env::consts::OS))
+            || (!self.affected_arch.is_empty()
+                && !self
+                    .affected_arch
+                    .iter()
+                    .any(|arch| arch == std::env::consts::ARCH))
+    }
+}
+
+/// audit の結果
+#[derive(Serialize)]
+pub struct AuditReport {
+    pub database: DatabaseInfo,
+    pub advisories: Vec<AdvisoryFinding>,
+}
+
+/// アドバイザリの検出結果をチェック結果 (SARIF などへの変換用) に変換する
+///
+/// 脆弱性はエラー、unsound はワーニング、その他の informational advisory はノートとして扱う。
+/// dev-dependencies 経由でのみ依存している場合はレベルを 1 段階下げる。
+pub fn advisory_finding_set(metadata: &Metadata, advisories: &[AdvisoryFinding]) -> FindingSet {
+    let mut set = FindingSet::new("audit");
+    for advisory in advisories {
+        set.rule(&advisory.id, &advisory.title, advisory.url.as_deref());
+        let mut level = match advisory.kind.as_str() {
+            "vulnerability" => Level::Error,
+            "unsound" => Level::Warning,
+            _ => Level::Note,
+        };
+        if advisory.dev_only {
+            level = match level {
+                Level::Error => Level::Warning,
+                _ => Level::Note,
+            };
+        }
+        let package = metadata
+            .packages
+            .iter()
+            .find(|p| p.id.repr == advisory.package_id);
+        let (file, line) = match package {
+            Some(package) => {
+                let (file, line) = findings::lockfile_location(metadata, package);
+                (Some(file), line)
+            }
+            None => (None, None),
+        };
+        let mut message = format!(
+            "{}@{}: {} ({})",
+            advisory.package, advisory.version, advisory.title, advisory.id
+        );
+        if !advisory.patched_versions.is_empty() {
+            message.push_str(&format!(
+                ". Patched versions: {}",
+                advisory.patched_versions.join(", ")
+            ));
+        }
+        set.push(Finding {
+            rule: advisory.id.clone(),
+            level,
+            message,
+            package: Some(format!("{}@{}", advisory.package, advisory.version)),
+            file,
+            line,
+            dependency_path: advisory.dependency_path.clone(),
+        });
+    }
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_metadata::MetadataCommand;
+
+    #[test]
+    fn test_find_advisories() {
+        let root = std::env::temp_dir().join(format!("advisory-db-test-{}", std::process::id()));
+        let dir = root.join("crates").join("serde");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("RUSTSEC-2099-0001.md"),
+            r#"```toml
+[advisory]
+id = "RUSTSEC-2099-0001"
+package = "serde"
+date = "2099-01-01"
+url = "https://example.com/advisory"
+
+[versions]
+patched = [">= 999.0.0"]
+```
+
+# Test advisory
+
+This advisory only exists for testing.
+"#,
+        )
+        .unwrap();
+        let db = Database::open(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let findings = find_advisories(&metadata, &db);
+        let finding = findings.iter().find(|f| f.package == "serde").unwrap();
+        assert_eq!(finding.id, "RUSTSEC-2099-0001");
+        assert_eq!(finding.kind, "vulnerability");
+        assert_eq!(finding.title, "Test advisory");
+        assert!(!finding.dev_only);
+        assert!(
+            finding
+                .dependency_path
+                .last()
+                .unwrap()
+                .starts_with("serde@")
+        );
+    }
+
+    #[test]
+    fn test_fetch() {
+        let root = std::env::temp_dir().join(format!("advisory-fetch-test-{}", std::process::id()));
+        let (origin, clone) = (root.join("origin"), root.join("clone"));
+        std::fs::create_dir_all(&origin).unwrap();
+        let commit = |message: &str| {
+            std::fs::write(origin.join("README.md"), message).unwrap();
+            git::git(&origin, &["add", "."])?;
+            git::git(
+                &origin,
+                &[
+                    "-c",
+                    "user.name=test",
+                    "-c",
+                    "user.email=test@example.com",
+                    "commit",
+                    "-q",
+                    "-m",
+                    message,
+                ],
+            )
+        };
+        let result = (|| {
+            git::git(&origin, &["init", "-q"])?;
+            commit("first")?;
+            let url = origin.display().to_string();
+            fetch(&url, &clone)?;
+            let stale = is_stale(&clone);
+            commit("second")?;
+            fetch(&url, &clone)?;
+            let content = std::fs::read_to_string(clone.join("README.md")).unwrap();
+            Ok::<_, mcp_attr::Error>((stale, content, last_updated(&clone)))
+        })();
+        std::fs::remove_dir_all(&root).unwrap();
+        let (stale, content, last_updated) = result.unwrap();
+        assert!(!stale);
+        assert_eq!(content, "second");
+        assert!(last_updated.is_some_and(|date| date.contains('T')));
+    }
+}
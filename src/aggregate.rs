@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use cargo_metadata::Metadata;
+use serde::Serialize;
+
+use crate::advisory::{self, AdvisoryDatabase, DatabaseInfo};
+
+#[derive(Serialize)]
+pub struct AggregateReport {
+    pub workspaces: Vec<String>,
+    /// 読み込めなかったワークスペースとエラー
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    /// 多くのワークスペースで使用されているクレート (使用しているワークスペースの数の多い順)
+    pub most_used: Vec<CrateUsage>,
+    /// ワークスペースごとに異なるバージョンが使用されているクレート
+    pub fragmentation: Vec<VersionFragmentation>,
+    /// アドバイザリの影響を受けるワークスペース
+    pub advisories: Vec<AdvisoryExposure>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advisories_error: Option<String>,
+    /// 検査に使用したアドバイザリデータベース
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advisory_database: Option<DatabaseInfo>,
+}
+
+#[derive(Serialize)]
+pub struct CrateUsage {
+    pub name: String,
+    pub workspace_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct VersionFragmentation {
+    pub name: String,
+    /// バージョンごとの、そのバージョンを使用しているワークスペース
+    pub versions: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct AdvisoryExposure {
+    pub id: String,
+    pub title: String,
+    pub package: String,
+    /// 影響を受けるワークスペースと使用中のバージョン (`workspace: version`)
+    pub workspaces: Vec<String>,
+}
+
+/// 複数のワークスペースの依存関係を集計する
+///
+/// `workspaces` は (ワークスペースの名前, メタデータ) の組。`limit` は `most_used` に含めるクレートの数。
+pub fn aggregate(
+    workspaces: &[(String, Arc<Metadata>)],
+    errors: Vec<String>,
+    db: std::result::Result<&AdvisoryDatabase, String>,
+    limit: usize,
+) -> AggregateReport {
+    // クレート名 -> バージョン -> ワークスペース
+    let mut usage: BTreeMap<&str, BTreeMap<String, BTreeSet<&str>>> = BTreeMap::new();
+    for (name, metadata) in workspaces {
+        for package in &metadata.packages {
+            if metadata.workspace_members.contains(&package.id) {
+                continue;
+            }
+            usage
+                .entry(&package.name)
+                .or_default()
+                .entry(package.version.to_string())
+                .or_default()
+                .insert(name);
+        }
+    }
+
+    let mut most_used: Vec<CrateUsage> = usage
+        .iter()
+        .map(|(name, versions)| CrateUsage {
+            name: name.to_string(),
+            workspace_count: versions.values().flatten().collect::<BTreeSet<_>>().len(),
+        })
+        .collect();
+    most_used.sort_by_key(|u| std::cmp::Reverse(u.workspace_count));
+    most_used.truncate(limit);
+
+    let fragmentation = usage
+        .iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, versions)| VersionFragmentation {
+            name: name.to_string(),
+            versions: versions
+                .iter()
+                .map(|(v, w)| (v.clone(), w.iter().map(|w| w.to_string()).collect()))
+                .collect(),
+        })
+        .collect();
+
+    let (advisories, advisories_error, advisory_database) = match db {
+        Ok(database) => {
+            let mut exposures: BTreeMap<String, AdvisoryExposure> = BTreeMap::new();
+            for (name, metadata) in workspaces {
+                for finding in advisory::find_advisories(metadata, &database.db) {
+                    let key = format!("{} {}", finding.id, finding.package);
+                    let exposure = exposures.entry(key).or_insert_with(|| AdvisoryExposure {
+                        id: finding.id.clone(),
+                        title: finding.title.clone(),
+                        package: finding.package.clone(),
+                        workspaces: Vec::new(),
+                    });
+                    let entry = format!("{name}: {}", finding.version);
+                    if !exposure.workspaces.contains(&entry) {
+                        exposure.workspaces.push(entry);
+                    }
+                }
+            }
+            (
+                exposures.into_values().collect(),
+                None,
+                Some(database.info.clone()),
+            )
+        }
+        Err(e) => (Vec::new(), Some(e), None),
+    };
+
+    AggregateReport {
+        workspaces: workspaces.iter().map(|(name, _)| name.clone()).collect(),
+        errors,
+        most_used,
+        fragmentation,
+        advisories,
+        advisories_error,
+        advisory_database,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_aggregate() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let workspaces = vec![
+            ("a".to_string(), Arc::new(metadata.clone())),
+            ("b".to_string(), Arc::new(metadata)),
+        ];
+        let report = aggregate(&workspaces, Vec::new(), Err("offline".to_string()), 3);
+        assert_eq!(report.most_used.len(), 3);
+        assert!(report.most_used.iter().all(|u| u.workspace_count == 2));
+        assert!(
+            report
+                .fragmentation
+                .iter()
+                .all(|f| f.versions.values().all(|w| w == &["a", "b"]))
+        );
+        assert_eq!(report.advisories_error.as_deref(), Some("offline"));
+        assert!(report.advisory_database.is_none());
+    }
+}
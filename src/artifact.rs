@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use cargo_metadata::{DependencyKind, Package};
+use serde::Serialize;
+
+/// 成果物依存関係 (bindeps、`-Z bindeps`) の指定
+///
+/// cargo_metadata の `Dependency` には含まれないため、Cargo.toml から読み取る。
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct ArtifactSpec {
+    /// 成果物の種類 (`bin`、`bin:<name>`、`cdylib`、`staticlib`)
+    pub kinds: Vec<String>,
+    /// 成果物に加えてライブラリとしても依存するかどうか (`lib = true`)
+    pub lib: bool,
+    /// 成果物をビルドするターゲット (`target` はビルド中のターゲットと同じであることを表す)
+    pub target: Option<String>,
+}
+
+/// 1 つのパッケージの Cargo.toml で宣言された成果物依存関係
+#[derive(Default)]
+pub struct ArtifactDependencies(BTreeMap<(&'static str, String), ArtifactSpec>);
+
+fn table_kind(table: &str) -> Option<&'static str> {
+    match table {
+        "dependencies" => Some("normal"),
+        "dev-dependencies" | "dev_dependencies" => Some("dev"),
+        "build-dependencies" | "build_dependencies" => Some("build"),
+        _ => None,
+    }
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+fn artifact_spec(dependency: &toml::Value) -> Option<ArtifactSpec> {
+    let kinds = match dependency.get("artifact")? {
+        toml::Value::String(kind) => vec![kind.clone()],
+        toml::Value::Array(kinds) => kinds
+            .iter()
+            .filter_map(|k| k.as_str().map(|k| k.to_string()))
+            .collect(),
+        _ => return None,
+    };
+    Some(ArtifactSpec {
+        kinds,
+        lib: dependency
+            .get("lib")
+            .and_then(|l| l.as_bool())
+            .unwrap_or(false),
+        target: dependency
+            .get("target")
+            .and_then(|t| t.as_str())
+            .map(|t| t.to_string()),
+    })
+}
+
+impl ArtifactDependencies {
+    /// Cargo.toml の内容から成果物依存関係を読み取る (`[target.*]` 以下のテーブルを含む)
+    pub fn parse(manifest: &str) -> Self {
+        let Ok(manifest) = toml::from_str::<toml::Table>(manifest) else {
+            return Self::default();
+        };
+        let mut tables = vec![&manifest];
+        if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+            tables.extend(targets.values().filter_map(|t| t.as_table()));
+        }
+        let mut artifacts = BTreeMap::new();
+        for table in tables {
+            for (name, dependencies) in table {
+                let (Some(kind), Some(dependencies)) = (table_kind(name), dependencies.as_table())
+                else {
+                    continue;
+                };
+                for (key, dependency) in dependencies {
+                    if let Some(spec) = artifact_spec(dependency) {
+                        let package = dependency
+                            .get("package")
+                            .and_then(|p| p.as_str())
+                            .unwrap_or(key);
+                        artifacts.insert((kind, package.to_string()), spec);
+                    }
+                }
+            }
+        }
+        Self(artifacts)
+    }
+
+    /// パッケージの Cargo.toml から成果物依存関係を読み取る (読み込めない場合は空)
+    ///
+    /// レジストリに公開されたパッケージは成果物依存関係を持てないため、ソースを持たないパッケージだけを読み込む。
+    pub fn load(package: &Package) -> Self {
+        if package.source.is_some() {
+            return Self::default();
+        }
+        match std::fs::read_to_string(&package.manifest_path) {
+            Ok(text) => Self::parse(&text),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 依存関係の種類とパッケージ名に対応する成果物依存関係の指定
+    pub fn get(&self, kind: DependencyKind, package: &str) -> Option<&ArtifactSpec> {
+        self.0.get(&(kind_name(kind), package.to_string()))
+    }
+}
+
+impl ArtifactSpec {
+    /// `bin:tool,cdylib+lib@wasm32-unknown-unknown` のような短い文字列
+    pub fn label(&self) -> String {
+        let mut label = self.kinds.join(",");
+        if self.lib {
+            label.push_str("+lib");
+        }
+        if let Some(target) = &self.target {
+            label.push('@');
+            label.push_str(target);
+        }
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let artifacts = ArtifactDependencies::parse(
+            r#"
+[dependencies]
+serde = "1"
+helper = { path = "../tool", package = "tool", artifact = ["bin:tool", "cdylib"], lib = true }
+
+[build-dependencies]
+tool = { path = "../tool", artifact = "bin", target = "wasm32-unknown-unknown" }
+
+[target.'cfg(unix)'.dev-dependencies]
+runner = { path = "../runner", artifact = "bin", target = "target" }
+"#,
+        );
+        let normal = artifacts.get(DependencyKind::Normal, "tool").unwrap();
+        assert_eq!(normal.kinds, ["bin:tool", "cdylib"]);
+        assert!(normal.lib);
+        assert_eq!(normal.label(), "bin:tool,cdylib+lib");
+        let build = artifacts.get(DependencyKind::Build, "tool").unwrap();
+        assert_eq!(build.label(), "bin@wasm32-unknown-unknown");
+        let dev = artifacts
+            .get(DependencyKind::Development, "runner")
+            .unwrap();
+        assert_eq!(dev.target.as_deref(), Some("target"));
+        assert!(artifacts.get(DependencyKind::Normal, "serde").is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::{HashMap, HashSet};
+
+use cargo_metadata::{Metadata, PackageId};
+
+use crate::findings::{self, Finding, FindingSet, Level};
+use crate::graph::Graph;
+
+/// 依存関係の予算
+#[derive(Default)]
+pub struct Budget {
+    /// ワークスペースメンバーを除いた依存パッケージ数の上限
+    pub max_total_crates: Option<usize>,
+    /// 1 つの直接依存が新たに持ち込むパッケージ数の上限
+    pub max_new_crates_per_dependency: Option<usize>,
+    /// ワークスペースメンバーから最も深い依存パッケージまでの辺の数の上限
+    pub max_depth: Option<usize>,
+}
+
+/// 直接依存ごとの、そのパッケージを経由してのみ到達できるパッケージ数
+pub(crate) struct Contribution<'a> {
+    pub member: &'a PackageId,
+    pub dependency: &'a PackageId,
+    pub new_crates: usize,
+}
+
+/// ワークスペースメンバーの直接依存ごとに、他の直接依存からは到達できないパッケージの数を数える
+///
+/// 数には直接依存自身を含む。
+pub(crate) fn contributions<'a>(graph: &Graph<'a>, include_dev: bool) -> Vec<Contribution<'a>> {
+    let metadata = graph.metadata;
+    let mut direct: Vec<(&PackageId, &PackageId)> = Vec::new();
+    for member in graph.members() {
+        for dep in graph.dependencies(member, include_dev) {
+            if !metadata.workspace_members.contains(dep) && !direct.iter().any(|(_, d)| *d == dep) {
+                direct.push((member, dep));
+            }
+        }
+    }
+    let closures: Vec<HashSet<&PackageId>> = direct
+        .iter()
+        .map(|(_, dep)| graph.reachable(dep, include_dev))
+        .collect();
+    let mut owners: HashMap<&PackageId, usize> = HashMap::new();
+    for closure in &closures {
+        for id in closure {
+            *owners.entry(id).or_default() += 1;
+        }
+    }
+    let mut contributions: Vec<Contribution> = direct
+        .iter()
+        .zip(&closures)
+        .map(|((member, dependency), closure)| Contribution {
+            member,
+            dependency,
+            new_crates: closure
+                .iter()
+                .filter(|id| owners[*id] == 1 && !metadata.workspace_members.contains(*id))
+                .count(),
+        })
+        .collect();
+    contributions.sort_by_key(|c| std::cmp::Reverse(c.new_crates));
+    contributions
+}
+
+/// パッケージから最も深い依存パッケージまでの深さ (ワークスペースメンバーには入らない)
+pub(crate) struct Depths<'g, 'a> {
+    graph: &'g Graph<'a>,
+    include_dev: bool,
+    /// 最も深いパスで次に辿るパッケージ
+    next: HashMap<&'a PackageId, Option<&'a PackageId>>,
+    depth: HashMap<&'a PackageId, usize>,
+    visiting: HashSet<&'a PackageId>,
+}
+
+impl<'g, 'a> Depths<'g, 'a> {
+    pub fn new(graph: &'g Graph<'a>, include_dev: bool) -> Self {
+        Self {
+            graph,
+            include_dev,
+            next: HashMap::new(),
+            depth: HashMap::new(),
+            visiting: HashSet::new(),
+        }
+    }
+
+    /// `id` から最も深い依存パッケージまでの辺の数
+    pub fn depth(&mut self, id: &'a PackageId) -> usize {
+        if let Some(d) = self.depth.get(id) {
+            return *d;
+        }
+        if !self.visiting.insert(id) {
+            return 0;
+        }
+        let mut best = (0, None);
+        for dep in self.graph.dependencies(id, self.include_dev) {
+            if self.graph.metadata.workspace_members.contains(dep) {
+                continue;
+            }
+            let d = self.depth(dep) + 1;
+            if d > best.0 {
+                best = (d, Some(dep));
+            }
+        }
+        self.visiting.remove(id);
+        self.next.insert(id, best.1);
+        self.depth.insert(id, best.0);
+        best.0
+    }
+
+    /// `id` から最も深い依存パッケージまでのパス (`id` を含む)
+    pub fn path(&mut self, id: &'a PackageId) -> Vec<&'a PackageId> {
+        self.depth(id);
+        let mut path = Vec::new();
+        let mut current = Some(id);
+        while let Some(id) = current {
+            path.push(id);
+            current = self.next.get(id).copied().flatten();
+        }
+        path
+    }
+}
+
+/// ワークスペースメンバーから最も深い依存パッケージまでのパスを返す
+pub(crate) fn deepest_path<'a>(graph: &Graph<'a>, include_dev: bool) -> Vec<&'a PackageId> {
+    let mut depths = Depths::new(graph, include_dev);
+    let mut root = None;
+    let mut max = 0;
+    for member in graph.members() {
+        let d = depths.depth(member);
+        if root.is_none() || d > max {
+            root = Some(member);
+            max = d;
+        }
+    }
+    match root {
+        Some(root) => depths.path(root),
+        None => Vec::new(),
+    }
+}
+
+/// 依存関係が予算内に収まっているか検査する
+pub fn check_budget(metadata: &Metadata, budget: &Budget) -> FindingSet {
+    let graph = Graph::new(metadata);
+    let mut set = FindingSet::new("dependency-budget");
+    let manifest = |id: &PackageId| {
+        graph
+            .package(id)
+            .map(|p| p.manifest_path.clone())
+            .unwrap_or_else(|| metadata.workspace_root.join("Cargo.toml"))
+    };
+    let contributions = contributions(&graph, true);
+
+    if let Some(max) = budget.max_total_crates {
+        set.rule(
+            "budget/total-crates",
+            "The total number of dependencies must not exceed the budget",
+            None,
+        );
+        let total = metadata
+            .packages
+            .iter()
+            .filter(|p| !metadata.workspace_members.contains(&p.id))
+            .count();
+        if total > max {
+            let top: Vec<String> = contributions
+                .iter()
+                .take(5)
+                .map(|c| format!("{} (+{})", graph.label(c.dependency), c.new_crates))
+                .collect();
+            set.push(Finding {
+                rule: "budget/total-crates".to_string(),
+                level: Level::Error,
+                message: format!(
+                    "The dependency graph contains {} crates, exceeding the budget of {} by {}. Largest contributors: {}",
+                    total,
+                    max,
+                    total - max,
+                    top.join(", ")
+                ),
+                package: None,
+                file: Some(metadata.workspace_root.join("Cargo.toml")),
+                line: None,
+                dependency_path: Vec::new(),
+            });
+        }
+    }
+
+    if let Some(max) = budget.max_new_crates_per_dependency {
+        set.rule(
+            "budget/new-crates-per-dependency",
+            "A direct dependency must not bring in more new crates than the budget",
+            None,
+        );
+        for c in contributions.iter().filter(|c| c.new_crates > max) {
+            let manifest = manifest(c.member);
+            let line = graph
+                .package(c.dependency)
+                .and_then(|p| findings::find_key_line(&manifest, &p.name));
+            set.push(Finding {
+                rule: "budget/new-crates-per-dependency".to_string(),
+                level: Level::Error,
+                message: format!(
+                    "{} brings in {} crates not used by any other dependency, exceeding the budget of {} by {}",
+                    graph.label(c.dependency),
+                    c.new_crates,
+                    max,
+                    c.new_crates - max
+                ),
+                package: Some(graph.label(c.dependency)),
+                file: Some(manifest),
+                line,
+                dependency_path: vec![graph.label(c.member), graph.label(c.dependency)],
+            });
+        }
+    }
+
+    if let Some(max) = budget.max_depth {
+        set.rule(
+            "budget/depth",
+            "The dependency graph must not be deeper than the budget",
+            None,
+        );
+        let path = deepest_path(&graph, true);
+        let depth = path.len().saturating_sub(1);
+        if depth > max {
+            let responsible = path.get(1).copied().unwrap_or(path[0]);
+            set.push(Finding {
+                rule: "budget/depth".to_string(),
+                level: Level::Error,
+                message: format!(
+                    "The dependency graph is {} levels deep through {}, exceeding the budget of {} by {}",
+                    depth,
+                    graph.label(responsible),
+                    max,
+                    depth - max
+                ),
+                package: Some(graph.label(responsible)),
+                file: Some(manifest(path[0])),
+                line: None,
+                dependency_path: path.iter().map(|id| graph.label(id)).collect(),
+            });
+        }
+    }
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_metadata::MetadataCommand;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_check_budget() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let budget = Budget {
+            max_total_crates: Some(1),
+            max_new_crates_per_dependency: Some(0),
+            max_depth: Some(1),
+        };
+        let set = check_budget(&metadata, &budget);
+        let rules: BTreeSet<&str> = set.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(
+            rules,
+            BTreeSet::from([
+                "budget/depth",
+                "budget/new-crates-per-dependency",
+                "budget/total-crates"
+            ])
+        );
+        let depth = set
+            .findings
+            .iter()
+            .find(|f| f.rule == "budget/depth")
+            .unwrap();
+        assert!(depth.dependency_path.len() > 2);
+    }
+}
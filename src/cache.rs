@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use cargo_metadata::Metadata;
+use mcp_attr::Result;
+use serde::Serialize;
+
+use crate::provider::MetadataOptions;
+
+/// キャッシュしているメタデータと、取得したときの入力ファイルの状態
+pub struct CachedMetadata {
+    pub metadata: Arc<Metadata>,
+    inputs: Vec<(PathBuf, Option<FileStamp>)>,
+}
+
+/// ファイルの更新日時と大きさ (ファイルが存在しない場合は `None`)
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+fn stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(FileStamp {
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+    })
+}
+
+/// メタデータの結果に影響するファイル (指定したマニフェスト、ワークスペースのルートの Cargo.toml と Cargo.lock、メンバーのマニフェスト)
+fn input_files(metadata: &Metadata, manifest_path: &Path) -> Vec<PathBuf> {
+    let root = metadata.workspace_root.as_std_path();
+    let mut files = vec![
+        manifest_path.to_path_buf(),
+        root.join("Cargo.toml"),
+        root.join("Cargo.lock"),
+    ];
+    files.extend(
+        metadata
+            .workspace_packages()
+            .iter()
+            .map(|p| p.manifest_path.clone().into_std_path_buf()),
+    );
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// `refresh_metadata` の結果
+#[derive(Serialize)]
+pub struct RefreshResult {
+    pub manifest_path: String,
+    pub workspace_root: String,
+    /// 取得し直す前にキャッシュされていたかどうか
+    pub was_cached: bool,
+    pub workspace_members: Vec<String>,
+    pub packages: usize,
+}
+
+/// `clear_cache` の結果
+#[derive(Serialize)]
+pub struct ClearResult {
+    /// 破棄したキャッシュのマニフェストのパス
+    pub cleared: Vec<String>,
+}
+
+impl CachedMetadata {
+    pub fn new(metadata: Arc<Metadata>, manifest_path: &Path) -> Self {
+        let inputs = input_files(&metadata, manifest_path)
+            .into_iter()
+            .map(|path| {
+                let stamp = stamp(&path);
+                (path, stamp)
+            })
+            .collect();
+        Self { metadata, inputs }
+    }
+
+    /// 取得したあとに入力ファイルが変更、作成または削除されていないかどうか
+    ///
+    /// `cargo add` などで Cargo.toml や Cargo.lock が書き換えられたあとに古い依存関係を返さないよう、ツールの呼び出しごとに確認する。
+    pub fn is_fresh(&self) -> bool {
+        self.inputs
+            .iter()
+            .all(|(path, before)| stamp(path) == *before)
+    }
+}
+
+/// キャッシュの項目のキー
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CacheKey {
+    /// 正規化したマニフェストのパス
+    pub manifest_path: PathBuf,
+    pub options: MetadataOptions,
+}
+
+/// マニフェストのパスとオプションの組ごとのキャッシュの項目
+///
+/// 項目ごとのロックは、メタデータの取得中だけ保持する。
+/// 同じプロジェクトに対する同時の呼び出しは 1 回の取得を待ち、他のプロジェクトの呼び出しは待たされない。
+type Entry = Arc<tokio::sync::Mutex<Option<CachedMetadata>>>;
+
+/// 正規化したマニフェストのパスと `cargo metadata` のオプションの組ごとにメタデータをキャッシュする
+///
+/// 全体のロックは項目の追加と列挙の間だけ保持するため、キャッシュ済みのメタデータの読み取りは互いに競合しない。
+#[derive(Default)]
+pub struct MetadataCache {
+    entries: RwLock<HashMap<CacheKey, Entry>>,
+}
+
+impl MetadataCache {
+    fn entry(&self, key: &CacheKey) -> Entry {
+        if let Some(entry) = self.entries.read().unwrap().get(key) {
+            return entry.clone();
+        }
+        self.entries
+            .write()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone()
+    }
+
+    fn entries(&self) -> Vec<(CacheKey, Entry)> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.clone()))
+            .collect()
+    }
+
+    /// キャッシュしているメタデータが最新であればそれを返し、そうでなければ `load` で取得してキャッシュする
+    pub async fn get_or_load(
+        &self,
+        key: &CacheKey,
+        load: impl Future<Output = Result<Metadata>>,
+    ) -> Result<Arc<Metadata>> {
+        let entry = self.entry(key);
+        let mut entry = entry.lock().await;
+        if let Some(cached) = &*entry
+            && cached.is_fresh()
+        {
+            return Ok(cached.metadata.clone());
+        }
+        let metadata = Arc::new(load.await?);
+        *entry = Some(CachedMetadata::new(metadata.clone(), &key.manifest_path));
+        Ok(metadata)
+    }
+
+    /// キャッシュしているメタデータが最新であれば返す (取得中の場合は待たずに `None` を返す)
+    pub fn get_fresh(&self, key: &CacheKey) -> Option<Arc<Metadata>> {
+        let entry = self.entries.read().unwrap().get(key)?.clone();
+        let entry = entry.try_lock().ok()?;
+        entry
+            .as_ref()
+            .filter(|cached| cached.is_fresh())
+            .map(|cached| cached.metadata.clone())
+    }
+
+    /// キャッシュを使わずに `load` で取得し、キャッシュを置き換える (取得し直す前にキャッシュされていたかどうかも返す)
+    pub async fn reload(
+        &self,
+        key: &CacheKey,
+        load: impl Future<Output = Result<Metadata>>,
+    ) -> Result<(Arc<Metadata>, bool)> {
+        let entry = self.entry(key);
+        let mut entry = entry.lock().await;
+        let was_cached = entry.take().is_some();
+        let metadata = Arc::new(load.await?);
+        *entry = Some(CachedMetadata::new(metadata.clone(), &key.manifest_path));
+        Ok((metadata, was_cached))
+    }
+
+    /// キャッシュしている、依存グラフを絞り込まずに取得したメタデータ (取得中のものは除く)
+    pub fn cached(&self) -> Vec<Arc<Metadata>> {
+        self.entries()
+            .into_iter()
+            .filter(|(key, _)| key.options.is_complete())
+            .filter_map(|(_, entry)| {
+                let entry = entry.try_lock().ok()?;
+                entry.as_ref().map(|c| c.metadata.clone())
+            })
+            .collect()
+    }
+
+    /// ワークスペースのキャッシュしているメタデータをすべて破棄し、破棄したメタデータのうち 1 つを返す
+    ///
+    /// 同じワークスペースのメタデータは、メンバーごとのマニフェストのパスでキャッシュされていることがある。
+    /// 他のプロジェクトの取得を待たないよう、ワークスペースの外のマニフェストの項目と、取得中の項目には触れない
+    /// (取得中の項目は、取得し終えたときの入力ファイルの状態で鮮度を確認する)。
+    pub fn invalidate(&self, workspace_root: &Path) -> Option<Arc<Metadata>> {
+        let mut invalidated = None;
+        for (key, entry) in self.entries() {
+            if !key.manifest_path.starts_with(workspace_root) {
+                continue;
+            }
+            let Ok(mut entry) = entry.try_lock() else {
+                continue;
+            };
+            if let Some(cached) =
+                entry.take_if(|c| c.metadata.workspace_root.as_std_path() == workspace_root)
+            {
+                invalidated = Some(cached.metadata);
+            }
+        }
+        invalidated
+    }
+
+    /// キャッシュしているすべてのメタデータを破棄し、破棄したマニフェストのパスを返す
+    ///
+    /// 取得中の項目は、取得を待たないよう破棄しない。
+    pub fn clear(&self) -> Vec<PathBuf> {
+        let mut cleared = Vec::new();
+        for (key, entry) in self.entries() {
+            if entry
+                .try_lock()
+                .is_ok_and(|mut entry| entry.take().is_some())
+            {
+                cleared.push(key.manifest_path);
+            }
+        }
+        cleared.sort();
+        cleared.dedup();
+        cleared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_fresh() {
+        let root = std::env::temp_dir().join(format!("cache-test-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let manifest_path = root.join("Cargo.toml");
+        std::fs::write(&manifest_path, "[package]\nname = \"app\"\n").unwrap();
+        let mut metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        metadata.workspace_root = root.clone().try_into().unwrap();
+        metadata.workspace_members.clear();
+
+        let cached = CachedMetadata::new(Arc::new(metadata.clone()), &manifest_path);
+        assert!(cached.is_fresh());
+        std::fs::write(
+            &manifest_path,
+            "[package]\nname = \"app\"\n[dependencies]\n",
+        )
+        .unwrap();
+        assert!(!cached.is_fresh());
+
+        let cached = CachedMetadata::new(Arc::new(metadata), &manifest_path);
+        std::fs::write(root.join("Cargo.lock"), "version = 4\n").unwrap();
+        assert!(!cached.is_fresh());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
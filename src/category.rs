@@ -0,0 +1,420 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use cargo_metadata::{Metadata, Package, PackageId};
+use serde::Serialize;
+
+use crate::graph::Graph;
+
+/// 機能分類と、その分類に属するとみなす crates.io のカテゴリ (前方一致) およびキーワードの対応表
+///
+/// (分類名, カテゴリ, キーワード)。上から順に照合し、最初に一致した分類を採用する。
+const CLUSTERS: &[(&str, &[&str], &[&str])] = &[
+    (
+        "http",
+        &[
+            "web-programming::http-client",
+            "web-programming::http-server",
+        ],
+        &[
+            "http",
+            "https",
+            "http-client",
+            "http-server",
+            "rest",
+            "hyper",
+        ],
+    ),
+    (
+        "serialization",
+        &["encoding"],
+        &[
+            "serde",
+            "serialization",
+            "json",
+            "toml",
+            "yaml",
+            "msgpack",
+            "protobuf",
+            "cbor",
+        ],
+    ),
+    (
+        "async-runtime",
+        &["asynchronous"],
+        &["async", "futures", "tokio", "runtime", "await"],
+    ),
+    (
+        "crypto",
+        &["cryptography"],
+        &[
+            "crypto",
+            "cryptography",
+            "tls",
+            "ssl",
+            "openssl",
+            "rustls",
+            "hash",
+            "sha2",
+        ],
+    ),
+    (
+        "error-handling",
+        &["rust-patterns::error-handling"],
+        &["error", "error-handling", "errors"],
+    ),
+    (
+        "logging",
+        &["development-tools::debugging"],
+        &["log", "logging", "tracing", "telemetry"],
+    ),
+    (
+        "cli",
+        &["command-line-interface", "command-line-utilities"],
+        &["cli", "argparse", "terminal"],
+    ),
+    (
+        "database",
+        &["database", "database-implementations"],
+        &["database", "sql", "orm"],
+    ),
+    (
+        "date-time",
+        &["date-and-time"],
+        &["date", "time", "datetime", "timezone"],
+    ),
+    (
+        "text-processing",
+        &["text-processing", "parser-implementations", "parsing"],
+        &["regex", "parser", "unicode"],
+    ),
+    (
+        "compression",
+        &["compression"],
+        &["compression", "zip", "gzip", "zstd"],
+    ),
+    (
+        "proc-macro",
+        &["development-tools::procedural-macro-helpers"],
+        &["proc-macro", "derive", "macro"],
+    ),
+    (
+        "ffi",
+        &[
+            "external-ffi-bindings",
+            "development-tools::ffi",
+            "api-bindings",
+        ],
+        &["ffi", "bindings", "sys"],
+    ),
+    (
+        "networking",
+        &["network-programming", "web-programming"],
+        &["network", "socket", "websocket", "web"],
+    ),
+    (
+        "concurrency",
+        &["concurrency"],
+        &["concurrency", "parallel", "lock", "atomic"],
+    ),
+    (
+        "data-structures",
+        &["data-structures", "algorithms"],
+        &["collections", "hashmap", "data-structure"],
+    ),
+    ("filesystem", &["filesystem"], &["filesystem", "fs", "path"]),
+    ("os", &["os"], &["os", "platform", "posix", "windows"]),
+    ("development-tools", &["development-tools"], &[]),
+];
+
+/// 同じ機能を提供するとみなすクレートの組の対応表
+///
+/// (組の名前, クレート名)。同じ組のクレートが 2 つ以上依存グラフに含まれる場合に重複として報告する。
+const OVERLAP_GROUPS: &[(&str, &[&str])] = &[
+    ("json", &["serde_json", "simd-json", "sonic-rs", "json"]),
+    (
+        "yaml",
+        &["serde_yaml", "serde_yml", "yaml-rust", "yaml-rust2"],
+    ),
+    ("tls", &["openssl", "rustls", "native-tls"]),
+    (
+        "error-handling",
+        &["anyhow", "eyre", "failure", "error-chain", "snafu"],
+    ),
+    ("async-runtime", &["tokio", "async-std", "smol"]),
+    (
+        "http-client",
+        &["reqwest", "ureq", "surf", "isahc", "attohttpc", "curl"],
+    ),
+    (
+        "logging-backend",
+        &[
+            "env_logger",
+            "simplelog",
+            "fern",
+            "log4rs",
+            "flexi_logger",
+            "tracing-subscriber",
+        ],
+    ),
+    (
+        "cli-parser",
+        &[
+            "clap",
+            "structopt",
+            "argh",
+            "pico-args",
+            "gumdrop",
+            "lexopt",
+        ],
+    ),
+    ("date-time", &["chrono", "time", "jiff"]),
+    ("lazy-init", &["lazy_static", "once_cell"]),
+    ("random", &["rand", "fastrand", "oorandom", "nanorand"]),
+    ("fast-hash", &["ahash", "fxhash", "rustc-hash", "foldhash"]),
+    ("regex", &["regex", "fancy-regex", "onig", "pcre2"]),
+    ("base64", &["base64", "data-encoding"]),
+    ("channel", &["crossbeam-channel", "flume", "async-channel"]),
+];
+
+/// 直接依存に複数のクレートがある場合に、機能が重複している可能性があるとみなす機能分類
+const OVERLAP_CLUSTERS: &[&str] = &[
+    "http",
+    "crypto",
+    "error-handling",
+    "logging",
+    "cli",
+    "date-time",
+    "database",
+];
+
+/// 分類できなかったパッケージの分類名
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+#[derive(Serialize)]
+pub struct CategoryMap {
+    pub clusters: Vec<Cluster>,
+}
+
+#[derive(Serialize)]
+pub struct Cluster {
+    pub name: String,
+    pub crates: Vec<ClusteredCrate>,
+}
+
+#[derive(Serialize)]
+pub struct ClusteredCrate {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+    /// 分類の根拠 (`category`、`keyword` または `none`)
+    pub matched_by: &'static str,
+    /// このパッケージに直接依存しているワークスペースメンバー
+    pub used_by: Vec<String>,
+}
+
+/// パッケージの categories と keywords から機能分類を推定する
+///
+/// 対応表に一致しない場合は、crates.io のカテゴリの最上位の階層をそのまま分類名とする。
+pub fn classify(package: &Package) -> (String, &'static str) {
+    let categories: Vec<String> = package
+        .categories
+        .iter()
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    let keywords: Vec<String> = package
+        .keywords
+        .iter()
+        .map(|k| k.to_ascii_lowercase())
+        .collect();
+    for (name, cluster_categories, _) in CLUSTERS {
+        if categories
+            .iter()
+            .any(|c| cluster_categories.iter().any(|p| c.starts_with(p)))
+        {
+            return (name.to_string(), "category");
+        }
+    }
+    for (name, _, cluster_keywords) in CLUSTERS {
+        if keywords
+            .iter()
+            .any(|k| cluster_keywords.contains(&k.as_str()))
+        {
+            return (name.to_string(), "keyword");
+        }
+    }
+    match categories.first() {
+        Some(category) => (
+            category.split("::").next().unwrap_or(category).to_string(),
+            "category",
+        ),
+        None => (UNCATEGORIZED.to_string(), "none"),
+    }
+}
+
+/// ワークスペースメンバーが直接依存しているパッケージと、依存しているメンバーの一覧
+pub fn direct_dependencies<'a>(graph: &Graph<'a>) -> BTreeMap<&'a PackageId, BTreeSet<String>> {
+    let metadata = graph.metadata;
+    let mut direct: BTreeMap<&PackageId, BTreeSet<String>> = BTreeMap::new();
+    for member in graph.members() {
+        let Some(member_package) = graph.package(member) else {
+            continue;
+        };
+        for dep in graph.dependencies(member, true) {
+            if !metadata.workspace_members.contains(dep) {
+                direct
+                    .entry(dep)
+                    .or_default()
+                    .insert(member_package.name.clone());
+            }
+        }
+    }
+    direct
+}
+
+/// 直接依存を機能分類ごとにまとめる
+pub fn category_map(metadata: &Metadata) -> CategoryMap {
+    let graph = Graph::new(metadata);
+    let mut clusters: BTreeMap<String, Vec<ClusteredCrate>> = BTreeMap::new();
+    for (id, used_by) in direct_dependencies(&graph) {
+        let Some(package) = graph.package(id) else {
+            continue;
+        };
+        let (cluster, matched_by) = classify(package);
+        clusters.entry(cluster).or_default().push(ClusteredCrate {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            description: package.description.clone(),
+            categories: package.categories.clone(),
+            keywords: package.keywords.clone(),
+            matched_by,
+            used_by: used_by.into_iter().collect(),
+        });
+    }
+    let mut clusters: Vec<Cluster> = clusters
+        .into_iter()
+        .map(|(name, mut crates)| {
+            crates.sort_by(|a, b| a.name.cmp(&b.name));
+            Cluster { name, crates }
+        })
+        .collect();
+    // 分類できなかったパッケージは最後に並べる
+    clusters.sort_by_key(|c| c.name == UNCATEGORIZED);
+    CategoryMap { clusters }
+}
+
+#[derive(Serialize)]
+pub struct OverlapReport {
+    pub overlaps: Vec<Overlap>,
+}
+
+#[derive(Serialize)]
+pub struct Overlap {
+    pub group: String,
+    /// `high` (既知の重複の組に一致) または `low` (直接依存の機能分類が一致)
+    pub confidence: &'static str,
+    pub crates: Vec<OverlapCrate>,
+}
+
+#[derive(Serialize)]
+pub struct OverlapCrate {
+    pub name: String,
+    pub versions: Vec<String>,
+    /// いずれかのワークスペースメンバーが直接依存しているかどうか
+    pub direct: bool,
+    /// このクレートに (推移的に) 依存しているワークスペースメンバー
+    pub members: Vec<String>,
+    /// ワークスペースメンバーからこのクレートに至る最短の依存パス
+    pub dependency_path: Vec<String>,
+}
+
+/// 同じ機能を提供する可能性のあるクレートが依存グラフに複数含まれていないか推定する
+///
+/// 既知の重複の組に一致するものは依存グラフ全体から、機能分類が一致するものは直接依存から検出する。
+pub fn overlap_report(metadata: &Metadata) -> OverlapReport {
+    let graph = Graph::new(metadata);
+    let direct = direct_dependencies(&graph);
+    let reachable: Vec<(&str, HashSet<&PackageId>)> = graph
+        .members()
+        .filter_map(|id| Some((graph.package(id)?.name.as_str(), graph.reachable(id, true))))
+        .collect();
+    let overlap_crate = |name: &str| {
+        let packages: Vec<&Package> = metadata
+            .packages
+            .iter()
+            .filter(|p| p.name == name && !metadata.workspace_members.contains(&p.id))
+            .collect();
+        let first = packages.first()?;
+        let members: BTreeSet<&str> = reachable
+            .iter()
+            .filter(|(_, r)| packages.iter().any(|p| r.contains(&p.id)))
+            .map(|(m, _)| *m)
+            .collect();
+        Some(OverlapCrate {
+            name: name.to_string(),
+            versions: packages.iter().map(|p| p.version.to_string()).collect(),
+            direct: packages.iter().any(|p| direct.contains_key(&p.id)),
+            members: members.into_iter().map(str::to_string).collect(),
+            dependency_path: graph.path_labels(&first.id, true),
+        })
+    };
+
+    let mut overlaps = Vec::new();
+    let mut reported = BTreeSet::new();
+    for (group, names) in OVERLAP_GROUPS {
+        let crates: Vec<OverlapCrate> = names.iter().filter_map(|n| overlap_crate(n)).collect();
+        if crates.len() > 1 {
+            reported.extend(crates.iter().map(|c| c.name.clone()));
+            overlaps.push(Overlap {
+                group: group.to_string(),
+                confidence: "high",
+                crates,
+            });
+        }
+    }
+
+    let mut clusters: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+    for id in direct.keys() {
+        if let Some(package) = graph.package(id) {
+            let (cluster, _) = classify(package);
+            if OVERLAP_CLUSTERS.contains(&cluster.as_str()) {
+                clusters.entry(cluster).or_default().insert(&package.name);
+            }
+        }
+    }
+    for (cluster, names) in clusters {
+        if names.len() > 1 && names.iter().any(|n| !reported.contains(*n)) {
+            overlaps.push(Overlap {
+                group: cluster,
+                confidence: "low",
+                crates: names.iter().filter_map(|n| overlap_crate(n)).collect(),
+            });
+        }
+    }
+    OverlapReport { overlaps }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_metadata::MetadataCommand;
+
+    #[test]
+    fn test_category_map() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let map = category_map(&metadata);
+        let cluster_of = |name: &str| {
+            map.clusters
+                .iter()
+                .find(|c| c.crates.iter().any(|p| p.name == name))
+                .map(|c| c.name.as_str())
+        };
+        assert_eq!(cluster_of("serde_json"), Some("serialization"));
+        assert_eq!(cluster_of("tokio"), Some("async-runtime"));
+        assert_eq!(cluster_of("unicode-ident"), None);
+    }
+}
@@ -0,0 +1,301 @@
+use cargo_metadata::camino::Utf8Path;
+use cargo_metadata::semver::Version;
+use cargo_metadata::{Metadata, Package};
+use mcp_attr::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::{index, pkgid};
+
+/// 変更履歴とみなすファイル名 (大文字小文字は区別しない)
+const CHANGELOG_FILES: &[&str] = &[
+    "CHANGELOG.md",
+    "CHANGES.md",
+    "RELEASES.md",
+    "HISTORY.md",
+    "NEWS.md",
+    "CHANGELOG",
+];
+
+#[derive(Serialize)]
+pub struct ChangelogReport {
+    pub name: String,
+    pub current_version: String,
+    pub target_version: String,
+    /// 変更履歴を取得できた場所
+    pub sources: Vec<String>,
+    /// `current_version` より新しく `target_version` 以下のバージョンの変更履歴 (新しい順)
+    pub sections: Vec<ChangelogSection>,
+    /// バージョンごとに分割できなかった変更履歴の本文
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ChangelogSection {
+    pub version: String,
+    pub heading: String,
+    pub text: String,
+    /// `changelog` または `github-release`
+    pub source: &'static str,
+}
+
+/// 依存関係の、使用中のバージョンから指定したバージョンまでの変更履歴を取得する
+///
+/// GitHub のデフォルトブランチの CHANGELOG.md を探し、取得できない場合はローカルのソースの変更履歴を使用する。
+/// 更新先のバージョンの記述が見つからない場合は、GitHub Releases のリリースノートで補う。
+/// `GITHUB_TOKEN` 環境変数が設定されている場合は GitHub API の認証に使用する。
+pub fn changelog(
+    metadata: &Metadata,
+    crate_name: &str,
+    target_version: Option<&str>,
+) -> Result<ChangelogReport> {
+    let package = pkgid::resolve_latest(metadata, crate_name)?;
+    let current = &package.version;
+    let target = match target_version {
+        Some(version) => match Version::parse(version.trim_start_matches('v')) {
+            Ok(version) => version,
+            Err(e) => bail_kind!(ErrorKind::InvalidVersion, version, e),
+        },
+        None => index::latest_version(&package.name)?,
+    };
+    let in_range = |v: &Version| v > current && v <= &target;
+
+    let mut report = ChangelogReport {
+        name: package.name.clone(),
+        current_version: current.to_string(),
+        target_version: target.to_string(),
+        sources: Vec::new(),
+        sections: Vec::new(),
+        raw: None,
+        errors: Vec::new(),
+    };
+
+    let mut changelog = None;
+    let github = package.repository.as_deref().and_then(github_repository);
+    if changelog.is_none()
+        && let Some((owner, repo)) = &github
+    {
+        changelog = remote_changelog(owner, repo, &package.name, &mut report.errors);
+    }
+    if changelog.is_none() {
+        changelog = local_changelog(package);
+    }
+    if let Some((source, text)) = changelog {
+        let sections: Vec<ChangelogSection> = split_sections(&text)
+            .into_iter()
+            .filter(|(v, _, _)| in_range(v))
+            .map(|(version, heading, text)| ChangelogSection {
+                version: version.to_string(),
+                heading,
+                text,
+                source: "changelog",
+            })
+            .collect();
+        report.sources.push(source);
+        if sections.is_empty() {
+            report.raw = Some(text);
+        }
+        report.sections = sections;
+    }
+
+    let covered = |sections: &[ChangelogSection], v: &Version| {
+        sections.iter().any(|s| s.version == v.to_string())
+    };
+    if !covered(&report.sections, &target)
+        && let Some((owner, repo)) = &github
+    {
+        match github_releases(owner, repo) {
+            Ok(releases) => {
+                let mut found = false;
+                for (version, heading, text) in releases {
+                    if in_range(&version) && !covered(&report.sections, &version) {
+                        found = true;
+                        report.sections.push(ChangelogSection {
+                            version: version.to_string(),
+                            heading,
+                            text,
+                            source: "github-release",
+                        });
+                    }
+                }
+                if found {
+                    report
+                        .sources
+                        .push(format!("https://github.com/{owner}/{repo}/releases"));
+                    report.raw = None;
+                }
+            }
+            Err(e) => report.errors.push(e),
+        }
+    }
+    report.sections.sort_by(|a, b| {
+        let version = |s: &ChangelogSection| Version::parse(&s.version).ok();
+        version(b).cmp(&version(a))
+    });
+    Ok(report)
+}
+
+/// パッケージのソースディレクトリにある変更履歴
+fn local_changelog(package: &Package) -> Option<(String, String)> {
+    let dir = package.manifest_path.parent()?;
+    let entries = dir.read_dir_utf8().ok()?;
+    for entry in entries.flatten() {
+        if CHANGELOG_FILES
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(entry.file_name()))
+        {
+            let path: &Utf8Path = entry.path();
+            if let Ok(text) = std::fs::read_to_string(path) {
+                return Some((path.to_string(), text));
+            }
+        }
+    }
+    None
+}
+
+/// リポジトリの URL から GitHub の所有者とリポジトリ名を取り出す
+fn github_repository(url: &str) -> Option<(String, String)> {
+    let rest = url
+        .trim_end_matches('/')
+        .split_once("github.com/")?
+        .1
+        .trim_end_matches(".git");
+    let mut parts = rest.split('/');
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    let repo = parts.next().filter(|s| !s.is_empty())?;
+    Some((owner.to_string(), repo.trim_end_matches(".git").to_string()))
+}
+
+/// GitHub のデフォルトブランチにある変更履歴 (リポジトリ直下とクレート名のディレクトリを探す)
+fn remote_changelog(
+    owner: &str,
+    repo: &str,
+    crate_name: &str,
+    errors: &mut Vec<String>,
+) -> Option<(String, String)> {
+    for dir in ["", &format!("{crate_name}/")] {
+        for file in CHANGELOG_FILES {
+            let url = format!("https://raw.githubusercontent.com/{owner}/{repo}/HEAD/{dir}{file}");
+            match index::http_get(&url, &[]) {
+                Ok(Some(text)) => return Some((url, text)),
+                Ok(None) => {}
+                Err(e) => {
+                    errors.push(format!("{url}: {e}"));
+                    return None;
+                }
+            }
+        }
+    }
+    None
+}
+
+/// GitHub Releases のリリースノートを (バージョン, 見出し, 本文) の列で返す
+fn github_releases(
+    owner: &str,
+    repo: &str,
+) -> std::result::Result<Vec<(Version, String, String)>, String> {
+    let url = format!("https://api.github.com/repos/{owner}/{repo}/releases?per_page=100");
+    let token = std::env::var("GITHUB_TOKEN").ok();
+    let authorization = token.as_ref().map(|t| format!("Bearer {t}"));
+    let mut headers = vec![("Accept", "application/vnd.github+json")];
+    if let Some(authorization) = &authorization {
+        headers.push(("Authorization", authorization));
+    }
+    let Some(text) = index::http_get(&url, &headers).map_err(|e| format!("{url}: {e}"))? else {
+        return Ok(Vec::new());
+    };
+    let releases: Vec<Value> = serde_json::from_str(&text).map_err(|e| format!("{url}: {e}"))?;
+    Ok(releases
+        .iter()
+        .filter_map(|release| {
+            let tag = release["tag_name"].as_str()?;
+            let version = find_version(tag)?;
+            let heading = release["name"]
+                .as_str()
+                .filter(|n| !n.is_empty())
+                .unwrap_or(tag)
+                .to_string();
+            let body = release["body"].as_str().unwrap_or_default().to_string();
+            Some((version, heading, body))
+        })
+        .collect())
+}
+
+/// 文字列に含まれる最初の semver 形式のバージョンを返す (`v1.2.3`、`[1.2.3]`、`crate-1.2.3` など)
+fn find_version(text: &str) -> Option<Version> {
+    text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+'))
+        .flat_map(|token| {
+            let bytes = token.as_bytes();
+            // `crate-1.2.3` や `v1.2.3` のような接頭辞を取り除いた候補を列挙する
+            (0..bytes.len())
+                .filter(move |&i| {
+                    bytes[i].is_ascii_digit() && (i == 0 || !bytes[i - 1].is_ascii_digit())
+                })
+                .map(move |i| token[i..].trim_end_matches('.'))
+        })
+        .find_map(|candidate| Version::parse(candidate).ok())
+}
+
+/// Markdown の変更履歴を、バージョンを含む見出しごとに (バージョン, 見出し, 本文) に分割する
+///
+/// 最初にバージョンを含んでいた見出しと同じ階層の見出しで区切り、それより深い見出しは本文に含める。
+fn split_sections(text: &str) -> Vec<(Version, String, String)> {
+    let mut sections: Vec<(Option<Version>, String, Vec<&str>)> = Vec::new();
+    let mut level = None;
+    for line in text.lines() {
+        let depth = line.chars().take_while(|c| *c == '#').count();
+        if depth > 0 && level.is_none_or(|l| depth <= l) {
+            let heading = line[depth..].trim().to_string();
+            match find_version(&heading) {
+                Some(version) => {
+                    level = Some(depth);
+                    sections.push((Some(version), heading, Vec::new()));
+                    continue;
+                }
+                // 同じ階層のバージョン以外の見出し (例: `## Unreleased`) で区切る
+                None if level == Some(depth) => {
+                    sections.push((None, heading, Vec::new()));
+                    continue;
+                }
+                None => {}
+            }
+        }
+        if let Some((_, _, lines)) = sections.last_mut() {
+            lines.push(line);
+        }
+    }
+    sections
+        .into_iter()
+        .filter_map(|(version, heading, lines)| {
+            Some((version?, heading, lines.join("\n").trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_sections() {
+        let text = "# Changelog\n\n## Unreleased\n- wip\n\n## [1.2.0] - 2024-01-01\n### Added\n- feature\n\n## v1.1.0\n- fix\n";
+        let sections = split_sections(text);
+        let versions: Vec<String> = sections.iter().map(|(v, _, _)| v.to_string()).collect();
+        assert_eq!(versions, ["1.2.0", "1.1.0"]);
+        assert_eq!(sections[0].1, "[1.2.0] - 2024-01-01");
+        assert_eq!(sections[0].2, "### Added\n- feature");
+
+        assert_eq!(
+            find_version("serde_json-v1.0.100"),
+            Some(Version::new(1, 0, 100))
+        );
+        assert_eq!(
+            github_repository("https://github.com/serde-rs/json.git"),
+            Some(("serde-rs".to_string(), "json".to_string()))
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use cargo_metadata::{Metadata, Package};
+use mcp_attr::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::detail::Detail;
+use crate::error::{ErrorKind, bail_kind};
+use crate::findings::FindingReport;
+use crate::graph::Graph;
+use crate::summary::{self, DirectDependency};
+use crate::{category, license, lint, native};
+
+/// 個別のリソースとして公開する分析レポートの名前と説明
+pub const REPORTS: &[(&str, &str)] = &[
+    (
+        "overview",
+        "Project overview (workspace shape, members, categories, risks)",
+    ),
+    (
+        "categories",
+        "Direct dependencies grouped by functional category",
+    ),
+    (
+        "overlaps",
+        "Dependencies that may provide overlapping functionality",
+    ),
+    ("licenses", "Normalized SPDX license of every package"),
+    ("native", "Packages that link native system libraries"),
+    ("lint", "Manifest lint findings for workspace members"),
+];
+
+/// 依存グラフ中の 1 つのパッケージのリソースの内容
+#[derive(Serialize)]
+pub struct PackageChunk {
+    pub name: String,
+    pub version: String,
+    pub id: String,
+    /// ワークスペースメンバーの場合は `None`
+    pub source: Option<String>,
+    pub workspace_member: bool,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub repository: Option<String>,
+    pub features: Vec<String>,
+    pub dependencies: Vec<DirectDependency>,
+    /// このパッケージに依存しているパッケージ (`name@version`)
+    pub dependents: Vec<String>,
+}
+
+/// パッケージのリソースの URI
+///
+/// `+` を含むビルドメタデータ付きのバージョンも 1 つのパスセグメントとして扱えるように、予約文字をエンコードする。
+pub fn package_uri(project: &str, package: &Package) -> String {
+    format!(
+        "cargo://{project}/crates/{}/{}",
+        package.name,
+        package.version.to_string().replace('+', "%2B")
+    )
+}
+
+pub fn report_uri(project: &str, report: &str) -> String {
+    format!("cargo://{project}/reports/{report}")
+}
+
+/// パッケージのリソースの内容を作成する
+pub fn package_chunk(metadata: &Metadata, package: &Package) -> PackageChunk {
+    let graph = Graph::new(metadata);
+    let mut dependents: Vec<String> = metadata
+        .resolve
+        .iter()
+        .flat_map(|r| &r.nodes)
+        .filter(|n| n.deps.iter().any(|d| d.pkg == package.id))
+        .map(|n| graph.label(&n.id))
+        .collect();
+    dependents.sort();
+    PackageChunk {
+        name: package.name.clone(),
+        version: package.version.to_string(),
+        id: package.id.repr.clone(),
+        source: package.source.as_ref().map(|s| s.repr.clone()),
+        workspace_member: metadata.workspace_members.contains(&package.id),
+        description: package.description.clone(),
+        license: package.license.clone(),
+        repository: package.repository.clone(),
+        features: package.features.keys().cloned().collect(),
+        dependencies: summary::direct_dependencies(&graph, package),
+        dependents,
+    }
+}
+
+/// 名前で指定した分析レポートを作成する
+pub fn report(metadata: &Metadata, name: &str) -> Result<Value> {
+    let value = match name {
+        "overview" => serde_json::to_value(summary::project_overview(metadata, Detail::Normal)),
+        "categories" => serde_json::to_value(category::category_map(metadata)),
+        "overlaps" => serde_json::to_value(category::overlap_report(metadata)),
+        "licenses" => serde_json::to_value(license::license_manifest(metadata)),
+        "native" => serde_json::to_value(native::native_dependency_report(metadata)),
+        "lint" => serde_json::to_value(FindingReport::new(&lint::lint_manifests(metadata))),
+        _ => {
+            let names: Vec<&str> = REPORTS.iter().map(|(name, _)| *name).collect();
+            bail_kind!(ErrorKind::UnknownReport, name, names.join(", "))
+        }
+    };
+    match value {
+        Ok(value) => Ok(value),
+        Err(e) => bail_kind!(ErrorKind::SerializeFailed, format!("report `{name}`"), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_uri() {
+        let package: Package = serde_json::from_value(serde_json::json!({
+            "name": "toml",
+            "version": "1.1.8+spec-1.1.0",
+            "id": "registry+https://github.com/rust-lang/crates.io-index#toml@1.1.8+spec-1.1.0",
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+            "manifest_path": "/toml/Cargo.toml",
+        }))
+        .unwrap();
+        assert_eq!(
+            package_uri("app", &package),
+            "cargo://app/crates/toml/1.1.8%2Bspec-1.1.0"
+        );
+        assert_eq!(report_uri("app", "lint"), "cargo://app/reports/lint");
+    }
+}
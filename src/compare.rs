@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo_metadata::Metadata;
+use cargo_metadata::semver::Version;
+use serde::Serialize;
+
+use crate::advisory::{self, DatabaseInfo};
+use crate::license::normalize_license;
+use crate::lockfile::{self, LockedPackage, Lockfile, LockfileChange};
+use crate::policy::compat_key;
+
+#[derive(Serialize)]
+pub struct ProjectComparison {
+    pub a: ProjectSummary,
+    pub b: ProjectSummary,
+    /// 両方のプロジェクトが依存しているクレート
+    pub shared: Vec<SharedDependency>,
+    pub only_in_a: Vec<CrateVersions>,
+    pub only_in_b: Vec<CrateVersions>,
+    pub licenses: LicenseDelta,
+    pub advisories: AdvisoryDelta,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advisories_error: Option<String>,
+    /// 検査に使用したアドバイザリデータベース
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advisory_database: Option<DatabaseInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ProjectSummary {
+    pub workspace_root: String,
+    pub members: Vec<String>,
+    /// ワークスペースメンバーを除くパッケージの数
+    pub dependency_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct SharedDependency {
+    pub name: String,
+    pub versions_a: Vec<String>,
+    pub versions_b: Vec<String>,
+    /// 使用しているバージョンが一致しないかどうか
+    pub version_mismatch: bool,
+    /// semver 互換なバージョンを共有していないかどうか (統合時に重複する)
+    pub incompatible: bool,
+}
+
+#[derive(Serialize)]
+pub struct CrateVersions {
+    pub name: String,
+    pub versions: Vec<String>,
+}
+
+/// 片方のプロジェクトの依存関係にのみ含まれるライセンスと、それを使用するクレート
+#[derive(Serialize)]
+pub struct LicenseDelta {
+    pub only_in_a: Vec<LicenseUsage>,
+    pub only_in_b: Vec<LicenseUsage>,
+}
+
+#[derive(Serialize)]
+pub struct LicenseUsage {
+    pub license: String,
+    pub crates: Vec<String>,
+}
+
+/// アドバイザリの ID と影響を受けるパッケージ (`RUSTSEC-XXXX-XXXX (name@version)`)
+#[derive(Serialize, Default)]
+pub struct AdvisoryDelta {
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub shared: Vec<String>,
+}
+
+/// ワークスペースメンバーを除く、クレート名ごとの使用中のバージョン
+fn dependencies(metadata: &Metadata) -> BTreeMap<&str, BTreeSet<&Version>> {
+    let mut dependencies: BTreeMap<&str, BTreeSet<&Version>> = BTreeMap::new();
+    for package in &metadata.packages {
+        if !metadata.workspace_members.contains(&package.id) {
+            dependencies
+                .entry(&package.name)
+                .or_default()
+                .insert(&package.version);
+        }
+    }
+    dependencies
+}
+
+/// ライセンスごとの、そのライセンスを使用するクレート (`name@version`)
+fn licenses(metadata: &Metadata) -> BTreeMap<String, BTreeSet<String>> {
+    let mut licenses: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for package in &metadata.packages {
+        if metadata.workspace_members.contains(&package.id) {
+            continue;
+        }
+        let ids = match &package.license {
+            Some(license) => normalize_license(license).license_ids,
+            None => vec!["(none)".to_string()],
+        };
+        for id in ids {
+            licenses
+                .entry(id)
+                .or_default()
+                .insert(format!("{}@{}", package.name, package.version));
+        }
+    }
+    licenses
+}
+
+fn versions(versions: &BTreeSet<&Version>) -> Vec<String> {
+    versions.iter().map(|v| v.to_string()).collect()
+}
+
+fn summary(metadata: &Metadata) -> ProjectSummary {
+    ProjectSummary {
+        workspace_root: metadata.workspace_root.to_string(),
+        members: metadata
+            .workspace_packages()
+            .iter()
+            .map(|p| p.name.clone())
+            .collect(),
+        dependency_count: metadata
+            .packages
+            .iter()
+            .filter(|p| !metadata.workspace_members.contains(&p.id))
+            .count(),
+    }
+}
+
+/// 2 つのプロジェクトの依存関係、ライセンス、アドバイザリを比較する
+pub fn compare_projects(a: &Metadata, b: &Metadata) -> ProjectComparison {
+    let (deps_a, deps_b) = (dependencies(a), dependencies(b));
+    let mut shared = Vec::new();
+    let mut only_in_a = Vec::new();
+    for (name, versions_a) in &deps_a {
+        match deps_b.get(name) {
+            Some(versions_b) => {
+                let keys_a: BTreeSet<String> = versions_a.iter().map(|v| compat_key(v)).collect();
+                shared.push(SharedDependency {
+                    name: name.to_string(),
+                    versions_a: versions(versions_a),
+                    versions_b: versions(versions_b),
+                    version_mismatch: versions_a != versions_b,
+                    incompatible: !versions_b.iter().any(|v| keys_a.contains(&compat_key(v))),
+                });
+            }
+            None => only_in_a.push(CrateVersions {
+                name: name.to_string(),
+                versions: versions(versions_a),
+            }),
+        }
+    }
+    let only_in_b = deps_b
+        .iter()
+        .filter(|(name, _)| !deps_a.contains_key(*name))
+        .map(|(name, v)| CrateVersions {
+            name: name.to_string(),
+            versions: versions(v),
+        })
+        .collect();
+
+    let (licenses_a, licenses_b) = (licenses(a), licenses(b));
+    let only = |x: &BTreeMap<String, BTreeSet<String>>, y: &BTreeMap<String, BTreeSet<String>>| {
+        x.iter()
+            .filter(|(license, _)| !y.contains_key(*license))
+            .map(|(license, crates)| LicenseUsage {
+                license: license.clone(),
+                crates: crates.iter().cloned().collect(),
+            })
+            .collect()
+    };
+    let licenses = LicenseDelta {
+        only_in_a: only(&licenses_a, &licenses_b),
+        only_in_b: only(&licenses_b, &licenses_a),
+    };
+
+    let (advisories, advisories_error, advisory_database) = match advisory::load_database() {
+        Ok(database) => {
+            let ids = |metadata: &Metadata| -> BTreeSet<String> {
+                advisory::find_advisories(metadata, &database.db)
+                    .into_iter()
+                    .map(|f| format!("{} ({}@{})", f.id, f.package, f.version))
+                    .collect()
+            };
+            let (ids_a, ids_b) = (ids(a), ids(b));
+            let delta = AdvisoryDelta {
+                only_in_a: ids_a.difference(&ids_b).cloned().collect(),
+                only_in_b: ids_b.difference(&ids_a).cloned().collect(),
+                shared: ids_a.intersection(&ids_b).cloned().collect(),
+            };
+            (delta, None, Some(database.info))
+        }
+        Err(e) => (
+            AdvisoryDelta::default(),
+            Some(e.to_error_object(false).message),
+            None,
+        ),
+    };
+
+    ProjectComparison {
+        a: summary(a),
+        b: summary(b),
+        shared,
+        only_in_a,
+        only_in_b,
+        licenses,
+        advisories,
+        advisories_error,
+        advisory_database,
+    }
+}
+
+/// 2 つの時点のメタデータの差分
+#[derive(Serialize)]
+pub struct MetadataDiff {
+    pub members_added: Vec<String>,
+    pub members_removed: Vec<String>,
+    /// パッケージごとの追加、削除、アップグレード、ダウングレード
+    pub changes: Vec<LockfileChange>,
+    /// 両方にある同じバージョンのパッケージの、有効なフィーチャーの変化
+    pub feature_changes: Vec<FeatureChange>,
+}
+
+#[derive(Serialize)]
+pub struct FeatureChange {
+    /// `name@version`
+    pub package: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// メタデータのパッケージを、ロックファイルの差分と同じ方法で比べるためのロックファイル
+fn as_lockfile(metadata: &Metadata) -> Lockfile {
+    Lockfile {
+        version: None,
+        packages: metadata
+            .packages
+            .iter()
+            .map(|p| LockedPackage {
+                name: p.name.clone(),
+                version: p.version.to_string(),
+                source: p.source.as_ref().map(|s| s.repr.clone()),
+                checksum: None,
+                dependencies: Vec::new(),
+            })
+            .collect(),
+    }
+}
+
+/// パッケージ (`name@version`) ごとの、resolve グラフで有効になったフィーチャー
+fn enabled_features(metadata: &Metadata) -> BTreeMap<String, BTreeSet<&String>> {
+    metadata
+        .resolve
+        .iter()
+        .flat_map(|r| &r.nodes)
+        .filter_map(|node| {
+            let package = metadata.packages.iter().find(|p| p.id == node.id)?;
+            Some((
+                format!("{}@{}", package.name, package.version),
+                node.features.iter().collect(),
+            ))
+        })
+        .collect()
+}
+
+fn member_names(metadata: &Metadata) -> BTreeSet<String> {
+    metadata
+        .workspace_packages()
+        .iter()
+        .map(|p| p.name.clone())
+        .collect()
+}
+
+/// 変更前と変更後のメタデータの、メンバー、パッケージとバージョン、有効なフィーチャーの差分
+pub fn diff_metadata(before: &Metadata, after: &Metadata) -> MetadataDiff {
+    let (members_before, members_after) = (member_names(before), member_names(after));
+    let (features_before, features_after) = (enabled_features(before), enabled_features(after));
+    let feature_changes = features_before
+        .iter()
+        .filter_map(|(package, old)| {
+            let new = features_after.get(package)?;
+            let added: Vec<String> = new.difference(old).map(|f| f.to_string()).collect();
+            let removed: Vec<String> = old.difference(new).map(|f| f.to_string()).collect();
+            (!added.is_empty() || !removed.is_empty()).then(|| FeatureChange {
+                package: package.clone(),
+                added,
+                removed,
+            })
+        })
+        .collect();
+    MetadataDiff {
+        members_added: members_after.difference(&members_before).cloned().collect(),
+        members_removed: members_before.difference(&members_after).cloned().collect(),
+        changes: lockfile::diff(&as_lockfile(before), &as_lockfile(after)),
+        feature_changes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::semver::Version;
+
+    use super::*;
+    use crate::lockfile::ChangeKind;
+
+    #[test]
+    fn test_diff_metadata() {
+        let before: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let mut after = before.clone();
+        after.packages[1].version = Version::new(0, 2, 0);
+        after.resolve.as_mut().unwrap().nodes[0].features = vec!["cli".to_string()];
+
+        let diff = diff_metadata(&before, &after);
+        assert!(diff.members_added.is_empty() && diff.members_removed.is_empty());
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].name, "fixture-core");
+        assert_eq!(diff.changes[0].kind, ChangeKind::Upgraded);
+        assert_eq!(diff.feature_changes.len(), 1);
+        assert_eq!(diff.feature_changes[0].package, "app@0.2.0");
+        assert_eq!(diff.feature_changes[0].removed, ["default"]);
+    }
+}
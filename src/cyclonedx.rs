@@ -0,0 +1,315 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use cargo_metadata::{Metadata, Package, PackageId};
+use serde_json::{Value, json};
+
+use crate::advisory::{AdvisoryFinding, DatabaseInfo};
+use crate::graph::Graph;
+use crate::license::normalize_license;
+use crate::sbom::{checksum, lockfile_checksums, sbom_packages};
+
+const SPEC_VERSION: &str = "1.5";
+
+/// パッケージの Package URL (`pkg:cargo/name@version`)
+pub fn purl(package: &Package) -> String {
+    format!("pkg:cargo/{}@{}", package.name, package.version)
+}
+
+fn tool_metadata(metadata: &Metadata) -> Value {
+    let mut value = json!({
+        "timestamp": jiff::Timestamp::now().to_string(),
+        "tools": {
+            "components": [{
+                "type": "application",
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            }]
+        },
+    });
+    if let Some(root) = metadata.root_package() {
+        value["component"] = json!({
+            "type": "application",
+            "bom-ref": purl(root),
+            "name": root.name,
+            "version": root.version.to_string(),
+            "purl": purl(root),
+        });
+    }
+    value
+}
+
+/// resolve グラフから CycloneDX 1.5 の BOM ドキュメントを生成する
+///
+/// ルートパッケージは `metadata.component` に、それ以外のパッケージは `components` に含める。
+/// dev-dependencies からしか到達できないパッケージは、スコープを `excluded` にする。
+pub fn bom_document(metadata: &Metadata, include_dev: bool) -> Value {
+    let graph = Graph::new(metadata);
+    let packages = sbom_packages(&graph, include_dev);
+    let checksums = lockfile_checksums(metadata);
+    let mut required: HashSet<&PackageId> = HashSet::new();
+    for member in graph.members() {
+        required.extend(graph.reachable(member, false));
+    }
+
+    // 同じ名前とバージョンのパッケージが複数のソースにある場合は、パッケージ ID で区別する
+    let mut refs: HashMap<&PackageId, String> = HashMap::new();
+    let mut used: HashSet<String> = HashSet::new();
+    for package in &packages {
+        let mut bom_ref = purl(package);
+        if !used.insert(bom_ref.clone()) {
+            bom_ref = package.id.repr.clone();
+        }
+        refs.insert(&package.id, bom_ref);
+    }
+
+    let root = metadata.root_package().map(|p| &p.id);
+    let components: Vec<Value> = packages
+        .iter()
+        .filter(|p| Some(&p.id) != root)
+        .map(|package| {
+            let mut value = json!({
+                "type": "library",
+                "bom-ref": refs[&package.id],
+                "name": package.name,
+                "version": package.version.to_string(),
+                "scope": if required.contains(&package.id) { "required" } else { "excluded" },
+                "purl": purl(package),
+            });
+            if let Some(description) = &package.description {
+                value["description"] = json!(description.trim());
+            }
+            if let Some(checksum) = checksum(&checksums, package) {
+                value["hashes"] = json!([{ "alg": "SHA-256", "content": checksum }]);
+            }
+            if let Some(license) = package.license.as_deref() {
+                value["licenses"] = match normalize_license(license) {
+                    l if l.spdx_valid => json!([{ "expression": l.spdx }]),
+                    _ => json!([{ "license": { "name": license } }]),
+                };
+            }
+            let mut references = Vec::new();
+            if let Some(url) = &package.repository {
+                references.push(json!({ "type": "vcs", "url": url }));
+            }
+            if let Some(url) = &package.homepage {
+                references.push(json!({ "type": "website", "url": url }));
+            }
+            if let Some(url) = &package.documentation {
+                references.push(json!({ "type": "documentation", "url": url }));
+            }
+            if !references.is_empty() {
+                value["externalReferences"] = json!(references);
+            }
+            value
+        })
+        .collect();
+
+    let dependencies: Vec<Value> = packages
+        .iter()
+        .map(|package| {
+            let mut depends_on: Vec<&String> = graph
+                .edges(&package.id, include_dev)
+                .filter_map(|edge| refs.get(&edge.pkg))
+                .collect();
+            depends_on.sort();
+            depends_on.dedup();
+            json!({ "ref": refs[&package.id], "dependsOn": depends_on })
+        })
+        .collect();
+
+    let mut tool_metadata = tool_metadata(metadata);
+    if let Some(root) = root.and_then(|id| graph.package(id)) {
+        tool_metadata["component"]["bom-ref"] = json!(refs[&root.id]);
+        if let Some(license) = root.license.as_deref().map(normalize_license)
+            && license.spdx_valid
+        {
+            tool_metadata["component"]["licenses"] = json!([{ "expression": license.spdx }]);
+        }
+    }
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": SPEC_VERSION,
+        "version": 1,
+        "metadata": tool_metadata,
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+/// アドバイザリの検出結果から CycloneDX VEX ドキュメントを生成する
+///
+/// 脆弱性と unsound のアドバイザリのみを対象とし、それ以外の informational advisory は含めない。
+/// 検査に使用したアドバイザリデータベースの更新日時は、metadata の properties に記録する。
+pub fn vex_document(
+    metadata: &Metadata,
+    findings: &[AdvisoryFinding],
+    database: &DatabaseInfo,
+) -> Value {
+    let mut components = BTreeMap::new();
+    let mut vulnerabilities = Vec::new();
+
+    for finding in findings
+        .iter()
+        .filter(|f| f.kind == "vulnerability" || f.kind == "unsound")
+    {
+        let Some(package) = metadata
+            .packages
+            .iter()
+            .find(|p| p.id.repr == finding.package_id)
+        else {
+            continue;
+        };
+        let bom_ref = purl(package);
+        components.entry(bom_ref.clone()).or_insert_with(|| {
+            json!({
+                "type": "library",
+                "bom-ref": bom_ref,
+                "name": package.name,
+                "version": package.version.to_string(),
+                "purl": bom_ref,
+            })
+        });
+
+        let path = finding.dependency_path.join(" -> ");
+        let (status, analysis) = if finding.excludes_host_platform() {
+            (
+                "unaffected",
+                json!({
+                    "state": "not_affected",
+                    "justification": "requires_environment",
+                    "detail": format!(
+                        "Only affects os={:?} arch={:?}; analyzed on {}/{}. Dependency path: {}",
+                        finding.affected_os,
+                        finding.affected_arch,
+                        std::env::consts::OS,
+                        std::env::consts::ARCH,
+                        path
+                    ),
+                }),
+            )
+        } else if finding.dev_only {
+            (
+                "unaffected",
+                json!({
+                    "state": "not_affected",
+                    "justification": "code_not_reachable",
+                    "detail": format!("Only reachable through dev-dependencies: {path}"),
+                }),
+            )
+        } else {
+            (
+                "affected",
+                json!({
+                    "state": "in_triage",
+                    "detail": format!("Dependency path: {path}"),
+                }),
+            )
+        };
+
+        let mut vulnerability = json!({
+            "bom-ref": format!("{}/{}", finding.id, bom_ref),
+            "id": finding.id,
+            "source": {
+                "name": "RustSec",
+                "url": finding.url,
+            },
+            "description": finding.title,
+            "detail": finding.description,
+            "published": format!("{}T00:00:00Z", finding.date),
+            "affects": [{
+                "ref": bom_ref,
+                "versions": [{ "version": finding.version, "status": status }],
+            }],
+            "analysis": analysis,
+        });
+        if let Some(vector) = &finding.cvss {
+            let method = if vector.starts_with("CVSS:4") {
+                "CVSSv4"
+            } else if vector.starts_with("CVSS:3.1") {
+                "CVSSv31"
+            } else {
+                "CVSSv3"
+            };
+            vulnerability["ratings"] = json!([{
+                "source": { "name": "RustSec" },
+                "method": method,
+                "vector": vector,
+                "score": finding.cvss_score,
+                "severity": finding.severity,
+            }]);
+        }
+        if !finding.aliases.is_empty() {
+            vulnerability["references"] = finding
+                .aliases
+                .iter()
+                .map(|alias| json!({ "id": alias, "source": { "name": alias_source(alias) } }))
+                .collect();
+        }
+        if !finding.patched_versions.is_empty() {
+            vulnerability["recommendation"] = json!(format!(
+                "Upgrade {} to a version matching {}",
+                finding.package,
+                finding.patched_versions.join(" or ")
+            ));
+        }
+        vulnerabilities.push(vulnerability);
+    }
+
+    let mut tool_metadata = tool_metadata(metadata);
+    let properties: Vec<Value> = [
+        ("rustsec:advisory-db:last-updated", &database.last_updated),
+        ("rustsec:advisory-db:update-error", &database.update_error),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some(json!({ "name": name, "value": value.as_ref()? })))
+    .collect();
+    if !properties.is_empty() {
+        tool_metadata["properties"] = json!(properties);
+    }
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": SPEC_VERSION,
+        "version": 1,
+        "metadata": tool_metadata,
+        "components": components.into_values().collect::<Vec<_>>(),
+        "vulnerabilities": vulnerabilities,
+    })
+}
+
+fn alias_source(alias: &str) -> &'static str {
+    if alias.starts_with("CVE-") {
+        "NVD"
+    } else if alias.starts_with("GHSA-") {
+        "GitHub"
+    } else {
+        "RustSec"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bom_document() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let document = bom_document(&metadata, false);
+        assert_eq!(document["bomFormat"], "CycloneDX");
+        assert_eq!(document["specVersion"], "1.5");
+        let components = document["components"].as_array().unwrap();
+        assert_eq!(components.len(), 2);
+        let core = components
+            .iter()
+            .find(|c| c["name"] == "fixture-core")
+            .unwrap();
+        assert_eq!(core["purl"], "pkg:cargo/fixture-core@0.1.0");
+        assert_eq!(core["scope"], "required");
+        assert_eq!(core["licenses"][0]["expression"], "MIT OR Apache-2.0");
+        let dependencies = document["dependencies"].as_array().unwrap();
+        assert!(dependencies.contains(&json!({
+            "ref": "pkg:cargo/app@0.2.0",
+            "dependsOn": ["pkg:cargo/fixture-core@0.1.0"],
+        })));
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use cargo_metadata::semver::VersionReq;
+use cargo_metadata::{Metadata, Package};
+use mcp_attr::Result;
+use serde::Deserialize;
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::findings::{self, Finding, FindingSet, Level};
+use crate::graph::Graph;
+use crate::license::{self, LicensePolicy};
+use crate::policy::CrateEntry;
+
+/// 設定ファイルを省略した場合に、ワークスペースルートから順に探すファイル名 (cargo-deny と同じ)
+pub const DEFAULT_CONFIG_FILES: &[&str] = &["deny.toml", ".deny.toml", ".cargo/deny.toml"];
+
+/// crates.io のインデックスの URL (git とスパースプロトコル)
+const CRATES_IO_INDEXES: &[&str] = &[
+    "https://github.com/rust-lang/crates.io-index",
+    "https://index.crates.io/",
+];
+
+/// cargo-deny の設定ファイル (`deny.toml`) のうち、`licenses`、`bans`、`sources` のチェックに使う部分
+///
+/// cargo-deny の他の設定 (`advisories`、`graph` など) は無視する。
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct DenyConfig {
+    #[serde(default)]
+    pub licenses: Licenses,
+    #[serde(default)]
+    pub bans: Bans,
+    #[serde(default)]
+    pub sources: Sources,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct Licenses {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// 古い cargo-deny の拒否リスト
+    #[serde(default)]
+    pub deny: Vec<String>,
+    #[serde(default)]
+    pub exceptions: Vec<LicenseException>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LicenseException {
+    /// クレート名 (古い cargo-deny では `name`)
+    #[serde(rename = "crate", alias = "name")]
+    pub krate: String,
+    pub allow: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct Bans {
+    /// 同じクレートの複数のバージョンを見つけた場合の扱い。省略時は `warn`
+    #[serde(default)]
+    pub multiple_versions: LintLevel,
+    #[serde(default)]
+    pub deny: Vec<PackageSpec>,
+    /// 空でない場合、ここに含まれるクレートだけを許可する
+    #[serde(default)]
+    pub allow: Vec<PackageSpec>,
+    /// `multiple-versions` の対象から除くクレート
+    #[serde(default)]
+    pub skip: Vec<PackageSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Sources {
+    /// 許可されていないレジストリのパッケージを見つけた場合の扱い。省略時は `warn`
+    #[serde(default)]
+    pub unknown_registry: LintLevel,
+    /// 許可されていない git リポジトリのパッケージを見つけた場合の扱い。省略時は `warn`
+    #[serde(default)]
+    pub unknown_git: LintLevel,
+    /// 許可するレジストリのインデックスの URL。省略時は crates.io のみ
+    #[serde(default = "default_registries")]
+    pub allow_registry: Vec<String>,
+    #[serde(default)]
+    pub allow_git: Vec<String>,
+    /// ホスティングサービスごとに、許可する組織 (`github = ["rust-lang"]` など)
+    #[serde(default)]
+    pub allow_org: BTreeMap<String, Vec<String>>,
+}
+
+fn default_registries() -> Vec<String> {
+    vec![CRATES_IO_INDEXES[0].to_string()]
+}
+
+impl Default for Sources {
+    fn default() -> Self {
+        Self {
+            unknown_registry: LintLevel::default(),
+            unknown_git: LintLevel::default(),
+            allow_registry: default_registries(),
+            allow_git: Vec::new(),
+            allow_org: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    #[default]
+    Warn,
+    Deny,
+}
+
+impl LintLevel {
+    fn level(self) -> Option<Level> {
+        match self {
+            LintLevel::Allow => None,
+            LintLevel::Warn => Some(Level::Warning),
+            LintLevel::Deny => Some(Level::Error),
+        }
+    }
+}
+
+/// cargo-deny のクレートの指定 (`"name"`、`"name@version"`、`{ crate = "name@version" }`、`{ name = "name", version = "..." }`)
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum PackageSpec {
+    Spec(String),
+    Table {
+        #[serde(rename = "crate", alias = "name")]
+        krate: String,
+        version: Option<String>,
+        reason: Option<String>,
+    },
+}
+
+impl PackageSpec {
+    /// バージョンは semver の要求バージョンとして扱う
+    fn to_entry(&self) -> Result<CrateEntry> {
+        let (spec, version, reason) = match self {
+            PackageSpec::Spec(spec) => (spec, None, None),
+            PackageSpec::Table {
+                krate,
+                version,
+                reason,
+            } => (krate, version.as_deref(), reason.clone()),
+        };
+        let (name, version) = match spec.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (spec.as_str(), version),
+        };
+        let version = match version.map(str::parse::<VersionReq>).transpose() {
+            Ok(version) => version,
+            Err(e) => bail_kind!(ErrorKind::InvalidPolicy, format!("`{spec}`: {e}")),
+        };
+        Ok(CrateEntry {
+            name: name.to_string(),
+            version,
+            reason,
+        })
+    }
+}
+
+impl DenyConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => bail_kind!(ErrorKind::ReadFailed, path.display(), e),
+        };
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        match toml::from_str(text) {
+            Ok(config) => Ok(config),
+            Err(e) => bail_kind!(ErrorKind::InvalidPolicy, e),
+        }
+    }
+}
+
+/// ワークスペースルートにある cargo-deny の設定ファイル (見つからない場合は `deny.toml` のパス)
+pub fn default_config_path(metadata: &Metadata) -> PathBuf {
+    let root = metadata.workspace_root.as_std_path();
+    DEFAULT_CONFIG_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| root.join(DEFAULT_CONFIG_FILES[0]))
+}
+
+/// URL の比較に使う形 (末尾の `/` と `.git` を除いたもの)
+fn normalize_url(url: &str) -> &str {
+    let url = url.trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url)
+}
+
+/// ソースが許可されていない場合のルールと説明
+fn check_source<'a>(sources: &Sources, source: &'a str) -> Option<(&'static str, &'a str)> {
+    if let Some(index) = source
+        .strip_prefix("registry+")
+        .or_else(|| source.strip_prefix("sparse+"))
+    {
+        let is_crates_io = CRATES_IO_INDEXES.iter().any(|url| url == &index);
+        let allowed = sources.allow_registry.iter().any(|allowed| {
+            normalize_url(allowed) == normalize_url(index)
+                || (is_crates_io && CRATES_IO_INDEXES.contains(&allowed.as_str()))
+        });
+        return (!allowed).then_some(("sources/unknown-registry", index));
+    }
+    if let Some(url) = source.strip_prefix("git+") {
+        let url = url.split(['?', '#']).next().unwrap_or(url);
+        let allowed = sources
+            .allow_git
+            .iter()
+            .any(|allowed| normalize_url(allowed) == normalize_url(url))
+            || sources.allow_org.iter().any(|(host, orgs)| {
+                let host = match host.as_str() {
+                    "github" => "github.com",
+                    "gitlab" => "gitlab.com",
+                    "bitbucket" => "bitbucket.org",
+                    host => host,
+                };
+                orgs.iter()
+                    .any(|org| url.starts_with(&format!("https://{host}/{org}/")))
+            });
+        return (!allowed).then_some(("sources/unknown-git", url));
+    }
+    None
+}
+
+/// cargo-deny の設定の `licenses`、`bans`、`sources` のチェックを、ワークスペースメンバー以外の全パッケージに対して行う
+pub fn evaluate(metadata: &Metadata, config: &DenyConfig) -> Result<FindingSet> {
+    let graph = Graph::new(metadata);
+    let mut set = FindingSet::new("cargo-deny");
+    let dependencies: Vec<&Package> = metadata
+        .packages
+        .iter()
+        .filter(|p| !metadata.workspace_members.contains(&p.id))
+        .collect();
+    let push = |set: &mut FindingSet, rule: &str, level, message: String, package: &Package| {
+        let (file, line) = findings::lockfile_location(metadata, package);
+        set.push(Finding {
+            rule: rule.to_string(),
+            level,
+            message,
+            package: Some(format!("{}@{}", package.name, package.version)),
+            file: Some(file),
+            line,
+            dependency_path: graph.path_labels(&package.id, true),
+        });
+    };
+
+    let licenses = &config.licenses;
+    let mut exceptions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for exception in &licenses.exceptions {
+        exceptions
+            .entry(exception.krate.clone())
+            .or_default()
+            .extend(exception.allow.iter().cloned());
+    }
+    set.extend(license::check_license_policy(
+        metadata,
+        &LicensePolicy {
+            allow: licenses.allow.clone(),
+            deny: licenses.deny.clone(),
+            exceptions,
+            preset: None,
+        },
+    ));
+
+    let bans = &config.bans;
+    let deny = entries(&bans.deny)?;
+    let allow = entries(&bans.allow)?;
+    let skip = entries(&bans.skip)?;
+    set.rule(
+        "bans/denied",
+        "Dependencies must not match an entry of `bans.deny`",
+        Some("https://embarkstudios.github.io/cargo-deny/checks/bans/cfg.html#the-deny-field-optional"),
+    );
+    set.rule(
+        "bans/not-allowed",
+        "Every dependency must match an entry of `bans.allow` when it is not empty",
+        Some("https://embarkstudios.github.io/cargo-deny/checks/bans/cfg.html#the-allow-field-optional"),
+    );
+    set.rule(
+        "bans/multiple-versions",
+        "A crate should not appear in multiple versions",
+        Some("https://embarkstudios.github.io/cargo-deny/checks/bans/cfg.html#the-multiple-versions-field-optional"),
+    );
+    let mut versions: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+    for package in &dependencies {
+        let label = format!("{}@{}", package.name, package.version);
+        if let Some(entry) = deny.iter().find(|e| e.matches(package)) {
+            let message = format!("{label} matches the `bans.deny` entry {}", entry.describe());
+            push(&mut set, "bans/denied", Level::Error, message, package);
+        } else if !allow.is_empty() && !allow.iter().any(|e| e.matches(package)) {
+            let message = format!("{label} is not in `bans.allow`");
+            push(&mut set, "bans/not-allowed", Level::Error, message, package);
+        }
+        if !skip.iter().any(|e| e.matches(package)) {
+            versions.entry(&package.name).or_default().push(package);
+        }
+    }
+    if let Some(level) = bans.multiple_versions.level() {
+        for (name, mut packages) in versions {
+            if packages.len() < 2 {
+                continue;
+            }
+            packages.sort_by(|a, b| a.version.cmp(&b.version));
+            let list: Vec<String> = packages.iter().map(|p| p.version.to_string()).collect();
+            for package in packages {
+                let message = format!("{name} appears in multiple versions ({})", list.join(", "));
+                push(&mut set, "bans/multiple-versions", level, message, package);
+            }
+        }
+    }
+
+    let sources = &config.sources;
+    set.rule(
+        "sources/unknown-registry",
+        "Dependencies must come from a registry listed in `sources.allow-registry`",
+        Some("https://embarkstudios.github.io/cargo-deny/checks/sources/cfg.html#the-unknown-registry-field-optional"),
+    );
+    set.rule(
+        "sources/unknown-git",
+        "Git dependencies must come from a repository listed in `sources.allow-git` or `sources.allow-org`",
+        Some("https://embarkstudios.github.io/cargo-deny/checks/sources/cfg.html#the-unknown-git-field-optional"),
+    );
+    for package in &dependencies {
+        let Some(source) = &package.source else {
+            continue;
+        };
+        let Some((rule, url)) = check_source(sources, &source.repr) else {
+            continue;
+        };
+        let (lint, kind) = match rule {
+            "sources/unknown-registry" => (sources.unknown_registry, "registry"),
+            _ => (sources.unknown_git, "git repository"),
+        };
+        if let Some(level) = lint.level() {
+            let message = format!(
+                "{}@{} comes from the {kind} `{url}`, which is not allowed",
+                package.name, package.version
+            );
+            push(&mut set, rule, level, message, package);
+        }
+    }
+    Ok(set)
+}
+
+fn entries(specs: &[PackageSpec]) -> Result<Vec<CrateEntry>> {
+    specs.iter().map(PackageSpec::to_entry).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_check_source() {
+        let sources = DenyConfig::parse(
+            r#"
+[sources]
+allow-git = ["https://github.com/example/allowed.git"]
+allow-org = { github = ["rust-lang"] }
+"#,
+        )
+        .unwrap()
+        .sources;
+        let check = |source: &str| check_source(&sources, source).map(|(rule, _)| rule);
+        assert_eq!(
+            check("registry+https://github.com/rust-lang/crates.io-index"),
+            None
+        );
+        assert_eq!(check("sparse+https://index.crates.io/"), None);
+        assert_eq!(
+            check("registry+https://example.com/index"),
+            Some("sources/unknown-registry")
+        );
+        assert_eq!(
+            check("git+https://github.com/example/allowed?rev=abc#abcdef"),
+            None
+        );
+        assert_eq!(check("git+https://github.com/rust-lang/cargo#abcdef"), None);
+        assert_eq!(
+            check("git+https://github.com/other/repo#abcdef"),
+            Some("sources/unknown-git")
+        );
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let config = DenyConfig::parse(
+            r#"
+[licenses]
+allow = ["MIT", "Apache-2.0"]
+exceptions = [{ crate = "unicode-ident", allow = ["Unicode-3.0"] }]
+
+[bans]
+multiple-versions = "deny"
+deny = ["serde_json", { crate = "serde@<1" }]
+skip = [{ name = "syn", version = "2" }]
+
+[advisories]
+ignore = ["RUSTSEC-2099-0001"]
+"#,
+        )
+        .unwrap();
+        let set = evaluate(&metadata, &config).unwrap();
+        let find = |package: &str, rule: &str| {
+            set.findings.iter().any(|f| {
+                f.rule == rule && f.package.as_ref().is_some_and(|p| p.starts_with(package))
+            })
+        };
+        assert!(find("serde_json@", "bans/denied"));
+        assert!(!find("serde@", "bans/denied"));
+        assert!(!find("unicode-ident@", "license/not-allowed"));
+        assert!(!find("syn@2.", "bans/multiple-versions"));
+        assert!(!set.findings.iter().any(|f| f.rule.starts_with("sources/")));
+
+        assert!(DenyConfig::parse("[bans]\nmultiple-versions = \"sometimes\"").is_err());
+        let config = DenyConfig::parse("[bans]\ndeny = [\"serde@not-a-version\"]").unwrap();
+        assert!(evaluate(&metadata, &config).is_err());
+    }
+}
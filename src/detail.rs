@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use mcp_attr::Result;
+use serde_json::{Map, Value};
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// ツールが返す情報の詳細さ
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Detail {
+    Summary,
+    Normal,
+    Full,
+}
+
+impl Detail {
+    /// 詳細さを解析する (省略時は `default`)
+    pub fn parse(detail: Option<&str>, default: Self) -> Result<Self> {
+        match detail {
+            None => Ok(default),
+            Some("summary") => Ok(Self::Summary),
+            Some("normal") => Ok(Self::Normal),
+            Some("full") => Ok(Self::Full),
+            Some(other) => bail_kind!(ErrorKind::UnknownDetailLevel, other),
+        }
+    }
+
+    /// この詳細さで返す一覧の要素数の上限 (`None` は無制限)
+    pub fn limit(self, summary: usize, normal: usize) -> Option<usize> {
+        match self {
+            Self::Summary => Some(summary),
+            Self::Normal => Some(normal),
+            Self::Full => None,
+        }
+    }
+
+    /// `apply` で切り詰める配列の要素数の上限 (`None` は無制限)
+    pub fn max_items(self) -> Option<usize> {
+        self.limits().map(|limits| limits.items)
+    }
+
+    fn limits(self) -> Option<Limits> {
+        match self {
+            Self::Full => None,
+            Self::Normal => Some(Limits {
+                depth: 4,
+                items: 50,
+            }),
+            Self::Summary => Some(Limits {
+                depth: 2,
+                items: 10,
+            }),
+        }
+    }
+
+    /// ツールの出力の JSON を、詳細さに応じて切り詰める
+    ///
+    /// `summary` と `normal` では、null のフィールドを除き、オブジェクトの入れ子の深さと配列の要素数を制限する。
+    /// 省略した要素は、パス (`packages[].dependencies` など) ごとの数として `_omitted` に記録する。
+    /// トップレベルが配列で要素を省略した場合は、`items` と `_omitted` を持つオブジェクトで包む。
+    pub fn apply(self, value: Value) -> Value {
+        let Some(limits) = self.limits() else {
+            return value;
+        };
+        let mut omitted = BTreeMap::new();
+        let root = if value.is_array() { "items" } else { "" };
+        let Some(value) = limits.prune(value, 1, root, &mut omitted) else {
+            return Value::Null;
+        };
+        if omitted.is_empty() {
+            return value;
+        }
+        let omitted = Value::Object(
+            omitted
+                .into_iter()
+                .map(|(path, count)| (path, Value::from(count)))
+                .collect(),
+        );
+        match value {
+            Value::Object(mut map) => {
+                map.insert("_omitted".to_string(), omitted);
+                Value::Object(map)
+            }
+            value => {
+                let mut map = Map::new();
+                map.insert("items".to_string(), value);
+                map.insert("_omitted".to_string(), omitted);
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+struct Limits {
+    /// オブジェクトの入れ子の深さの上限 (トップレベルのオブジェクトが 1)
+    depth: usize,
+    /// 配列の要素数の上限
+    items: usize,
+}
+
+impl Limits {
+    /// 値を制限内に切り詰める (値全体が深さの上限を超える場合は `None`)
+    fn prune(
+        &self,
+        value: Value,
+        depth: usize,
+        path: &str,
+        omitted: &mut BTreeMap<String, usize>,
+    ) -> Option<Value> {
+        match value {
+            Value::Object(map) => {
+                if depth > self.depth {
+                    return None;
+                }
+                let mut pruned = Map::new();
+                for (key, child) in map {
+                    if child.is_null() {
+                        continue;
+                    }
+                    let child_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    let count = match &child {
+                        Value::Array(items) => items.len(),
+                        _ => 1,
+                    };
+                    match self.prune(child, depth + 1, &child_path, omitted) {
+                        Some(child) => {
+                            pruned.insert(key, child);
+                        }
+                        None => *omitted.entry(child_path).or_default() += count,
+                    }
+                }
+                Some(Value::Object(pruned))
+            }
+            Value::Array(mut items) => {
+                if items.len() > self.items {
+                    *omitted.entry(path.to_string()).or_default() += items.len() - self.items;
+                    items.truncate(self.items);
+                }
+                let item_path = format!("{path}[]");
+                let mut pruned = Vec::with_capacity(items.len());
+                for item in items {
+                    pruned.push(self.prune(item, depth, &item_path, omitted)?);
+                }
+                Some(Value::Array(pruned))
+            }
+            value => Some(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply() {
+        let value = json!({
+            "name": "app",
+            "license": null,
+            "packages": [
+                { "name": "a", "authors": ["x"], "dependencies": [{ "name": "b" }, { "name": "c" }] },
+                { "name": "b", "authors": [], "dependencies": [] },
+            ],
+        });
+        assert_eq!(Detail::Full.apply(value.clone()), value);
+        assert_eq!(
+            Detail::Summary.apply(value),
+            json!({
+                "name": "app",
+                "packages": [
+                    { "name": "a", "authors": ["x"] },
+                    { "name": "b", "authors": [], "dependencies": [] },
+                ],
+                "_omitted": { "packages[].dependencies": 2 },
+            })
+        );
+
+        let items: Vec<Value> = (0..12).map(Value::from).collect();
+        assert_eq!(
+            Detail::Summary.apply(Value::Array(items))["_omitted"],
+            json!({ "items": 2 })
+        );
+    }
+}
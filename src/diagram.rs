@@ -0,0 +1,322 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use cargo_metadata::{DependencyKind, PackageId};
+
+use crate::export::kind_name;
+use crate::graph::Graph;
+
+/// 依存関係の図の描き方
+#[derive(Default)]
+pub struct DiagramOptions {
+    /// 辿る深さの上限 (`None` は無制限)
+    pub max_depth: Option<usize>,
+    /// dev-dependencies を含めるかどうか
+    pub include_dev: bool,
+    /// 同じパッケージを 1 つのノードにまとめるかどうか (偽の場合は、経路ごとに別のノードとして木の形に展開する)
+    pub dedup: bool,
+    /// ワークスペースメンバー間の依存関係のみを描くかどうか
+    pub workspace_only: bool,
+}
+
+struct DiagramNode {
+    key: String,
+    label: String,
+    workspace_member: bool,
+}
+
+struct DiagramEdge {
+    from: usize,
+    to: usize,
+    /// `normal`、`build`、`dev` の組み合わせ
+    kinds: Vec<&'static str>,
+}
+
+#[derive(Default)]
+struct Diagram {
+    nodes: Vec<DiagramNode>,
+    edges: Vec<DiagramEdge>,
+}
+
+impl Diagram {
+    fn push_node(&mut self, graph: &Graph, id: &PackageId, key: String) -> usize {
+        let label = match graph.package(id) {
+            Some(package) => format!("{} v{}", package.name, package.version),
+            None => id.repr.clone(),
+        };
+        self.nodes.push(DiagramNode {
+            key,
+            label,
+            workspace_member: graph.metadata.workspace_members.contains(id),
+        });
+        self.nodes.len() - 1
+    }
+}
+
+/// `id` の依存先と、その辺の依存関係の種類 (依存先の名前とバージョンの順)
+fn children<'a>(
+    graph: &Graph<'a>,
+    id: &PackageId,
+    options: &DiagramOptions,
+) -> Vec<(&'a PackageId, Vec<&'static str>)> {
+    let include_dev = options.include_dev;
+    let mut children: Vec<(&PackageId, Vec<&'static str>)> = graph
+        .edges(id, include_dev)
+        .filter(|dep| {
+            !options.workspace_only || graph.metadata.workspace_members.contains(&dep.pkg)
+        })
+        .map(|dep| {
+            let kinds: BTreeSet<&'static str> = if dep.dep_kinds.is_empty() {
+                BTreeSet::from(["normal"])
+            } else {
+                dep.dep_kinds
+                    .iter()
+                    .filter(|k| include_dev || k.kind != DependencyKind::Development)
+                    .map(|k| kind_name(&k.kind))
+                    .collect()
+            };
+            (&dep.pkg, kinds.into_iter().collect())
+        })
+        .collect();
+    children.sort_by_key(|(id, _)| graph.package(id).map(|p| (&p.name, &p.version)));
+    children
+}
+
+fn collect(graph: &Graph, roots: &[&PackageId], options: &DiagramOptions) -> Diagram {
+    let mut diagram = Diagram::default();
+    let within_depth = |depth: usize| options.max_depth.is_none_or(|max| depth < max);
+    if options.dedup {
+        // 幅優先で辿り、各パッケージを最も浅い位置で展開する
+        let mut indexes: HashMap<&PackageId, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for &root in roots {
+            if !indexes.contains_key(root) {
+                indexes.insert(root, diagram.push_node(graph, root, root.repr.clone()));
+                queue.push_back((root, 0));
+            }
+        }
+        while let Some((id, depth)) = queue.pop_front() {
+            if !within_depth(depth) {
+                continue;
+            }
+            for (child, kinds) in children(graph, id, options) {
+                let to = match indexes.get(child) {
+                    Some(&index) => index,
+                    None => {
+                        let index = diagram.push_node(graph, child, child.repr.clone());
+                        indexes.insert(child, index);
+                        queue.push_back((child, depth + 1));
+                        index
+                    }
+                };
+                diagram.edges.push(DiagramEdge {
+                    from: indexes[id],
+                    to,
+                    kinds,
+                });
+            }
+        }
+    } else {
+        // 深さ優先で辿り、経路ごとに別のノードを作る (循環する経路は辿らない)
+        let mut stack: Vec<(&PackageId, usize, Vec<&PackageId>)> = Vec::new();
+        for &root in roots.iter().rev() {
+            let index = diagram.push_node(graph, root, format!("n{}", diagram.nodes.len()));
+            stack.push((root, index, vec![root]));
+        }
+        while let Some((id, index, path)) = stack.pop() {
+            if !within_depth(path.len() - 1) {
+                continue;
+            }
+            let mut next = Vec::new();
+            for (child, kinds) in children(graph, id, options) {
+                if path.contains(&child) {
+                    continue;
+                }
+                let to = diagram.push_node(graph, child, format!("n{}", diagram.nodes.len()));
+                diagram.edges.push(DiagramEdge {
+                    from: index,
+                    to,
+                    kinds,
+                });
+                let mut path = path.clone();
+                path.push(child);
+                next.push((child, to, path));
+            }
+            stack.extend(next.into_iter().rev());
+        }
+    }
+    diagram
+}
+
+/// DOT の文字列リテラル
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `roots` から辿れる依存グラフを Graphviz の DOT 形式で表す
+///
+/// ワークスペースメンバーは塗りつぶし、build-dependencies は点線、dev-dependencies は破線の辺で描く。
+pub fn dot(graph: &Graph, roots: &[&PackageId], options: &DiagramOptions) -> String {
+    let diagram = collect(graph, roots, options);
+    let mut dot = String::from("digraph dependencies {\n");
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+    for node in &diagram.nodes {
+        dot.push_str(&format!(
+            "    {} [label={}{}];\n",
+            dot_string(&node.key),
+            dot_string(&node.label),
+            if node.workspace_member {
+                ", style=filled, fillcolor=\"#e0e0e0\""
+            } else {
+                ""
+            }
+        ));
+    }
+    for edge in &diagram.edges {
+        let mut attributes = Vec::new();
+        if !edge.kinds.contains(&"normal") {
+            if edge.kinds.contains(&"build") {
+                attributes.push("style=dotted".to_string());
+            } else {
+                attributes.push("style=dashed".to_string());
+            }
+        }
+        if edge.kinds != ["normal"] {
+            attributes.push(format!("label={}", dot_string(&edge.kinds.join(","))));
+        }
+        dot.push_str(&format!(
+            "    {} -> {}",
+            dot_string(&diagram.nodes[edge.from].key),
+            dot_string(&diagram.nodes[edge.to].key)
+        ));
+        if !attributes.is_empty() {
+            dot.push_str(&format!(" [{}]", attributes.join(", ")));
+        }
+        dot.push_str(";\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Mermaid のラベル (`"` は実体参照にする)
+fn mermaid_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "#quot;"))
+}
+
+/// `roots` から辿れる依存グラフを Mermaid の `graph TD` として表す
+///
+/// そのまま Markdown に貼り付けられるように、` ```mermaid ` のコードブロックで囲む。
+/// build-dependencies と dev-dependencies は点線の辺で描き、種類をラベルに付ける。
+pub fn mermaid(graph: &Graph, roots: &[&PackageId], options: &DiagramOptions) -> String {
+    let diagram = collect(graph, roots, options);
+    let mut mermaid = String::from("```mermaid\ngraph TD\n");
+    for (i, node) in diagram.nodes.iter().enumerate() {
+        mermaid.push_str(&format!("    n{i}[{}]\n", mermaid_string(&node.label)));
+    }
+    for edge in &diagram.edges {
+        let arrow = if edge.kinds.contains(&"normal") {
+            "-->"
+        } else {
+            "-.->"
+        };
+        let label = if edge.kinds == ["normal"] {
+            String::new()
+        } else {
+            format!("|{}|", edge.kinds.join(","))
+        };
+        mermaid.push_str(&format!("    n{} {arrow}{label} n{}\n", edge.from, edge.to));
+    }
+    let members: Vec<String> = diagram
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| node.workspace_member)
+        .map(|(i, _)| format!("n{i}"))
+        .collect();
+    if !members.is_empty() {
+        mermaid.push_str("    classDef member fill:#e0e0e0,stroke:#333,font-weight:bold\n");
+        mermaid.push_str(&format!("    class {} member\n", members.join(",")));
+    }
+    mermaid.push_str("```\n");
+    mermaid
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::{Metadata, MetadataCommand};
+
+    use super::*;
+
+    #[test]
+    fn test_dot() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let graph = Graph::new(&metadata);
+        let roots: Vec<&PackageId> = graph.members().collect();
+        let options = DiagramOptions {
+            dedup: true,
+            ..DiagramOptions::default()
+        };
+        let dot = dot(&graph, &roots, &options);
+        assert!(dot.starts_with("digraph dependencies {\n"));
+        assert!(dot.contains(
+            "    \"path+file:///fixture/app#0.2.0\" -> \"path+file:///fixture/core#fixture-core@0.1.0\";\n"
+        ));
+        assert_eq!(dot.matches(" [label=").count(), 2);
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let graph = Graph::new(&metadata);
+        let roots: Vec<&PackageId> = graph.members().collect();
+        let count = |options: DiagramOptions| collect(&graph, &roots, &options).nodes.len();
+        let deduped = count(DiagramOptions {
+            dedup: true,
+            ..DiagramOptions::default()
+        });
+        let expanded = count(DiagramOptions::default());
+        let members = count(DiagramOptions {
+            dedup: true,
+            workspace_only: true,
+            ..DiagramOptions::default()
+        });
+        assert_eq!(members, roots.len());
+        let shallow = count(DiagramOptions {
+            max_depth: Some(1),
+            dedup: true,
+            ..DiagramOptions::default()
+        });
+        assert!(expanded > deduped);
+        assert!(shallow < deduped);
+        assert_eq!(
+            shallow,
+            1 + children(&graph, roots[0], &DiagramOptions::default()).len(),
+            "depth 1 only contains the root and its direct dependencies"
+        );
+    }
+
+    #[test]
+    fn test_mermaid() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let graph = Graph::new(&metadata);
+        let roots: Vec<&PackageId> = graph.members().collect();
+        let options = DiagramOptions {
+            dedup: true,
+            workspace_only: true,
+            ..DiagramOptions::default()
+        };
+        assert_eq!(
+            mermaid(&graph, &roots, &options),
+            "```mermaid\n\
+             graph TD\n    \
+             n0[\"app v0.2.0\"]\n    \
+             n1[\"fixture-core v0.1.0\"]\n    \
+             n0 --> n1\n    \
+             classDef member fill:#e0e0e0,stroke:#333,font-weight:bold\n    \
+             class n0,n1 member\n\
+             ```\n"
+        );
+    }
+}
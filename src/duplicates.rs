@@ -0,0 +1,122 @@
+use std::collections::{BTreeMap, HashSet};
+
+use cargo_metadata::{Package, PackageId};
+use serde::Serialize;
+
+use crate::explain;
+use crate::graph::Graph;
+use crate::policy::compat_key;
+
+/// 複数のバージョンが依存グラフに含まれているクレート
+#[derive(Serialize)]
+pub struct DuplicateDependency {
+    pub name: String,
+    /// semver 非互換なバージョン (`1.x` と `2.x` など) が含まれているかどうか
+    pub semver_incompatible: bool,
+    pub versions: Vec<DuplicateVersion>,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateVersion {
+    pub version: String,
+    pub pkgid: String,
+    /// このバージョンに直接依存しているパッケージ (`name@version`)
+    pub dependents: Vec<String>,
+    /// ワークスペースメンバーからこのバージョンに至る経路 (`name@version` の列)
+    pub paths: Vec<Vec<String>>,
+}
+
+/// ワークスペースメンバーから到達できるパッケージのうち、同じ名前で複数のバージョンがあるものを探す (`cargo tree -d` 相当)
+///
+/// バージョンごとの経路は `max_paths` 個までにする。
+pub fn find_duplicates(
+    graph: &Graph,
+    include_dev: bool,
+    max_paths: usize,
+) -> Vec<DuplicateDependency> {
+    let mut reachable: HashSet<&PackageId> = HashSet::new();
+    for member in graph.members() {
+        reachable.extend(graph.reachable(member, include_dev));
+    }
+    let mut by_name: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+    for id in &reachable {
+        if let Some(package) = graph.package(id) {
+            by_name.entry(&package.name).or_default().push(package);
+        }
+    }
+
+    let mut duplicates = Vec::new();
+    for (name, mut packages) in by_name {
+        if packages.len() < 2 {
+            continue;
+        }
+        packages.sort_by(|a, b| a.version.cmp(&b.version).then(a.id.cmp(&b.id)));
+        let compat: HashSet<String> = packages.iter().map(|p| compat_key(&p.version)).collect();
+        let versions = packages
+            .iter()
+            .map(|package| {
+                let mut dependents: Vec<String> = reachable
+                    .iter()
+                    .filter(|id| {
+                        graph
+                            .dependencies(id, include_dev)
+                            .any(|dep| *dep == package.id)
+                    })
+                    .map(|id| graph.label(id))
+                    .collect();
+                dependents.sort();
+                let explanation = explain::explain(graph, package, include_dev, max_paths);
+                DuplicateVersion {
+                    version: package.version.to_string(),
+                    pkgid: package.id.repr.clone(),
+                    dependents,
+                    paths: explanation
+                        .paths
+                        .into_iter()
+                        .map(|path| {
+                            std::iter::once(path.member)
+                                .chain(path.steps.into_iter().map(|s| s.to))
+                                .collect()
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        duplicates.push(DuplicateDependency {
+            name: name.to_string(),
+            semver_incompatible: compat.len() > 1,
+            versions,
+        });
+    }
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::{Metadata, MetadataCommand};
+
+    use super::*;
+
+    #[test]
+    fn test_find_duplicates() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let graph = Graph::new(&metadata);
+        for duplicate in find_duplicates(&graph, false, 5) {
+            assert!(duplicate.versions.len() >= 2);
+            for version in &duplicate.versions {
+                assert!(!version.dependents.is_empty());
+                assert!(!version.paths.is_empty());
+                assert!(version.paths.iter().all(|path| {
+                    path.last().unwrap() == &format!("{}@{}", duplicate.name, version.version)
+                }));
+            }
+        }
+
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        assert!(find_duplicates(&Graph::new(&metadata), true, 5).is_empty());
+    }
+}
@@ -0,0 +1,456 @@
+use std::future::Future;
+
+use mcp_attr::{Error, ErrorCode};
+
+/// エラーメッセージの言語
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Language {
+    #[default]
+    En,
+    Ja,
+}
+
+tokio::task_local! {
+    static LANGUAGE: Language;
+}
+
+impl Language {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "en" => Some(Self::En),
+            "ja" => Some(Self::Ja),
+            _ => None,
+        }
+    }
+
+    /// この言語でエラーメッセージを作成しながら、非同期処理を実行する
+    ///
+    /// サーバーはリクエストごとに、`--lang` で指定した言語を設定する。
+    pub fn scope<F: Future>(self, f: F) -> impl Future<Output = F::Output> {
+        LANGUAGE.scope(self, f)
+    }
+
+    /// この言語でエラーメッセージを作成しながら、同期処理を実行する (`spawn_blocking` の中で言語を引き継ぐために使う)
+    pub fn sync_scope<T>(self, f: impl FnOnce() -> T) -> T {
+        LANGUAGE.sync_scope(self, f)
+    }
+
+    /// 実行中の処理に設定された言語 (設定されていなければ英語)
+    pub fn current() -> Self {
+        LANGUAGE.try_with(|language| *language).unwrap_or_default()
+    }
+}
+
+/// クライアントが処理を分岐できる、安定したエラーの種類
+///
+/// エラーメッセージは `[<id>] <メッセージ>` の形式で、`id` は言語によらず変わらない。
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorKind {
+    ReadOnlyMode,
+    WriteDisabled,
+    ProjectNotLoaded,
+    ManifestNotFound,
+    ManifestPathRequired,
+    AmbiguousProject,
+    PathNotAllowed,
+    NoWorkspaces,
+    NoRootPackage,
+    PackageRequired,
+    NotWorkspaceMember,
+    NotProjectMember,
+    PackageNotFound,
+    UnknownPackage,
+    PackageNotInProject,
+    NotRegistryDependency,
+    InvalidPkgid,
+    InvalidPkgidVersion,
+    AmbiguousPkgid,
+    InvalidCrateName,
+    InvalidVersion,
+    InvalidVersionReq,
+    InvalidProfile,
+    UnknownDetailLevel,
+    UnknownFormat,
+    UnknownDependencyKind,
+    UnknownReport,
+    UnknownPolicyField,
+    UnknownLicensePreset,
+    InvalidPolicy,
+    InvalidQuery,
+    InvalidCursor,
+    InvalidPattern,
+    InvalidRevision,
+    NotATable,
+    DependencyNotInManifest,
+    InheritedDependency,
+    CrateNotFound,
+    NoReleasedVersion,
+    IndexFetchFailed,
+    IndexParseFailed,
+    AdvisoryFetchFailed,
+    AdvisoryLoadFailed,
+    MetadataFailed,
+    TaskFailed,
+    MetadataLocked,
+    ResolveFailed,
+    CargoSpawnFailed,
+    CargoCommandFailed,
+    GitFailed,
+    ToolchainUnavailable,
+    SandboxFailed,
+    ReadFailed,
+    ParseFailed,
+    WriteFailed,
+    SerializeFailed,
+}
+
+impl ErrorKind {
+    /// エラーの種類の安定した識別子
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::ReadOnlyMode => "read_only_mode",
+            Self::WriteDisabled => "write_disabled",
+            Self::ProjectNotLoaded => "project_not_loaded",
+            Self::ManifestNotFound => "manifest_not_found",
+            Self::ManifestPathRequired => "manifest_path_required",
+            Self::AmbiguousProject => "ambiguous_project",
+            Self::PathNotAllowed => "path_not_allowed",
+            Self::NoWorkspaces => "no_workspaces",
+            Self::NoRootPackage => "no_root_package",
+            Self::PackageRequired => "package_required",
+            Self::NotWorkspaceMember => "not_workspace_member",
+            Self::NotProjectMember => "not_project_member",
+            Self::PackageNotFound => "package_not_found",
+            Self::UnknownPackage => "unknown_package",
+            Self::PackageNotInProject => "package_not_in_project",
+            Self::NotRegistryDependency => "not_registry_dependency",
+            Self::InvalidPkgid => "invalid_pkgid",
+            Self::InvalidPkgidVersion => "invalid_pkgid_version",
+            Self::AmbiguousPkgid => "ambiguous_pkgid",
+            Self::InvalidCrateName => "invalid_crate_name",
+            Self::InvalidVersion => "invalid_version",
+            Self::InvalidVersionReq => "invalid_version_req",
+            Self::InvalidProfile => "invalid_profile",
+            Self::UnknownDetailLevel => "unknown_detail_level",
+            Self::UnknownFormat => "unknown_format",
+            Self::UnknownDependencyKind => "unknown_dependency_kind",
+            Self::UnknownReport => "unknown_report",
+            Self::UnknownPolicyField => "unknown_policy_field",
+            Self::UnknownLicensePreset => "unknown_license_preset",
+            Self::InvalidPolicy => "invalid_policy",
+            Self::InvalidQuery => "invalid_query",
+            Self::InvalidCursor => "invalid_cursor",
+            Self::InvalidPattern => "invalid_pattern",
+            Self::InvalidRevision => "invalid_revision",
+            Self::NotATable => "not_a_table",
+            Self::DependencyNotInManifest => "dependency_not_in_manifest",
+            Self::InheritedDependency => "inherited_dependency",
+            Self::CrateNotFound => "crate_not_found",
+            Self::NoReleasedVersion => "no_released_version",
+            Self::IndexFetchFailed => "index_fetch_failed",
+            Self::IndexParseFailed => "index_parse_failed",
+            Self::AdvisoryFetchFailed => "advisory_fetch_failed",
+            Self::AdvisoryLoadFailed => "advisory_load_failed",
+            Self::MetadataFailed => "metadata_failed",
+            Self::TaskFailed => "task_failed",
+            Self::MetadataLocked => "metadata_locked",
+            Self::ResolveFailed => "resolve_failed",
+            Self::CargoSpawnFailed => "cargo_spawn_failed",
+            Self::CargoCommandFailed => "cargo_command_failed",
+            Self::GitFailed => "git_failed",
+            Self::ToolchainUnavailable => "toolchain_unavailable",
+            Self::SandboxFailed => "sandbox_failed",
+            Self::ReadFailed => "read_failed",
+            Self::ParseFailed => "parse_failed",
+            Self::WriteFailed => "write_failed",
+            Self::SerializeFailed => "serialize_failed",
+        }
+    }
+
+    /// JSON-RPC のエラーコード
+    pub fn code(self) -> ErrorCode {
+        match self {
+            Self::ReadOnlyMode | Self::WriteDisabled => ErrorCode::INVALID_REQUEST,
+            Self::NoRootPackage
+            | Self::IndexFetchFailed
+            | Self::IndexParseFailed
+            | Self::AdvisoryFetchFailed
+            | Self::AdvisoryLoadFailed
+            | Self::MetadataFailed
+            | Self::TaskFailed
+            | Self::MetadataLocked
+            | Self::ResolveFailed
+            | Self::CargoSpawnFailed
+            | Self::CargoCommandFailed
+            | Self::ToolchainUnavailable
+            | Self::SandboxFailed
+            | Self::ReadFailed
+            | Self::ParseFailed
+            | Self::WriteFailed
+            | Self::SerializeFailed => ErrorCode::INTERNAL_ERROR,
+            _ => ErrorCode::INVALID_PARAMS,
+        }
+    }
+
+    /// メッセージのテンプレート (`{}` は引数で順に置き換える)
+    fn template(self, language: Language) -> &'static str {
+        use Language::{En, Ja};
+        match (self, language) {
+            (Self::ReadOnlyMode, En) => {
+                "The server is running in read-only mode and never modifies the project"
+            }
+            (Self::ReadOnlyMode, Ja) => {
+                "サーバーは読み取り専用モードで動作しているため、プロジェクトを変更しません"
+            }
+            (Self::WriteDisabled, En) => {
+                "Write mode is disabled. Start the server with `--allow-write` to enable tools that modify Cargo.toml"
+            }
+            (Self::WriteDisabled, Ja) => {
+                "書き込みモードが無効です。Cargo.toml を変更するツールを使うには、`--allow-write` を指定してサーバーを起動してください"
+            }
+            (Self::ProjectNotLoaded, En) => {
+                "Project `{}` is not loaded. Call a tool with its manifest_path or start the server with `--workspace <path>`"
+            }
+            (Self::ProjectNotLoaded, Ja) => {
+                "プロジェクト `{}` は読み込まれていません。manifest_path を指定してツールを呼び出すか、`--workspace <path>` を指定してサーバーを起動してください"
+            }
+            (Self::ManifestNotFound, En) => {
+                "No Cargo.toml was found in `{}` or any of its parent directories"
+            }
+            (Self::ManifestNotFound, Ja) => {
+                "`{}` とその親ディレクトリに Cargo.toml が見つかりません"
+            }
+            (Self::ManifestPathRequired, En) => {
+                "manifest_path is required because no default project is configured and no Cargo project was found in the client's roots"
+            }
+            (Self::ManifestPathRequired, Ja) => {
+                "既定のプロジェクトが設定されておらず、クライアントのルートにも Cargo プロジェクトが見つからないため、manifest_path を指定してください"
+            }
+            (Self::AmbiguousProject, En) => {
+                "The client's roots contain multiple Cargo projects. Specify manifest_path as one of: {}"
+            }
+            (Self::AmbiguousProject, Ja) => {
+                "クライアントのルートに複数の Cargo プロジェクトがあります。manifest_path に次のいずれかを指定してください: {}"
+            }
+            (Self::PathNotAllowed, En) => {
+                "`{}` is outside the directories the server is allowed to access: {}"
+            }
+            (Self::PathNotAllowed, Ja) => {
+                "`{}` はサーバーがアクセスを許可されたディレクトリの外にあります: {}"
+            }
+            (Self::NoWorkspaces, En) => {
+                "No workspaces are configured. Pass manifest_paths or start the server with `--workspace <path>`"
+            }
+            (Self::NoWorkspaces, Ja) => {
+                "ワークスペースが設定されていません。manifest_paths を指定するか、`--workspace <path>` を指定してサーバーを起動してください"
+            }
+            (Self::NoRootPackage, En) => "No root package found",
+            (Self::NoRootPackage, Ja) => "ルートパッケージが見つかりません",
+            (Self::PackageRequired, En) => {
+                "The project is a virtual workspace without a root package. Specify package as one of: {}"
+            }
+            (Self::PackageRequired, Ja) => {
+                "プロジェクトはルートパッケージのない仮想ワークスペースです。package に次のいずれかを指定してください: {}"
+            }
+            (Self::NotWorkspaceMember, En) => "Package `{}` is not a workspace member",
+            (Self::NotWorkspaceMember, Ja) => {
+                "パッケージ `{}` はワークスペースのメンバーではありません"
+            }
+            (Self::NotProjectMember, En) => "Package `{}` is not a member of `{}`",
+            (Self::NotProjectMember, Ja) => "パッケージ `{}` は `{}` のメンバーではありません",
+            (Self::PackageNotFound, En) => "Package `{}` is not in the dependency graph",
+            (Self::PackageNotFound, Ja) => "パッケージ `{}` は依存グラフにありません",
+            (Self::UnknownPackage, En) => {
+                "Package `{}` is not in the dependency graph. Valid names include: {}"
+            }
+            (Self::UnknownPackage, Ja) => {
+                "パッケージ `{}` は依存グラフにありません。指定できる名前の例: {}"
+            }
+            (Self::PackageNotInProject, En) => {
+                "Package `{}@{}` is not in the dependency graph of `{}`"
+            }
+            (Self::PackageNotInProject, Ja) => {
+                "パッケージ `{}@{}` は `{}` の依存グラフにありません"
+            }
+            (Self::NotRegistryDependency, En) => {
+                "Package `{}` is not a registry or git dependency in the dependency graph"
+            }
+            (Self::NotRegistryDependency, Ja) => {
+                "パッケージ `{}` は依存グラフ中のレジストリまたは git の依存関係ではありません"
+            }
+            (Self::InvalidPkgid, En) => "Invalid package ID specification `{}`",
+            (Self::InvalidPkgid, Ja) => "パッケージ ID 仕様 `{}` が不正です",
+            (Self::InvalidPkgidVersion, En) => {
+                "Invalid version `{}` in package ID specification `{}`"
+            }
+            (Self::InvalidPkgidVersion, Ja) => {
+                "バージョン `{}` が不正です (パッケージ ID 仕様 `{}`)"
+            }
+            (Self::AmbiguousPkgid, En) => {
+                "Package ID specification `{}` is ambiguous. Use one of: {}"
+            }
+            (Self::AmbiguousPkgid, Ja) => {
+                "パッケージ ID 仕様 `{}` に複数のパッケージが一致します。次のいずれかを指定してください: {}"
+            }
+            (Self::InvalidCrateName, En) => "Invalid crate name `{}`",
+            (Self::InvalidCrateName, Ja) => "クレート名 `{}` が不正です",
+            (Self::InvalidVersion, En) => "Invalid version `{}`: {}",
+            (Self::InvalidVersion, Ja) => "バージョン `{}` が不正です: {}",
+            (Self::InvalidVersionReq, En) => "Invalid version requirement `{}`: {}",
+            (Self::InvalidVersionReq, Ja) => "バージョン要件 `{}` が不正です: {}",
+            (Self::InvalidProfile, En) => "Invalid profile `{}`",
+            (Self::InvalidProfile, Ja) => "プロファイル `{}` が不正です",
+            (Self::UnknownDetailLevel, En) => {
+                "Unknown detail level `{}`. Expected one of: summary, normal, full"
+            }
+            (Self::UnknownDetailLevel, Ja) => {
+                "不明な詳細さ `{}` です。summary、normal、full のいずれかを指定してください"
+            }
+            (Self::UnknownFormat, En) => "Unknown format `{}`. Expected one of: {}",
+            (Self::UnknownFormat, Ja) => "不明な形式 `{}` です。次のいずれかを指定してください: {}",
+            (Self::UnknownDependencyKind, En) => {
+                "Unknown dependency kind `{}`. Expected one of: normal, dev, build"
+            }
+            (Self::UnknownDependencyKind, Ja) => {
+                "不明な依存関係の種類 `{}` です。normal、dev、build のいずれかを指定してください"
+            }
+            (Self::UnknownReport, En) => "Unknown report `{}`. Expected one of: {}",
+            (Self::UnknownReport, Ja) => {
+                "不明なレポート `{}` です。次のいずれかを指定してください: {}"
+            }
+            (Self::UnknownPolicyField, En) => "Unknown required field `{}`. Expected one of: {}",
+            (Self::UnknownPolicyField, Ja) => {
+                "不明な必須フィールド `{}` です。次のいずれかを指定してください: {}"
+            }
+            (Self::UnknownLicensePreset, En) => "Unknown license preset `{}`. Expected one of: {}",
+            (Self::UnknownLicensePreset, Ja) => {
+                "不明なライセンスのプリセット `{}` です。次のいずれかを指定してください: {}"
+            }
+            (Self::InvalidPolicy, En) => "Invalid policy file: {}",
+            (Self::InvalidPolicy, Ja) => "ポリシーファイルが不正です: {}",
+            (Self::InvalidQuery, En) => "Invalid query `{}`: {}",
+            (Self::InvalidQuery, Ja) => "クエリ `{}` が不正です: {}",
+            (Self::InvalidCursor, En) => {
+                "Invalid cursor `{}`. Pass the `_page.next_cursor` value of the previous page"
+            }
+            (Self::InvalidCursor, Ja) => {
+                "カーソル `{}` が不正です。前のページの `_page.next_cursor` の値を指定してください"
+            }
+            (Self::InvalidPattern, En) => "Invalid pattern `{}`: {}",
+            (Self::InvalidPattern, Ja) => "パターン `{}` が不正です: {}",
+            (Self::InvalidRevision, En) => "`{}` is not a commit in the git repository",
+            (Self::InvalidRevision, Ja) => "`{}` は git リポジトリのコミットではありません",
+            (Self::NotATable, En) => "`{}` is not a table",
+            (Self::NotATable, Ja) => "`{}` がテーブルではありません",
+            (Self::DependencyNotInManifest, En) => "Dependency `{}` is not in the manifest",
+            (Self::DependencyNotInManifest, Ja) => "依存関係 `{}` はマニフェストにありません",
+            (Self::InheritedDependency, En) => {
+                "Dependency `{}` is inherited from the workspace. Edit `[workspace.dependencies]` in the workspace root instead"
+            }
+            (Self::InheritedDependency, Ja) => {
+                "依存関係 `{}` はワークスペースから継承されています。代わりにワークスペースルートの `[workspace.dependencies]` を編集してください"
+            }
+            (Self::CrateNotFound, En) => "Crate `{}` was not found on crates.io",
+            (Self::CrateNotFound, Ja) => "クレート `{}` は crates.io に見つかりません",
+            (Self::NoReleasedVersion, En) => "No released version of `{}` was found",
+            (Self::NoReleasedVersion, Ja) => "`{}` のリリースされたバージョンが見つかりません",
+            (Self::IndexFetchFailed, En) => "Failed to fetch the index entry for `{}`: {}",
+            (Self::IndexFetchFailed, Ja) => "`{}` のインデックスの取得に失敗しました: {}",
+            (Self::IndexParseFailed, En) => "Failed to parse the index entry for `{}`: {}",
+            (Self::IndexParseFailed, Ja) => "`{}` のインデックスの解析に失敗しました: {}",
+            (Self::AdvisoryFetchFailed, En) => "Failed to fetch advisory database from {}: {}",
+            (Self::AdvisoryFetchFailed, Ja) => {
+                "{} からのアドバイザリデータベースの取得に失敗しました: {}"
+            }
+            (Self::AdvisoryLoadFailed, En) => "Failed to load advisory database at {}: {}",
+            (Self::AdvisoryLoadFailed, Ja) => {
+                "{} のアドバイザリデータベースの読み込みに失敗しました: {}"
+            }
+            (Self::MetadataFailed, En) => "Failed to get cargo metadata: {}",
+            (Self::MetadataFailed, Ja) => "cargo metadata の取得に失敗しました: {}",
+            (Self::TaskFailed, En) => "Background task failed: {}",
+            (Self::TaskFailed, Ja) => "バックグラウンドの処理に失敗しました: {}",
+            (Self::MetadataLocked, En) => {
+                "Failed to get cargo metadata in read-only mode (Cargo.lock must exist and be up to date): {}"
+            }
+            (Self::MetadataLocked, Ja) => {
+                "読み取り専用モードでの cargo metadata の取得に失敗しました (Cargo.lock が存在し、最新である必要があります): {}"
+            }
+            (Self::ResolveFailed, En) => "Failed to resolve `{}`: {}",
+            (Self::ResolveFailed, Ja) => "`{}` の依存関係の解決に失敗しました: {}",
+            (Self::CargoSpawnFailed, En) => "Failed to run cargo: {}",
+            (Self::CargoSpawnFailed, Ja) => "cargo の実行に失敗しました: {}",
+            (Self::CargoCommandFailed, En) => "`cargo {}` failed: {}",
+            (Self::CargoCommandFailed, Ja) => "`cargo {}` が失敗しました: {}",
+            (Self::GitFailed, En) => "`git {}` failed: {}",
+            (Self::GitFailed, Ja) => "`git {}` が失敗しました: {}",
+            (Self::ToolchainUnavailable, En) => {
+                "Failed to run `rustup run {} cargo`: {}. A rustup-managed nightly toolchain is required"
+            }
+            (Self::ToolchainUnavailable, Ja) => {
+                "`rustup run {} cargo` の実行に失敗しました: {}。rustup で管理された nightly ツールチェーンが必要です"
+            }
+            (Self::SandboxFailed, En) => "Failed to create a sandbox copy of the workspace: {}",
+            (Self::SandboxFailed, Ja) => {
+                "サンドボックスへのワークスペースのコピーの作成に失敗しました: {}"
+            }
+            (Self::ReadFailed, En) => "Failed to read {}: {}",
+            (Self::ReadFailed, Ja) => "{} の読み込みに失敗しました: {}",
+            (Self::ParseFailed, En) => "Failed to parse {}: {}",
+            (Self::ParseFailed, Ja) => "{} の解析に失敗しました: {}",
+            (Self::WriteFailed, En) => "Failed to write {}: {}",
+            (Self::WriteFailed, Ja) => "{} の書き込みに失敗しました: {}",
+            (Self::SerializeFailed, En) => "Failed to serialize {}: {}",
+            (Self::SerializeFailed, Ja) => "{} のシリアライズに失敗しました: {}",
+        }
+    }
+
+    /// 現在の言語で、引数を埋め込んだメッセージを作成する
+    pub fn message(self, args: &[String]) -> String {
+        let mut args = args.iter();
+        let mut parts = self.template(Language::current()).split("{}");
+        let mut message = parts.next().unwrap_or_default().to_string();
+        for part in parts {
+            message.push_str(args.next().map(|a| a.as_str()).unwrap_or_default());
+            message.push_str(part);
+        }
+        message
+    }
+
+    /// `[<id>] <メッセージ>` をメッセージとする公開エラーを作成する
+    pub fn error(self, args: &[String]) -> Error {
+        Error::new(self.code())
+            .with_message(format!("[{}] {}", self.id(), self.message(args)), true)
+    }
+}
+
+/// エラーの種類とメッセージの引数を指定して、公開エラーを返す
+macro_rules! bail_kind {
+    ($kind:expr $(, $arg:expr)* $(,)?) => {
+        return ::std::result::Result::Err($crate::error::ErrorKind::error(
+            $kind,
+            &[$(::std::string::ToString::to_string(&$arg)),*],
+        ))
+    };
+}
+pub(crate) use bail_kind;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error() {
+        let kind = ErrorKind::PackageNotFound;
+        let message = |error: Error| error.to_error_object(false).message;
+        assert_eq!(
+            message(kind.error(&["serde".to_string()])),
+            "[package_not_found] Package `serde` is not in the dependency graph"
+        );
+        assert_eq!(
+            message(Language::Ja.sync_scope(|| kind.error(&["serde".to_string()]))),
+            "[package_not_found] パッケージ `serde` は依存グラフにありません"
+        );
+        assert_eq!(kind.error(&[]).to_error_object(false).code, kind.code());
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{HashMap, HashSet};
+
+use cargo_metadata::{DependencyKind, NodeDep, Package, PackageId};
+use serde::Serialize;
+
+use crate::graph::Graph;
+
+/// `explain_dependency` の結果 (一致したパッケージのバージョンごと)
+#[derive(Serialize)]
+pub struct DependencyExplanation {
+    /// `name@version`
+    pub package: String,
+    pub pkgid: String,
+    /// ワークスペースメンバーからこのパッケージに至る経路
+    pub paths: Vec<DependencyPath>,
+    /// 経路の数が上限に達し、一部の経路を省略したかどうか
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+pub struct DependencyPath {
+    /// 経路の起点のワークスペースメンバー (`name@version`)
+    pub member: String,
+    pub steps: Vec<PathStep>,
+}
+
+/// 経路の 1 つの辺 (`from` が `to` に依存している)
+#[derive(Serialize)]
+pub struct PathStep {
+    pub from: String,
+    pub to: String,
+    /// `normal`、`build`、`dev` の組み合わせ
+    pub kinds: Vec<&'static str>,
+    /// `from` の Cargo.toml で optional として宣言されているかどうか
+    pub optional: bool,
+    /// optional な依存関係を有効にした `from` のフィーチャー
+    pub enabled_by: Vec<String>,
+    /// `from` が `to` に要求しているフィーチャー (`default` は既定のフィーチャーを使う場合)
+    pub features: Vec<String>,
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+/// 辺の種類 (種類が記録されていない古い cargo の出力は通常の依存関係とみなす)
+fn edge_kinds(dep: &NodeDep) -> Vec<DependencyKind> {
+    let mut kinds: Vec<DependencyKind> = dep.dep_kinds.iter().map(|k| k.kind).collect();
+    if kinds.is_empty() {
+        kinds.push(DependencyKind::Normal);
+    }
+    kinds.dedup();
+    kinds
+}
+
+/// `from` から `to` への辺を、`from` の Cargo.toml の宣言と有効なフィーチャーから説明する
+pub(crate) fn describe_step(
+    graph: &Graph,
+    from: &Package,
+    to: &Package,
+    edge: &NodeDep,
+) -> PathStep {
+    let kinds = edge_kinds(edge);
+    let declared: Vec<_> = from
+        .dependencies
+        .iter()
+        .filter(|d| d.name == to.name && kinds.contains(&d.kind))
+        .collect();
+    let enabled: Vec<&String> = graph
+        .node(&from.id)
+        .map(|n| n.features.iter().collect())
+        .unwrap_or_default();
+
+    let mut enabled_by = Vec::new();
+    let mut features = Vec::new();
+    for dep in &declared {
+        features.extend(dep.features.iter().cloned());
+        if dep.uses_default_features {
+            features.push("default".to_string());
+        }
+        let key = dep.rename.as_deref().unwrap_or(&dep.name);
+        for feature in &enabled {
+            for entry in from.features.get(*feature).into_iter().flatten() {
+                let (target, feature_of_dep, weak) = match entry.split_once('/') {
+                    Some((target, f)) => match target.strip_suffix('?') {
+                        Some(target) => (target, Some(f), true),
+                        None => (target, Some(f), false),
+                    },
+                    None => (entry.strip_prefix("dep:").unwrap_or(entry), None, false),
+                };
+                if target != key {
+                    continue;
+                }
+                if let Some(f) = feature_of_dep {
+                    features.push(f.to_string());
+                }
+                if dep.optional && !weak {
+                    enabled_by.push(feature.to_string());
+                }
+            }
+        }
+    }
+    enabled_by.sort();
+    enabled_by.dedup();
+    features.sort();
+    features.dedup();
+    PathStep {
+        from: format!("{}@{}", from.name, from.version),
+        to: format!("{}@{}", to.name, to.version),
+        kinds: kinds.into_iter().map(kind_name).collect(),
+        optional: declared.iter().any(|d| d.optional),
+        enabled_by,
+        features,
+    }
+}
+
+/// `target` に到達できるパッケージの集合 (`target` 自身を含む)
+fn reaching<'a>(
+    graph: &Graph<'a>,
+    target: &'a PackageId,
+    include_dev: bool,
+) -> HashSet<&'a PackageId> {
+    let mut dependents: HashMap<&PackageId, Vec<&PackageId>> = HashMap::new();
+    for package in &graph.metadata.packages {
+        for dep in graph.dependencies(&package.id, include_dev) {
+            dependents.entry(dep).or_default().push(&package.id);
+        }
+    }
+    let mut reaching = HashSet::from([target]);
+    let mut stack = vec![target];
+    while let Some(id) = stack.pop() {
+        for dependent in dependents.get(id).into_iter().flatten() {
+            if reaching.insert(dependent) {
+                stack.push(dependent);
+            }
+        }
+    }
+    reaching
+}
+
+/// ワークスペースメンバーから `target` に至る経路を、最大 `max_paths` 個まで列挙する
+///
+/// 同じパッケージを 2 度通る経路 (循環) は含めない。`target` に到達できないパッケージは辿らない。
+pub fn explain(
+    graph: &Graph,
+    target: &Package,
+    include_dev: bool,
+    max_paths: usize,
+) -> DependencyExplanation {
+    let reaching = reaching(graph, &target.id, include_dev);
+    let mut paths = Vec::new();
+    let mut truncated = false;
+    let mut members: Vec<&PackageId> = graph.members().collect();
+    members.sort_by_key(|id| graph.label(id));
+    for member in members {
+        if !reaching.contains(member) {
+            continue;
+        }
+        // 経路上のパッケージと辺、パッケージごとの未探索の辺のスタックで深さ優先探索する
+        let mut route = vec![member];
+        let mut route_edges: Vec<&NodeDep> = Vec::new();
+        let mut stack = vec![sorted_edges(graph, member, include_dev, &reaching)];
+        while let Some(next) = stack.last_mut() {
+            let Some(edge) = next.pop() else {
+                stack.pop();
+                route.pop();
+                route_edges.pop();
+                continue;
+            };
+            if route.contains(&&edge.pkg) {
+                continue;
+            }
+            if edge.pkg == target.id {
+                if paths.len() == max_paths {
+                    truncated = true;
+                    break;
+                }
+                route_edges.push(edge);
+                paths.push(path(graph, member, &route_edges));
+                route_edges.pop();
+                continue;
+            }
+            route.push(&edge.pkg);
+            route_edges.push(edge);
+            stack.push(sorted_edges(graph, &edge.pkg, include_dev, &reaching));
+        }
+        if truncated {
+            break;
+        }
+    }
+    DependencyExplanation {
+        package: format!("{}@{}", target.name, target.version),
+        pkgid: target.id.repr.clone(),
+        paths,
+        truncated,
+    }
+}
+
+/// `target` に到達できるパッケージへの辺 (表示名の逆順。末尾から取り出すと表示名の順になる)
+fn sorted_edges<'a>(
+    graph: &Graph<'a>,
+    id: &PackageId,
+    include_dev: bool,
+    reaching: &HashSet<&PackageId>,
+) -> Vec<&'a NodeDep> {
+    let mut edges: Vec<&NodeDep> = graph
+        .edges(id, include_dev)
+        .filter(|d| reaching.contains(&d.pkg))
+        .collect();
+    edges.sort_by_key(|d| std::cmp::Reverse(graph.label(&d.pkg)));
+    edges
+}
+
+fn path(graph: &Graph, member: &PackageId, edges: &[&NodeDep]) -> DependencyPath {
+    let mut from = member;
+    let mut steps = Vec::new();
+    for edge in edges {
+        if let (Some(from_package), Some(to_package)) =
+            (graph.package(from), graph.package(&edge.pkg))
+        {
+            steps.push(describe_step(graph, from_package, to_package, edge));
+        }
+        from = &edge.pkg;
+    }
+    DependencyPath {
+        member: graph.label(member),
+        steps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_explain() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let graph = Graph::new(&metadata);
+        let serde_derive = metadata
+            .packages
+            .iter()
+            .find(|p| p.name == "serde_derive")
+            .unwrap();
+
+        let explanation = explain(&graph, serde_derive, false, 100);
+        assert!(!explanation.paths.is_empty());
+        for path in &explanation.paths {
+            assert_eq!(path.steps[0].from, path.member);
+            assert_eq!(path.steps.last().unwrap().to, explanation.package);
+        }
+        // serde の `derive` フィーチャーが optional な serde_derive を有効にしている
+        let step = explanation
+            .paths
+            .iter()
+            .flat_map(|p| &p.steps)
+            .find(|s| s.from.starts_with("serde@") && s.to == explanation.package)
+            .unwrap();
+        assert!(step.optional);
+        assert!(step.enabled_by.contains(&"derive".to_string()));
+
+        let explanation = explain(&graph, serde_derive, false, 1);
+        assert_eq!(explanation.paths.len(), 1);
+        assert!(explanation.truncated);
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use cargo_metadata::{DependencyKind, Metadata, Package, PackageId};
+use mcp_attr::Result;
+use serde_json::{Value, json};
+
+use crate::artifact::ArtifactDependencies;
+use crate::detail::Detail;
+use crate::error::{ErrorKind, bail_kind};
+use crate::findings::xml_escape;
+use crate::graph::Graph;
+
+/// 依存グラフのエクスポート形式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// JSON Graph Format (<https://jsongraphformat.info/>)
+    Jgf,
+    GraphMl,
+}
+
+impl GraphFormat {
+    pub fn parse(format: Option<&str>) -> Result<Self> {
+        match format.unwrap_or("jgf") {
+            "jgf" => Ok(Self::Jgf),
+            "graphml" => Ok(Self::GraphMl),
+            other => bail_kind!(ErrorKind::UnknownFormat, other, "jgf, graphml"),
+        }
+    }
+}
+
+/// エクスポートする依存グラフのノード
+struct ExportNode<'a> {
+    id: &'a PackageId,
+    name: &'a str,
+    version: String,
+    /// `workspace`、`path`、`crates-io`、`registry` または `git`
+    source: &'static str,
+    license: Option<&'a str>,
+}
+
+struct ExportEdge<'a> {
+    from: &'a PackageId,
+    to: &'a PackageId,
+    /// `normal`、`build`、`dev` の組み合わせ
+    kinds: Vec<&'static str>,
+    /// プラットフォーム固有の依存関係の場合の `cfg(...)` またはターゲットトリプル
+    targets: Vec<String>,
+    /// 成果物依存関係の場合の成果物の種類とターゲット (`bin@wasm32-unknown-unknown` など)
+    artifacts: Vec<String>,
+}
+
+pub(crate) fn kind_name(kind: &DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+/// パッケージのソースの種類 (`workspace`、`path`、`crates-io`、`registry` または `git`)
+pub(crate) fn source_name(metadata: &Metadata, package: &Package) -> &'static str {
+    match &package.source {
+        None if metadata.workspace_members.contains(&package.id) => "workspace",
+        None => "path",
+        Some(s) if s.is_crates_io() => "crates-io",
+        Some(s) if s.repr.starts_with("git+") => "git",
+        Some(_) => "registry",
+    }
+}
+
+/// ワークスペースメンバーから到達できるパッケージと依存関係の辺を集める
+fn collect<'a>(graph: &Graph<'a>, include_dev: bool) -> (Vec<ExportNode<'a>>, Vec<ExportEdge<'a>>) {
+    let metadata = graph.metadata;
+    let mut ids: BTreeSet<&PackageId> = BTreeSet::new();
+    for member in graph.members() {
+        ids.extend(graph.reachable(member, include_dev));
+    }
+    let nodes = ids
+        .iter()
+        .filter_map(|id| {
+            let package = graph.package(id)?;
+            Some(ExportNode {
+                id,
+                name: &package.name,
+                version: package.version.to_string(),
+                source: source_name(metadata, package),
+                license: package.license.as_deref(),
+            })
+        })
+        .collect();
+    let mut edges = Vec::new();
+    for id in &ids {
+        let Some(package) = graph.package(id) else {
+            continue;
+        };
+        let artifacts = ArtifactDependencies::load(package);
+        for dep in graph.edges(id, include_dev) {
+            let kinds: BTreeSet<&'static str> = if dep.dep_kinds.is_empty() {
+                BTreeSet::from(["normal"])
+            } else {
+                dep.dep_kinds
+                    .iter()
+                    .filter(|k| include_dev || k.kind != DependencyKind::Development)
+                    .map(|k| kind_name(&k.kind))
+                    .collect()
+            };
+            let targets: BTreeSet<String> = dep
+                .dep_kinds
+                .iter()
+                .filter_map(|k| k.target.as_ref().map(|t| t.to_string()))
+                .collect();
+            let name = graph.package(&dep.pkg).map(|p| p.name.as_str());
+            let artifact_labels: BTreeSet<String> = dep
+                .dep_kinds
+                .iter()
+                .filter(|k| include_dev || k.kind != DependencyKind::Development)
+                .filter_map(|k| artifacts.get(k.kind, name?))
+                .map(|a| a.label())
+                .collect();
+            edges.push(ExportEdge {
+                from: id,
+                to: &dep.pkg,
+                kinds: kinds.into_iter().collect(),
+                targets: targets.into_iter().collect(),
+                artifacts: artifact_labels.into_iter().collect(),
+            });
+        }
+    }
+    (nodes, edges)
+}
+
+/// 依存グラフを指定した形式の文字列としてエクスポートする
+///
+/// ノードの ID には cargo のパッケージ ID を使用する。
+pub fn export_graph(metadata: &Metadata, format: GraphFormat, include_dev: bool) -> String {
+    let graph = Graph::new(metadata);
+    let (nodes, edges) = collect(&graph, include_dev);
+    match format {
+        GraphFormat::Jgf => {
+            serde_json::to_string_pretty(&jgf(metadata, &nodes, &edges)).unwrap_or_default()
+        }
+        GraphFormat::GraphMl => graphml(&nodes, &edges),
+    }
+}
+
+fn jgf(metadata: &Metadata, nodes: &[ExportNode], edges: &[ExportEdge]) -> Value {
+    let nodes: serde_json::Map<String, Value> = nodes
+        .iter()
+        .map(|node| {
+            (
+                node.id.repr.clone(),
+                json!({
+                    "label": format!("{}@{}", node.name, node.version),
+                    "metadata": {
+                        "name": node.name,
+                        "version": node.version,
+                        "source": node.source,
+                        "license": node.license,
+                    }
+                }),
+            )
+        })
+        .collect();
+    let edges: Vec<Value> = edges
+        .iter()
+        .map(|edge| {
+            json!({
+                "source": edge.from.repr,
+                "target": edge.to.repr,
+                "relation": edge.kinds.join(","),
+                "metadata": {
+                    "kinds": edge.kinds,
+                    "targets": edge.targets,
+                    "artifacts": edge.artifacts,
+                },
+            })
+        })
+        .collect();
+    json!({
+        "graph": {
+            "id": metadata.workspace_root.as_str(),
+            "type": "cargo-dependencies",
+            "directed": true,
+            "nodes": nodes,
+            "edges": edges,
+        }
+    })
+}
+
+fn graphml(nodes: &[ExportNode], edges: &[ExportEdge]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for (key, target) in [
+        ("label", "node"),
+        ("name", "node"),
+        ("version", "node"),
+        ("source", "node"),
+        ("license", "node"),
+        ("kinds", "edge"),
+        ("targets", "edge"),
+        ("artifacts", "edge"),
+    ] {
+        xml.push_str(&format!(
+            "  <key id=\"{key}\" for=\"{target}\" attr.name=\"{key}\" attr.type=\"string\"/>\n"
+        ));
+    }
+    xml.push_str("  <graph id=\"dependencies\" edgedefault=\"directed\">\n");
+    for node in nodes {
+        xml.push_str(&format!(
+            "    <node id=\"{}\">\n",
+            xml_escape(&node.id.repr)
+        ));
+        let label = format!("{}@{}", node.name, node.version);
+        let mut data = vec![
+            ("label", label.as_str()),
+            ("name", node.name),
+            ("version", node.version.as_str()),
+            ("source", node.source),
+        ];
+        if let Some(license) = node.license {
+            data.push(("license", license));
+        }
+        for (key, value) in data {
+            xml.push_str(&format!(
+                "      <data key=\"{key}\">{}</data>\n",
+                xml_escape(value)
+            ));
+        }
+        xml.push_str("    </node>\n");
+    }
+    for edge in edges {
+        xml.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\">\n",
+            xml_escape(&edge.from.repr),
+            xml_escape(&edge.to.repr)
+        ));
+        xml.push_str(&format!(
+            "      <data key=\"kinds\">{}</data>\n",
+            edge.kinds.join(",")
+        ));
+        if !edge.targets.is_empty() {
+            xml.push_str(&format!(
+                "      <data key=\"targets\">{}</data>\n",
+                xml_escape(&edge.targets.join(","))
+            ));
+        }
+        if !edge.artifacts.is_empty() {
+            xml.push_str(&format!(
+                "      <data key=\"artifacts\">{}</data>\n",
+                xml_escape(&edge.artifacts.join(" "))
+            ));
+        }
+        xml.push_str("    </edge>\n");
+    }
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+/// CSV のフィールド (`,`、`"`、改行を含む場合は `"` で囲む)
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// `dependency_csv` の列
+const CSV_COLUMNS: [&str; 7] = [
+    "name",
+    "version",
+    "relation",
+    "kind",
+    "license",
+    "source",
+    "repository",
+];
+
+/// ワークスペースメンバーから到達できるパッケージを、1 行に 1 パッケージの CSV として表す
+///
+/// `relation` はワークスペースメンバーを `workspace`、ワークスペースメンバーが直接依存しているパッケージを `direct`、それ以外を `transitive` とする。
+/// `kind` は、依存グラフ内でそのパッケージに向かう辺の依存関係の種類 (`normal`、`build`、`dev`) を `;` で区切ったもの。
+/// 列は `detail` で決まり、`summary` は名前、バージョン、関係まで、`normal` はさらに種類とライセンスまで、`full` はすべての列にする。
+pub fn dependency_csv(metadata: &Metadata, include_dev: bool, detail: Detail) -> String {
+    let graph = Graph::new(metadata);
+    let (nodes, edges) = collect(&graph, include_dev);
+    let members: HashSet<&PackageId> = graph.members().collect();
+    let mut kinds: BTreeMap<&PackageId, BTreeSet<&str>> = BTreeMap::new();
+    let mut direct: HashSet<&PackageId> = HashSet::new();
+    for edge in &edges {
+        kinds.entry(edge.to).or_default().extend(&edge.kinds);
+        if members.contains(edge.from) {
+            direct.insert(edge.to);
+        }
+    }
+
+    let mut rows: Vec<(&ExportNode, &Package)> = nodes
+        .iter()
+        .filter_map(|node| Some((node, graph.package(node.id)?)))
+        .collect();
+    rows.sort_by(|a, b| (a.0.name, &a.1.version, a.0.id).cmp(&(b.0.name, &b.1.version, b.0.id)));
+    let columns = match detail {
+        Detail::Summary => 3,
+        Detail::Normal => 5,
+        Detail::Full => CSV_COLUMNS.len(),
+    };
+    let mut csv = format!("{}\n", CSV_COLUMNS[..columns].join(","));
+    for (node, package) in rows {
+        let relation = if members.contains(node.id) {
+            "workspace"
+        } else if direct.contains(node.id) {
+            "direct"
+        } else {
+            "transitive"
+        };
+        let kind: Vec<&str> = kinds
+            .get(node.id)
+            .map(|k| k.iter().copied().collect())
+            .unwrap_or_default();
+        let fields = [
+            node.name,
+            &node.version,
+            relation,
+            &kind.join(";"),
+            node.license.unwrap_or_default(),
+            node.source,
+            package.repository.as_deref().unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields[..columns].iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_export_graph() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let jgf: Value =
+            serde_json::from_str(&export_graph(&metadata, GraphFormat::Jgf, false)).unwrap();
+        let graph = &jgf["graph"];
+        assert_eq!(graph["directed"], true);
+        let nodes = graph["nodes"].as_object().unwrap();
+        let edges = graph["edges"].as_array().unwrap();
+        assert!(nodes.values().any(|n| n["metadata"]["name"] == "serde"));
+        assert!(
+            edges
+                .iter()
+                .all(|e| nodes.contains_key(e["source"].as_str().unwrap())
+                    && nodes.contains_key(e["target"].as_str().unwrap()))
+        );
+
+        let graphml = export_graph(&metadata, GraphFormat::GraphMl, false);
+        assert_eq!(graphml.matches("<node ").count(), nodes.len());
+        assert_eq!(graphml.matches("<edge ").count(), edges.len());
+    }
+
+    #[test]
+    fn test_dependency_csv() {
+        assert_eq!(csv_field("MIT"), "MIT");
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        assert_eq!(
+            dependency_csv(&metadata, false, Detail::Full),
+            "name,version,relation,kind,license,source,repository\n\
+             app,0.2.0,workspace,,MIT,workspace,\n\
+             fixture-core,0.1.0,workspace,normal,MIT OR Apache-2.0,workspace,\n"
+        );
+        assert_eq!(
+            dependency_csv(&metadata, false, Detail::Summary),
+            "name,version,relation\n\
+             app,0.2.0,workspace\n\
+             fixture-core,0.1.0,workspace\n"
+        );
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let csv = dependency_csv(&metadata, false, Detail::Full);
+        let serde = csv.lines().find(|l| l.starts_with("serde,")).unwrap();
+        assert!(serde.contains(",direct,normal,"));
+        assert!(serde.contains(",MIT OR Apache-2.0,"));
+        assert!(serde.contains(",crates-io,"));
+    }
+}
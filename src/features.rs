@@ -0,0 +1,482 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use cargo_metadata::{Metadata, Package, PackageId};
+use serde::Serialize;
+
+use crate::explain::describe_step;
+use crate::graph::Graph;
+use crate::hakari::expand_features;
+
+/// フィーチャーの統合 (unification) 後に、パッケージで実際に有効になったフィーチャー
+#[derive(Serialize)]
+pub struct ResolvedFeatures {
+    /// `name@version`
+    pub package: String,
+    pub pkgid: String,
+    pub workspace_member: bool,
+    /// 有効になったフィーチャー
+    pub enabled: Vec<String>,
+    /// 宣言されているが有効になっていないフィーチャー
+    pub disabled: Vec<String>,
+    /// `default` フィーチャーが有効かどうか
+    pub default_enabled: bool,
+}
+
+/// resolve グラフのノードごとに、有効になったフィーチャーと有効になっていないフィーチャーを返す
+///
+/// `packages` を指定した場合はそれらのパッケージだけを返す。結果はワークスペースメンバー、名前、バージョンの順に並べる。
+pub fn resolved_features(
+    metadata: &Metadata,
+    packages: Option<&[&Package]>,
+) -> Vec<ResolvedFeatures> {
+    let graph = Graph::new(metadata);
+    let mut result: Vec<ResolvedFeatures> = metadata
+        .resolve
+        .iter()
+        .flat_map(|r| &r.nodes)
+        .filter(|node| packages.is_none_or(|packages| packages.iter().any(|p| p.id == node.id)))
+        .filter_map(|node| {
+            let package = graph.package(&node.id)?;
+            let mut enabled = node.features.clone();
+            enabled.sort();
+            let mut disabled: Vec<String> = package
+                .features
+                .keys()
+                .filter(|f| !enabled.contains(f))
+                .cloned()
+                .collect();
+            disabled.sort();
+            Some(ResolvedFeatures {
+                package: format!("{}@{}", package.name, package.version),
+                pkgid: package.id.repr.clone(),
+                workspace_member: metadata.workspace_members.contains(&package.id),
+                default_enabled: enabled.iter().any(|f| f == "default"),
+                enabled,
+                disabled,
+            })
+        })
+        .collect();
+    result.sort_by(|a, b| {
+        b.workspace_member
+            .cmp(&a.workspace_member)
+            .then_with(|| a.package.cmp(&b.package))
+    });
+    result
+}
+
+/// 複数の依存元から要求されている依存関係のフィーチャーの統合の結果
+#[derive(Serialize)]
+pub struct FeatureUnification {
+    /// `name@version`
+    pub package: String,
+    /// 統合後に有効になったフィーチャー
+    pub unified: Vec<String>,
+    pub requests: Vec<FeatureRequest>,
+}
+
+/// 依存元が要求しているフィーチャー
+#[derive(Serialize)]
+pub struct FeatureRequest {
+    /// `name@version`
+    pub dependent: String,
+    pub workspace_member: bool,
+    /// Cargo.toml で要求しているフィーチャー (`default` は既定のフィーチャーを使う場合)
+    pub requested: Vec<String>,
+    /// 他の依存元は要求しておらず、この依存元の要求だけで有効になっているフィーチャー
+    pub exclusive: Vec<String>,
+}
+
+/// 複数の依存元から要求されている依存関係ごとに、依存元が要求するフィーチャーと統合後のフィーチャーを比べる
+///
+/// ある依存元の要求 (`tokio/full` など) だけで有効になっているフィーチャーがある依存関係を、
+/// そのようなフィーチャーが多い順に返す。
+pub fn unification(graph: &Graph, include_dev: bool) -> Vec<FeatureUnification> {
+    let mut reachable: HashSet<&PackageId> = HashSet::new();
+    for member in graph.members() {
+        reachable.extend(graph.reachable(member, include_dev));
+    }
+    let mut requests: HashMap<&PackageId, Vec<(&Package, Vec<String>)>> = HashMap::new();
+    for id in &reachable {
+        let Some(from) = graph.package(id) else {
+            continue;
+        };
+        for edge in graph.edges(id, include_dev) {
+            if let Some(to) = graph.package(&edge.pkg) {
+                let step = describe_step(graph, from, to, edge);
+                requests
+                    .entry(&to.id)
+                    .or_default()
+                    .push((from, step.features));
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    for (id, requests) in requests {
+        let (Some(package), Some(node)) = (graph.package(id), graph.node(id)) else {
+            continue;
+        };
+        if requests.len() < 2 {
+            continue;
+        }
+        let expanded: Vec<BTreeSet<String>> = requests
+            .iter()
+            .map(|(_, requested)| expand_features(package, requested))
+            .collect();
+        let requests: Vec<FeatureRequest> = requests
+            .iter()
+            .enumerate()
+            .map(|(i, (dependent, requested))| {
+                let others: BTreeSet<&String> = expanded
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .flat_map(|(_, features)| features)
+                    .collect();
+                FeatureRequest {
+                    dependent: format!("{}@{}", dependent.name, dependent.version),
+                    workspace_member: graph.metadata.workspace_members.contains(&dependent.id),
+                    requested: requested.clone(),
+                    exclusive: expanded[i]
+                        .iter()
+                        .filter(|f| !others.contains(f) && node.features.contains(f))
+                        .cloned()
+                        .collect(),
+                }
+            })
+            .collect();
+        if requests.iter().all(|r| r.exclusive.is_empty()) {
+            continue;
+        }
+        let mut unified = node.features.clone();
+        unified.sort();
+        let mut requests = requests;
+        requests.sort_by(|a, b| {
+            b.exclusive
+                .len()
+                .cmp(&a.exclusive.len())
+                .then_with(|| a.dependent.cmp(&b.dependent))
+        });
+        result.push(FeatureUnification {
+            package: format!("{}@{}", package.name, package.version),
+            unified,
+            requests,
+        });
+    }
+    let exclusive =
+        |u: &FeatureUnification| u.requests.iter().map(|r| r.exclusive.len()).sum::<usize>();
+    result.sort_by(|a, b| {
+        exclusive(b)
+            .cmp(&exclusive(a))
+            .then_with(|| a.package.cmp(&b.package))
+    });
+    result
+}
+
+/// 宣言されているフィーチャーと、ソースコードの `feature = "..."` による参照の突き合わせの結果
+#[derive(Serialize)]
+pub struct FeatureUsage {
+    /// `name@version`
+    pub package: String,
+    /// 走査したソースファイルの数
+    pub scanned_files: usize,
+    /// 宣言されているが、ソースコードから参照されていないフィーチャー (`default` を除く)
+    pub unreferenced: Vec<UnreferencedFeature>,
+    /// ソースコードから参照されているが、宣言されていないフィーチャー
+    pub undeclared: Vec<FeatureReference>,
+}
+
+#[derive(Serialize)]
+pub struct UnreferencedFeature {
+    pub feature: String,
+    /// このフィーチャーが有効にするフィーチャーと依存関係 (他のフィーチャーをまとめるだけのフィーチャーは参照されなくてもよい)
+    pub enables: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct FeatureReference {
+    pub feature: String,
+    /// 参照している箇所 (`path:line`)
+    pub locations: Vec<String>,
+}
+
+/// 行に含まれる `feature = "..."` のフィーチャー名 (`target_feature` などは含めない)
+fn feature_references(line: &str) -> Vec<&str> {
+    let mut features = Vec::new();
+    let mut rest = line;
+    while let Some(i) = rest.find("feature") {
+        let before = rest[..i].chars().next_back();
+        let after = rest[i + "feature".len()..].trim_start();
+        rest = &rest[i + "feature".len()..];
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        if let Some(value) = value.strip_prefix('"')
+            && let Some((name, _)) = value.split_once('"')
+        {
+            features.push(name);
+        }
+    }
+    features
+}
+
+/// `dir` 以下の `.rs` ファイル (隠しディレクトリと `target` ディレクトリは辿らない)
+fn rust_files(dir: &Utf8Path, files: &mut BTreeSet<Utf8PathBuf>) {
+    let Ok(entries) = dir.read_dir_utf8() else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                rust_files(entry.path(), files);
+            }
+        } else if name.ends_with(".rs") {
+            files.insert(entry.path().to_path_buf());
+        }
+    }
+}
+
+/// パッケージのターゲットのソースファイル
+///
+/// ビルドスクリプトはそのファイルだけを、その他のターゲットはルートのソースファイルのディレクトリ以下を走査する。
+pub(crate) fn source_files(package: &Package) -> BTreeSet<Utf8PathBuf> {
+    let mut files = BTreeSet::new();
+    for target in &package.targets {
+        if target.kind.iter().any(|k| k == "custom-build") {
+            files.insert(target.src_path.clone());
+        } else if let Some(dir) = target.src_path.parent() {
+            rust_files(dir, &mut files);
+        }
+    }
+    files
+}
+
+/// パッケージが宣言しているフィーチャーと、ソースコードの `cfg(feature = "...")` などによる参照を突き合わせる
+///
+/// 行コメントの行は無視する。
+pub fn feature_usage(package: &Package) -> FeatureUsage {
+    let files = source_files(package);
+    let mut references: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let base = package.manifest_path.parent();
+    for file in &files {
+        let Ok(text) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let display = base
+            .and_then(|base| file.strip_prefix(base).ok())
+            .unwrap_or(file);
+        for (i, line) in text.lines().enumerate() {
+            if line.trim_start().starts_with("//") {
+                continue;
+            }
+            for feature in feature_references(line) {
+                references
+                    .entry(feature.to_string())
+                    .or_default()
+                    .push(format!("{display}:{}", i + 1));
+            }
+        }
+    }
+    let mut unreferenced: Vec<UnreferencedFeature> = package
+        .features
+        .iter()
+        .filter(|(feature, _)| *feature != "default" && !references.contains_key(*feature))
+        .map(|(feature, enables)| UnreferencedFeature {
+            feature: feature.clone(),
+            enables: enables.clone(),
+        })
+        .collect();
+    unreferenced.sort_by(|a, b| a.feature.cmp(&b.feature));
+    FeatureUsage {
+        package: format!("{}@{}", package.name, package.version),
+        scanned_files: files.len(),
+        unreferenced,
+        undeclared: references
+            .into_iter()
+            .filter(|(feature, _)| !package.features.contains_key(feature))
+            .map(|(feature, locations)| FeatureReference { feature, locations })
+            .collect(),
+    }
+}
+
+/// フィーチャーを有効にしたときに、合わせて有効になるフィーチャーと依存関係
+#[derive(Serialize)]
+pub struct FeatureDependencies {
+    pub feature: String,
+    /// フィーチャーのテーブルに直接書かれているフィーチャー
+    pub features: Vec<String>,
+    /// 推移的に有効になるフィーチャー (`features` を含み、このフィーチャー自身は含まない)
+    pub transitive_features: Vec<String>,
+    /// 推移的に有効になる optional な依存関係 (`dep:name`、`name/feature` または暗黙のフィーチャーによるもの)
+    pub optional_dependencies: Vec<String>,
+    /// 推移的に有効になる依存関係のフィーチャー (`name/feature`、`name?/feature`)
+    pub dependency_features: Vec<String>,
+}
+
+/// パッケージのフィーチャーのテーブルを、フィーチャーごとに有効になるフィーチャーと依存関係に展開する
+pub fn feature_dependency_map(package: &Package) -> Vec<FeatureDependencies> {
+    let optional: HashSet<&str> = package
+        .dependencies
+        .iter()
+        .filter(|d| d.optional)
+        .map(|d| d.rename.as_deref().unwrap_or(&d.name))
+        .collect();
+    package
+        .features
+        .iter()
+        .map(|(feature, entries)| {
+            let mut transitive = expand_features(package, std::slice::from_ref(feature));
+            transitive.remove(feature);
+            let mut optional_dependencies = BTreeSet::new();
+            let mut dependency_features = BTreeSet::new();
+            let all = std::iter::once(feature).chain(&transitive);
+            for entry in all.flat_map(|f| package.features.get(f).into_iter().flatten()) {
+                if let Some(dep) = entry.strip_prefix("dep:") {
+                    optional_dependencies.insert(dep.to_string());
+                } else if let Some((dep, _)) = entry.split_once('/') {
+                    if optional.contains(dep) {
+                        optional_dependencies.insert(dep.to_string());
+                    }
+                    dependency_features.insert(entry.clone());
+                }
+            }
+            FeatureDependencies {
+                feature: feature.clone(),
+                features: entries
+                    .iter()
+                    .filter(|e| package.features.contains_key(*e))
+                    .cloned()
+                    .collect(),
+                transitive_features: transitive.into_iter().collect(),
+                optional_dependencies: optional_dependencies.into_iter().collect(),
+                dependency_features: dependency_features.into_iter().collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempWorkspace;
+
+    #[test]
+    fn test_resolved_features() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let features = resolved_features(&metadata, None);
+        assert_eq!(features.len(), 2);
+        let app = &features[0];
+        assert_eq!(app.package, "app@0.2.0");
+        assert_eq!(app.enabled, ["cli", "default"]);
+        assert!(app.default_enabled);
+        let core = &features[1];
+        assert!(core.enabled.is_empty());
+        assert!(!core.default_enabled);
+
+        let only_core = resolved_features(&metadata, Some(&[&metadata.packages[1]]));
+        assert_eq!(only_core.len(), 1);
+        assert_eq!(only_core[0].package, "fixture-core@0.1.0");
+    }
+
+    #[test]
+    fn test_unification() {
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let unification = unification(&Graph::new(&metadata), false);
+        // このクレートの `tokio/full` は、他の依存元が要求していないフィーチャーを有効にしている
+        let tokio = unification
+            .iter()
+            .find(|u| u.package.starts_with("tokio@"))
+            .unwrap();
+        let request = tokio.requests.iter().find(|r| r.workspace_member).unwrap();
+        assert!(request.requested.contains(&"full".to_string()));
+        assert!(!request.exclusive.is_empty());
+        assert!(request.exclusive.iter().all(|f| tokio.unified.contains(f)));
+        assert!(unification.iter().all(|u| u.requests.len() >= 2));
+    }
+
+    #[test]
+    fn test_feature_references() {
+        assert_eq!(
+            feature_references(r#"#[cfg(all(feature = "a", not(feature="b")))]"#),
+            ["a", "b"]
+        );
+        assert_eq!(
+            feature_references(r#"if cfg!(target_feature = "avx2") {}"#),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            feature_references(r#"#[cfg_attr(feature = "serde", derive(Serialize))]"#),
+            ["serde"]
+        );
+    }
+
+    #[test]
+    fn test_feature_usage() {
+        let workspace = TempWorkspace::new("feature-usage");
+        workspace.write(
+            "Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[features]\ndefault = [\"used\"]\nused = []\nunused = []\n",
+        );
+        workspace.write(
+            "src/lib.rs",
+            "#[cfg(feature = \"used\")]\nmod inner;\n// #[cfg(feature = \"unused\")]\n",
+        );
+        workspace.write("src/inner.rs", "#[cfg(feature = \"missing\")]\nfn f() {}\n");
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(workspace.manifest_path())
+            .no_deps()
+            .exec()
+            .unwrap();
+        let usage = feature_usage(metadata.root_package().unwrap());
+        assert_eq!(usage.scanned_files, 2);
+        let unreferenced: Vec<&str> = usage
+            .unreferenced
+            .iter()
+            .map(|f| f.feature.as_str())
+            .collect();
+        assert_eq!(unreferenced, ["unused"]);
+        assert_eq!(usage.undeclared.len(), 1);
+        assert_eq!(usage.undeclared[0].feature, "missing");
+        assert_eq!(usage.undeclared[0].locations, ["src/inner.rs:1"]);
+    }
+
+    #[test]
+    fn test_feature_dependency_map() {
+        let mut metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let app = &mut metadata.packages[0];
+        for dep in &mut app.dependencies {
+            dep.optional = true;
+        }
+        app.features = [
+            ("default", vec!["cli"]),
+            ("cli", vec!["core", "serde?/derive"]),
+            ("core", vec!["dep:fixture-core"]),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.into_iter().map(String::from).collect()))
+        .collect();
+
+        let map = feature_dependency_map(app);
+        let features: Vec<&str> = map.iter().map(|f| f.feature.as_str()).collect();
+        assert_eq!(features, ["cli", "core", "default"]);
+        let default = &map[2];
+        assert_eq!(default.features, ["cli"]);
+        assert_eq!(default.transitive_features, ["cli", "core"]);
+        assert_eq!(default.optional_dependencies, ["fixture-core"]);
+        assert_eq!(default.dependency_features, ["serde?/derive"]);
+        assert!(map[1].dependency_features.is_empty());
+    }
+}
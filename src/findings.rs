@@ -0,0 +1,357 @@
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use cargo_metadata::{Metadata, Package};
+use mcp_attr::Result;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::render::ValueFormat;
+
+/// チェック系ツールの出力形式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 検出結果の JSON を、共通の出力形式 (`json`、`toml` など) で返す
+    Value(ValueFormat),
+    Sarif,
+    Junit,
+}
+
+impl OutputFormat {
+    pub fn parse(format: Option<&str>) -> Result<Self> {
+        match format.unwrap_or("json") {
+            "sarif" => Ok(Self::Sarif),
+            "junit" => Ok(Self::Junit),
+            other => match ValueFormat::from_name(other) {
+                Some(format) => Ok(Self::Value(format)),
+                None => bail_kind!(
+                    ErrorKind::UnknownFormat,
+                    other,
+                    format!("{}, sarif, junit", ValueFormat::NAMES)
+                ),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Note => "note",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// チェック系ツールが報告するルール
+#[derive(Serialize, Clone)]
+pub struct Rule {
+    pub id: String,
+    pub description: String,
+    pub help_uri: Option<String>,
+}
+
+/// チェック系ツールが報告する個々の検出結果
+#[derive(Serialize, Clone)]
+pub struct Finding {
+    pub rule: String,
+    pub level: Level,
+    pub message: String,
+    pub package: Option<String>,
+    /// 検出結果の位置を示すファイル (Cargo.toml または Cargo.lock)
+    pub file: Option<Utf8PathBuf>,
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dependency_path: Vec<String>,
+}
+
+/// 1 回のチェックで得られたルールと検出結果の集合
+pub struct FindingSet {
+    /// チェックを行ったツール名 (SARIF の automationDetails に使用する)
+    pub tool: &'static str,
+    pub rules: Vec<Rule>,
+    pub findings: Vec<Finding>,
+}
+
+impl FindingSet {
+    pub fn new(tool: &'static str) -> Self {
+        Self {
+            tool,
+            rules: Vec::new(),
+            findings: Vec::new(),
+        }
+    }
+
+    /// ルールを登録する (同じ ID のルールは一度だけ登録される)
+    pub fn rule(&mut self, id: &str, description: &str, help_uri: Option<&str>) {
+        if !self.rules.iter().any(|r| r.id == id) {
+            self.rules.push(Rule {
+                id: id.to_string(),
+                description: description.to_string(),
+                help_uri: help_uri.map(str::to_string),
+            });
+        }
+    }
+
+    pub fn push(&mut self, finding: Finding) {
+        self.findings.push(finding);
+    }
+
+    /// 別のチェックのルールと検出結果を取り込む
+    pub fn extend(&mut self, other: FindingSet) {
+        for rule in other.rules {
+            if !self.rules.iter().any(|r| r.id == rule.id) {
+                self.rules.push(rule);
+            }
+        }
+        self.findings.extend(other.findings);
+    }
+}
+
+#[derive(Serialize)]
+pub struct FindingReport<'a> {
+    pub passed: bool,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub note_count: usize,
+    pub findings: &'a [Finding],
+}
+
+impl<'a> FindingReport<'a> {
+    pub fn new(set: &'a FindingSet) -> Self {
+        let count = |level| set.findings.iter().filter(|f| f.level == level).count();
+        let error_count = count(Level::Error);
+        Self {
+            passed: error_count == 0,
+            error_count,
+            warning_count: count(Level::Warning),
+            note_count: count(Level::Note),
+            findings: &set.findings,
+        }
+    }
+}
+
+/// 検出結果を SARIF 2.1.0 のログに変換する
+///
+/// ファイルの位置はワークスペースルートからの相対パス (`%SRCROOT%` 基準) で出力する。
+pub fn sarif(metadata: &Metadata, set: &FindingSet) -> Value {
+    let root = &metadata.workspace_root;
+    let rules: Vec<Value> = set
+        .rules
+        .iter()
+        .map(|rule| {
+            let mut value = json!({
+                "id": rule.id,
+                "shortDescription": { "text": rule.description },
+            });
+            if let Some(uri) = &rule.help_uri {
+                value["helpUri"] = json!(uri);
+            }
+            value
+        })
+        .collect();
+    let results: Vec<Value> = set
+        .findings
+        .iter()
+        .map(|finding| {
+            let mut result = json!({
+                "ruleId": finding.rule,
+                "ruleIndex": set.rules.iter().position(|r| r.id == finding.rule),
+                "level": finding.level.as_str(),
+                "message": { "text": finding.message },
+            });
+            if let Some(file) = &finding.file {
+                let mut location = match file.strip_prefix(root) {
+                    Ok(relative) => json!({
+                        "artifactLocation": { "uri": relative.as_str(), "uriBaseId": "%SRCROOT%" }
+                    }),
+                    Err(_) => json!({
+                        "artifactLocation": { "uri": format!("file://{file}") }
+                    }),
+                };
+                if let Some(line) = finding.line {
+                    location["region"] = json!({ "startLine": line });
+                }
+                result["locations"] = json!([{ "physicalLocation": location }]);
+            }
+            if let Some(package) = &finding.package {
+                result["properties"] = json!({ "package": package });
+            }
+            result
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "automationDetails": { "id": format!("{}/", set.tool) },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": format!("file://{root}/") }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// 検出結果を JUnit XML に変換する
+///
+/// ルールごとに 1 つのテストケースを出力し、エラーレベルの検出結果があるルールを失敗とする。
+/// ワーニングとノートは失敗扱いにせず、`system-out` に出力する。
+pub fn junit(set: &FindingSet) -> String {
+    let failures = set
+        .rules
+        .iter()
+        .filter(|rule| {
+            set.findings
+                .iter()
+                .any(|f| f.rule == rule.id && f.level == Level::Error)
+        })
+        .count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+        xml_escape(env!("CARGO_PKG_NAME")),
+        set.rules.len(),
+        failures
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n",
+        xml_escape(set.tool),
+        set.rules.len(),
+        failures
+    ));
+    for rule in &set.rules {
+        let findings: Vec<&Finding> = set.findings.iter().filter(|f| f.rule == rule.id).collect();
+        let errors: Vec<&&Finding> = findings
+            .iter()
+            .filter(|f| f.level == Level::Error)
+            .collect();
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\">\n",
+            xml_escape(set.tool),
+            xml_escape(&rule.id)
+        ));
+        if !errors.is_empty() {
+            let body: Vec<String> = errors.iter().map(|f| describe(f)).collect();
+            xml.push_str(&format!(
+                "      <failure message=\"{}\" type=\"error\">{}</failure>\n",
+                xml_escape(&format!(
+                    "{}: {} violation(s)",
+                    rule.description,
+                    errors.len()
+                )),
+                xml_escape(&body.join("\n"))
+            ));
+        }
+        let others: Vec<String> = findings
+            .iter()
+            .filter(|f| f.level != Level::Error)
+            .map(|f| format!("[{}] {}", f.level.as_str(), describe(f)))
+            .collect();
+        if !others.is_empty() {
+            xml.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                xml_escape(&others.join("\n"))
+            ));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn describe(finding: &Finding) -> String {
+    let mut text = finding.message.clone();
+    if let Some(file) = &finding.file {
+        text.push_str(&format!(" ({file}"));
+        if let Some(line) = finding.line {
+            text.push_str(&format!(":{line}"));
+        }
+        text.push(')');
+    }
+    text
+}
+
+pub fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// TOML ファイル中で指定したキー (またはテーブルヘッダ) が記述された行の行番号 (1 始まり) を返す
+pub fn find_key_line(path: &Utf8Path, key: &str) -> Option<usize> {
+    let text = std::fs::read_to_string(path).ok()?;
+    text.lines()
+        .position(|line| {
+            line.trim_start()
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '=', '.']))
+        })
+        .map(|i| i + 1)
+}
+
+/// Cargo.lock 内でパッケージのエントリが始まる位置を返す
+pub fn lockfile_location(metadata: &Metadata, package: &Package) -> (Utf8PathBuf, Option<usize>) {
+    let path = metadata.workspace_root.join("Cargo.lock");
+    let name_line = format!("name = \"{}\"", package.name);
+    let version_line = format!("version = \"{}\"", package.version);
+    let line = std::fs::read_to_string(&path).ok().and_then(|text| {
+        let lines: Vec<&str> = text.lines().collect();
+        lines
+            .windows(2)
+            .position(|w| w[0] == name_line && w[1] == version_line)
+            .map(|i| i + 1)
+    });
+    (path, line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junit() {
+        let mut set = FindingSet::new("test-tool");
+        set.rule("rule/a", "Rule <A>", None);
+        set.rule("rule/b", "Rule B", None);
+        set.push(Finding {
+            rule: "rule/a".to_string(),
+            level: Level::Error,
+            message: "a & b".to_string(),
+            package: None,
+            file: Some("Cargo.toml".into()),
+            line: Some(3),
+            dependency_path: Vec::new(),
+        });
+        let xml = junit(&set);
+        assert!(xml.contains(r#"<testsuite name="test-tool" tests="2" failures="1""#));
+        assert!(xml.contains(r#"message="Rule &lt;A&gt;: 1 violation(s)""#));
+        assert!(xml.contains("a &amp; b (Cargo.toml:3)</failure>"));
+        assert!(xml.contains(r#"<testcase classname="test-tool" name="rule/b">"#));
+    }
+}
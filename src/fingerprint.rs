@@ -0,0 +1,317 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use cargo_metadata::Metadata;
+use jiff::Timestamp;
+use serde::Serialize;
+use serde_json::Value;
+
+/// 理由に列挙する変更されたファイルの最大数
+const MAX_CHANGED_FILES: usize = 20;
+
+#[derive(Serialize)]
+pub struct FingerprintReport {
+    pub profile_dir: String,
+    pub units: Vec<UnitFreshness>,
+}
+
+/// ワークスペースのメンバーの 1 つのビルド単位 (`lib`、`bin`、`test-bin` など) の状態
+#[derive(Serialize)]
+pub struct UnitFreshness {
+    pub package: String,
+    /// フィンガープリントのファイル名から取り出した単位名 (例: `bin-foo`、`test-lib-foo`)
+    pub unit: String,
+    /// 最後のビルドが開始された日時
+    pub built_at: Option<String>,
+    /// 最後のビルドで有効だったフィーチャー
+    pub features: Vec<String>,
+    pub rustflags: Vec<String>,
+    /// 再ビルドされないと推定されるかどうか
+    pub fresh: bool,
+    pub reasons: Vec<String>,
+    /// フィーチャー、プロファイル、RUSTFLAGS などの組み合わせが異なるビルドの数
+    ///
+    /// 2 以上の場合は、異なる設定でのビルド (例: `cargo build` と `cargo clippy`、メンバーごとの異なるフィーチャー) が交互に行われ、再ビルドの原因になっている可能性がある。
+    pub variants: usize,
+}
+
+/// ターゲットディレクトリにある cargo のフィンガープリントから、ワークスペースのメンバーが再ビルドされるかを推定する
+///
+/// 最後のビルド以降に変更されたソースファイルと、ビルド時と値が異なる環境変数・RUSTFLAGS を理由として報告する。
+/// フィンガープリントの形式は cargo の内部仕様であり、依存クレートの変更や rustc の更新は検出できないため、結果は推定となる。
+pub fn fingerprint_report(metadata: &Metadata, profile: &str) -> FingerprintReport {
+    let profile_dir = metadata.target_directory.as_std_path().join(profile);
+    let workspace_root = metadata.workspace_root.as_std_path();
+    let current_rustflags = current_rustflags();
+    let mut units = Vec::new();
+
+    for package in metadata.workspace_packages() {
+        let mut fingerprints: BTreeMap<String, Vec<Fingerprint>> = BTreeMap::new();
+        let targets: Vec<&str> = package.targets.iter().map(|t| t.name.as_str()).collect();
+        for dir in package_fingerprint_dirs(&profile_dir, &package.name) {
+            for fingerprint in read_fingerprints(&dir, &targets) {
+                fingerprints
+                    .entry(fingerprint.unit.clone())
+                    .or_default()
+                    .push(fingerprint);
+            }
+        }
+        for (unit, mut variants) in fingerprints {
+            variants.sort_by_key(|f| f.invoked);
+            let count = variants
+                .iter()
+                .map(|f| &f.configuration)
+                .collect::<BTreeSet<_>>()
+                .len();
+            let Some(latest) = variants.pop() else {
+                continue;
+            };
+            let mut reasons = Vec::new();
+            let dep_info = read_dep_info(&profile_dir, &latest);
+            match &dep_info {
+                Some(dep_info) => {
+                    let changed: Vec<String> = dep_info
+                        .files
+                        .iter()
+                        .filter(|file| {
+                            let path = workspace_root.join(file);
+                            match (modified(&path), latest.invoked) {
+                                (Some(modified), Some(invoked)) => modified > invoked,
+                                (None, _) => true,
+                                _ => false,
+                            }
+                        })
+                        .cloned()
+                        .collect();
+                    if !changed.is_empty() {
+                        let mut files: Vec<&str> = changed
+                            .iter()
+                            .take(MAX_CHANGED_FILES)
+                            .map(|f| f.as_str())
+                            .collect();
+                        if changed.len() > MAX_CHANGED_FILES {
+                            files.push("...");
+                        }
+                        reasons.push(format!(
+                            "{} source file(s) changed or removed since the last build: {}",
+                            changed.len(),
+                            files.join(", ")
+                        ));
+                    }
+                    for (name, value) in &dep_info.env {
+                        let current = std::env::var(name).ok();
+                        if &current != value {
+                            reasons.push(format!(
+                                "environment variable `{name}` changed (built with {}, now {})",
+                                describe(value.as_deref()),
+                                describe(current.as_deref())
+                            ));
+                        }
+                    }
+                }
+                None => reasons.push("no dep-info file was found for the last build".to_string()),
+            }
+            if latest.rustflags != current_rustflags {
+                reasons.push(format!(
+                    "RUSTFLAGS changed (built with {:?}, now {:?})",
+                    latest.rustflags, current_rustflags
+                ));
+            }
+            units.push(UnitFreshness {
+                package: package.name.clone(),
+                unit,
+                built_at: latest
+                    .invoked
+                    .and_then(|t| Timestamp::try_from(t).ok())
+                    .map(|t| t.to_string()),
+                features: latest.features,
+                rustflags: latest.rustflags,
+                fresh: reasons.is_empty(),
+                reasons,
+                variants: count,
+            });
+        }
+    }
+    FingerprintReport {
+        profile_dir: profile_dir.display().to_string(),
+        units,
+    }
+}
+
+struct Fingerprint {
+    unit: String,
+    /// フィンガープリントのディレクトリ名のハッシュ (`deps` 以下の dep-info ファイルと共通)
+    hash: String,
+    crate_name: String,
+    invoked: Option<SystemTime>,
+    features: Vec<String>,
+    rustflags: Vec<String>,
+    /// ビルドの設定 (フィーチャー、プロファイル、RUSTFLAGS など) を表す値
+    configuration: String,
+}
+
+/// `.fingerprint` 以下の、パッケージのフィンガープリントのディレクトリ (`<package>-<hash>`)
+fn package_fingerprint_dirs(profile_dir: &Path, package: &str) -> Vec<PathBuf> {
+    let prefix = format!("{package}-");
+    std::fs::read_dir(profile_dir.join(".fingerprint"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix(&prefix)
+                .is_some_and(|hash| !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// フィンガープリントのディレクトリにある、各ビルド単位のフィンガープリント
+///
+/// 単位名は `<種類>-<ターゲット名>` の形式のため、`targets` のいずれかで終わるものを対象とする。
+fn read_fingerprints(dir: &Path, targets: &[&str]) -> Vec<Fingerprint> {
+    let hash = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.rsplit_once('-'))
+        .map(|(_, hash)| hash.to_string())
+        .unwrap_or_default();
+    let invoked = modified(&dir.join("invoked.timestamp"));
+    let mut fingerprints = Vec::new();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let Some(unit) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Some(json) = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+        else {
+            continue;
+        };
+        // `features` は JSON の配列を文字列として埋め込んだもの
+        let features = json["features"]
+            .as_str()
+            .and_then(|f| serde_json::from_str(f).ok())
+            .unwrap_or_default();
+        let rustflags = json["rustflags"]
+            .as_array()
+            .map(|flags| {
+                flags
+                    .iter()
+                    .filter_map(|f| f.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let Some(target) = targets
+            .iter()
+            .filter(|t| unit.ends_with(&format!("-{t}")))
+            .max_by_key(|t| t.len())
+        else {
+            continue;
+        };
+        let configuration = ["features", "profile", "rustflags", "config", "compile_kind"]
+            .iter()
+            .map(|key| json[key].to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        fingerprints.push(Fingerprint {
+            unit: unit.to_string(),
+            hash: hash.clone(),
+            crate_name: target.replace('-', "_"),
+            invoked,
+            features,
+            rustflags,
+            configuration,
+        });
+    }
+    fingerprints
+}
+
+/// rustc が出力した dep-info ファイル (`deps/<crate>-<hash>.d`) の内容
+struct DepInfo {
+    files: Vec<String>,
+    /// `# env-dep:NAME=value` に記録された環境変数 (cargo が設定する `CARGO_*` を除く)
+    env: Vec<(String, Option<String>)>,
+}
+
+fn read_dep_info(profile_dir: &Path, fingerprint: &Fingerprint) -> Option<DepInfo> {
+    let path = profile_dir
+        .join("deps")
+        .join(format!("{}-{}.d", fingerprint.crate_name, fingerprint.hash));
+    let text = std::fs::read_to_string(path).ok()?;
+    Some(parse_dep_info(&text))
+}
+
+fn parse_dep_info(text: &str) -> DepInfo {
+    let mut files = Vec::new();
+    let mut env = Vec::new();
+    for line in text.lines() {
+        if let Some(dep) = line.strip_prefix("# env-dep:") {
+            let (name, value) = match dep.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (dep, None),
+            };
+            if !name.starts_with("CARGO_") && !name.starts_with("CLIPPY_") {
+                env.push((name.to_string(), value));
+            }
+        } else if let Some(file) = line.strip_suffix(':')
+            && !file.is_empty()
+            && !line.starts_with('#')
+        {
+            // 依存ファイルは `path:` の形式で 1 行ずつ列挙される (空白は `\ ` でエスケープされる)
+            files.push(file.replace("\\ ", " "));
+        }
+    }
+    DepInfo { files, env }
+}
+
+fn current_rustflags() -> Vec<String> {
+    if let Ok(flags) = std::env::var("CARGO_ENCODED_RUSTFLAGS") {
+        return flags
+            .split('\x1f')
+            .filter(|f| !f.is_empty())
+            .map(String::from)
+            .collect();
+    }
+    std::env::var("RUSTFLAGS")
+        .map(|flags| flags.split_whitespace().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn describe(value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("`{value}`"),
+        None => "unset".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dep_info() {
+        let text = "/t/debug/deps/foo-0123.d: src/main.rs src/my\\ file.rs\n\nsrc/main.rs:\nsrc/my\\ file.rs:\n\n# env-dep:CARGO_PKG_NAME=foo\n# env-dep:API_URL=https://example.com\n# env-dep:DEBUG_MODE\n";
+        let dep_info = parse_dep_info(text);
+        assert_eq!(dep_info.files, ["src/main.rs", "src/my file.rs"]);
+        assert_eq!(
+            dep_info.env,
+            [
+                (
+                    "API_URL".to_string(),
+                    Some("https://example.com".to_string())
+                ),
+                ("DEBUG_MODE".to_string(), None)
+            ]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use cargo_metadata::Metadata;
+use cargo_metadata::semver::Version;
+use jiff::Timestamp;
+use serde::Serialize;
+
+use crate::category;
+use crate::graph::Graph;
+use crate::index;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+const DAYS_PER_YEAR: f64 = 365.25;
+
+#[derive(Serialize)]
+pub struct FreshnessReport {
+    pub generated_at: String,
+    pub summary: FreshnessSummary,
+    pub dependencies: Vec<DependencyFreshness>,
+    /// インデックスを取得できなかったクレートとエラー
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct FreshnessSummary {
+    pub dependency_count: usize,
+    pub up_to_date_count: usize,
+    /// 最新版を使用している直接依存の割合 (0.0 - 1.0)
+    pub up_to_date_ratio: f64,
+    /// 使用中のバージョンが公開されてからの日数の平均値と中央値
+    pub mean_age_days: Option<f64>,
+    pub median_age_days: Option<f64>,
+    /// 使用中のバージョンと最新版の公開日の差 (年単位) の合計
+    pub total_libyears: f64,
+    pub oldest: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DependencyFreshness {
+    pub name: String,
+    pub version: String,
+    pub used_by: Vec<String>,
+    /// 使用中のバージョンの公開日時
+    pub released: Option<String>,
+    /// 使用中のバージョンが公開されてからの日数
+    pub age_days: Option<i64>,
+    pub latest_version: Option<String>,
+    pub latest_released: Option<String>,
+    /// クレートが最後にリリースされてからの日数
+    pub days_since_last_release: Option<i64>,
+    /// 使用中のバージョンより新しく、yank されていないバージョンの数
+    pub versions_behind: usize,
+    pub up_to_date: bool,
+    /// 使用中のバージョンと最新版の公開日の差 (年単位)
+    pub libyears: Option<f64>,
+}
+
+fn days_between(from: Timestamp, to: Timestamp) -> f64 {
+    to.duration_since(from).as_secs() as f64 / SECONDS_PER_DAY
+}
+
+/// 直接依存ごとに、使用中のバージョンの古さと最後のリリースからの経過日数を計算する
+///
+/// 公開日時は crates.io のインデックスに記録されている `pubtime` を使用するため、古いバージョンでは不明になることがある。
+pub fn freshness_report(metadata: &Metadata) -> FreshnessReport {
+    let graph = Graph::new(metadata);
+    let now = Timestamp::now();
+    let mut dependencies = Vec::new();
+    let mut errors = Vec::new();
+
+    for (id, used_by) in category::direct_dependencies(&graph) {
+        let Some(package) = graph.package(id) else {
+            continue;
+        };
+        if !package.source.as_ref().is_some_and(|s| s.is_crates_io()) {
+            continue;
+        }
+        let entries = match index::crate_versions(&package.name) {
+            Ok(entries) => entries,
+            Err(e) => {
+                errors.push(format!(
+                    "{}: {}",
+                    package.name,
+                    e.to_error_object(false).message
+                ));
+                continue;
+            }
+        };
+        let mut versions: Vec<(Version, Option<Timestamp>)> = entries
+            .iter()
+            .filter(|e| !e.yanked)
+            .filter_map(|e| {
+                let version = Version::parse(&e.vers).ok()?;
+                let released = e.pubtime.as_deref().and_then(|t| t.parse().ok());
+                Some((version, released))
+            })
+            .filter(|(v, _)| v.pre.is_empty() || !package.version.pre.is_empty())
+            .collect();
+        versions.sort_by(|a, b| b.0.cmp(&a.0));
+
+        let released = entries
+            .iter()
+            .find(|e| Version::parse(&e.vers).is_ok_and(|v| v == package.version))
+            .and_then(|e| e.pubtime.as_deref())
+            .and_then(|t| t.parse::<Timestamp>().ok());
+        let latest = versions.first();
+        let last_release = versions.iter().filter_map(|(_, t)| *t).max();
+        let versions_behind = versions
+            .iter()
+            .filter(|(v, _)| *v > package.version)
+            .count();
+        let libyears = match (released, latest.and_then(|(_, t)| *t)) {
+            (Some(released), Some(latest)) => {
+                Some((days_between(released, latest) / DAYS_PER_YEAR).max(0.0))
+            }
+            _ => None,
+        };
+        dependencies.push(DependencyFreshness {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            used_by: used_by.into_iter().collect(),
+            released: released.map(|t| t.to_string()),
+            age_days: released.map(|t| days_between(t, now) as i64),
+            latest_version: latest.map(|(v, _)| v.to_string()),
+            latest_released: latest.and_then(|(_, t)| *t).map(|t| t.to_string()),
+            days_since_last_release: last_release.map(|t| days_between(t, now) as i64),
+            versions_behind,
+            up_to_date: versions_behind == 0,
+            libyears,
+        });
+    }
+
+    FreshnessReport {
+        generated_at: now.to_string(),
+        summary: summarize(&dependencies),
+        dependencies,
+        errors,
+    }
+}
+
+fn summarize(dependencies: &[DependencyFreshness]) -> FreshnessSummary {
+    let mut ages: Vec<i64> = dependencies.iter().filter_map(|d| d.age_days).collect();
+    ages.sort();
+    let up_to_date_count = dependencies.iter().filter(|d| d.up_to_date).count();
+    let median_age_days = match ages.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(ages[n / 2] as f64),
+        n => Some((ages[n / 2 - 1] + ages[n / 2]) as f64 / 2.0),
+    };
+    FreshnessSummary {
+        dependency_count: dependencies.len(),
+        up_to_date_count,
+        up_to_date_ratio: if dependencies.is_empty() {
+            1.0
+        } else {
+            up_to_date_count as f64 / dependencies.len() as f64
+        },
+        mean_age_days: (!ages.is_empty())
+            .then(|| ages.iter().sum::<i64>() as f64 / ages.len() as f64),
+        median_age_days,
+        total_libyears: dependencies.iter().filter_map(|d| d.libyears).sum(),
+        oldest: dependencies
+            .iter()
+            .filter(|d| d.age_days.is_some())
+            .max_by_key(|d| d.age_days)
+            .map(|d| format!("{}@{}", d.name, d.version)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(age_days: Option<i64>, up_to_date: bool) -> DependencyFreshness {
+        DependencyFreshness {
+            name: format!("dep{}", age_days.unwrap_or(-1)),
+            version: "1.0.0".to_string(),
+            used_by: Vec::new(),
+            released: None,
+            age_days,
+            latest_version: None,
+            latest_released: None,
+            days_since_last_release: None,
+            versions_behind: if up_to_date { 0 } else { 1 },
+            up_to_date,
+            libyears: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&[
+            dependency(Some(10), true),
+            dependency(Some(30), false),
+            dependency(None, true),
+            dependency(Some(20), true),
+        ]);
+        assert_eq!(summary.dependency_count, 4);
+        assert_eq!(summary.up_to_date_ratio, 0.75);
+        assert_eq!(summary.mean_age_days, Some(20.0));
+        assert_eq!(summary.median_age_days, Some(20.0));
+        assert_eq!(summary.total_libyears, 2.0);
+        assert_eq!(summary.oldest.as_deref(), Some("dep30@1.0.0"));
+    }
+}
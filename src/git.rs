@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use mcp_attr::Result;
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// `dir` で git を実行し、標準出力を返す
+pub fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = match Command::new("git").current_dir(dir).args(args).output() {
+        Ok(output) => output,
+        Err(e) => bail_kind!(ErrorKind::GitFailed, args.join(" "), e),
+    };
+    if !output.status.success() {
+        bail_kind!(
+            ErrorKind::GitFailed,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// リビジョン `rev` をコミットのハッシュに解決する
+///
+/// `-` で始まるリビジョンが git のオプションとして解釈されないよう、`--end-of-options` の後に渡す。
+pub fn resolve_commit(dir: &Path, rev: &str) -> Result<String> {
+    let spec = format!("{rev}^{{commit}}");
+    match git(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            "--end-of-options",
+            &spec,
+        ],
+    ) {
+        Ok(hash) => Ok(hash.trim().to_string()),
+        Err(_) => bail_kind!(ErrorKind::InvalidRevision, rev),
+    }
+}
+
+/// リビジョン `rev` での `path` の内容 (`git show <rev>:<path>`)
+///
+/// `path` はリポジトリのルートからではなく、`dir` からの相対パスとして解釈する。
+pub fn show_file(dir: &Path, rev: &str, path: &str) -> Result<String> {
+    let commit = resolve_commit(dir, rev)?;
+    git(dir, &["show", &format!("{commit}:./{path}")])
+}
+
+/// リビジョンをチェックアウトした一時的な git worktree
+///
+/// 破棄時に `git worktree remove` で削除する。
+pub struct Worktree {
+    repository: PathBuf,
+    dir: PathBuf,
+    /// リポジトリのルートから、worktree を作成したディレクトリへの相対パス
+    prefix: PathBuf,
+}
+
+impl Worktree {
+    /// `dir` を含むリポジトリの `rev` を一時ディレクトリにチェックアウトする
+    pub fn new(dir: &Path, rev: &str) -> Result<Self> {
+        let repository = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+        let prefix = PathBuf::from(git(dir, &["rev-parse", "--show-prefix"])?.trim());
+        let commit = resolve_commit(dir, rev)?;
+        let worktree = std::env::temp_dir().join(format!(
+            "{}-worktree-{}-{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id(),
+            jiff::Timestamp::now().as_nanosecond()
+        ));
+        let path = worktree.to_string_lossy();
+        git(
+            &repository,
+            &["worktree", "add", "--detach", "--quiet", &path, &commit],
+        )?;
+        Ok(Self {
+            repository,
+            dir: worktree,
+            prefix,
+        })
+    }
+
+    /// worktree 内で、作成時に指定したディレクトリに対応するディレクトリ
+    pub fn dir(&self) -> PathBuf {
+        self.dir.join(&self.prefix)
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let path = self.dir.to_string_lossy();
+        if git(&self.repository, &["worktree", "remove", "--force", &path]).is_err() {
+            let _ = std::fs::remove_dir_all(&self.dir);
+            let _ = git(&self.repository, &["worktree", "prune"]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git() {
+        let root = std::env::temp_dir().join(format!("git-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        let run = |args: &[&str]| git(&root, args);
+        let result = (|| {
+            run(&["init", "-q"])?;
+            std::fs::write(root.join("sub/file.txt"), "first\n").unwrap();
+            run(&["add", "."])?;
+            run(&[
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "-m",
+                "first",
+            ])?;
+            std::fs::write(root.join("sub/file.txt"), "second\n").unwrap();
+            let committed = show_file(&root.join("sub"), "HEAD", "file.txt")?;
+            let missing = show_file(&root, "HEAD", "missing.txt");
+            let option = show_file(&root, "--output=injected", "file.txt");
+            let worktree_option = Worktree::new(&root, "--orphan=injected").err();
+            let worktree = Worktree::new(&root.join("sub"), "HEAD")?;
+            let checked_out = std::fs::read_to_string(worktree.dir().join("file.txt")).unwrap();
+            let worktree_dir = worktree.dir();
+            drop(worktree);
+            let injected = root.join("injected").exists();
+            Ok::<_, mcp_attr::Error>((
+                committed,
+                missing,
+                option,
+                worktree_option,
+                injected,
+                checked_out,
+                worktree_dir,
+            ))
+        })();
+        std::fs::remove_dir_all(&root).unwrap();
+        let (committed, missing, option, worktree_option, injected, checked_out, worktree_dir) =
+            result.unwrap();
+        assert_eq!(committed, "first\n");
+        assert_eq!(checked_out, "first\n");
+        assert!(!worktree_dir.exists());
+        let message = missing.err().unwrap().to_error_object(false).message;
+        assert!(message.starts_with("[git_failed]"), "{message}");
+        let message = option.err().unwrap().to_error_object(false).message;
+        assert!(message.starts_with("[invalid_revision]"), "{message}");
+        let message = worktree_option.unwrap().to_error_object(false).message;
+        assert!(message.starts_with("[invalid_revision]"), "{message}");
+        assert!(!injected);
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use cargo_metadata::{DependencyKind, Metadata, Node, NodeDep, Package, PackageId};
+
+use crate::hakari;
+
+/// resolve グラフを辿るためのインデックス
+///
+/// hakari の workspace-hack クレートは依存関係の集計を歪めるため、ワークスペースメンバーとしても依存先としても扱わない。
+pub struct Graph<'a> {
+    pub metadata: &'a Metadata,
+    packages: HashMap<&'a PackageId, &'a Package>,
+    nodes: HashMap<&'a PackageId, &'a Node>,
+    workspace_hack: Option<&'a PackageId>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new(metadata: &'a Metadata) -> Self {
+        let packages = metadata.packages.iter().map(|p| (&p.id, p)).collect();
+        let nodes = metadata
+            .resolve
+            .iter()
+            .flat_map(|r| &r.nodes)
+            .map(|n| (&n.id, n))
+            .collect();
+        Self {
+            metadata,
+            packages,
+            nodes,
+            workspace_hack: hakari::workspace_hack(metadata).map(|p| &p.id),
+        }
+    }
+
+    /// workspace-hack クレートを除くワークスペースメンバー
+    pub fn members(&self) -> impl Iterator<Item = &'a PackageId> + '_ {
+        self.metadata
+            .workspace_members
+            .iter()
+            .filter(|id| Some(*id) != self.workspace_hack)
+    }
+
+    pub fn package(&self, id: &PackageId) -> Option<&'a Package> {
+        self.packages.get(id).copied()
+    }
+
+    pub fn node(&self, id: &PackageId) -> Option<&'a Node> {
+        self.nodes.get(id).copied()
+    }
+
+    /// パッケージを `name@version` 形式で表す
+    pub fn label(&self, id: &PackageId) -> String {
+        match self.package(id) {
+            Some(p) => format!("{}@{}", p.name, p.version),
+            None => id.repr.clone(),
+        }
+    }
+
+    /// 指定したパッケージから出る依存関係の辺を列挙する
+    ///
+    /// `include_dev` が `false` の場合、dev-dependencies としてのみ依存している辺は辿らない。
+    pub fn edges(
+        &self,
+        id: &PackageId,
+        include_dev: bool,
+    ) -> impl Iterator<Item = &'a NodeDep> + '_ {
+        self.node(id)
+            .into_iter()
+            .flat_map(|n| &n.deps)
+            .filter(|d| Some(&d.pkg) != self.workspace_hack)
+            .filter(move |d| {
+                include_dev
+                    || d.dep_kinds.is_empty()
+                    || d.dep_kinds
+                        .iter()
+                        .any(|k| k.kind != DependencyKind::Development)
+            })
+    }
+
+    /// 指定したパッケージが依存しているパッケージを列挙する
+    ///
+    /// `include_dev` が `false` の場合、dev-dependencies としてのみ依存している辺は辿らない。
+    pub fn dependencies(
+        &self,
+        id: &PackageId,
+        include_dev: bool,
+    ) -> impl Iterator<Item = &'a PackageId> + '_ {
+        self.edges(id, include_dev).map(|d| &d.pkg)
+    }
+
+    /// 指定したパッケージから推移的に到達できるパッケージの集合 (指定したパッケージ自身を含む)
+    pub fn reachable(&self, id: &'a PackageId, include_dev: bool) -> HashSet<&'a PackageId> {
+        let mut visited = HashSet::from([id]);
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            for dep in self.dependencies(id, include_dev) {
+                if visited.insert(dep) {
+                    stack.push(dep);
+                }
+            }
+        }
+        visited
+    }
+
+    /// ワークスペースメンバーから指定したパッケージまでの最短の依存パスを探索する
+    ///
+    /// パスはワークスペースメンバーから始まり、指定したパッケージで終わる。
+    pub fn path_to(&self, target: &PackageId, include_dev: bool) -> Option<Vec<&'a PackageId>> {
+        let mut parents: HashMap<&PackageId, Option<&PackageId>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for member in self.members() {
+            parents.insert(member, None);
+            queue.push_back(member);
+        }
+        while let Some(id) = queue.pop_front() {
+            if id == target {
+                let mut path = vec![id];
+                let mut current = id;
+                while let Some(Some(parent)) = parents.get(current) {
+                    path.push(parent);
+                    current = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for dep in self.dependencies(id, include_dev) {
+                if !parents.contains_key(dep) {
+                    parents.insert(dep, Some(id));
+                    queue.push_back(dep);
+                }
+            }
+        }
+        None
+    }
+
+    /// [`Graph::path_to`] の結果を `name@version` の列で返す
+    pub fn path_labels(&self, target: &PackageId, include_dev: bool) -> Vec<String> {
+        self.path_to(target, include_dev)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|id| self.label(id))
+            .collect()
+    }
+}
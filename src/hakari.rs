@@ -0,0 +1,250 @@
+use std::collections::BTreeSet;
+
+use cargo_metadata::{Metadata, Package};
+use serde::Serialize;
+
+use crate::graph::Graph;
+use crate::sandbox::cargo_command;
+
+/// hakari の設定ファイル (ワークスペースルートからの相対パス)
+const CONFIG_FILES: &[&str] = &[".config/hakari.toml", ".guppy/hakari.toml", "hakari.toml"];
+
+/// hakari が生成する依存関係のセクションの開始行
+const SECTION_MARKER: &str = "### BEGIN HAKARI SECTION";
+
+/// hakari の workspace-hack クレートを探す
+///
+/// hakari の設定ファイルの `hakari-package` で指定されたメンバー、
+/// またはマニフェストに hakari が生成したセクションがあるメンバーを workspace-hack とみなす。
+pub fn workspace_hack(metadata: &Metadata) -> Option<&Package> {
+    let members = metadata.workspace_packages();
+    if let Some(name) = configured_package(metadata)
+        && let Some(package) = members.iter().find(|p| p.name == name)
+    {
+        return Some(package);
+    }
+    members.into_iter().find(|p| {
+        std::fs::read_to_string(&p.manifest_path).is_ok_and(|text| text.contains(SECTION_MARKER))
+    })
+}
+
+fn configured_package(metadata: &Metadata) -> Option<String> {
+    CONFIG_FILES.iter().find_map(|file| {
+        let text = std::fs::read_to_string(metadata.workspace_root.join(file)).ok()?;
+        let config: toml::Table = text.parse().ok()?;
+        config.get("hakari-package")?.as_str().map(String::from)
+    })
+}
+
+#[derive(Serialize)]
+pub struct WorkspaceHackReport {
+    /// workspace-hack クレート (見つからない場合は `None`)
+    pub package: Option<String>,
+    pub manifest_path: Option<String>,
+    /// `cargo-hakari` (`cargo hakari verify` の結果) または `heuristic` (resolve グラフとの比較)
+    pub checked_by: &'static str,
+    pub up_to_date: bool,
+    /// workspace-hack に依存していないワークスペースメンバー
+    pub members_without_hack: Vec<String>,
+    /// 統合されたフィーチャーのうち、workspace-hack で有効にしていないもの
+    pub missing_features: Vec<MissingFeatures>,
+    /// `cargo hakari verify` の出力
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MissingFeatures {
+    pub name: String,
+    pub features: Vec<String>,
+}
+
+/// workspace-hack クレートが現在のフィーチャーの統合に対して最新かどうかを調べる
+///
+/// `cargo-hakari` がインストールされている場合は `cargo hakari verify` の結果を使用する。
+/// `read_only` の場合は、`cargo hakari verify` が内部で実行する cargo metadata が Cargo.lock を作成、更新しうるため実行しない。
+/// インストールされていない場合と `read_only` の場合は、workspace-hack が依存するクレートについて、resolve グラフで有効になっている
+/// フィーチャーが workspace-hack のマニフェストで全て有効になっているかを比較する (ターゲット固有の依存関係は区別しない)。
+pub fn check_workspace_hack(metadata: &Metadata, read_only: bool) -> WorkspaceHackReport {
+    let Some(hack) = workspace_hack(metadata) else {
+        return WorkspaceHackReport {
+            package: None,
+            manifest_path: None,
+            checked_by: "heuristic",
+            up_to_date: true,
+            members_without_hack: Vec::new(),
+            missing_features: Vec::new(),
+            output: None,
+        };
+    };
+    let graph = Graph::new(metadata);
+    let members_without_hack: Vec<String> = graph
+        .members()
+        .filter_map(|id| {
+            let node = graph.node(id)?;
+            let package = graph.package(id)?;
+            (!node.deps.iter().any(|d| d.pkg == hack.id)).then(|| package.name.clone())
+        })
+        .collect();
+
+    let verify = if read_only {
+        None
+    } else {
+        cargo_command(metadata.workspace_root.as_std_path())
+            .args(["hakari", "verify"])
+            .output()
+            .ok()
+            // サブコマンドが見つからない場合と区別するため、cargo-hakari 自身の出力があるものだけを使う
+            .filter(|o| {
+                o.status.success()
+                    || !String::from_utf8_lossy(&o.stderr).contains("no such command")
+            })
+    };
+    let missing_features = missing_features(&graph, hack);
+    match verify {
+        Some(output) => {
+            let text = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            WorkspaceHackReport {
+                package: Some(hack.name.clone()),
+                manifest_path: Some(hack.manifest_path.to_string()),
+                checked_by: "cargo-hakari",
+                up_to_date: output.status.success() && members_without_hack.is_empty(),
+                members_without_hack,
+                missing_features,
+                output: Some(text.trim().to_string()),
+            }
+        }
+        None => WorkspaceHackReport {
+            package: Some(hack.name.clone()),
+            manifest_path: Some(hack.manifest_path.to_string()),
+            checked_by: "heuristic",
+            up_to_date: members_without_hack.is_empty() && missing_features.is_empty(),
+            members_without_hack,
+            missing_features,
+            output: None,
+        },
+    }
+}
+
+fn missing_features(graph: &Graph, hack: &Package) -> Vec<MissingFeatures> {
+    let Some(node) = graph.node(&hack.id) else {
+        return Vec::new();
+    };
+    let mut missing = Vec::new();
+    for dep in &node.deps {
+        let (Some(package), Some(resolved)) = (graph.package(&dep.pkg), graph.node(&dep.pkg))
+        else {
+            continue;
+        };
+        let declarations: Vec<_> = hack
+            .dependencies
+            .iter()
+            .filter(|d| d.name == package.name)
+            .collect();
+        if declarations.is_empty() {
+            continue;
+        }
+        let mut declared: Vec<String> = declarations
+            .iter()
+            .flat_map(|d| d.features.iter().cloned())
+            .collect();
+        if declarations.iter().any(|d| d.uses_default_features) {
+            declared.push("default".to_string());
+        }
+        let enabled = expand_features(package, &declared);
+        let features: Vec<String> = resolved
+            .features
+            .iter()
+            .filter(|f| !enabled.contains(*f))
+            .cloned()
+            .collect();
+        if !features.is_empty() {
+            missing.push(MissingFeatures {
+                name: package.name.clone(),
+                features,
+            });
+        }
+    }
+    missing
+}
+
+/// フィーチャーが有効にするフィーチャーを推移的に展開する (`dep:` と `crate/feature` は除く)
+pub fn expand_features(package: &Package, features: &[String]) -> BTreeSet<String> {
+    let mut enabled = BTreeSet::new();
+    let mut stack: Vec<&str> = features.iter().map(|f| f.as_str()).collect();
+    while let Some(feature) = stack.pop() {
+        if !package.features.contains_key(feature) && feature != "default" {
+            // 暗黙のフィーチャーとしての省略可能な依存関係
+            enabled.insert(feature.to_string());
+            continue;
+        }
+        if !enabled.insert(feature.to_string()) {
+            continue;
+        }
+        for implied in package.features.get(feature).into_iter().flatten() {
+            if !implied.starts_with("dep:") && !implied.contains('/') {
+                stack.push(implied);
+            }
+        }
+    }
+    enabled
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+    use crate::test_util::TempWorkspace;
+
+    #[test]
+    fn test_workspace_hack() {
+        let workspace = TempWorkspace::new("hakari");
+        workspace.write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"app\", \"workspace-hack\"]\nresolver = \"2\"\n",
+        );
+        workspace.write(
+            ".config/hakari.toml",
+            "hakari-package = \"workspace-hack\"\n",
+        );
+        workspace.write(
+            "app/Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        );
+        workspace.write("app/src/lib.rs", "");
+        workspace.write(
+            "workspace-hack/Cargo.toml",
+            "[package]\nname = \"workspace-hack\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n### BEGIN HAKARI SECTION\n### END HAKARI SECTION\n",
+        );
+        workspace.write("workspace-hack/src/lib.rs", "");
+        // cargo-hakari の代わりに、`cargo hakari` を実行したことが分かるエイリアスを使う
+        workspace.write(
+            ".cargo/config.toml",
+            "[alias]\nhakari = [\"locate-project\"]\n",
+        );
+        let metadata = MetadataCommand::new()
+            .manifest_path(workspace.manifest_path())
+            .exec()
+            .unwrap();
+        let report = check_workspace_hack(&metadata, false);
+        let read_only = check_workspace_hack(&metadata, true);
+        let graph = Graph::new(&metadata);
+        let members: Vec<&str> = graph
+            .members()
+            .filter_map(|id| graph.package(id))
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(report.package.as_deref(), Some("workspace-hack"));
+        assert_eq!(report.members_without_hack, ["app"]);
+        assert!(!report.up_to_date);
+        assert_eq!(report.checked_by, "cargo-hakari");
+        assert_eq!(read_only.checked_by, "heuristic");
+        assert!(read_only.output.is_none());
+        assert_eq!(members, ["app"]);
+    }
+}
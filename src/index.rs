@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use cargo_metadata::Metadata;
+use cargo_metadata::semver::Version;
+use mcp_attr::Result;
+use serde::{Deserialize, Serialize};
+use ureq::tls::{RootCerts, TlsConfig};
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// crates.io の sparse index の URL
+const SPARSE_INDEX_URL: &str = "https://index.crates.io";
+
+/// HTTP で取得するレスポンスの最大サイズ
+const MAX_RESPONSE_SIZE: u64 = 32 * 1024 * 1024;
+
+/// `CARGO_HOME` (未設定の場合は `~/.cargo`)
+pub fn cargo_home() -> PathBuf {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")))
+        .unwrap_or_else(|| PathBuf::from(".cargo"))
+}
+
+/// レジストリのインデックスに記録された、公開済みの 1 つのバージョン
+#[derive(Deserialize, Clone)]
+pub struct IndexEntry {
+    pub name: String,
+    pub vers: String,
+    #[serde(default)]
+    pub yanked: bool,
+    pub rust_version: Option<String>,
+    /// 公開日時 (比較的新しく公開されたバージョンにのみ記録されている)
+    pub pubtime: Option<String>,
+}
+
+/// インデックス内でのクレートのファイルのパス (`se/rd/serde` など)
+fn index_file(name: &str) -> String {
+    let name = name.to_ascii_lowercase();
+    match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    }
+}
+
+/// コマンドライン引数やパスに使用するクレート名が、crates.io で使用できる文字だけで構成されているかを検証する
+pub fn validate_crate_name(crate_name: &str) -> Result<()> {
+    let valid_name = !crate_name.is_empty()
+        && crate_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid_name {
+        bail_kind!(ErrorKind::InvalidCrateName, crate_name);
+    }
+    Ok(())
+}
+
+/// crates.io に公開されたクレートの全てのバージョンを取得する
+///
+/// sparse index から取得できない場合は、cargo がローカルにキャッシュしたインデックスを使用する。
+pub fn crate_versions(name: &str) -> Result<Vec<IndexEntry>> {
+    validate_crate_name(name)?;
+    let file = index_file(name);
+    let fetched = http_get(&format!("{SPARSE_INDEX_URL}/{file}"), &[]);
+    let text = match fetched {
+        Ok(Some(text)) => text,
+        Ok(None) => bail_kind!(ErrorKind::CrateNotFound, name),
+        Err(e) => match read_cache(&file) {
+            Some(text) => text,
+            None => bail_kind!(ErrorKind::IndexFetchFailed, name, e),
+        },
+    };
+    let mut entries = Vec::new();
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<IndexEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => bail_kind!(ErrorKind::IndexParseFailed, name, e),
+        }
+    }
+    Ok(entries)
+}
+
+/// yank されていない最新の安定版のバージョン
+pub fn latest_version(crate_name: &str) -> Result<Version> {
+    let latest = crate_versions(crate_name)?
+        .iter()
+        .filter(|e| !e.yanked)
+        .filter_map(|e| Version::parse(&e.vers).ok())
+        .filter(|v| v.pre.is_empty())
+        .max();
+    match latest {
+        Some(version) => Ok(version),
+        None => bail_kind!(ErrorKind::NoReleasedVersion, crate_name),
+    }
+}
+
+/// HTTP リクエストの全体のタイムアウト (応答しないレジストリでツールの呼び出しが止まらないようにする)
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 全てのリクエストで共有する HTTP クライアント (接続を再利用する)
+///
+/// cargo と同様に、証明書の検証には OS の証明書ストアを使用する。
+fn agent() -> &'static ureq::Agent {
+    static AGENT: OnceLock<ureq::Agent> = OnceLock::new();
+    AGENT.get_or_init(|| {
+        ureq::Agent::config_builder()
+            .tls_config(
+                TlsConfig::builder()
+                    .root_certs(RootCerts::PlatformVerifier)
+                    .build(),
+            )
+            .timeout_global(Some(HTTP_TIMEOUT))
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .into()
+    })
+}
+
+/// URL の内容を文字列として取得する (存在しない場合は `None`)
+///
+/// ブロッキングするため、ツールからは `spawn_blocking` の中で呼び出す。
+pub fn http_get(
+    url: &str,
+    headers: &[(&str, &str)],
+) -> std::result::Result<Option<String>, String> {
+    let mut request = agent().get(url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    match request.call() {
+        Ok(mut response) => response
+            .body_mut()
+            .with_config()
+            .limit(MAX_RESPONSE_SIZE)
+            .read_to_string()
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(ureq::Error::StatusCode(404 | 410 | 451)) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// cargo がキャッシュした sparse index のファイルを読み込み、1 行 1 バージョンの形式に変換する
+///
+/// キャッシュは、ヘッダ (形式のバージョン 1 バイト、インデックスのバージョン 4 バイト、リビジョン) に続いて、
+/// バージョンと JSON の組が NUL 区切りで並んだ形式になっている。
+fn read_cache(file: &str) -> Option<String> {
+    let index_dir = cargo_home().join("registry").join("index");
+    let cache = std::fs::read_dir(index_dir)
+        .ok()?
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .starts_with("index.crates.io-")
+        })
+        .map(|e| e.path().join(".cache").join(file))
+        .find(|p| p.is_file())?;
+    let data = std::fs::read(cache).ok()?;
+    let mut chunks = data.get(5..)?.split(|b| *b == 0).skip(1);
+    let mut lines = Vec::new();
+    while let (Some(_version), Some(json)) = (chunks.next(), chunks.next()) {
+        lines.push(String::from_utf8_lossy(json).into_owned());
+    }
+    Some(lines.join("\n"))
+}
+
+#[derive(Serialize)]
+pub struct VersionHistory {
+    pub name: String,
+    /// 依存グラフで使用されているバージョン
+    pub current_versions: Vec<String>,
+    /// yank されていない最新のバージョン (プレリリースを除く)
+    pub latest_stable: Option<String>,
+    /// yank されていない最新のバージョン (プレリリースを含む)
+    pub latest: Option<String>,
+    /// 使用中の最も新しいバージョンより新しく、yank されていないバージョン
+    pub newer_versions: Vec<String>,
+    /// 公開された全てのバージョン (新しい順)
+    pub versions: Vec<PublishedVersion>,
+}
+
+#[derive(Serialize)]
+pub struct PublishedVersion {
+    pub version: String,
+    pub yanked: bool,
+    pub published: Option<String>,
+    pub rust_version: Option<String>,
+    /// 依存グラフで使用されているかどうか
+    pub in_use: bool,
+}
+
+/// crates.io に公開されたバージョンの履歴を、依存グラフで使用中のバージョンと対応付けて返す
+pub fn version_history(metadata: &Metadata, name: &str) -> Result<VersionHistory> {
+    let mut entries: Vec<(Version, IndexEntry)> = crate_versions(name)?
+        .into_iter()
+        .filter_map(|e| Some((Version::parse(&e.vers).ok()?, e)))
+        .collect();
+    entries.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut current: Vec<&Version> = metadata
+        .packages
+        .iter()
+        .filter(|p| p.name.eq_ignore_ascii_case(name))
+        .filter(|p| p.source.as_ref().is_some_and(|s| s.is_crates_io()))
+        .map(|p| &p.version)
+        .collect();
+    current.sort();
+    let newest_in_use = current.last().copied();
+
+    let available = || entries.iter().filter(|(_, e)| !e.yanked);
+    Ok(VersionHistory {
+        name: entries
+            .first()
+            .map(|(_, e)| e.name.clone())
+            .unwrap_or_else(|| name.to_string()),
+        current_versions: current.iter().map(|v| v.to_string()).collect(),
+        latest_stable: available()
+            .find(|(v, _)| v.pre.is_empty())
+            .map(|(v, _)| v.to_string()),
+        latest: available().next().map(|(v, _)| v.to_string()),
+        newer_versions: available()
+            .filter(|(v, _)| newest_in_use.is_some_and(|current| v > current))
+            .map(|(v, _)| v.to_string())
+            .collect(),
+        versions: entries
+            .iter()
+            .map(|(v, e)| PublishedVersion {
+                version: v.to_string(),
+                yanked: e.yanked,
+                published: e.pubtime.clone(),
+                rust_version: e.rust_version.clone(),
+                in_use: current.contains(&v),
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_file() {
+        assert_eq!(index_file("a"), "1/a");
+        assert_eq!(index_file("cc"), "2/cc");
+        assert_eq!(index_file("syn"), "3/s/syn");
+        assert_eq!(index_file("Serde"), "se/rd/serde");
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo_metadata::Metadata;
+use cargo_metadata::camino::Utf8Path;
+use mcp_attr::Result;
+use serde::Serialize;
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// ワークスペースの `[workspace.package]` と `[workspace.dependencies]` の継承の状況
+#[derive(Serialize)]
+pub struct InheritanceReport {
+    /// `[workspace.package]` で定義されているキー
+    pub workspace_package_keys: Vec<String>,
+    /// `[workspace.dependencies]` で定義されている依存関係 (キーと要求バージョン)
+    pub workspace_dependencies: BTreeMap<String, Option<String>>,
+    pub members: Vec<MemberInheritance>,
+    /// どのメンバーも継承していない `[workspace.dependencies]` の依存関係
+    pub unused_workspace_dependencies: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct MemberInheritance {
+    pub package: String,
+    /// `key.workspace = true` で継承している `[package]` のキー
+    pub inherited_package_keys: Vec<String>,
+    /// `[workspace.package]` にあるのに、メンバーで値を書いている `[package]` のキー
+    pub redeclared_package_keys: Vec<RedeclaredKey>,
+    /// `workspace = true` で継承している依存関係
+    pub inherited_dependencies: Vec<DependencyEntry>,
+    /// `[workspace.dependencies]` にあるのに、メンバーでバージョンを書いている依存関係
+    pub redeclared_dependencies: Vec<RedeclaredDependency>,
+}
+
+#[derive(Serialize)]
+pub struct RedeclaredKey {
+    pub key: String,
+    /// 値がワークスペースの値と同じかどうか (同じ場合は `key.workspace = true` にできる)
+    pub same_as_workspace: bool,
+}
+
+#[derive(Serialize)]
+pub struct DependencyEntry {
+    pub name: String,
+    /// `normal`、`dev`、`build`
+    pub kind: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct RedeclaredDependency {
+    pub name: String,
+    pub kind: &'static str,
+    /// メンバーで書かれている要求バージョン
+    pub version: Option<String>,
+    /// `[workspace.dependencies]` の要求バージョン
+    pub workspace_version: Option<String>,
+}
+
+pub(crate) fn read_manifest(path: &Utf8Path) -> Result<toml::Table> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => bail_kind!(ErrorKind::ReadFailed, path, e),
+    };
+    match text.parse() {
+        Ok(table) => Ok(table),
+        Err(e) => bail_kind!(ErrorKind::ParseFailed, path, e),
+    }
+}
+
+fn table_kind(table: &str) -> Option<&'static str> {
+    match table {
+        "dependencies" => Some("normal"),
+        "dev-dependencies" | "dev_dependencies" => Some("dev"),
+        "build-dependencies" | "build_dependencies" => Some("build"),
+        _ => None,
+    }
+}
+
+fn is_inherited(value: &toml::Value) -> bool {
+    value.get("workspace").and_then(|w| w.as_bool()) == Some(true)
+}
+
+/// 依存関係の宣言の要求バージョン (`"1.0"` または `{ version = "1.0" }`)
+fn version(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(version) => Some(version.clone()),
+        _ => value
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+    }
+}
+
+/// 依存関係の宣言のパッケージ名 (`package` で名前を変えた場合はその名前)
+fn package_name<'a>(key: &'a str, value: &'a toml::Value) -> &'a str {
+    value.get("package").and_then(|p| p.as_str()).unwrap_or(key)
+}
+
+/// マニフェストの依存関係のテーブル (`[target.*]` 以下を含む) の依存関係の種類、キー、宣言
+fn dependencies(manifest: &toml::Table) -> Vec<(&'static str, &String, &toml::Value)> {
+    let mut tables = vec![manifest];
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        tables.extend(targets.values().filter_map(|t| t.as_table()));
+    }
+    let mut result = Vec::new();
+    for table in tables {
+        for (name, dependencies) in table {
+            if let (Some(kind), Some(dependencies)) = (table_kind(name), dependencies.as_table()) {
+                result.extend(dependencies.iter().map(|(key, value)| (kind, key, value)));
+            }
+        }
+    }
+    result
+}
+
+/// ワークスペースのルートとメンバーの Cargo.toml を読み、どのメンバーが何を継承しているか、
+/// 継承できるのに値を書いているキーと依存関係はどれかを調べる
+pub fn inheritance(metadata: &Metadata) -> Result<InheritanceReport> {
+    let root = read_manifest(&metadata.workspace_root.join("Cargo.toml"))?;
+    let workspace = root.get("workspace").and_then(|w| w.as_table());
+    let table = |name: &str| {
+        workspace
+            .and_then(|w| w.get(name))
+            .and_then(|t| t.as_table())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let workspace_package = table("package");
+    let workspace_dependencies = table("dependencies");
+
+    let mut used = BTreeSet::new();
+    let mut members = Vec::new();
+    for package in metadata.workspace_packages() {
+        let manifest = read_manifest(&package.manifest_path)?;
+        let mut inherited_package_keys = Vec::new();
+        let mut redeclared_package_keys = Vec::new();
+        if let Some(keys) = manifest.get("package").and_then(|p| p.as_table()) {
+            for (key, value) in keys {
+                if is_inherited(value) {
+                    inherited_package_keys.push(key.clone());
+                } else if let Some(workspace_value) = workspace_package.get(key) {
+                    redeclared_package_keys.push(RedeclaredKey {
+                        key: key.clone(),
+                        same_as_workspace: value == workspace_value,
+                    });
+                }
+            }
+        }
+        let mut inherited_dependencies = Vec::new();
+        let mut redeclared_dependencies = Vec::new();
+        for (kind, key, value) in dependencies(&manifest) {
+            if is_inherited(value) {
+                used.insert(key.clone());
+                inherited_dependencies.push(DependencyEntry {
+                    name: key.clone(),
+                    kind,
+                });
+                continue;
+            }
+            let name = package_name(key, value);
+            let declared = workspace_dependencies
+                .iter()
+                .find(|(k, v)| package_name(k, v) == name);
+            if let Some((_, workspace_value)) = declared {
+                redeclared_dependencies.push(RedeclaredDependency {
+                    name: key.clone(),
+                    kind,
+                    version: version(value),
+                    workspace_version: version(workspace_value),
+                });
+            }
+        }
+        members.push(MemberInheritance {
+            package: package.name.clone(),
+            inherited_package_keys,
+            redeclared_package_keys,
+            inherited_dependencies,
+            redeclared_dependencies,
+        });
+    }
+
+    Ok(InheritanceReport {
+        workspace_package_keys: workspace_package.keys().cloned().collect(),
+        unused_workspace_dependencies: workspace_dependencies
+            .keys()
+            .filter(|k| !used.contains(*k))
+            .cloned()
+            .collect(),
+        workspace_dependencies: workspace_dependencies
+            .iter()
+            .map(|(k, v)| (k.clone(), version(v)))
+            .collect(),
+        members,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+    use crate::test_util::TempWorkspace;
+
+    #[test]
+    fn test_inheritance() {
+        let workspace = TempWorkspace::new("inheritance");
+        workspace.write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"a\", \"b\"]\nresolver = \"2\"\n\n\
+             [workspace.package]\nedition = \"2021\"\nlicense = \"MIT\"\n\n\
+             [workspace.dependencies]\nb = { path = \"b\", version = \"0.1.0\" }\nunused = \"1.0\"\n",
+        );
+        workspace.write(
+            "a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition.workspace = true\nlicense = \"MIT\"\n\n\
+             [dependencies]\nb.workspace = true\n\n[dev-dependencies]\nb = { path = \"../b\", version = \"0.1\" }\n",
+        );
+        workspace.write("a/src/lib.rs", "");
+        workspace.write(
+            "b/Cargo.toml",
+            "[package]\nname = \"b\"\nversion = \"0.1.0\"\nedition = \"2018\"\n",
+        );
+        workspace.write("b/src/lib.rs", "");
+        let metadata = MetadataCommand::new()
+            .manifest_path(workspace.manifest_path())
+            .no_deps()
+            .exec()
+            .unwrap();
+        let report = inheritance(&metadata).unwrap();
+
+        assert_eq!(report.workspace_package_keys, ["edition", "license"]);
+        assert_eq!(report.unused_workspace_dependencies, ["unused"]);
+        let a = report.members.iter().find(|m| m.package == "a").unwrap();
+        assert_eq!(a.inherited_package_keys, ["edition"]);
+        assert_eq!(a.redeclared_package_keys[0].key, "license");
+        assert!(a.redeclared_package_keys[0].same_as_workspace);
+        assert_eq!(a.inherited_dependencies[0].name, "b");
+        assert_eq!(a.redeclared_dependencies[0].kind, "dev");
+        assert_eq!(a.redeclared_dependencies[0].version.as_deref(), Some("0.1"));
+        assert_eq!(
+            a.redeclared_dependencies[0].workspace_version.as_deref(),
+            Some("0.1.0")
+        );
+        let b = report.members.iter().find(|m| m.package == "b").unwrap();
+        assert!(!b.redeclared_package_keys[0].same_as_workspace);
+    }
+}
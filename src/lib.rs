@@ -0,0 +1,3833 @@
+pub mod advisory;
+pub mod aggregate;
+pub mod artifact;
+pub mod budget;
+pub mod cache;
+pub mod category;
+pub mod changelog;
+pub mod chunks;
+pub mod compare;
+pub mod cyclonedx;
+pub mod deny;
+pub mod detail;
+pub mod diagram;
+pub mod duplicates;
+pub mod environment;
+pub mod error;
+pub mod explain;
+pub mod export;
+pub mod features;
+pub mod findings;
+pub mod fingerprint;
+pub mod freshness;
+pub mod git;
+pub mod graph;
+pub mod hakari;
+pub mod index;
+pub mod inheritance;
+pub mod license;
+pub mod lint;
+pub mod locate;
+pub mod lockfile;
+pub mod manifest;
+pub mod matrix;
+pub mod metrics;
+pub mod minimal;
+pub mod native;
+pub mod outdated;
+pub mod page;
+pub mod pkgid;
+pub mod policy;
+pub mod preview;
+pub mod provider;
+pub mod public_api;
+pub mod query;
+pub mod render;
+pub mod report;
+pub mod requirements;
+pub mod resolve;
+pub mod review;
+pub mod roots;
+pub mod sandbox;
+pub mod sbom;
+pub mod search;
+pub mod sources;
+pub mod subcommand;
+pub mod summary;
+pub mod target_dir;
+#[cfg(test)]
+mod test_util;
+pub mod tree;
+pub mod unsafe_code;
+pub mod upgrade;
+pub mod vet;
+mod watch;
+pub mod workspace;
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use cargo_metadata::{DependencyKind, Metadata, Package};
+use detail::Detail;
+use error::{ErrorKind, Language, bail_kind};
+use findings::{FindingReport, FindingSet, OutputFormat};
+use mcp_attr::Result;
+use mcp_attr::jsoncall::{Handler, Session, SessionContext, SessionResult};
+use mcp_attr::schema::{
+    CallToolRequestParams, CallToolResult, CompleteRequestParams, CompleteResult,
+    GetPromptRequestParams, GetPromptResult, Implementation, ListPromptsRequestParams,
+    ListPromptsResult, ListResourceTemplatesRequestParams, ListResourceTemplatesResult,
+    ListResourcesRequestParams, ListResourcesResult, ListToolsRequestParams, ListToolsResult,
+    ReadResourceRequestParams, ReadResourceResult, Resource, ServerCapabilities,
+};
+use mcp_attr::server::{McpServer, RequestContext, mcp_server};
+use provider::{CargoMetadataProvider, MetadataOptions, MetadataProvider};
+use render::ValueFormat;
+use serde::Serialize;
+
+/// 既定のプロジェクトを設定する環境変数 (`--manifest-path` を指定しなかった場合に使う)
+pub const MANIFEST_PATH_ENV: &str = "CARGO_METADATA_MCP_MANIFEST";
+
+/// コマンドライン引数で指定するサーバーの設定
+#[derive(Default)]
+pub struct ServerOptions {
+    /// Cargo.toml を変更するツールを有効にするかどうか (`--allow-write`)
+    pub allow_write: bool,
+    /// プロジェクトのファイルを一切変更しないことを保証するかどうか (`--read-only`)
+    ///
+    /// cargo には `--locked` を指定し、Cargo.lock の更新が必要な場合はエラーにする。
+    /// cargo の実行で Cargo.lock が変わりうるツールは、サンドボックスの一時的なコピーでのみ実行する。
+    pub read_only: bool,
+    /// ツールで manifest_path を省略したときに使うプロジェクト (`--manifest-path <path>`)
+    ///
+    /// Cargo.toml のパスのほか、プロジェクトのディレクトリも指定できる。
+    pub manifest_path: Option<PathBuf>,
+    /// ツールで指定できるプロジェクトを含むディレクトリ (`--allow-dir <dir>`、複数指定可)
+    ///
+    /// 空の場合は制限しない。
+    pub allowed_dirs: Vec<PathBuf>,
+    /// 複数のワークスペースを集計するツールの対象とする Cargo.toml (`--workspace <path>`、複数指定可)
+    pub workspaces: Vec<PathBuf>,
+    /// 全てのパッケージと分析レポートを個別のリソースとして列挙するかどうか (`--chunked-resources`)
+    pub chunked_resources: bool,
+    /// プロジェクトの Cargo.lock の変更を監視して通知するかどうか (`--watch`)
+    pub watch: bool,
+    /// エラーメッセージの言語 (`--lang en` または `--lang ja`)
+    pub language: Language,
+}
+
+impl ServerOptions {
+    /// コマンドライン引数 (プログラム名を除く) を解析する
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> std::result::Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--allow-write" => options.allow_write = true,
+                "--read-only" => options.read_only = true,
+                "--chunked-resources" => options.chunked_resources = true,
+                "--watch" => options.watch = true,
+                "--lang" => match args.next().as_deref().and_then(Language::parse) {
+                    Some(language) => options.language = language,
+                    None => return Err("`--lang` requires `en` or `ja`".to_string()),
+                },
+                "--manifest-path" => match args.next() {
+                    Some(path) => options.manifest_path = Some(PathBuf::from(path)),
+                    None => return Err("`--manifest-path` requires a path".to_string()),
+                },
+                "--allow-dir" => match args.next() {
+                    Some(dir) => options.allowed_dirs.push(PathBuf::from(dir)),
+                    None => return Err("`--allow-dir` requires a directory".to_string()),
+                },
+                "--workspace" => match args.next() {
+                    Some(path) => options.workspaces.push(PathBuf::from(path)),
+                    None => return Err("`--workspace` requires a path to Cargo.toml".to_string()),
+                },
+                _ => return Err(format!("unknown argument `{arg}`")),
+            }
+        }
+        if options.allow_write && options.read_only {
+            return Err("`--allow-write` cannot be used with `--read-only`".to_string());
+        }
+        Ok(options)
+    }
+}
+
+/// Cargo プロジェクトのメタデータを提供する MCP サーバー
+///
+/// リクエストは `Tools` に渡し、エラーメッセージはサーバーごとの言語 (`options.language`) で作成する。
+pub struct CargoMetadataServer(Tools);
+
+/// ツール、リソースとプロンプトの実装
+struct Tools(Arc<ServerData>);
+
+impl CargoMetadataServer {
+    /// `cargo metadata` でメタデータを取得するサーバーを作成する
+    pub fn new(options: ServerOptions) -> Self {
+        Self::with_provider(options, CargoMetadataProvider)
+    }
+
+    /// 指定した方法でメタデータを取得するサーバーを作成する
+    pub fn with_provider(
+        options: ServerOptions,
+        provider: impl MetadataProvider + 'static,
+    ) -> Self {
+        Self(Tools(Arc::new(ServerData::new(
+            options,
+            Arc::new(provider),
+        ))))
+    }
+
+    /// エラーメッセージの言語
+    fn language(&self) -> Language {
+        self.0.0.options.language
+    }
+
+    /// セッションに渡すハンドラー
+    ///
+    /// `McpServer::into_handler` のハンドラーに加えて、クライアントのルート (`roots/list`) を問い合わせるためのセッションを記録する。
+    pub fn into_session_handler(self) -> impl Handler + Send + Sync + 'static {
+        let state = self.0.0.clone();
+        roots::RootsHandler {
+            inner: self.into_handler(),
+            state,
+        }
+    }
+
+    /// 標準入出力で MCP サーバーを実行する (`watch` が有効な場合は Cargo.lock の監視も開始する)
+    pub async fn serve_stdio(self) -> SessionResult<()> {
+        let state = self.0.0.clone();
+        let watch = state.options.watch;
+        let session = Session::from_stdio(self.into_session_handler());
+        if watch {
+            tokio::spawn(watch::watch_lockfiles(state, session.context()));
+        }
+        session.wait().await
+    }
+}
+
+struct ServerData {
+    options: ServerOptions,
+    metadata: cache::MetadataCache,
+    provider: Arc<dyn MetadataProvider>,
+    /// クライアントとのセッション (最初のメッセージを受け取ったときに記録する)
+    session: OnceLock<SessionContext>,
+    /// クライアントのルートで見つけたプロジェクトのマニフェスト (`notifications/roots/list_changed` で破棄する)
+    root_manifests: Mutex<Option<Vec<PathBuf>>>,
+}
+
+impl ServerData {
+    fn new(options: ServerOptions, provider: Arc<dyn MetadataProvider>) -> Self {
+        Self {
+            options,
+            metadata: cache::MetadataCache::default(),
+            provider,
+            session: OnceLock::new(),
+            root_manifests: Mutex::new(None),
+        }
+    }
+    /// 書き込みモードが有効でなければエラーを返す
+    fn ensure_writable(&self) -> Result<()> {
+        if self.options.read_only {
+            bail_kind!(ErrorKind::ReadOnlyMode);
+        }
+        if !self.options.allow_write {
+            bail_kind!(ErrorKind::WriteDisabled);
+        }
+        Ok(())
+    }
+
+    /// マニフェストを変更し、キャッシュしているメタデータを破棄する
+    async fn edit_manifest(
+        &self,
+        manifest_path: PathBuf,
+        package: Option<&str>,
+        edit: impl FnOnce(&mut toml_edit::DocumentMut) -> Result<()>,
+    ) -> Result<manifest::ManifestEdit> {
+        self.ensure_writable()?;
+        let metadata = self.get_metadata(manifest_path.clone()).await?;
+        let target = match package {
+            Some(spec) => match pkgid::resolve(metadata.workspace_packages(), spec) {
+                Ok(package) => package.manifest_path.clone().into_std_path_buf(),
+                Err(_) => bail_kind!(ErrorKind::NotWorkspaceMember, spec),
+            },
+            None => manifest_path,
+        };
+        let root = metadata.workspace_root.clone().into_std_path_buf();
+        let result = manifest::apply(&target, &root, edit);
+        self.metadata.invalidate(&root);
+        result
+    }
+
+    /// ツールに指定されたパス (Cargo.toml、プロジェクトのディレクトリまたはプロジェクト内のファイル) から Cargo.toml のパスを求める
+    ///
+    /// パスが省略された場合は、`--manifest-path` で設定したプロジェクトを使い、
+    /// 設定されていなければ、クライアントのルートで見つけたプロジェクトが 1 つだけであればそれを使う。
+    /// `--allow-dir` を指定した場合、その外にあるプロジェクトはエラーにする。
+    async fn manifest_path(&self, path: Option<&str>) -> Result<PathBuf> {
+        let manifest_path = match (path, &self.options.manifest_path) {
+            (Some(path), _) => locate::locate_manifest(Path::new(path))?,
+            (None, Some(path)) => locate::locate_manifest(path)?,
+            (None, None) => {
+                let manifests = self.root_manifests().await;
+                match manifests.as_slice() {
+                    [manifest] => manifest.clone(),
+                    [] => bail_kind!(ErrorKind::ManifestPathRequired),
+                    _ => {
+                        let candidates: Vec<String> =
+                            manifests.iter().map(|m| m.display().to_string()).collect();
+                        bail_kind!(ErrorKind::AmbiguousProject, candidates.join(", "))
+                    }
+                }
+            }
+        };
+        self.ensure_allowed(&manifest_path)?;
+        Ok(manifest_path)
+    }
+
+    /// パスが `--allow-dir` で指定したディレクトリの外にあればエラーを返す
+    fn ensure_allowed(&self, path: &Path) -> Result<()> {
+        if locate::is_allowed(path, &self.options.allowed_dirs) {
+            return Ok(());
+        }
+        let allowed: Vec<String> = self
+            .options
+            .allowed_dirs
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect();
+        bail_kind!(
+            ErrorKind::PathNotAllowed,
+            path.display(),
+            allowed.join(", ")
+        );
+    }
+
+    /// クライアントのルートで見つけたプロジェクトのマニフェスト
+    async fn root_manifests(&self) -> Vec<PathBuf> {
+        if let Some(manifests) = &*self.root_manifests.lock().unwrap() {
+            return manifests.clone();
+        }
+        let manifests = match self.session.get() {
+            Some(session) => roots::list_root_manifests(session).await,
+            None => Vec::new(),
+        };
+        let manifests: Vec<PathBuf> = manifests
+            .into_iter()
+            .filter(|m| locate::is_allowed(m, &self.options.allowed_dirs))
+            .collect();
+        *self.root_manifests.lock().unwrap() = Some(manifests.clone());
+        manifests
+    }
+
+    async fn get_metadata(&self, manifest_path: PathBuf) -> Result<Arc<Metadata>> {
+        self.get_metadata_with(manifest_path, MetadataOptions::default())
+            .await
+    }
+
+    /// オプションを指定してメタデータを取得する (オプションの組ごとにキャッシュする)
+    async fn get_metadata_with(
+        &self,
+        manifest_path: PathBuf,
+        options: MetadataOptions,
+    ) -> Result<Arc<Metadata>> {
+        let key = self.cache_key(&manifest_path, options);
+        self.metadata
+            .get_or_load(
+                &key,
+                self.load_metadata_with(manifest_path, key.options.clone()),
+            )
+            .await
+    }
+
+    /// パッケージのマニフェストの情報だけが必要な場合のメタデータ
+    ///
+    /// 依存関係を解決したメタデータがキャッシュにあればそれを使い、なければ `--no-deps` で取得する。
+    async fn get_package_metadata(&self, manifest_path: PathBuf) -> Result<Arc<Metadata>> {
+        let key = self.cache_key(&manifest_path, MetadataOptions::default());
+        if let Some(metadata) = self.metadata.get_fresh(&key) {
+            return Ok(metadata);
+        }
+        self.get_metadata_with(
+            manifest_path,
+            MetadataOptions {
+                no_deps: true,
+                ..MetadataOptions::default()
+            },
+        )
+        .await
+    }
+
+    /// キャッシュを使わずにメタデータを取得し直し、キャッシュを置き換える
+    async fn refresh_metadata(&self, manifest_path: PathBuf) -> Result<cache::RefreshResult> {
+        let key = self.cache_key(&manifest_path, MetadataOptions::default());
+        let (metadata, was_cached) = self
+            .metadata
+            .reload(
+                &key,
+                self.load_metadata_with(manifest_path, key.options.clone()),
+            )
+            .await?;
+        Ok(cache::RefreshResult {
+            manifest_path: key.manifest_path.display().to_string(),
+            workspace_root: metadata.workspace_root.to_string(),
+            was_cached,
+            workspace_members: metadata
+                .workspace_packages()
+                .iter()
+                .map(|p| p.name.clone())
+                .collect(),
+            packages: metadata.packages.len(),
+        })
+    }
+
+    /// リソースとして公開するプロジェクト (キャッシュしているメタデータ、`--workspace` と `--manifest-path` で設定したもの、クライアントのルートにあるもの)
+    async fn projects(&self) -> Vec<Arc<Metadata>> {
+        let mut projects = self.metadata.cached();
+        projects.sort_by(|a, b| a.workspace_root.cmp(&b.workspace_root));
+        projects.dedup_by(|a, b| a.workspace_root == b.workspace_root);
+        let mut configured = self.options.workspaces.clone();
+        configured.extend(self.options.manifest_path.clone());
+        configured.extend(self.root_manifests().await);
+        for path in &configured {
+            if let Ok(metadata) = self.get_metadata(path.clone()).await
+                && !projects
+                    .iter()
+                    .any(|p| p.workspace_root == metadata.workspace_root)
+            {
+                projects.push(metadata);
+            }
+        }
+        projects
+    }
+
+    /// リソースの URI で指定したプロジェクトのメタデータ
+    async fn project(&self, project: &str) -> Result<Arc<Metadata>> {
+        let projects = self.projects().await;
+        match projects
+            .iter()
+            .find(|m| summary::project_key(m, &projects) == project)
+        {
+            Some(metadata) => Ok(metadata.clone()),
+            None => bail_kind!(ErrorKind::ProjectNotLoaded, project),
+        }
+    }
+
+    /// キャッシュを使わずにメタデータを取得する
+    ///
+    /// `cargo metadata` はサブプロセスの終了を待つため、非同期ランタイムのスレッドを止めないよう `spawn_blocking` で実行する。
+    async fn load_metadata(&self, manifest_path: PathBuf) -> Result<Metadata> {
+        self.load_metadata_with(manifest_path, MetadataOptions::default())
+            .await
+    }
+
+    /// オプションを指定して、キャッシュを使わずにメタデータを取得する (読み取り専用モードでは常に `--locked`)
+    async fn load_metadata_with(
+        &self,
+        manifest_path: PathBuf,
+        options: MetadataOptions,
+    ) -> Result<Metadata> {
+        let provider = self.provider.clone();
+        let options = self.effective_options(options);
+        let language = Language::current();
+        match tokio::task::spawn_blocking(move || {
+            language.sync_scope(|| provider.metadata(&manifest_path, &options))
+        })
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => bail_kind!(ErrorKind::MetadataFailed, e),
+        }
+    }
+
+    /// サーバーの設定を反映した `cargo metadata` のオプション
+    fn effective_options(&self, options: MetadataOptions) -> MetadataOptions {
+        MetadataOptions {
+            locked: self.options.read_only,
+            ..options
+        }
+    }
+
+    /// メタデータのキャッシュのキー (正規化できないパスはそのまま使う)
+    fn cache_key(&self, manifest_path: &Path, options: MetadataOptions) -> cache::CacheKey {
+        cache::CacheKey {
+            manifest_path: manifest_path
+                .canonicalize()
+                .unwrap_or_else(|_| manifest_path.to_path_buf()),
+            options: self.effective_options(options),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PackageInfo {
+    name: String,
+    version: String,
+    pkgid: String,
+    authors: Vec<String>,
+    description: Option<String>,
+    repository: Option<String>,
+    license: Option<String>,
+    dependencies: Vec<DependencyInfo>,
+}
+
+#[derive(Serialize)]
+struct DependencyInfo {
+    name: String,
+    version: String,
+    /// 依存グラフで解決されたパッケージの ID
+    pkgid: Option<String>,
+    /// `normal`、`dev` または `build`
+    kind: &'static str,
+    optional: bool,
+    features: Vec<String>,
+    /// 成果物依存関係 (`artifact = "bin"` など) の場合の成果物の種類とターゲット
+    artifact: Option<artifact::ArtifactSpec>,
+}
+
+impl McpServer for CargoMetadataServer {
+    fn server_info(&self) -> Implementation {
+        self.0.server_info()
+    }
+
+    fn instructions(&self) -> Option<String> {
+        self.0.instructions()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        self.0.capabilities()
+    }
+
+    fn prompts_list(
+        &self,
+        p: ListPromptsRequestParams,
+        cx: &mut RequestContext,
+    ) -> impl Future<Output = Result<ListPromptsResult>> + Send {
+        self.language().scope(self.0.prompts_list(p, cx))
+    }
+
+    fn prompts_get(
+        &self,
+        p: GetPromptRequestParams,
+        cx: &mut RequestContext,
+    ) -> impl Future<Output = Result<GetPromptResult>> + Send {
+        self.language().scope(self.0.prompts_get(p, cx))
+    }
+
+    fn resources_list(
+        &self,
+        p: ListResourcesRequestParams,
+        cx: &mut RequestContext,
+    ) -> impl Future<Output = Result<ListResourcesResult>> + Send {
+        self.language().scope(self.0.resources_list(p, cx))
+    }
+
+    fn resources_templates_list(
+        &self,
+        p: ListResourceTemplatesRequestParams,
+        cx: &mut RequestContext,
+    ) -> impl Future<Output = Result<ListResourceTemplatesResult>> + Send {
+        self.language()
+            .scope(self.0.resources_templates_list(p, cx))
+    }
+
+    fn resources_read(
+        &self,
+        p: ReadResourceRequestParams,
+        cx: &mut RequestContext,
+    ) -> impl Future<Output = Result<ReadResourceResult>> + Send {
+        self.language().scope(self.0.resources_read(p, cx))
+    }
+
+    fn tools_list(
+        &self,
+        p: ListToolsRequestParams,
+        cx: &mut RequestContext,
+    ) -> impl Future<Output = Result<ListToolsResult>> + Send {
+        self.language().scope(self.0.tools_list(p, cx))
+    }
+
+    fn tools_call(
+        &self,
+        p: CallToolRequestParams,
+        cx: &mut RequestContext,
+    ) -> impl Future<Output = Result<CallToolResult>> + Send {
+        self.language().scope(self.0.tools_call(p, cx))
+    }
+
+    fn completion_complete(
+        &self,
+        p: CompleteRequestParams,
+        cx: &mut RequestContext,
+    ) -> impl Future<Output = Result<CompleteResult>> + Send {
+        self.language().scope(self.0.completion_complete(p, cx))
+    }
+}
+
+#[mcp_server]
+impl McpServer for Tools {
+    fn instructions(&self) -> Option<String> {
+        let state = &self.0;
+        if state.options.read_only {
+            Some("This server is running in read-only mode: it never writes to the project. cargo is run with `--locked`, and tools that may change Cargo.lock run only on temporary copies of the workspace.".to_string())
+        } else if state.options.allow_write {
+            Some("This server is running in write mode: `add_dependency`, `remove_dependency` and `set_dependency_version` modify Cargo.toml.".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Cargo Metadata MCP Server
+    ///
+    /// このサーバーはCargoプロジェクトのメタデータ情報を提供します。
+    /// プロジェクトの依存関係、パッケージ情報、ビルドターゲットなどを取得できます。
+    #[prompt]
+    async fn cargo_metadata_prompt(&self) -> Result<&str> {
+        Ok(
+            "Cargo Metadataサーバーへようこそ！このサーバーを使用して、Cargoプロジェクトのメタデータ情報を取得できます。",
+        )
+    }
+
+    /// プロジェクトのメタデータを取得します
+    ///
+    /// 指定されたCargoプロジェクトのメタデータを取得します。
+    /// fields を指定すると、指定したフィールドだけを残して出力を小さくできます。特定の値だけが必要な場合は `query_metadata` も使えます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn get_metadata(
+        &self,
+        manifest_path: Option<String>,
+        /// 依存関係を絞り込むターゲットトリプル (`x86_64-unknown-linux-musl` など、`cargo metadata --filter-platform` 相当)。省略時はすべてのプラットフォーム
+        target_triple: Option<String>,
+        /// 有効にするフィーチャー (`cargo metadata --features` 相当。`package/feature` の形式も使えます)
+        features: Option<Vec<String>>,
+        /// true の場合はすべてのフィーチャーを有効にする (`--all-features` 相当)。省略時は false
+        all_features: Option<bool>,
+        /// true の場合は既定のフィーチャーを無効にする (`--no-default-features` 相当)。省略時は false
+        no_default_features: Option<bool>,
+        /// true の場合は依存関係を解決せず、ワークスペースのメンバーだけを返す (`cargo metadata --no-deps` 相当)。省略時は false
+        no_deps: Option<bool>,
+        /// 残すフィールドのパスを `,` で区切ったもの (`packages.name,packages.version,workspace_members` など)。配列は要素ごとに適用します。省略時はすべてのフィールド
+        fields: Option<String>,
+        /// 1 ページのパッケージ数。指定すると `packages` をページに分割し、`_page` (`total`、`offset`、`next_cursor`) を付けて返します。detail が `summary` の場合は 10、`normal` の場合は 50 までに抑えます。省略時は分割しない
+        page_size: Option<usize>,
+        /// 次のページを取得するためのカーソル (前のページの `_page.next_cursor` の値)
+        cursor: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let page = page::Page::parse(page_size, cursor.as_deref())?.within(detail);
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata_with(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                MetadataOptions {
+                    no_deps: no_deps.unwrap_or(false),
+                    ..metadata_options(target_triple, features, all_features, no_default_features)
+                },
+            )
+            .await?;
+
+        if fields.is_none() && !page.is_requested() {
+            return to_output(metadata, "metadata", detail, format);
+        }
+        let mut value = to_value(metadata, "metadata")?;
+        if let Some(fields) = fields {
+            value = query::select_fields(value, &fields);
+        }
+        to_output(
+            &page.apply(value, Some("packages")),
+            "metadata",
+            detail,
+            format,
+        )
+    }
+
+    /// プロジェクトのメタデータから、JSONPath のクエリに一致する値だけを返します
+    ///
+    /// `get_metadata` と同じメタデータ (`cargo metadata` の出力) に JSONPath (RFC 9535) のクエリを適用し、一致した値を文書内の順に配列で返します。
+    /// 名前 (`.name`、`['name']`)、`*`、添え字 (`[0]`、`[-1]`)、スライス (`[0:10]`)、`..`、
+    /// フィルター (`[?(@.name=="serde")]`、`&&`、`||`、`!`、`==`、`!=`、`<`、`<=`、`>`、`>=`) に対応します。
+    /// 例: `$.packages[?(@.name=="serde")].version`、`$.resolve.root`、`$.packages[*].name`
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn query_metadata(
+        &self,
+        manifest_path: Option<String>,
+        /// JSONPath のクエリ (`$` から始まる)
+        query: String,
+        /// true の場合は依存関係を解決せず、ワークスペースのメンバーだけを対象にする (`cargo metadata --no-deps` 相当)。省略時は false
+        no_deps: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let manifest_path = state.manifest_path(manifest_path.as_deref()).await?;
+        let metadata = &*if no_deps.unwrap_or(false) {
+            state.get_package_metadata(manifest_path).await?
+        } else {
+            state.get_metadata(manifest_path).await?
+        };
+
+        let matches = query::query(&to_value(metadata, "metadata")?, &query)?;
+        to_output(&matches, "query result", detail, format)
+    }
+
+    /// 依存グラフのパッケージを名前で検索します
+    ///
+    /// ワークスペースメンバーと依存関係のすべてのパッケージから、名前がパターンに一致するものを名前とバージョンの順に返します。
+    /// パターンは既定では glob (`*` は任意の文字列、`?` は任意の 1 文字) として名前全体と照合し、regex を指定すると正規表現として名前の一部と照合します。
+    /// 各パッケージの relation は、ワークスペースメンバーが `workspace`、ワークスペースメンバーが直接依存しているパッケージが `direct`、それ以外が `transitive` です。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn search_packages(
+        &self,
+        manifest_path: Option<String>,
+        /// パッケージ名のパターン (例: `serde*`、`tokio-?*`)
+        pattern: String,
+        /// パターンを正規表現として扱うかどうか。省略時は false
+        regex: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let pattern = search::NamePattern::parse(&pattern, regex.unwrap_or(false))?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let matches = search::search_packages(metadata, &pattern);
+        to_output(&matches, "package matches", detail, format)
+    }
+
+    /// プロジェクトのパッケージ情報を取得します
+    ///
+    /// 指定されたCargoプロジェクトのパッケージ情報を取得します。
+    /// packageを省略するとルートパッケージの情報を返します (ルートパッケージのない仮想ワークスペースではpackageが必要です)。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn get_package_info(
+        &self,
+        manifest_path: Option<String>,
+        /// パッケージ名またはパッケージ ID 仕様 (ワークスペースのメンバーまたは依存グラフの任意のパッケージ)。省略時はルートパッケージ
+        package: Option<String>,
+        /// 依存関係を絞り込むターゲットトリプル (`x86_64-unknown-linux-musl` など、`cargo metadata --filter-platform` 相当)。省略時はすべてのプラットフォーム
+        target_triple: Option<String>,
+        /// 有効にするフィーチャー (`cargo metadata --features` 相当。`package/feature` の形式も使えます)
+        features: Option<Vec<String>>,
+        /// true の場合はすべてのフィーチャーを有効にする (`--all-features` 相当)。省略時は false
+        all_features: Option<bool>,
+        /// true の場合は既定のフィーチャーを無効にする (`--no-default-features` 相当)。省略時は false
+        no_default_features: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let options = metadata_options(target_triple, features, all_features, no_default_features);
+        let resolved_only = !options.is_complete();
+        let metadata = &*state
+            .get_metadata_with(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                options,
+            )
+            .await?;
+
+        let package = pkgid::select_package(metadata, package.as_deref())?;
+
+        let dependencies = get_dependencies(package, metadata, None, resolved_only);
+
+        let package_info = PackageInfo {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            pkgid: package.id.repr.clone(),
+            authors: package.authors.clone(),
+            description: package.description.clone(),
+            repository: package.repository.clone(),
+            license: package.license.clone(),
+            dependencies,
+        };
+
+        to_output(&package_info, "package info", detail, format)
+    }
+
+    /// プロジェクトの依存関係リストを取得します
+    ///
+    /// 指定されたCargoプロジェクトの依存関係リストを取得します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn get_dependencies(
+        &self,
+        manifest_path: Option<String>,
+        /// 依存関係の種類 (`normal`、`dev`、`build`)。省略時はすべての種類
+        kind: Option<String>,
+        /// 依存関係を絞り込むターゲットトリプル (`x86_64-unknown-linux-musl` など、`cargo metadata --filter-platform` 相当)。省略時はすべてのプラットフォーム
+        target_triple: Option<String>,
+        /// 有効にするフィーチャー (`cargo metadata --features` 相当。`package/feature` の形式も使えます)
+        features: Option<Vec<String>>,
+        /// true の場合はすべてのフィーチャーを有効にする (`--all-features` 相当)。省略時は false
+        all_features: Option<bool>,
+        /// true の場合は既定のフィーチャーを無効にする (`--no-default-features` 相当)。省略時は false
+        no_default_features: Option<bool>,
+        /// 1 ページの要素数。指定すると一覧をページに分割し、`_page` (`total`、`offset`、`next_cursor`) を付けて返します。detail が `summary` の場合は 10、`normal` の場合は 50 までに抑えます。省略時は分割しない
+        page_size: Option<usize>,
+        /// 次のページを取得するためのカーソル (前のページの `_page.next_cursor` の値)
+        cursor: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let kind = parse_dependency_kind(kind.as_deref())?;
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let page = page::Page::parse(page_size, cursor.as_deref())?.within(detail);
+        let state = &self.0;
+        let options = metadata_options(target_triple, features, all_features, no_default_features);
+        let resolved_only = !options.is_complete();
+        let metadata = &*state
+            .get_metadata_with(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                options,
+            )
+            .await?;
+
+        let root_package = match metadata.root_package() {
+            Some(pkg) => pkg,
+            None => bail_kind!(ErrorKind::NoRootPackage),
+        };
+
+        let dependencies = get_dependencies(root_package, metadata, kind, resolved_only);
+
+        to_output(
+            &page.apply(to_value(&dependencies, "dependencies")?, None),
+            "dependencies",
+            detail,
+            format,
+        )
+    }
+
+    /// プロジェクトのビルドターゲットを取得します
+    ///
+    /// 指定されたCargoプロジェクトのビルドターゲットを取得します。
+    /// 依存関係を解決せずに取得するため、大きなワークスペースでも高速です。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_targets(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let root_package = match metadata.root_package() {
+            Some(pkg) => pkg,
+            None => bail_kind!(ErrorKind::NoRootPackage),
+        };
+
+        to_output(&root_package.targets, "targets", detail, format)
+    }
+
+    /// プロジェクトのワークスペース情報を取得します
+    ///
+    /// 指定されたCargoプロジェクトのワークスペース情報を取得します。
+    /// name、path_prefix、publish_only でメンバーを絞り込めます。compact を指定すると、メンバーごとに名前、バージョン、パス、ターゲットの種類だけを返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn get_workspace_info(
+        &self,
+        manifest_path: Option<String>,
+        /// パッケージ名のパターン (`*` は任意の文字列、`?` は任意の 1 文字)
+        name: Option<String>,
+        /// パッケージのディレクトリの接頭辞 (ワークスペースのルートからの相対パスまたは絶対パス)
+        path_prefix: Option<String>,
+        /// crates.io に公開可能なパッケージ (`publish = false` でない) だけを返すかどうか。省略時は false
+        publish_only: Option<bool>,
+        /// メンバーごとに名前、バージョン、パス、ターゲットの種類だけを返すかどうか。省略時は false
+        compact: Option<bool>,
+        /// 1 ページの要素数。指定すると一覧をページに分割し、`_page` (`total`、`offset`、`next_cursor`) を付けて返します。detail が `summary` の場合は 10、`normal` の場合は 50 までに抑えます。省略時は分割しない
+        page_size: Option<usize>,
+        /// 次のページを取得するためのカーソル (前のページの `_page.next_cursor` の値)
+        cursor: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let page = page::Page::parse(page_size, cursor.as_deref())?.within(detail);
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let filter = workspace::MemberFilter {
+            name: name.as_deref(),
+            path_prefix: path_prefix.as_deref(),
+            publish_only: publish_only.unwrap_or(false),
+        };
+        let workspace_members = workspace::filter_members(metadata, &filter);
+        if compact.unwrap_or(false) {
+            let summaries: Vec<workspace::MemberSummary> = workspace_members
+                .into_iter()
+                .map(|p| workspace::member_summary(metadata, p))
+                .collect();
+            return to_output(
+                &page.apply(to_value(&summaries, "workspace members")?, None),
+                "workspace members",
+                detail,
+                format,
+            );
+        }
+
+        to_output(
+            &page.apply(to_value(&workspace_members, "workspace members")?, None),
+            "workspace members",
+            detail,
+            format,
+        )
+    }
+
+    /// プロジェクトのフィーチャー情報を取得します
+    ///
+    /// 指定されたCargoプロジェクトのフィーチャー情報を取得します。
+    /// 依存関係を解決せずに取得するため、大きなワークスペースでも高速です。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_features(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let root_package = match metadata.root_package() {
+            Some(pkg) => pkg,
+            None => bail_kind!(ErrorKind::NoRootPackage),
+        };
+
+        to_output(&root_package.features, "features", detail, format)
+    }
+
+    /// プロジェクトのネイティブ依存関係を取得します
+    ///
+    /// `links` を持つパッケージや `-sys` クレートを列挙し、それらが必要とする
+    /// システムライブラリ (pkg-config 名) と apt / brew のインストールヒントを推定します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_native_dependencies(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let report = native::native_dependency_report(metadata);
+        to_output(&report, "native dependencies", detail, format)
+    }
+
+    /// パッケージごとのライセンス情報をエクスポートします
+    ///
+    /// 依存グラフ内の全パッケージについて、正規化された SPDX ライセンス式と
+    /// ライセンスファイルのパスを機械可読な形式で返します。
+    /// ライセンス表記の生成ツールなどへの入力として使用できます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn export_license_manifest(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let manifest = license::license_manifest(metadata);
+        to_output(&manifest, "license manifest", detail, format)
+    }
+
+    /// 依存グラフ内のパッケージを、ライセンス式ごとにまとめて返します
+    ///
+    /// resolve グラフ内の全パッケージを正規化された SPDX ライセンス式ごとにまとめ、ワークスペースメンバーとそれ以外のパッケージに分けて、
+    /// パッケージ数の多い順に返します。`license` を宣言していないパッケージは `unlicensed` に返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_license_report(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &license::license_report(metadata),
+            "license report",
+            detail,
+            format,
+        )
+    }
+
+    /// 依存関係をソースの種類ごとに分類して返します
+    ///
+    /// ワークスペースメンバーから到達できる依存関係を、`source` フィールドをもとに crates.io、その他のレジストリ、git、path、vendored
+    /// (`cargo vendor` でベンダリングされたもの) に分類し、レジストリや git リポジトリの URL、ディレクトリごとにまとめて返します。
+    /// git の依存関係には Cargo.lock に記録されたコミットも含めます。リリース前にレジストリ以外の依存関係を確認するために使用してください。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_dependency_sources(
+        &self,
+        manifest_path: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は `false`
+        include_dev: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &sources::source_breakdown(metadata, include_dev.unwrap_or(false)),
+            "dependency sources",
+            detail,
+            format,
+        )
+    }
+
+    /// 依存関係のレポートを Markdown で生成します
+    ///
+    /// ワークスペースメンバーの一覧、直接の依存関係 (バージョン要求、解決されたバージョン、ライセンス、説明) の表、
+    /// 複数のバージョンに解決されたクレートの表を、リリースノートや設計文書にそのまま貼り付けられる Markdown として返します。
+    /// 直接の依存関係には、依存グラフで解決されたワークスペースメンバー以外のパッケージのみを含めます。
+    /// detail が `summary` の場合はワークスペースメンバーの一覧のみ、`normal` の場合は直接の依存関係の表まで、`full` の場合はすべての節を返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn generate_dependency_report(
+        &self,
+        manifest_path: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は `false`
+        include_dev: Option<bool>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        Ok(report::markdown_report(
+            metadata,
+            include_dev.unwrap_or(false),
+            detail,
+        ))
+    }
+
+    /// 依存グラフから SBOM (ソフトウェア部品表) を生成します
+    ///
+    /// ワークスペースメンバーとそれらから到達できるパッケージについて、名前、バージョン、Package URL、
+    /// Cargo.lock のチェックサム、ライセンス式、依存関係を記述した SPDX 2.3 または CycloneDX 1.5 の JSON ドキュメントを返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn generate_sbom(
+        &self,
+        manifest_path: Option<String>,
+        /// SBOM の形式 (`spdx` または `cyclonedx`)。省略時は `spdx`
+        sbom_format: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は `false`
+        include_dev: Option<bool>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = sbom::SbomFormat::parse(sbom_format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let include_dev = include_dev.unwrap_or(false);
+        let document = match format {
+            sbom::SbomFormat::Spdx => sbom::spdx_document(metadata, include_dev),
+            sbom::SbomFormat::CycloneDx => cyclonedx::bom_document(metadata, include_dev),
+        };
+        to_output(&document, "SBOM", detail, ValueFormat::Json)
+    }
+
+    /// アドバイザリの検出結果を CycloneDX VEX 形式でエクスポートします
+    ///
+    /// RustSec アドバイザリデータベースで依存グラフを検査し、各アドバイザリについて
+    /// 影響の有無と、その判断の根拠となる依存パスを記述した CycloneDX 1.5 の VEX ドキュメントを返します。
+    /// アドバイザリデータベースは `$CARGO_HOME/advisory-db` を使用し、存在しない場合や前回の取得から 1 時間以上経っている場合は取得し直します。
+    /// データベースの最新のコミットの日時は、metadata の properties (`rustsec:advisory-db:last-updated`) に記録します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn export_vex(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let document = blocking(move || {
+            let database = advisory::load_database()?;
+            let findings = advisory::find_advisories(&metadata, &database.db);
+            Ok(cyclonedx::vex_document(
+                &metadata,
+                &findings,
+                &database.info,
+            ))
+        })
+        .await?;
+        to_output(&document, "VEX document", detail, format)
+    }
+
+    /// 依存関係のセキュリティアドバイザリを検査します
+    ///
+    /// RustSec アドバイザリデータベースで依存グラフを検査し、脆弱性や unsound、メンテナンス終了などの
+    /// アドバイザリに該当するパッケージを返します。
+    /// アドバイザリデータベースは `$CARGO_HOME/advisory-db` を使用し、存在しない場合や前回の取得から 1 時間以上経っている場合は取得し直します。
+    /// `sarif` と `junit` 以外の形式では、データベースの場所と最新のコミットの日時も返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn audit(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`、`sarif` または `junit`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = OutputFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let report = {
+            let metadata = metadata.clone();
+            blocking(move || {
+                let database = advisory::load_database()?;
+                Ok(advisory::AuditReport {
+                    advisories: advisory::find_advisories(&metadata, &database.db),
+                    database: database.info,
+                })
+            })
+            .await?
+        };
+        match format {
+            OutputFormat::Value(value_format) => {
+                to_output(&report, "advisories", detail, value_format)
+            }
+            _ => findings_output(
+                &metadata,
+                format,
+                &advisory::advisory_finding_set(&metadata, &report.advisories),
+                detail,
+            ),
+        }
+    }
+
+    /// 依存関係のライセンスがポリシーに適合しているか検査します
+    ///
+    /// ワークスペースメンバー以外の全パッケージのライセンス式を、許可リストと拒否リストに照らして評価します。
+    /// `OR` の場合はいずれかの選択肢が、`AND` の場合は全てのライセンスが許可されていれば適合とみなします。
+    /// preset を指定すると、コピーレフトの禁止などの SPDX ライセンスリストの性質による条件も適用します。違反はワークスペースメンバーからの依存パスとともに返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_license_policy(
+        &self,
+        manifest_path: Option<String>,
+        /// 許可する SPDX ライセンス ID の一覧。省略時は拒否リスト以外の全てのライセンスを許可する
+        allow: Option<Vec<String>>,
+        /// 拒否する SPDX ライセンス ID の一覧
+        deny: Option<Vec<String>>,
+        /// 許可リストと拒否リストに加えて適用する条件 (`no-copyleft`: コピーレフトを拒否、`osi-approved`: OSI 承認のみ、`fsf-free`: FSF 承認のみ)
+        preset: Option<String>,
+        /// 出力形式 (`json`、`sarif` または `junit`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = OutputFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let policy = license::LicensePolicy {
+            allow: allow.unwrap_or_default(),
+            deny: deny.unwrap_or_default(),
+            preset: preset
+                .as_deref()
+                .map(license::LicensePreset::parse)
+                .transpose()?,
+            ..Default::default()
+        };
+        let set = license::check_license_policy(metadata, &policy);
+        findings_output(metadata, format, &set, detail)
+    }
+
+    /// ワークスペースメンバーの Cargo.toml を検査します
+    ///
+    /// 公開に必要なフィールド (license, description など) の不足や、`*` による依存バージョン指定などを報告します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn lint_manifest(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`sarif` または `junit`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = OutputFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let set = lint::lint_manifests(metadata);
+        findings_output(metadata, format, &set, detail)
+    }
+
+    /// ワークスペースメンバーの依存関係の、緩すぎる要求バージョンを検査します
+    ///
+    /// レジストリの依存関係のうち、`*` (crates.io への公開時に拒否されます)、上限のない `>=` や `>`、
+    /// 複数の semver 互換な範囲にまたがる要求バージョン (`0`、`0.*`、`>=1, <3` など) を、Cargo.toml の位置とともに報告します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_version_requirements(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`sarif` または `junit`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = OutputFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let set = requirements::check_requirements(metadata);
+        findings_output(metadata, format, &set, detail)
+    }
+
+    /// TOML で記述されたポリシーファイルに従って、依存関係を一括で検査します
+    ///
+    /// 依存パッケージ数の上限 (`max-dependencies`)、禁止クレート (`banned`)、許可ライセンス (`[licenses]`)、
+    /// semver 非互換なバージョンの重複数の上限 (`max-duplicate-majors`)、必須フィールド (`required-fields`)、
+    /// バージョン範囲と理由を指定できるクレートの許可リスト・拒否リスト (`[[crates.allow]]`、`[[crates.deny]]`) を
+    /// 1 回の評価で検査し、違反を構造化された形式で返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_policy(
+        &self,
+        manifest_path: Option<String>,
+        /// ポリシーファイルへのパス。省略時はワークスペースルートの `cargo-policy.toml`
+        policy_path: Option<String>,
+        /// 出力形式 (`json`、`sarif` または `junit`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = OutputFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let policy_path = match policy_path {
+            Some(path) => {
+                let path = PathBuf::from(path);
+                state.ensure_allowed(&path)?;
+                path
+            }
+            None => metadata
+                .workspace_root
+                .join(policy::DEFAULT_POLICY_FILE)
+                .into_std_path_buf(),
+        };
+        let policy = policy::Policy::load(&policy_path)?;
+        let set = policy::evaluate(metadata, &policy);
+        findings_output(metadata, format, &set, detail)
+    }
+
+    /// cargo-deny の設定ファイル (`deny.toml`) に従って、依存関係を検査します
+    ///
+    /// 設定ファイルの `[licenses]` (`allow`、`exceptions`)、`[bans]` (`deny`、`allow`、`skip`、`multiple-versions`)、
+    /// `[sources]` (`allow-registry`、`allow-git`、`allow-org`、`unknown-registry`、`unknown-git`) のチェックを現在の依存グラフに対して行い、
+    /// CI で cargo-deny を実行する前に違反を報告します。`[advisories]` などの他のチェックは行いません。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_deny_policy(
+        &self,
+        manifest_path: Option<String>,
+        /// 設定ファイルへのパス。省略時はワークスペースルートの `deny.toml`、`.deny.toml`、`.cargo/deny.toml` の順に探す
+        config_path: Option<String>,
+        /// 出力形式 (`json`、`sarif` または `junit`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = OutputFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let config_path = match config_path {
+            Some(path) => {
+                let path = PathBuf::from(path);
+                state.ensure_allowed(&path)?;
+                path
+            }
+            None => deny::default_config_path(metadata),
+        };
+        let config = deny::DenyConfig::load(&config_path)?;
+        let set = deny::evaluate(metadata, &config)?;
+        findings_output(metadata, format, &set, detail)
+    }
+
+    /// cargo-vet の監査の記録から、依存関係の監査の状況を返します
+    ///
+    /// cargo-vet のストア (`supply-chain/config.toml`、`audits.toml`、`imports.lock`) を読み、
+    /// 依存グラフ内の crates.io のパッケージを、監査済み (全体の監査、または差分の監査の連鎖)、免除 (`exemptions`)、未監査、違反に分けて返します。
+    /// ストアの場所は `[workspace.metadata.vet]` の `store.path` に従います。公開者の信頼 (`trusted`) と監査基準の包含関係は考慮しません。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_vet_coverage(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(&vet::vet_report(metadata)?, "vet coverage", detail, format)
+    }
+
+    /// 依存関係が予算内に収まっているか検査します
+    ///
+    /// 依存パッケージの総数、1 つの直接依存が新たに持ち込むパッケージ数、依存グラフの深さの上限を指定でき、
+    /// 予算を超えた量と、その原因となっている直接依存やパスを報告します。指定しなかった予算は検査しません。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_dependency_budget(
+        &self,
+        manifest_path: Option<String>,
+        /// ワークスペースメンバーを除いた依存パッケージ数の上限
+        max_total_crates: Option<usize>,
+        /// 1 つの直接依存が、他の直接依存と共有せずに持ち込むパッケージ数の上限
+        max_new_crates_per_dependency: Option<usize>,
+        /// ワークスペースメンバーから最も深い依存パッケージまでの深さの上限
+        max_depth: Option<usize>,
+        /// 出力形式 (`json`、`sarif` または `junit`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = OutputFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let budget = budget::Budget {
+            max_total_crates,
+            max_new_crates_per_dependency,
+            max_depth,
+        };
+        let set = budget::check_budget(metadata, &budget);
+        findings_output(metadata, format, &set, detail)
+    }
+
+    /// 直接依存ごとに、依存グラフに持ち込むパッケージの数と依存の深さを返します
+    ///
+    /// 直接依存ごとに、推移的に到達できるパッケージの数、他の直接依存と共有していないパッケージの数、最も深い依存パッケージまでの深さを、
+    /// 推移的なパッケージの多い順に返します。依存グラフを大きくしている直接依存を見つけるために使用してください。
+    /// 直接依存と推移的な依存の数、ワークスペースメンバーから最も深い依存パッケージまでのパスも返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_dependency_metrics(
+        &self,
+        manifest_path: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は `false`
+        include_dev: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &metrics::dependency_metrics(metadata, include_dev.unwrap_or(false)),
+            "dependency metrics",
+            detail,
+            format,
+        )
+    }
+
+    /// プロジェクトを変更せずに、依存関係の追加候補を評価します
+    ///
+    /// 候補のクレートの依存関係の閉包、ライセンス、アドバイザリ、保守状況の手がかりと、
+    /// 現在の依存グラフへの影響 (新たに追加されるパッケージ、semver 非互換なバージョンの重複) を返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn review_candidate_dependency(
+        &self,
+        manifest_path: Option<String>,
+        /// 追加を検討しているクレート名
+        crate_name: String,
+        /// バージョン要件 (例: `1.2`、`=0.4.3`)。省略時は最新版
+        version: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let review =
+            blocking(move || review::review_candidate(&metadata, &crate_name, version.as_deref()))
+                .await?;
+        to_output(&review, "candidate review", detail, format)
+    }
+
+    /// 直接依存を機能分類 (HTTP、シリアライズ、非同期ランタイム、暗号など) ごとにまとめます
+    ///
+    /// 各パッケージの categories と keywords から分類を推定し、プロジェクトが依存している機能の一覧を返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_dependency_categories(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &category::category_map(metadata),
+            "dependency categories",
+            detail,
+            format,
+        )
+    }
+
+    /// 同じ機能を提供する可能性のあるクレートの重複を推定します
+    ///
+    /// 2 つの JSON ライブラリや、openssl と rustls の併用など、統合の余地がある依存関係を、
+    /// それらに依存しているワークスペースメンバーとともに返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn find_overlapping_dependencies(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &category::overlap_report(metadata),
+            "overlap report",
+            detail,
+            format,
+        )
+    }
+
+    /// crates.io に公開されたクレートのバージョン履歴を返します
+    ///
+    /// 各バージョンの yank の有無、公開日時 (インデックスに記録されている場合)、rust-version と、
+    /// 依存グラフで使用中のバージョンより新しいバージョンの一覧を返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_version_history(
+        &self,
+        manifest_path: Option<String>,
+        /// バージョン履歴を取得するクレート名またはパッケージ ID 仕様
+        crate_name: String,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let spec = pkgid::PackageIdSpec::parse(&crate_name)?;
+        let history = blocking(move || index::version_history(&metadata, &spec.name)).await?;
+        to_output(&history, "version history", detail, format)
+    }
+
+    /// 直接依存の鮮度 (使用中のバージョンの古さと、最後のリリースからの経過日数) を返します
+    ///
+    /// 直接依存ごとの公開日時、最新版との差 (バージョン数と libyear) と、プロジェクト全体の集計値を返します。
+    /// 公開日時は crates.io のインデックスに記録されている場合のみ取得できます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_dependency_freshness(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let report = blocking(move || Ok(freshness::freshness_report(&metadata))).await?;
+        to_output(&report, "freshness report", detail, format)
+    }
+
+    /// Cargo.lock のバージョンが、マニフェストの要求バージョンで選べる最新のバージョンより古い直接依存を返します
+    ///
+    /// ワークスペースメンバーの crates.io の直接依存ごとに、要求バージョンを満たす yank されていない最新のバージョンを crates.io のインデックスから調べ、
+    /// Cargo.lock でロックされているバージョンより新しい場合 (`cargo update` で更新される場合) に返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_lockfile_drift(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let drift = blocking(move || Ok(outdated::lockfile_drift(&metadata))).await?;
+        to_output(&drift, "lockfile drift", detail, format)
+    }
+
+    /// crates.io に新しいバージョンがある直接依存を、semver 互換な更新と非互換な更新に分けて返します
+    ///
+    /// ワークスペースメンバーの crates.io の直接依存ごとに、yank されていない最新のバージョンを crates.io のインデックスから調べます。
+    /// `compatible` には使用中のバージョンと semver 互換な新しいバージョンがある依存を、`breaking` には最新版が semver 非互換な依存を返します。
+    /// 使用中のバージョンがプレリリース版でない場合、プレリリース版は最新版とみなしません。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_outdated(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let outdated = blocking(move || Ok(outdated::check_outdated(&metadata))).await?;
+        to_output(&outdated, "outdated dependencies", detail, format)
+    }
+
+    /// プレリリース版に解決された依存関係と、プレリリース版を許可している要求バージョンを返します
+    ///
+    /// リリース前の安定性の確認のため、ワークスペースメンバーから到達できるパッケージのうち `0.1.0-alpha.3` のようなプレリリース版に解決されたものを、
+    /// 依存元とワークスペースメンバーからの経路とともに返します。
+    /// また、ワークスペースメンバーの要求バージョンのうち、プレリリース版を含む比較子 (`^1.0.0-beta.1` など) によってプレリリース版を許可しているものを返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn find_prerelease_dependencies(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &requirements::prerelease_report(metadata),
+            "prerelease dependencies",
+            detail,
+            format,
+        )
+    }
+
+    /// `=` で固定された依存関係と、同じクレートに対する他の要求バージョンとの衝突を返します
+    ///
+    /// 依存グラフ内の全てのパッケージの依存関係のうち、`=1.2.3` のように `=` で固定されたものを、
+    /// 同じクレートに対する他の要求バージョンが固定されたバージョンを満たすかどうかとともに返します。
+    /// 固定されたバージョンと semver 互換な範囲を要求しているのに満たさない要求バージョンは、cargo が解決できないため衝突 (`conflict`) とします。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn find_pinned_dependencies(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &requirements::pinned_dependencies(metadata),
+            "pinned dependencies",
+            detail,
+            format,
+        )
+    }
+
+    /// 依存関係の、使用中のバージョンから指定したバージョンまでの変更履歴を返します
+    ///
+    /// リポジトリの CHANGELOG.md または GitHub Releases のリリースノートから、
+    /// 使用中のバージョンより新しく、指定したバージョン以下のバージョンの記述を取り出します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_changelog(
+        &self,
+        manifest_path: Option<String>,
+        /// 変更履歴を取得するクレート名またはパッケージ ID 仕様 (`name@version` など)。名前のみの場合は最新のバージョン
+        crate_name: String,
+        /// 更新先のバージョン。省略時は最新の安定版
+        target_version: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let report = blocking(move || {
+            changelog::changelog(&metadata, &crate_name, target_version.as_deref())
+        })
+        .await?;
+        to_output(&report, "changelog", detail, format)
+    }
+
+    /// 1 つの依存関係を指定したバージョンに更新した場合の影響を調べます
+    ///
+    /// ワークスペースの一時的なコピーで `cargo update -p <crate> --precise <version>` を実行し、
+    /// 推移的な依存関係を含む Cargo.lock の全ての変更を返します。実際のプロジェクトは変更しません。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn simulate_upgrade(
+        &self,
+        manifest_path: Option<String>,
+        /// 更新するクレート名またはパッケージ ID 仕様 (`name@version` など)
+        crate_name: String,
+        /// 更新先のバージョン
+        version: String,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let simulation =
+            blocking(move || upgrade::simulate_upgrade(&metadata, &crate_name, &version)).await?;
+        to_output(&simulation, "upgrade simulation", detail, format)
+    }
+
+    /// `cargo update --dry-run` を実行し、依存関係を更新した場合の変更を返します
+    ///
+    /// Cargo.lock は変更しません。packages を指定した場合は、それらのパッケージのみを更新の対象とします。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn update_dry_run(
+        &self,
+        manifest_path: Option<String>,
+        /// 更新の対象とするパッケージ (`name` または `name@version`)。省略時は全てのパッケージ
+        packages: Option<Vec<String>>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let packages = packages.unwrap_or_default();
+        let preview = blocking(move || upgrade::update_dry_run(&metadata, &packages)).await?;
+        to_output(&preview, "update preview", detail, format)
+    }
+
+    /// 依存関係を宣言されたバージョン要件の下限で解決できるかを調べます
+    ///
+    /// 一時的なコピーで nightly の cargo の `-Z minimal-versions` を使用し、Cargo.lock は変更しません。
+    /// 依存関係の解決のみを行い、ビルドは行いません。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_minimal_versions(
+        &self,
+        manifest_path: Option<String>,
+        /// 使用する rustup のツールチェーン。省略時は `nightly`
+        toolchain: Option<String>,
+        /// true の場合は直接依存のみを最小バージョンにする (`-Z direct-minimal-versions`)。省略時は false
+        direct_only: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let check = blocking(move || {
+            minimal::check_minimal_versions(
+                &metadata,
+                toolchain.as_deref().unwrap_or("nightly"),
+                direct_only.unwrap_or(false),
+            )
+        })
+        .await?;
+        to_output(&check, "minimal versions check", detail, format)
+    }
+
+    /// 依存関係を追加した場合のマニフェストの差分と Cargo.lock の変更を返します
+    ///
+    /// 一時的なコピーで `cargo add` を実行するため、ファイルは変更しません。
+    /// 返されたパッチはワークスペースルートで `git apply` で適用できます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn preview_add_dependency(
+        &self,
+        manifest_path: Option<String>,
+        /// 追加するクレート名
+        crate_name: String,
+        /// バージョン要件。省略時は最新のバージョン
+        version: Option<String>,
+        /// 有効にするフィーチャー
+        features: Option<Vec<String>>,
+        /// true の場合は dev-dependencies に追加する。省略時は false
+        dev: Option<bool>,
+        /// 依存関係を追加するワークスペースのメンバー (名前またはパッケージ ID 仕様)。省略時は manifest_path のパッケージ
+        package: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let manifest_path = state.manifest_path(manifest_path.as_deref()).await?;
+        let metadata = state.get_metadata(manifest_path.clone()).await?;
+
+        let features = features.unwrap_or_default();
+        let preview = blocking(move || {
+            let dependency = preview::AddDependency {
+                crate_name: &crate_name,
+                version: version.as_deref(),
+                features: &features,
+                dev: dev.unwrap_or(false),
+                package: package.as_deref(),
+                manifest_path: &manifest_path,
+            };
+            preview::preview_add(&metadata, &dependency)
+        })
+        .await?;
+        to_output(&preview, "add preview", detail, format)
+    }
+
+    /// 依存関係を削除した場合のマニフェストの差分と、Cargo.lock から削除されるクレートを返します
+    ///
+    /// 一時的なコピーで `cargo remove` を実行するため、ファイルは変更しません。
+    /// 返されたパッチはワークスペースルートで `git apply` で適用できます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn preview_remove_dependency(
+        &self,
+        manifest_path: Option<String>,
+        /// 削除するクレート名
+        crate_name: String,
+        /// 依存関係を削除するワークスペースのメンバー (名前またはパッケージ ID 仕様)。省略時は manifest_path のパッケージ
+        package: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let manifest_path = state.manifest_path(manifest_path.as_deref()).await?;
+        let metadata = state.get_metadata(manifest_path.clone()).await?;
+
+        let preview = blocking(move || {
+            preview::preview_remove(&metadata, &manifest_path, &crate_name, package.as_deref())
+        })
+        .await?;
+        to_output(&preview, "remove preview", detail, format)
+    }
+
+    /// Cargo.toml に依存関係を追加し、適用した差分を返します
+    ///
+    /// サーバーを `--allow-write` で起動した場合のみ使用できます。コメントや書式は保持されます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn add_dependency(
+        &self,
+        manifest_path: Option<String>,
+        /// 追加するクレート名
+        crate_name: String,
+        /// バージョン要件。省略時は crates.io の最新のバージョン
+        version: Option<String>,
+        /// 有効にするフィーチャー
+        features: Option<Vec<String>>,
+        /// true の場合は dev-dependencies に追加する。省略時は false
+        dev: Option<bool>,
+        /// 依存関係を追加するワークスペースのメンバー (名前またはパッケージ ID 仕様)。省略時は manifest_path のパッケージ
+        package: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let features = features.unwrap_or_default();
+        // 最新のバージョンのインデックスからの取得はブロッキングするため、マニフェストを編集する前に取得しておく
+        state.ensure_writable()?;
+        let version = match version {
+            Some(version) => version,
+            None => {
+                let name = crate_name.clone();
+                blocking(move || index::latest_version(&name))
+                    .await?
+                    .to_string()
+            }
+        };
+        let manifest_path = state.manifest_path(manifest_path.as_deref()).await?;
+        let members = state
+            .get_metadata(manifest_path.clone())
+            .await?
+            .workspace_packages()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let edit = state
+            .edit_manifest(manifest_path, package.as_deref(), |document| {
+                manifest::ensure_package(document, &members)?;
+                manifest::add_dependency(
+                    document,
+                    &crate_name,
+                    Some(&version),
+                    &features,
+                    dev.unwrap_or(false),
+                )
+            })
+            .await?;
+        to_output(&edit, "manifest edit", detail, format)
+    }
+
+    /// Cargo.toml から依存関係を削除し、適用した差分を返します
+    ///
+    /// サーバーを `--allow-write` で起動した場合のみ使用できます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn remove_dependency(
+        &self,
+        manifest_path: Option<String>,
+        /// 削除するクレート名
+        crate_name: String,
+        /// 依存関係を削除するワークスペースのメンバー (名前またはパッケージ ID 仕様)。省略時は manifest_path のパッケージ
+        package: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let edit = state
+            .edit_manifest(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                package.as_deref(),
+                |document| manifest::remove_dependency(document, &crate_name),
+            )
+            .await?;
+        to_output(&edit, "manifest edit", detail, format)
+    }
+
+    /// Cargo.toml の依存関係のバージョン要件を変更し、適用した差分を返します
+    ///
+    /// サーバーを `--allow-write` で起動した場合のみ使用できます。
+    /// `[workspace.dependencies]` にある依存関係も更新します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn set_dependency_version(
+        &self,
+        manifest_path: Option<String>,
+        /// 変更するクレート名
+        crate_name: String,
+        /// 新しいバージョン要件
+        version: String,
+        /// 依存関係を変更するワークスペースのメンバー (名前またはパッケージ ID 仕様)。省略時は manifest_path のパッケージ
+        package: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let edit = state
+            .edit_manifest(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                package.as_deref(),
+                |document| manifest::set_dependency_version(document, &crate_name, &version),
+            )
+            .await?;
+        to_output(&edit, "manifest edit", detail, format)
+    }
+
+    /// サーバーから見た cargo に関係する環境を返します
+    ///
+    /// CARGO_HOME、RUSTUP_HOME、RUSTFLAGS、プロキシなどの環境変数と、cargo の設定ファイルによるソースの置き換えを返します。
+    /// ターミナルでの cargo の実行結果とサーバーの結果が異なる場合の調査に使用します。認証情報は伏せられます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_environment(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let report = blocking(move || Ok(environment::environment_report(&metadata))).await?;
+        to_output(&report, "environment", detail, format)
+    }
+
+    /// ターゲットディレクトリのプロファイルごとのサイズと、ビルド済みの成果物を返します
+    ///
+    /// ターゲットディレクトリは `CARGO_TARGET_DIR` と設定ファイルを反映して cargo が解決したものを使用します。
+    /// 不要な成果物の削除や、ビルドしたバイナリの場所の確認に使用します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_target_dir(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let report = target_dir::target_dir_report(metadata);
+        to_output(&report, "target directory", detail, format)
+    }
+
+    /// ワークスペースのメンバーが次のビルドで再ビルドされるかと、その理由を推定して返します
+    ///
+    /// ターゲットディレクトリにある cargo のフィンガープリントと dep-info ファイルを調べ、
+    /// 最後のビルド以降に変更されたファイル、値が変わった環境変数や RUSTFLAGS を報告します。
+    /// 異なるフィーチャーの組み合わせのビルドが混在している場合は `variants` が 2 以上になります。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_build_freshness(
+        &self,
+        manifest_path: Option<String>,
+        /// プロファイルの出力ディレクトリ名 (`debug`、`release` など)。省略時は `debug`
+        profile: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let profile = profile.unwrap_or_else(|| "debug".to_string());
+        if profile.contains("..") {
+            bail_kind!(ErrorKind::InvalidProfile, profile);
+        }
+        let report = fingerprint::fingerprint_report(metadata, &profile);
+        to_output(&report, "build freshness", detail, format)
+    }
+
+    /// インストールされている cargo のサブコマンドを返します
+    ///
+    /// PATH と CARGO_HOME/bin にある `cargo-*` の実行ファイルと、`cargo install` の記録によるバージョンを返します。
+    /// cargo-audit や cargo-deny など、連携できる既知のサブコマンドが使用可能かどうかも返します。
+    #[tool]
+    async fn get_installed_subcommands(
+        &self,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let report = subcommand::installed_subcommands();
+        to_output(&report, "installed subcommands", detail, format)
+    }
+
+    /// hakari の workspace-hack クレートが最新かどうかを調べます
+    ///
+    /// workspace-hack に依存していないメンバーと、統合されたフィーチャーのうち workspace-hack で有効にしていないものを返します。
+    /// cargo-hakari がインストールされている場合は `cargo hakari verify` の結果も使用します (`--read-only` で起動した場合は、Cargo.lock を変更しうるため使用しません)。
+    /// workspace-hack クレートは、依存関係の集計や分析から自動的に除外されます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn check_workspace_hack(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let read_only = state.options.read_only;
+        let report =
+            blocking(move || Ok(hakari::check_workspace_hack(&metadata, read_only))).await?;
+        to_output(&report, "workspace-hack check", detail, format)
+    }
+
+    /// 依存関係を `cargo tree` のような木の形式で表示します
+    ///
+    /// resolve グラフを、依存関係ごとに字下げした ASCII の木として返します。
+    /// build-dependencies と dev-dependencies は見出しを付けて分けて表示し、一度表示したパッケージの依存関係は繰り返さずに `(*)` を付けます。
+    /// packageを省略すると、すべてのワークスペースメンバーの木を返します。
+    /// max_depth を省略した場合の深さは detail で決まり、`summary` は直接の依存関係まで、`normal` は 3 段まで、`full` は無制限です。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_dependency_tree(
+        &self,
+        manifest_path: Option<String>,
+        /// 木の起点にするパッケージ名またはパッケージ ID 仕様。省略時はすべてのワークスペースメンバー
+        package: Option<String>,
+        /// 表示する深さの上限 (`cargo tree --depth` 相当)。省略時は detail に応じた深さ
+        max_depth: Option<usize>,
+        /// dev-dependencies を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// 依存関係を絞り込むターゲットトリプル (`x86_64-unknown-linux-musl` など、`cargo tree --target` 相当)。省略時はすべてのプラットフォーム
+        target_triple: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata_with(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                metadata_options(target_triple, None, None, None),
+            )
+            .await?;
+
+        let graph = graph::Graph::new(metadata);
+        let roots = match package.as_deref() {
+            Some(spec) => vec![&pkgid::select_package(metadata, Some(spec))?.id],
+            None => graph.members().collect(),
+        };
+        let options = tree::TreeOptions {
+            max_depth: max_depth.or(detail.limit(1, 3)),
+            include_dev: include_dev.unwrap_or(false),
+            ..tree::TreeOptions::default()
+        };
+        Ok(tree::render(&graph, &roots, &options))
+    }
+
+    /// パッケージに依存しているパッケージを `cargo tree --invert` のような木の形式で表示します
+    ///
+    /// resolve グラフを依存元の方向に辿り、指定したパッケージからワークスペースメンバーまでのすべての経路を返します。
+    /// `serde@1` のように複数のバージョンに一致する仕様を指定した場合は、バージョンごとの木を返します。
+    /// max_depth を省略した場合の深さは detail で決まり、`summary` は直接の依存元まで、`normal` は 3 段まで、`full` は無制限です。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_reverse_dependencies(
+        &self,
+        manifest_path: Option<String>,
+        /// 依存元を調べるパッケージ名またはパッケージ ID 仕様 (`serde`、`serde@1` など)
+        package: String,
+        /// 表示する深さの上限 (`cargo tree --depth` 相当)。省略時は detail に応じた深さ
+        max_depth: Option<usize>,
+        /// dev-dependencies を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// 依存関係を絞り込むターゲットトリプル (`x86_64-unknown-linux-musl` など、`cargo tree --target` 相当)。省略時はすべてのプラットフォーム
+        target_triple: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata_with(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                metadata_options(target_triple, None, None, None),
+            )
+            .await?;
+
+        let roots: Vec<&cargo_metadata::PackageId> = pkgid::matching_packages(metadata, &package)?
+            .into_iter()
+            .map(|p| &p.id)
+            .collect();
+        let options = tree::TreeOptions {
+            max_depth: max_depth.or(detail.limit(1, 3)),
+            include_dev: include_dev.unwrap_or(false),
+            invert: true,
+        };
+        Ok(tree::render(&graph::Graph::new(metadata), &roots, &options))
+    }
+
+    /// パッケージが依存グラフに含まれている理由を説明します
+    ///
+    /// ワークスペースメンバーから指定したパッケージに至る resolve グラフ上のすべての経路を返します。
+    /// 経路の各辺には、依存関係の種類、optional な依存関係を有効にしたフィーチャー、依存先に要求しているフィーチャーが含まれます。
+    /// `serde@1` のように複数のバージョンに一致する仕様を指定した場合は、バージョンごとに返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn explain_dependency(
+        &self,
+        manifest_path: Option<String>,
+        /// 調べるパッケージ名またはパッケージ ID 仕様 (`syn`、`syn@1` など)
+        package: String,
+        /// dev-dependencies を経由する経路を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// バージョンごとに返す経路の数の上限。省略時は 20
+        max_paths: Option<usize>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let graph = graph::Graph::new(metadata);
+        let explanations: Vec<explain::DependencyExplanation> =
+            pkgid::matching_packages(metadata, &package)?
+                .into_iter()
+                .map(|p| {
+                    explain::explain(
+                        &graph,
+                        p,
+                        include_dev.unwrap_or(false),
+                        max_paths.unwrap_or(20),
+                    )
+                })
+                .collect();
+        to_output(&explanations, "dependency explanation", detail, format)
+    }
+
+    /// 複数のバージョンが含まれているクレートを検出します
+    ///
+    /// `cargo tree -d` のように、依存グラフに同じ名前で異なるバージョンが含まれているクレートを、
+    /// バージョンごとの直接の依存元と、ワークスペースメンバーからの経路とともに返します。重複を解消するとビルド時間を短縮できます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn find_duplicate_dependencies(
+        &self,
+        manifest_path: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// バージョンごとに返す経路の数の上限。省略時は 5
+        max_paths: Option<usize>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let duplicates = duplicates::find_duplicates(
+            &graph::Graph::new(metadata),
+            include_dev.unwrap_or(false),
+            max_paths.unwrap_or(5),
+        );
+        to_output(&duplicates, "duplicate dependencies", detail, format)
+    }
+
+    /// resolve グラフを取得します
+    ///
+    /// `cargo metadata` が解決した依存グラフのノード (パッケージと有効になったフィーチャー) と、
+    /// ノードごとの解決済みの依存関係 (クレート名、依存先のパッケージ ID、依存関係の種類とプラットフォーム) を返します。
+    /// packageを指定すると、そのパッケージから到達できるノードだけを返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_resolve_graph(
+        &self,
+        manifest_path: Option<String>,
+        /// 起点にするパッケージ名またはパッケージ ID 仕様。省略時はグラフ全体
+        package: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は true
+        include_dev: Option<bool>,
+        /// 依存関係を絞り込むターゲットトリプル (`x86_64-unknown-linux-musl` など、`cargo metadata --filter-platform` 相当)。省略時はすべてのプラットフォーム
+        target_triple: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata_with(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                metadata_options(target_triple, None, None, None),
+            )
+            .await?;
+
+        let roots = match package.as_deref() {
+            Some(spec) => Some(vec![&pkgid::select_package(metadata, Some(spec))?.id]),
+            None => None,
+        };
+        let graph = resolve::resolve_graph(metadata, roots.as_deref(), include_dev.unwrap_or(true));
+        to_output(&graph, "resolve graph", detail, format)
+    }
+
+    /// 依存グラフのパッケージごとに、実際に有効になったフィーチャーを取得します
+    ///
+    /// フィーチャーの統合 (unification) 後の resolve グラフから、パッケージごとに有効になったフィーチャーと、
+    /// 宣言されているが有効になっていないフィーチャーを返します。`get_features` が返すのはルートパッケージの宣言だけです。
+    /// features、all_features、no_default_features を指定すると、そのフィーチャーの選択でビルドした場合の結果を返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn get_resolved_features(
+        &self,
+        manifest_path: Option<String>,
+        /// 対象のパッケージ名またはパッケージ ID 仕様 (`serde`、`tokio@1` など)。省略時はすべてのパッケージ
+        package: Option<String>,
+        /// 依存関係を絞り込むターゲットトリプル (`x86_64-unknown-linux-musl` など、`cargo metadata --filter-platform` 相当)。省略時はすべてのプラットフォーム
+        target_triple: Option<String>,
+        /// 有効にするフィーチャー (`cargo metadata --features` 相当。`package/feature` の形式も使えます)
+        features: Option<Vec<String>>,
+        /// true の場合はすべてのフィーチャーを有効にする (`--all-features` 相当)。省略時は false
+        all_features: Option<bool>,
+        /// true の場合は既定のフィーチャーを無効にする (`--no-default-features` 相当)。省略時は false
+        no_default_features: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata_with(
+                state.manifest_path(manifest_path.as_deref()).await?,
+                metadata_options(target_triple, features, all_features, no_default_features),
+            )
+            .await?;
+
+        let packages = match package.as_deref() {
+            Some(spec) => Some(pkgid::matching_packages(metadata, spec)?),
+            None => None,
+        };
+        let resolved = features::resolved_features(metadata, packages.as_deref());
+        to_output(&resolved, "resolved features", detail, format)
+    }
+
+    /// 複数の依存元が共有する依存関係のフィーチャーの統合 (unification) を分析します
+    ///
+    /// 依存元ごとに要求しているフィーチャーと、統合後に有効になったフィーチャーを比べ、
+    /// ある依存元の要求 (`tokio/full` など) だけで有効になっているフィーチャー (`exclusive`) がある依存関係を、そのようなフィーチャーが多い順に返します。
+    /// ワークスペースメンバーの 1 つのフィーチャーの選択が、他の依存元のビルドを大きくしていないかを調べるのに使います。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn analyze_feature_unification(
+        &self,
+        manifest_path: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let unification =
+            features::unification(&graph::Graph::new(metadata), include_dev.unwrap_or(false));
+        to_output(&unification, "feature unification", detail, format)
+    }
+
+    /// 宣言されているが使われていないフィーチャーを検出します
+    ///
+    /// パッケージの Cargo.toml で宣言されているフィーチャーと、ソースファイルの `cfg(feature = "...")`、`cfg_attr`、`cfg!` による参照を突き合わせ、
+    /// 宣言されているがソースコードから参照されていないフィーチャー (`default` を除く) と、参照されているが宣言されていないフィーチャーを返します。
+    /// 他のフィーチャーや依存関係を有効にするだけのフィーチャーは参照されなくても問題ないため、参照されていないフィーチャーには有効にする項目 (`enables`) を含めます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn find_unused_features(
+        &self,
+        manifest_path: Option<String>,
+        /// 対象のワークスペースメンバー (名前またはパッケージ ID 仕様)。省略時はルートパッケージ
+        package: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let package = pkgid::select_package(metadata, package.as_deref())?;
+        to_output(
+            &features::feature_usage(package),
+            "feature usage",
+            detail,
+            format,
+        )
+    }
+
+    /// フィーチャーごとに、有効になるフィーチャーと optional な依存関係を取得します
+    ///
+    /// パッケージのフィーチャーのテーブルを展開し、フィーチャーごとに直接書かれているフィーチャー、推移的に有効になるフィーチャー、
+    /// 有効になる optional な依存関係 (`dep:` 構文と暗黙のフィーチャーを含む)、依存関係のフィーチャー (`name/feature`、`name?/feature`) を返します。
+    /// フィーチャーを有効にしたときの影響範囲を調べるのに使います。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_feature_dependency_map(
+        &self,
+        manifest_path: Option<String>,
+        /// 対象のワークスペースメンバー (名前またはパッケージ ID 仕様)。省略時はルートパッケージ
+        package: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let package = pkgid::select_package(metadata, package.as_deref())?;
+        to_output(
+            &features::feature_dependency_map(package),
+            "feature dependency map",
+            detail,
+            format,
+        )
+    }
+
+    /// ワークスペースの設定の継承の状況を取得します
+    ///
+    /// ワークスペースルートの `[workspace.package]` と `[workspace.dependencies]` を読み、メンバーごとに `workspace = true` で継承しているキーと依存関係、
+    /// 継承できるのにメンバーで値やバージョンを書いているキーと依存関係を返します。どのメンバーも継承していない `[workspace.dependencies]` の依存関係も返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_workspace_inheritance(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let report = inheritance::inheritance(metadata)?;
+        to_output(&report, "workspace inheritance", detail, format)
+    }
+
+    /// `[package.metadata]` と `[workspace.metadata]` のテーブルを取得します
+    ///
+    /// cargo-release、docs.rs、cargo-dist などのツールが設定に使う `[package.metadata.*]` と `[workspace.metadata.*]` を返します。
+    /// keyを指定すると、その名前のテーブル (`docs.rs`、`release` など) だけを返します。
+    /// packageを省略した場合はルートパッケージのテーブルを返します (仮想ワークスペースでは `[workspace.metadata]` だけを返します)。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_custom_metadata(
+        &self,
+        manifest_path: Option<String>,
+        /// 対象のワークスペースメンバー (名前またはパッケージ ID 仕様)。省略時はルートパッケージ
+        package: Option<String>,
+        /// 取り出すテーブルの名前 (`docs.rs`、`release` など)。省略時はすべて
+        key: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let package = match package.as_deref() {
+            Some(spec) => Some(pkgid::select_package(metadata, Some(spec))?),
+            None => metadata.root_package(),
+        };
+        let custom = workspace::custom_metadata(metadata, package, key.as_deref());
+        to_output(&custom, "custom metadata", detail, format)
+    }
+
+    /// ワークスペースの既定のメンバーと除外されたパスを取得します
+    ///
+    /// メンバー、既定のメンバー (`default-members`、引数なしの `cargo build` の対象)、`[workspace]` の `members`、`default-members`、`exclude` のパターンと、
+    /// ワークスペースのディレクトリ以下にあるがメンバーではないパッケージとその理由 (`excluded`、`own_workspace`、`not_in_members`) を返します。
+    /// 「なぜこのクレートが `cargo build` の対象にならないのか」を調べるのに使います。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_workspace_layout(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let layout = workspace::workspace_layout(metadata)?;
+        to_output(&layout, "workspace layout", detail, format)
+    }
+
+    /// ワークスペースメンバーを crates.io に公開する順序を取得します
+    ///
+    /// ワークスペース内のパス依存関係 (通常の依存関係とビルド依存関係) でメンバーをトポロジカルソートし、公開する順序と、同時に公開できるメンバーの組を返します。
+    /// `publish = false` のメンバーと、それに依存しているため公開できないメンバー、循環する依存関係のため順序を決められないメンバーも返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_publish_order(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &workspace::publish_order(metadata),
+            "publish order",
+            detail,
+            format,
+        )
+    }
+
+    /// 変更されたファイルの影響を受けるワークスペースメンバーを取得します
+    ///
+    /// 変更されたファイルの一覧 (`git diff --name-only` の出力など) から、それらを含むメンバーと、そのメンバーに推移的に依存しているメンバー (dev-dependencies を含む) を返します。
+    /// Cargo.lock やワークスペースルートの Cargo.toml など、すべてのメンバーに影響するファイルが含まれている場合はすべてのメンバーを返します。
+    /// CI で影響を受けるクレートだけをビルド、テストするのに使います。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_affected_crates(
+        &self,
+        manifest_path: Option<String>,
+        /// 変更されたファイルのパス (ワークスペースのルートからの相対パスまたは絶対パス)
+        changed_files: Vec<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let affected = workspace::affected_members(metadata, &changed_files);
+        to_output(&affected, "affected crates", detail, format)
+    }
+
+    /// Cargo.lock の内容を取得します
+    ///
+    /// 依存関係を解決せずに Cargo.lock を直接読み、ロックされたパッケージごとのバージョン、ソース、チェックサム、依存先を返します。
+    /// nameを指定すると、その名前のパッケージだけを返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_lockfile(
+        &self,
+        manifest_path: Option<String>,
+        /// 返すパッケージの名前。省略時はすべて
+        name: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let path = metadata.workspace_root.join("Cargo.lock");
+        let mut lockfile = lockfile::Lockfile::load(path.as_std_path())?;
+        if let Some(name) = &name {
+            lockfile.packages.retain(|p| p.name == *name);
+        }
+        to_output(&lockfile, "lockfile", detail, format)
+    }
+
+    /// 2 つの Cargo.lock の差分を返します
+    ///
+    /// 2 つの Cargo.lock の内容、または git のリビジョンの Cargo.lock (`git show <rev>:Cargo.lock`) を比べ、
+    /// パッケージごとの追加、削除、アップグレード、ダウングレードと、semver 非互換な更新かどうかを返します。dependabot などの PR の変更の要約に使います。
+    /// before と before_ref をどちらも省略した場合は `HEAD` の Cargo.lock と、after と after_ref をどちらも省略した場合は作業ツリーの Cargo.lock と比べます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn diff_lockfile(
+        &self,
+        manifest_path: Option<String>,
+        /// 変更前の Cargo.lock の内容。指定した場合は before_ref を無視します
+        before: Option<String>,
+        /// 変更後の Cargo.lock の内容。指定した場合は after_ref を無視します
+        after: Option<String>,
+        /// 変更前の Cargo.lock を読む git のリビジョン (ブランチ、タグ、コミット)
+        before_ref: Option<String>,
+        /// 変更後の Cargo.lock を読む git のリビジョン
+        after_ref: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let changes = blocking(move || {
+            let root = metadata.workspace_root.as_std_path();
+            let before = match before {
+                Some(text) => text,
+                None => {
+                    git::show_file(root, before_ref.as_deref().unwrap_or("HEAD"), "Cargo.lock")?
+                }
+            };
+            let before = lockfile::Lockfile::parse(&before)?;
+            let after = match (after, after_ref) {
+                (Some(text), _) => lockfile::Lockfile::parse(&text)?,
+                (None, Some(rev)) => {
+                    lockfile::Lockfile::parse(&git::show_file(root, &rev, "Cargo.lock")?)?
+                }
+                (None, None) => lockfile::Lockfile::load(&root.join("Cargo.lock"))?,
+            };
+            Ok(lockfile::diff(&before, &after))
+        })
+        .await?;
+        to_output(&changes, "lockfile diff", detail, format)
+    }
+
+    /// git のリビジョンと作業ツリーのメタデータの差分を返します
+    ///
+    /// リビジョンを一時的な git worktree にチェックアウトして `cargo metadata` を実行し、作業ツリーのメタデータと比べて、
+    /// ワークスペースメンバーの増減、パッケージごとの追加、削除、アップグレード、ダウングレードと、有効なフィーチャーの変化を返します。
+    /// ブランチで依存関係の構造がどう変わったかの説明に使います。読み取り専用モードでは worktree を作成できないため使えません。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn diff_metadata(
+        &self,
+        manifest_path: Option<String>,
+        /// 比較の基準にする git のリビジョン (ブランチ、タグ、コミット)
+        base_ref: String,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        if state.options.read_only {
+            bail_kind!(ErrorKind::ReadOnlyMode);
+        }
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let root = metadata.workspace_root.clone().into_std_path_buf();
+        let worktree = blocking(move || git::Worktree::new(&root, &base_ref)).await?;
+        let base = state.load_metadata(worktree.dir().join("Cargo.toml")).await;
+        // worktree の削除も git の終了を待つため、取得に失敗した場合も含めて spawn_blocking で行う
+        blocking(move || {
+            drop(worktree);
+            Ok(())
+        })
+        .await?;
+        let base = base?;
+        to_output(
+            &compare::diff_metadata(&base, metadata),
+            "metadata diff",
+            detail,
+            format,
+        )
+    }
+
+    /// 依存関係のソースコードの `unsafe` の使用状況を返します
+    ///
+    /// cargo-geiger のように、ワークスペースメンバーとそれらから到達できる各パッケージのソースファイルを走査し、
+    /// `unsafe fn`、`unsafe` ブロック、`unsafe impl`、`unsafe trait` の数と、`#![forbid(unsafe_code)]` の有無を、使用数の多い順に返します。
+    /// ソースファイルはパッケージの Cargo.toml のディレクトリから探します。コメントと文字列リテラルの中の `unsafe` は数えません。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_unsafe_usage(
+        &self,
+        manifest_path: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は `false`
+        include_dev: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        // 全ての依存パッケージのソースファイルを読むため、非同期ランタイムのスレッドの外で走査する
+        let report = blocking(move || {
+            Ok(unsafe_code::unsafe_report(
+                &metadata,
+                include_dev.unwrap_or(false),
+            ))
+        })
+        .await?;
+        to_output(&report, "unsafe usage", detail, format)
+    }
+
+    /// 依存グラフを Graphviz の DOT 形式でエクスポートします
+    ///
+    /// 指定したパッケージ (省略時はすべてのワークスペースメンバー) から辿れる resolve グラフを、`dot -Tsvg` などで描画できる DOT の文字列として返します。
+    /// ワークスペースメンバーは塗りつぶし、build-dependencies は点線、dev-dependencies は破線の辺で描きます。
+    /// dedup が false の場合は、同じパッケージでも経路ごとに別のノードとして木の形に展開します。
+    /// 描く範囲は detail で決まり、`summary` はワークスペースメンバー間の依存関係のみ、`normal` はワークスペースメンバーとその直接の依存関係、
+    /// `full` は依存グラフ全体です (max_depth を指定した場合は max_depth を優先します)。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn export_dot_graph(
+        &self,
+        manifest_path: Option<String>,
+        /// グラフの起点にするパッケージ名またはパッケージ ID 仕様。省略時はすべてのワークスペースメンバー
+        package: Option<String>,
+        /// 辿る深さの上限 (`cargo tree --depth` 相当)。省略時は detail に応じた深さ
+        max_depth: Option<usize>,
+        /// 同じパッケージを 1 つのノードにまとめるかどうか。省略時は true
+        dedup: Option<bool>,
+        /// dev-dependencies を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let graph = graph::Graph::new(metadata);
+        let roots = match package.as_deref() {
+            Some(spec) => vec![&pkgid::select_package(metadata, Some(spec))?.id],
+            None => graph.members().collect(),
+        };
+        let options = diagram::DiagramOptions {
+            max_depth: max_depth.or((detail == Detail::Normal).then_some(1)),
+            include_dev: include_dev.unwrap_or(false),
+            dedup: dedup.unwrap_or(true),
+            workspace_only: detail == Detail::Summary,
+        };
+        Ok(diagram::dot(&graph, &roots, &options))
+    }
+
+    /// 依存グラフを Mermaid の図 (`graph TD`) としてエクスポートします
+    ///
+    /// 指定したパッケージ (省略時はすべてのワークスペースメンバー) から辿れる resolve グラフを、GitHub や Notion などでそのまま描画できる
+    /// ` ```mermaid ` のコードブロックとして返します。workspace_only が true の場合は、ワークスペースメンバー間の依存関係のみを描きます。
+    /// ワークスペースメンバーは強調し、build-dependencies と dev-dependencies は点線の辺で描きます。
+    /// 描く範囲は detail で決まり、`summary` はワークスペースメンバー間の依存関係のみ、`normal` はワークスペースメンバーとその直接の依存関係、
+    /// `full` は依存グラフ全体です (workspace_only や max_depth を指定した場合はそちらを優先します)。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    #[allow(clippy::too_many_arguments)]
+    async fn export_mermaid_graph(
+        &self,
+        manifest_path: Option<String>,
+        /// グラフの起点にするパッケージ名またはパッケージ ID 仕様。省略時はすべてのワークスペースメンバー
+        package: Option<String>,
+        /// ワークスペースメンバー間の依存関係のみを描くかどうか。省略時は detail が `summary` の場合のみ true
+        workspace_only: Option<bool>,
+        /// 辿る深さの上限 (`cargo tree --depth` 相当)。省略時は detail に応じた深さ
+        max_depth: Option<usize>,
+        /// 同じパッケージを 1 つのノードにまとめるかどうか。省略時は true
+        dedup: Option<bool>,
+        /// dev-dependencies を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let graph = graph::Graph::new(metadata);
+        let roots = match package.as_deref() {
+            Some(spec) => vec![&pkgid::select_package(metadata, Some(spec))?.id],
+            None => graph.members().collect(),
+        };
+        let options = diagram::DiagramOptions {
+            max_depth: max_depth.or((detail == Detail::Normal).then_some(1)),
+            include_dev: include_dev.unwrap_or(false),
+            dedup: dedup.unwrap_or(true),
+            workspace_only: workspace_only.unwrap_or(detail == Detail::Summary),
+        };
+        Ok(diagram::mermaid(&graph, &roots, &options))
+    }
+
+    /// 依存関係の一覧を CSV 形式でエクスポートします
+    ///
+    /// ワークスペースメンバーから到達できるパッケージを 1 行に 1 つずつ、名前、バージョン、関係 (`workspace`、`direct`、`transitive`)、
+    /// 依存関係の種類、ライセンス、ソース (`workspace`、`path`、`crates-io`、`registry`、`git`)、リポジトリの列で返します。
+    /// 表計算ソフトにそのまま読み込めます。列は detail で決まり、`summary` は名前、バージョン、関係まで、
+    /// `normal` はさらに依存関係の種類とライセンスまで、`full` はすべての列を返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn export_dependency_csv(
+        &self,
+        manifest_path: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        Ok(export::dependency_csv(
+            metadata,
+            include_dev.unwrap_or(false),
+            detail,
+        ))
+    }
+
+    /// 依存グラフを外部のグラフ分析ツール向けの形式でエクスポートします
+    ///
+    /// JSON Graph Format (`jgf`) は graph データベースや Cytoscape.js に、GraphML (`graphml`) は Gephi や Cytoscape に読み込めます。
+    /// ノードの ID には cargo のパッケージ ID を使用します。
+    /// detail は JSON Graph Format の場合のみ適用されます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn export_dependency_graph(
+        &self,
+        manifest_path: Option<String>,
+        /// グラフの形式 (`jgf` または `graphml`)。省略時は `jgf`
+        graph_format: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は false
+        include_dev: Option<bool>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let format = export::GraphFormat::parse(graph_format.as_deref())?;
+        let graph = export::export_graph(metadata, format, include_dev.unwrap_or(false));
+        match (format, detail) {
+            (export::GraphFormat::Jgf, Detail::Summary | Detail::Normal) => {
+                match serde_json::from_str::<serde_json::Value>(&graph) {
+                    Ok(value) => to_output(&value, "dependency graph", detail, ValueFormat::Json),
+                    Err(e) => bail_kind!(ErrorKind::ParseFailed, "dependency graph", e),
+                }
+            }
+            _ => Ok(graph),
+        }
+    }
+
+    /// 2 つのプロジェクトの依存関係を比較します
+    ///
+    /// 共通の依存関係 (バージョンの不一致を含む)、片方にのみ含まれるクレート、ライセンスとアドバイザリの差分を返します。
+    /// リポジトリの統合や、プロジェクト間の依存関係の整合性の確認に使用します。
+    /// manifest_a と manifest_b には、それぞれの Cargo.toml ファイルへのパス、またはプロジェクトのディレクトリを指定します。
+    #[tool]
+    async fn compare_projects(
+        &self,
+        manifest_a: String,
+        manifest_b: String,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let a = state
+            .get_metadata(state.manifest_path(Some(&manifest_a)).await?)
+            .await?;
+        let b = state
+            .get_metadata(state.manifest_path(Some(&manifest_b)).await?)
+            .await?;
+
+        let comparison = blocking(move || Ok(compare::compare_projects(&a, &b))).await?;
+        to_output(&comparison, "project comparison", detail, format)
+    }
+
+    /// 複数のワークスペースの依存関係を集計したレポートを返します
+    ///
+    /// 多くのワークスペースで使用されているクレート、ワークスペースごとのバージョンの分散、
+    /// アドバイザリの影響を受けるワークスペースを返します。
+    /// 対象のワークスペースは manifest_paths で指定するか、サーバーの起動時に `--workspace <path>` で設定します。
+    #[tool]
+    async fn aggregate_workspaces(
+        &self,
+        /// 対象とする Cargo.toml ファイルまたはプロジェクトのディレクトリへのパス。省略時は `--workspace` で設定したもの
+        manifest_paths: Option<Vec<String>>,
+        /// most_used に含めるクレートの数。省略時は 50
+        limit: Option<usize>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let paths: Vec<PathBuf> = match manifest_paths {
+            Some(paths) => {
+                let mut manifests = Vec::new();
+                for path in &paths {
+                    manifests.push(state.manifest_path(Some(path)).await?);
+                }
+                manifests
+            }
+            None => state.options.workspaces.clone(),
+        };
+        if paths.is_empty() {
+            bail_kind!(ErrorKind::NoWorkspaces);
+        }
+        let mut workspaces = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            match state.get_metadata(path.clone()).await {
+                Ok(metadata) => workspaces.push((summary::project_name(&metadata), metadata)),
+                Err(e) => errors.push(format!(
+                    "{}: {}",
+                    path.display(),
+                    e.to_error_object(false).message
+                )),
+            }
+        }
+        let db = blocking(|| Ok(advisory::load_database())).await?;
+        let db = db.as_ref().map_err(|e| e.to_error_object(false).message);
+        let report = aggregate::aggregate(&workspaces, errors, db, limit.unwrap_or(50));
+        to_output(&report, "aggregate report", detail, format)
+    }
+
+    /// プロジェクトの概要を簡潔にまとめて返します
+    ///
+    /// ワークスペースの構成、主要なメンバー、直接依存の機能分類、フィーチャー、
+    /// 重複バージョンや git 依存などの注意すべき点を、数 KB 未満に収まるようにまとめます。
+    /// 最初にプロジェクトの全体像を把握するために使用し、詳細は個別のツールで取得してください。
+    #[tool]
+    async fn summarize_project(
+        &self,
+        /// Cargo.toml ファイルまたはプロジェクトのディレクトリへのパス。省略時は `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクト
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `normal`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Normal)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+        // 詳細さに応じた切り詰めは project_overview で行うため、to_output ではもう一度切り詰めない
+        to_output(
+            &summary::project_overview(metadata, detail),
+            "project overview",
+            Detail::Full,
+            format,
+        )
+    }
+
+    /// 依存関係の統計を返します
+    ///
+    /// ワークスペースメンバー、直接の依存関係、推移的な依存関係、依存関係のクレート名、複数のバージョンに解決されたクレートの数と、
+    /// ワークスペースメンバーのターゲットの種類ごとの数、依存関係のライセンス式ごとのパッケージ数を 1 回の呼び出しで返します。
+    /// 初めて見るプロジェクトの規模を把握するために使用してください。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_summary(
+        &self,
+        manifest_path: Option<String>,
+        /// dev-dependencies を含めるかどうか。省略時は `false`
+        include_dev: Option<bool>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        to_output(
+            &summary::dependency_statistics(metadata, include_dev.unwrap_or(false)),
+            "dependency statistics",
+            detail,
+            format,
+        )
+    }
+
+    /// パッケージ ID 仕様に一致するパッケージの ID を返します
+    ///
+    /// `cargo pkgid` と同様に、`name`、`name@version`、`https://github.com/rust-lang/crates.io-index#name@version` などの
+    /// パッケージ ID 仕様に一致するパッケージを 1 つに特定し、完全なパッケージ ID と、
+    /// 依存グラフの中でそのパッケージを一意に特定できる最も短い仕様を返します。
+    /// 一致するパッケージが複数ある場合は、候補のパッケージ ID を含むエラーを返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_pkgid(
+        &self,
+        manifest_path: Option<String>,
+        /// パッケージ名またはパッケージ ID 仕様
+        package: String,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let package = pkgid::resolve(&metadata.packages, &package)?;
+        to_output(
+            &pkgid::pkgid_info(metadata, package),
+            "package ID",
+            detail,
+            format,
+        )
+    }
+
+    /// ライブラリの依存関係が公開 API に現れるかを調べ、`public` の宣言と照合します
+    ///
+    /// Cargo.toml の `public = true` の指定 (RFC 1977、`-Zpublic-dependency`) と、rustdoc JSON から調べた
+    /// 公開 API での依存クレートの使用を比較し、public と宣言すべき依存関係 (`declare-public`) と、
+    /// 公開 API に現れないのに public と宣言されている依存関係 (`declare-private`) を報告します。
+    /// rustdoc JSON が見つからないメンバーは、宣言だけを返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn audit_public_dependencies(
+        &self,
+        manifest_path: Option<String>,
+        /// `<crate>.json` 形式の rustdoc JSON があるディレクトリ。省略時はターゲットディレクトリの `doc`
+        rustdoc_dir: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let rustdoc_dir = rustdoc_dir.map(PathBuf::from);
+        if let Some(dir) = &rustdoc_dir {
+            state.ensure_allowed(dir)?;
+        }
+        let report = public_api::public_dependency_report(metadata, rustdoc_dir)?;
+        to_output(&report, "public dependency report", detail, format)
+    }
+
+    /// ビルドターゲットとフィーチャーの選択の組み合わせごとに、ビルドできるかどうかを表にします
+    ///
+    /// ライブラリ、バイナリ、example、テスト、ベンチマークのそれぞれについて、`required-features` と、
+    /// 既定のフィーチャー、`--no-default-features`、`--all-features`、指定したフィーチャーの組み合わせでのビルドの可否を返します。
+    /// 選択ごとの cargo の引数と、有効になるプラットフォーム固有の依存関係の `cfg` も含むため、CI のマトリックスの設計に使えます。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_target_matrix(
+        &self,
+        manifest_path: Option<String>,
+        /// 対象のワークスペースメンバー (パッケージ ID 仕様)。省略時は全てのメンバー
+        package: Option<String>,
+        /// 追加で調べるフィーチャーの組み合わせ (カンマ区切り)。`--no-default-features --features <set>` として評価します
+        feature_sets: Option<Vec<String>>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let packages = match &package {
+            Some(spec) => vec![pkgid::resolve(metadata.workspace_packages(), spec)?],
+            None => metadata.workspace_packages(),
+        };
+        let feature_sets = feature_sets.unwrap_or_default();
+        let matrices: Vec<matrix::TargetMatrix> = packages
+            .into_iter()
+            .map(|p| matrix::target_matrix(p, &feature_sets))
+            .collect();
+        to_output(&matrices, "target matrix", detail, format)
+    }
+
+    /// CI のフィーチャーのマトリックス用に、フィーチャーの組み合わせと cargo のコマンドを列挙します
+    ///
+    /// ワークスペースメンバーごとに、フィーチャーなし (`--no-default-features`)、既定のフィーチャー、各フィーチャー単独、すべてのフィーチャーの組み合わせと、
+    /// それぞれに対応する `cargo check --features ...` のコマンドを返します。
+    /// 名前の接頭辞が共通で同時に有効にされないフィーチャー (`tls-native` と `tls-rustls` など) は互いに排他的とみなし、
+    /// `--all-features` の代わりに、その中から 1 つずつ選んで残りをすべて有効にする組み合わせを返します。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn get_feature_matrix(
+        &self,
+        manifest_path: Option<String>,
+        /// 対象のワークスペースメンバー (パッケージ ID 仕様)。省略時は全てのメンバー
+        package: Option<String>,
+        /// コマンドに使う cargo のサブコマンド (`check`、`test`、`clippy` など)。省略時は `check`
+        subcommand: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let metadata = &*state
+            .get_package_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+
+        let packages = match &package {
+            Some(spec) => vec![pkgid::resolve(metadata.workspace_packages(), spec)?],
+            None => metadata.workspace_packages(),
+        };
+        let subcommand = subcommand.as_deref().unwrap_or("check");
+        let matrices: Vec<matrix::FeatureMatrix> = packages
+            .into_iter()
+            .map(|p| matrix::feature_matrix(p, subcommand))
+            .collect();
+        to_output(&matrices, "feature matrix", detail, format)
+    }
+
+    /// プロジェクトのメタデータを取得し直します
+    ///
+    /// キャッシュを使わずに `cargo metadata` を実行し、キャッシュしているメタデータを置き換えます。
+    /// キャッシュはマニフェストの変更を検出すると自動的に破棄されますが、検出できない変更 (`.cargo/config.toml` や環境変数など) のあとに使います。
+    /// manifest_pathには、Cargo.tomlファイルへのパス、またはプロジェクトのディレクトリ (プロジェクト内の任意のパス) を指定します。省略時は、サーバーの起動時に `--manifest-path` で設定したプロジェクト、またはクライアントのルート (roots) にあるプロジェクトを使います。
+    #[tool]
+    async fn refresh_metadata(
+        &self,
+        manifest_path: Option<String>,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let result = state
+            .refresh_metadata(state.manifest_path(manifest_path.as_deref()).await?)
+            .await?;
+        to_output(&result, "refresh result", detail, format)
+    }
+
+    /// キャッシュしているすべてのメタデータを破棄します
+    ///
+    /// 次にツールを呼び出したときに、プロジェクトごとに `cargo metadata` が実行し直されます。
+    #[tool]
+    async fn clear_cache(
+        &self,
+        /// 出力形式 (`json`、`toml`、`markdown-table`、`compact`)。省略時は `json`
+        format: Option<String>,
+        /// 返す情報の詳細さ (`summary`、`normal`、`full`)。省略時は `full`
+        detail: Option<String>,
+    ) -> Result<String> {
+        let detail = Detail::parse(detail.as_deref(), Detail::Full)?;
+        let format = ValueFormat::parse(format.as_deref())?;
+        let state = &self.0;
+        let cleared: Vec<String> = state
+            .metadata
+            .clear()
+            .iter()
+            .map(|key| key.display().to_string())
+            .collect();
+        to_output(
+            &cache::ClearResult { cleared },
+            "clear result",
+            detail,
+            format,
+        )
+    }
+
+    async fn resources_list(
+        &self,
+        _p: ListResourcesRequestParams,
+        _cx: &mut RequestContext,
+    ) -> Result<ListResourcesResult> {
+        let state = &self.0;
+        let mut resources = Vec::new();
+        let projects = state.projects().await;
+        for metadata in &projects {
+            let project = summary::project_key(metadata, &projects);
+            for package in metadata.workspace_packages() {
+                resources.push(
+                    Resource::new(
+                        &summary::summary_uri(&project, &package.name),
+                        &format!("{project}/{} summary", package.name),
+                    )
+                    .with_description(&format!("Summary of the `{}` package", package.name))
+                    .with_mime_type("application/json"),
+                );
+            }
+            if !state.options.chunked_resources {
+                continue;
+            }
+            for package in &metadata.packages {
+                resources.push(
+                    Resource::new(
+                        &chunks::package_uri(&project, package),
+                        &format!("{project}/{}@{}", package.name, package.version),
+                    )
+                    .with_mime_type("application/json"),
+                );
+            }
+            for (report, description) in chunks::REPORTS {
+                resources.push(
+                    Resource::new(
+                        &chunks::report_uri(&project, report),
+                        &format!("{project}/{report} report"),
+                    )
+                    .with_description(description)
+                    .with_mime_type("application/json"),
+                );
+            }
+        }
+        Ok(resources.into())
+    }
+
+    /// ワークスペースメンバーの概要
+    ///
+    /// パッケージの目的、ターゲット、フィーチャー、直接の依存関係を説明する文章と、その構造化データを返します。
+    /// project はワークスペースルートのディレクトリ名です (同じ名前のプロジェクトが複数ある場合は、ワークスペースルートのパスのハッシュを付けたもの)。
+    /// 対象のプロジェクトは、ツールで読み込んだものと、サーバーの起動時に `--workspace <path>` で設定したものです。
+    #[resource(
+        "cargo://{project}/packages/{name}/summary",
+        name = "package_summary",
+        mime_type = "application/json"
+    )]
+    async fn package_summary(&self, project: String, name: String) -> Result<String> {
+        let state = &self.0;
+        let metadata = state.project(&project).await?;
+        let Some(package) = metadata
+            .workspace_packages()
+            .into_iter()
+            .find(|p| p.name == name)
+        else {
+            bail_kind!(ErrorKind::NotProjectMember, name, project);
+        };
+        to_output(
+            &summary::package_summary(&metadata, package),
+            "package summary",
+            Detail::Full,
+            ValueFormat::Json,
+        )
+    }
+
+    /// 依存グラフ中の 1 つのパッケージ
+    ///
+    /// パッケージのソース、ライセンス、フィーチャー、直接の依存関係と、このパッケージに依存しているパッケージを返します。
+    /// `--chunked-resources` を指定して起動すると、全てのパッケージが `resources/list` で列挙されます。
+    #[resource(
+        "cargo://{project}/crates/{name}/{version}",
+        name = "package",
+        mime_type = "application/json"
+    )]
+    async fn package_resource(
+        &self,
+        project: String,
+        name: String,
+        version: String,
+    ) -> Result<String> {
+        let state = &self.0;
+        let metadata = state.project(&project).await?;
+        let version = version.replace("%2B", "+");
+        let Some(package) = metadata
+            .packages
+            .iter()
+            .find(|p| p.name == name && p.version.to_string() == version)
+        else {
+            bail_kind!(ErrorKind::PackageNotInProject, name, version, project);
+        };
+        to_output(
+            &chunks::package_chunk(&metadata, package),
+            "package",
+            Detail::Full,
+            ValueFormat::Json,
+        )
+    }
+
+    /// プロジェクトの分析レポート
+    ///
+    /// report には overview、categories、overlaps、licenses、native、lint のいずれかを指定します。
+    /// `--chunked-resources` を指定して起動すると、全てのレポートが `resources/list` で列挙されます。
+    #[resource(
+        "cargo://{project}/reports/{report}",
+        name = "report",
+        mime_type = "application/json"
+    )]
+    async fn report_resource(&self, project: String, report: String) -> Result<String> {
+        let state = &self.0;
+        let metadata = state.project(&project).await?;
+        to_output(
+            &chunks::report(&metadata, &report)?,
+            "report",
+            Detail::Full,
+            ValueFormat::Json,
+        )
+    }
+}
+
+/// ツールのパラメータから、依存グラフの解決に使う `cargo metadata` のオプションを作る
+fn metadata_options(
+    target_triple: Option<String>,
+    features: Option<Vec<String>>,
+    all_features: Option<bool>,
+    no_default_features: Option<bool>,
+) -> MetadataOptions {
+    let mut features = features.unwrap_or_default();
+    features.sort();
+    features.dedup();
+    MetadataOptions {
+        filter_platform: target_triple,
+        features,
+        all_features: all_features.unwrap_or(false),
+        no_default_features: no_default_features.unwrap_or(false),
+        ..MetadataOptions::default()
+    }
+}
+
+/// 依存関係の種類の指定 (`normal`、`dev`、`build`) を解釈する
+fn parse_dependency_kind(kind: Option<&str>) -> Result<Option<DependencyKind>> {
+    match kind {
+        None => Ok(None),
+        Some("normal") => Ok(Some(DependencyKind::Normal)),
+        Some("dev") => Ok(Some(DependencyKind::Development)),
+        Some("build") => Ok(Some(DependencyKind::Build)),
+        Some(other) => bail_kind!(ErrorKind::UnknownDependencyKind, other),
+    }
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+/// パッケージの直接の依存関係 (`kind` を指定した場合はその種類のものだけ)
+///
+/// `resolved_only` が真の場合、メタデータは `--filter-platform` やフィーチャーの指定付きで取得したものとして、
+/// resolve グラフに残っている (そのプラットフォームとフィーチャーの組み合わせで有効な) 依存関係だけを返す。
+fn get_dependencies(
+    package: &Package,
+    metadata: &Metadata,
+    kind: Option<DependencyKind>,
+    resolved_only: bool,
+) -> Vec<DependencyInfo> {
+    let artifacts = artifact::ArtifactDependencies::load(package);
+    let node = metadata
+        .resolve
+        .iter()
+        .flat_map(|r| &r.nodes)
+        .find(|n| n.id == package.id);
+    // resolve グラフでこの依存関係として解決されたパッケージ
+    let resolved = |dep: &cargo_metadata::Dependency| {
+        node.into_iter()
+            .flat_map(|n| &n.deps)
+            .filter(|d| d.dep_kinds.iter().any(|k| k.kind == dep.kind))
+            .find_map(|d| {
+                metadata
+                    .packages
+                    .iter()
+                    .find(|p| p.id == d.pkg && p.name == dep.name)
+            })
+    };
+    package
+        .dependencies
+        .iter()
+        .filter(|dep| kind.is_none_or(|kind| dep.kind == kind))
+        .filter(|dep| !resolved_only || node.is_none() || resolved(dep).is_some())
+        .map(|dep| {
+            let resolved_package =
+                resolved(dep).or_else(|| metadata.packages.iter().find(|p| p.name == dep.name));
+
+            let version = resolved_package
+                .map(|p| p.version.to_string())
+                .unwrap_or_else(|| dep.req.to_string());
+
+            DependencyInfo {
+                name: dep.name.clone(),
+                version,
+                pkgid: resolved_package.map(|p| p.id.repr.clone()),
+                kind: kind_name(dep.kind),
+                optional: dep.optional,
+                features: dep.features.clone(),
+                artifact: artifacts.get(dep.kind, &dep.name).cloned(),
+            }
+        })
+        .collect()
+}
+
+/// ネットワークやサブプロセスを待つ処理を、非同期ランタイムのスレッドを止めないよう `spawn_blocking` で実行する
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    let language = Language::current();
+    match tokio::task::spawn_blocking(move || language.sync_scope(f)).await {
+        Ok(result) => result,
+        Err(e) => bail_kind!(ErrorKind::TaskFailed, e),
+    }
+}
+
+/// ツールの出力を JSON の値に変換する (詳細さに応じた切り詰めは `to_output` で行う)
+fn to_value<T: Serialize + ?Sized>(value: &T, name: &str) -> Result<serde_json::Value> {
+    match serde_json::to_value(value) {
+        Ok(value) => Ok(value),
+        Err(e) => bail_kind!(ErrorKind::SerializeFailed, name, e),
+    }
+}
+
+/// ツールの出力を、詳細さに応じて切り詰めてから指定した形式の文字列にする
+fn to_output<T: Serialize + ?Sized>(
+    value: &T,
+    name: &str,
+    detail: Detail,
+    format: ValueFormat,
+) -> Result<String> {
+    let json = match (detail, format) {
+        (Detail::Full, ValueFormat::Json) => serde_json::to_string_pretty(value),
+        _ => match serde_json::to_value(value) {
+            Ok(value) => return format.render(&detail.apply(value), name),
+            Err(e) => Err(e),
+        },
+    };
+    match json {
+        Ok(json) => Ok(json),
+        Err(e) => bail_kind!(ErrorKind::SerializeFailed, name, e),
+    }
+}
+
+fn findings_output(
+    metadata: &Metadata,
+    format: OutputFormat,
+    set: &FindingSet,
+    detail: Detail,
+) -> Result<String> {
+    match format {
+        OutputFormat::Value(format) => {
+            to_output(&FindingReport::new(set), "findings", detail, format)
+        }
+        OutputFormat::Sarif => to_output(
+            &findings::sarif(metadata, set),
+            "SARIF log",
+            detail,
+            ValueFormat::Json,
+        ),
+        OutputFormat::Junit => Ok(findings::junit(set)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_get_metadata_with_invalid_path() {
+        let server_data =
+            ServerData::new(ServerOptions::default(), Arc::new(CargoMetadataProvider));
+        let result = server_data
+            .get_metadata(PathBuf::from("non_existent_path/Cargo.toml"))
+            .await;
+
+        assert!(result.is_err());
+        // エラーが発生することのみを確認
+    }
+
+    /// マニフェストのディレクトリをワークスペースのルートとするフィクスチャのメタデータを返す
+    ///
+    /// `/slow` 以下のマニフェストは、`release` に値が送られるまで返さない。
+    struct FixtureProvider {
+        release: Mutex<std::sync::mpsc::Receiver<()>>,
+        loads: Arc<AtomicUsize>,
+    }
+
+    impl MetadataProvider for FixtureProvider {
+        fn metadata(&self, manifest_path: &Path, _options: &MetadataOptions) -> Result<Metadata> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            if manifest_path.starts_with("/slow") {
+                self.release.lock().unwrap().recv().unwrap();
+            }
+            let mut metadata: Metadata =
+                serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+            metadata.workspace_root = manifest_path
+                .parent()
+                .unwrap()
+                .to_path_buf()
+                .try_into()
+                .unwrap();
+            Ok(metadata)
+        }
+    }
+
+    struct Fixture {
+        server_data: Arc<ServerData>,
+        release: std::sync::mpsc::Sender<()>,
+        loads: Arc<AtomicUsize>,
+    }
+
+    fn fixture() -> Fixture {
+        let (release, receiver) = std::sync::mpsc::channel();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let provider = FixtureProvider {
+            release: Mutex::new(receiver),
+            loads: loads.clone(),
+        };
+        let server_data = ServerData::new(ServerOptions::default(), Arc::new(provider));
+        Fixture {
+            server_data: Arc::new(server_data),
+            release,
+            loads,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_metadata_per_manifest() {
+        let Fixture {
+            server_data, loads, ..
+        } = fixture();
+        let root = async |path: &str| {
+            server_data
+                .get_metadata(PathBuf::from(path))
+                .await
+                .unwrap()
+                .workspace_root
+                .to_string()
+        };
+        assert_eq!(root("/a/Cargo.toml").await, "/a");
+        assert_eq!(root("/b/Cargo.toml").await, "/b");
+        assert_eq!(root("/a/Cargo.toml").await, "/a");
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+        assert_eq!(server_data.metadata.cached().len(), 2);
+        assert_eq!(server_data.projects().await.len(), 2);
+        assert!(server_data.metadata.invalidate(Path::new("/a")).is_some());
+        assert_eq!(server_data.metadata.cached().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_metadata_per_options() {
+        let Fixture {
+            server_data, loads, ..
+        } = fixture();
+        let musl = || MetadataOptions {
+            filter_platform: Some("x86_64-unknown-linux-musl".to_string()),
+            ..MetadataOptions::default()
+        };
+        let path = || PathBuf::from("/a/Cargo.toml");
+        server_data.get_metadata(path()).await.unwrap();
+        server_data.get_metadata_with(path(), musl()).await.unwrap();
+        server_data.get_metadata_with(path(), musl()).await.unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+        // フィーチャーの指定は順序と重複によらず同じキャッシュを使う
+        let features = |features: &[&str]| {
+            let features = features.iter().map(|f| f.to_string()).collect();
+            metadata_options(None, Some(features), None, None)
+        };
+        assert!(!features(&["cli"]).is_complete());
+        assert!(metadata_options(None, None, None, None).is_complete());
+        server_data
+            .get_metadata_with(path(), features(&["cli", "app/serde", "cli"]))
+            .await
+            .unwrap();
+        server_data
+            .get_metadata_with(path(), features(&["app/serde", "cli"]))
+            .await
+            .unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+        // 絞り込んだメタデータはリソースや監視の対象にしない
+        assert_eq!(server_data.metadata.cached().len(), 1);
+        assert_eq!(
+            server_data.metadata.clear(),
+            [PathBuf::from("/a/Cargo.toml")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_package_metadata() {
+        let Fixture {
+            server_data, loads, ..
+        } = fixture();
+        let path = || PathBuf::from("/a/Cargo.toml");
+        server_data.get_package_metadata(path()).await.unwrap();
+        server_data.get_package_metadata(path()).await.unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        // 依存関係を解決したメタデータがキャッシュにあればそれを使う
+        let full = server_data.get_metadata(path()).await.unwrap();
+        let metadata = server_data.get_package_metadata(path()).await.unwrap();
+        assert!(Arc::ptr_eq(&full, &metadata));
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_metadata_locks_per_project() {
+        let Fixture {
+            server_data,
+            release,
+            loads,
+        } = fixture();
+        let slow = |server_data: Arc<ServerData>| {
+            tokio::spawn(async move {
+                server_data
+                    .get_metadata(PathBuf::from("/slow/Cargo.toml"))
+                    .await
+            })
+        };
+        let first = slow(server_data.clone());
+        let second = slow(server_data.clone());
+        let fast = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            server_data.get_metadata(PathBuf::from("/fast/Cargo.toml")),
+        )
+        .await;
+        // 他のプロジェクトの取得中でも、キャッシュの破棄は待たされない
+        let invalidated = server_data.metadata.invalidate(Path::new("/fast"));
+        let cleared = server_data.metadata.clear();
+        release.send(()).unwrap();
+        assert!(
+            fast.is_ok(),
+            "blocked by a metadata load for another project"
+        );
+        assert!(invalidated.is_some());
+        assert!(cleared.is_empty());
+        assert!(first.await.unwrap().is_ok());
+        assert!(second.await.unwrap().is_ok());
+        // 同じプロジェクトに対する同時の呼び出しは 1 回の取得を共有する
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_get_dependencies_by_kind() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let mut app = metadata.packages[0].clone();
+        for (name, kind) in [("tempfile", "dev"), ("cc", "build")] {
+            app.dependencies.push(
+                serde_json::from_value(serde_json::json!({
+                    "name": name,
+                    "req": "^1",
+                    "kind": kind,
+                    "optional": false,
+                    "uses_default_features": true,
+                    "features": [],
+                    "target": null,
+                    "rename": null,
+                    "registry": null,
+                    "source": null,
+                }))
+                .unwrap(),
+            );
+        }
+        let names = |kind: &str| -> Vec<String> {
+            let kind = parse_dependency_kind(Some(kind)).unwrap();
+            get_dependencies(&app, &metadata, kind, false)
+                .into_iter()
+                .map(|d| format!("{}:{}", d.name, d.kind))
+                .collect()
+        };
+        assert_eq!(names("normal"), ["fixture-core:normal"]);
+        assert_eq!(names("dev"), ["tempfile:dev"]);
+        assert_eq!(names("build"), ["cc:build"]);
+        assert_eq!(get_dependencies(&app, &metadata, None, false).len(), 3);
+        assert!(parse_dependency_kind(Some("test")).is_err());
+    }
+
+    #[test]
+    fn test_server_options_from_args() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let options = ServerOptions::from_args(args(&["--read-only"])).unwrap();
+        assert!(options.read_only && !options.allow_write);
+        assert!(ServerOptions::from_args(args(&["--read-only", "--allow-write"])).is_err());
+        assert!(ServerOptions::from_args(args(&["--unknown"])).is_err());
+        let options = ServerOptions::from_args(args(&["--workspace", "a/Cargo.toml"])).unwrap();
+        assert_eq!(options.workspaces, [PathBuf::from("a/Cargo.toml")]);
+        assert!(ServerOptions::from_args(args(&["--workspace"])).is_err());
+        let options = ServerOptions::from_args(args(&["--chunked-resources"])).unwrap();
+        assert!(options.chunked_resources);
+        let options = ServerOptions::from_args(args(&["--watch"])).unwrap();
+        assert!(options.watch);
+        let options = ServerOptions::from_args(args(&["--manifest-path", "app"])).unwrap();
+        assert_eq!(options.manifest_path, Some(PathBuf::from("app")));
+        assert!(ServerOptions::from_args(args(&["--manifest-path"])).is_err());
+        let options =
+            ServerOptions::from_args(args(&["--allow-dir", "/src", "--allow-dir", "/work"]))
+                .unwrap();
+        assert_eq!(
+            options.allowed_dirs,
+            [PathBuf::from("/src"), PathBuf::from("/work")]
+        );
+        let options = ServerOptions::from_args(args(&["--lang", "ja"])).unwrap();
+        assert_eq!(options.language, Language::Ja);
+        assert!(ServerOptions::from_args(args(&["--lang", "fr"])).is_err());
+    }
+}
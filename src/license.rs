@@ -0,0 +1,491 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo_metadata::camino::Utf8PathBuf;
+use cargo_metadata::{Metadata, Package};
+use mcp_attr::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::findings::{self, Finding, FindingSet, Level};
+use crate::graph::Graph;
+
+/// ライセンスファイルとみなすファイル名の接頭辞 (大文字小文字は区別しない)
+const LICENSE_FILE_PREFIXES: &[&str] = &["license", "licence", "copying", "notice", "unlicense"];
+
+#[derive(Serialize)]
+pub struct LicenseManifest {
+    pub spdx_license_list_version: &'static str,
+    pub packages: Vec<PackageLicense>,
+}
+
+#[derive(Serialize)]
+pub struct PackageLicense {
+    pub name: String,
+    pub version: String,
+    pub id: String,
+    pub source: Option<String>,
+    pub workspace_member: bool,
+    pub authors: Vec<String>,
+    pub repository: Option<String>,
+    /// Cargo.toml に記述されたままのライセンス表記
+    pub license: Option<String>,
+    #[serde(flatten)]
+    pub normalized: NormalizedLicense,
+    pub license_files: Vec<Utf8PathBuf>,
+}
+
+#[derive(Serialize, Default)]
+pub struct NormalizedLicense {
+    /// 正規化された SPDX ライセンス式
+    pub spdx: Option<String>,
+    /// 正規化後の式が SPDX ライセンス式として妥当かどうか
+    pub spdx_valid: bool,
+    /// 式に含まれるライセンス (例外を含む) の一覧
+    pub license_ids: Vec<String>,
+}
+
+/// ライセンス表記を SPDX ライセンス式に正規化する
+///
+/// `MIT/Apache-2.0` のような旧来の表記や、不正確なライセンス名も可能な限り正規化する。
+pub fn normalize_license(license: &str) -> NormalizedLicense {
+    let canonical = match spdx::Expression::canonicalize(license) {
+        Ok(canonical) => canonical.unwrap_or_else(|| license.to_string()),
+        Err(_) => {
+            return NormalizedLicense {
+                spdx: None,
+                spdx_valid: false,
+                license_ids: Vec::new(),
+            };
+        }
+    };
+    match spdx::Expression::parse(&canonical) {
+        Ok(expr) => {
+            let ids: BTreeSet<String> = expr.requirements().map(|r| r.req.to_string()).collect();
+            NormalizedLicense {
+                spdx: Some(canonical),
+                spdx_valid: true,
+                license_ids: ids.into_iter().collect(),
+            }
+        }
+        Err(_) => NormalizedLicense {
+            spdx: Some(canonical),
+            spdx_valid: false,
+            license_ids: Vec::new(),
+        },
+    }
+}
+
+/// 全パッケージのライセンス情報とライセンスファイルのパスを取得する
+pub fn license_manifest(metadata: &Metadata) -> LicenseManifest {
+    let mut packages: Vec<PackageLicense> = metadata
+        .packages
+        .iter()
+        .map(|package| PackageLicense {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            id: package.id.repr.clone(),
+            source: package.source.as_ref().map(|s| s.repr.clone()),
+            workspace_member: metadata.workspace_members.contains(&package.id),
+            authors: package.authors.clone(),
+            repository: package.repository.clone(),
+            license: package.license.clone(),
+            normalized: package
+                .license
+                .as_deref()
+                .map(normalize_license)
+                .unwrap_or_default(),
+            license_files: license_files(package),
+        })
+        .collect();
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    LicenseManifest {
+        spdx_license_list_version: spdx::license_version(),
+        packages,
+    }
+}
+
+/// `license-file` で指定されたファイルと、パッケージディレクトリ直下のライセンスファイルを列挙する
+fn license_files(package: &Package) -> Vec<Utf8PathBuf> {
+    let mut files = BTreeSet::new();
+    if let Some(file) = package.license_file() {
+        files.insert(file);
+    }
+    if let Some(dir) = package.manifest_path.parent()
+        && let Ok(entries) = dir.read_dir_utf8()
+    {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_ascii_lowercase();
+            let is_file = entry.file_type().is_ok_and(|t| t.is_file());
+            if is_file && LICENSE_FILE_PREFIXES.iter().any(|p| name.starts_with(p)) {
+                files.insert(entry.path().to_path_buf());
+            }
+        }
+    }
+    files.into_iter().collect()
+}
+
+/// ライセンス式ごとにまとめたパッケージ
+#[derive(Serialize)]
+pub struct LicenseReport {
+    /// ワークスペースメンバー以外のパッケージ
+    pub third_party: Vec<LicenseGroup>,
+    pub workspace_members: Vec<LicenseGroup>,
+    /// `license` を宣言していないパッケージ
+    pub unlicensed: Vec<UnlicensedPackage>,
+}
+
+#[derive(Serialize)]
+pub struct LicenseGroup {
+    /// 正規化された SPDX ライセンス式 (正規化できない場合は Cargo.toml に記述されたまま)
+    pub license: String,
+    pub spdx_valid: bool,
+    pub count: usize,
+    /// `name@version`
+    pub packages: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct UnlicensedPackage {
+    /// `name@version`
+    pub package: String,
+    pub workspace_member: bool,
+    /// `license-file` で指定されたファイル
+    pub license_file: Option<Utf8PathBuf>,
+}
+
+/// resolve グラフ内の全パッケージを、ライセンス式ごとにワークスペースメンバーとそれ以外に分けてまとめる
+///
+/// グループはパッケージ数の多い順に並べる。resolve グラフがない場合は全パッケージを対象とする。
+pub fn license_report(metadata: &Metadata) -> LicenseReport {
+    let resolved: Option<BTreeSet<&str>> = metadata
+        .resolve
+        .as_ref()
+        .map(|r| r.nodes.iter().map(|n| n.id.repr.as_str()).collect());
+    let mut third_party: BTreeMap<(String, bool), Vec<String>> = BTreeMap::new();
+    let mut workspace_members: BTreeMap<(String, bool), Vec<String>> = BTreeMap::new();
+    let mut unlicensed = Vec::new();
+    for package in &metadata.packages {
+        if resolved
+            .as_ref()
+            .is_some_and(|ids| !ids.contains(package.id.repr.as_str()))
+        {
+            continue;
+        }
+        let label = format!("{}@{}", package.name, package.version);
+        let workspace_member = metadata.workspace_members.contains(&package.id);
+        let Some(license) = &package.license else {
+            unlicensed.push(UnlicensedPackage {
+                package: label,
+                workspace_member,
+                license_file: package.license_file(),
+            });
+            continue;
+        };
+        let normalized = normalize_license(license);
+        let key = (
+            normalized.spdx.unwrap_or_else(|| license.clone()),
+            normalized.spdx_valid,
+        );
+        let groups = if workspace_member {
+            &mut workspace_members
+        } else {
+            &mut third_party
+        };
+        groups.entry(key).or_default().push(label);
+    }
+    let groups = |groups: BTreeMap<(String, bool), Vec<String>>| {
+        let mut groups: Vec<LicenseGroup> = groups
+            .into_iter()
+            .map(|((license, spdx_valid), mut packages)| {
+                packages.sort();
+                LicenseGroup {
+                    license,
+                    spdx_valid,
+                    count: packages.len(),
+                    packages,
+                }
+            })
+            .collect();
+        groups.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.license.cmp(&b.license))
+        });
+        groups
+    };
+    unlicensed.sort_by(|a, b| a.package.cmp(&b.package));
+    LicenseReport {
+        third_party: groups(third_party),
+        workspace_members: groups(workspace_members),
+        unlicensed,
+    }
+}
+
+/// ライセンスの許可リストと拒否リスト
+///
+/// 各要素は SPDX ライセンス ID (例: `MIT`) または例外付きの表記 (例: `Apache-2.0 WITH LLVM-exception`)。
+/// ライセンス ID のみの要素は、そのライセンスに例外が付いたものにも一致する。大文字小文字は区別しない。
+#[derive(Default)]
+pub struct LicensePolicy {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    /// クレート名ごとに、`allow` に加えて許可するライセンス
+    pub exceptions: BTreeMap<String, Vec<String>>,
+    /// `allow` と `deny` に加えて適用する、ライセンスの性質による条件
+    pub preset: Option<LicensePreset>,
+}
+
+/// SPDX ライセンスリストの性質による、ライセンスの条件
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicensePreset {
+    /// コピーレフトのライセンス (GPL、LGPL、MPL など) を拒否する
+    NoCopyleft,
+    /// OSI に承認されたライセンスだけを許可する
+    OsiApproved,
+    /// FSF がフリーソフトウェアのライセンスと認めたものだけを許可する
+    FsfFree,
+}
+
+impl LicensePreset {
+    const NAMES: &[&str] = &["no-copyleft", "osi-approved", "fsf-free"];
+
+    pub fn parse(preset: &str) -> Result<Self> {
+        match preset {
+            "no-copyleft" => Ok(Self::NoCopyleft),
+            "osi-approved" => Ok(Self::OsiApproved),
+            "fsf-free" => Ok(Self::FsfFree),
+            other => bail_kind!(
+                ErrorKind::UnknownLicensePreset,
+                other,
+                Self::NAMES.join(", ")
+            ),
+        }
+    }
+
+    /// SPDX ライセンスリストにないライセンス (`LicenseRef-...`) は条件を満たさないものとする
+    fn is_allowed(self, req: &spdx::LicenseReq) -> bool {
+        let Some(id) = req.license.id() else {
+            return false;
+        };
+        match self {
+            Self::NoCopyleft => !id.is_copyleft(),
+            Self::OsiApproved => id.is_osi_approved(),
+            Self::FsfFree => id.is_fsf_free_libre(),
+        }
+    }
+}
+
+impl LicensePolicy {
+    fn is_allowed(&self, package: &str, req: &spdx::LicenseReq) -> bool {
+        let full = req.to_string();
+        let id = req.license.to_string();
+        let matches =
+            |entry: &String| entry.eq_ignore_ascii_case(&full) || entry.eq_ignore_ascii_case(&id);
+        let excepted = self
+            .exceptions
+            .get(package)
+            .is_some_and(|allow| allow.iter().any(matches));
+        if excepted {
+            return !self.deny.iter().any(matches);
+        }
+        (self.allow.is_empty() || self.allow.iter().any(matches))
+            && !self.deny.iter().any(matches)
+            && self.preset.is_none_or(|preset| preset.is_allowed(req))
+    }
+}
+
+/// ワークスペースメンバー以外の全パッケージのライセンスをポリシーに照らして検査する
+///
+/// `OR` で結合された式はいずれかの選択肢が許可されていれば、`AND` で結合された式は全てが許可されていれば適合とみなす。
+pub fn check_license_policy(metadata: &Metadata, policy: &LicensePolicy) -> FindingSet {
+    let graph = Graph::new(metadata);
+    let mut set = FindingSet::new("license-policy");
+    set.rule(
+        "license/not-allowed",
+        "The license expression is not satisfied by the allowed licenses",
+        None,
+    );
+    set.rule(
+        "license/missing",
+        "The package does not declare a license",
+        None,
+    );
+    set.rule(
+        "license/invalid",
+        "The license is not a valid SPDX license expression",
+        None,
+    );
+    set.rule(
+        "license/file-only",
+        "The package declares only a license file, which must be reviewed manually",
+        None,
+    );
+
+    for package in &metadata.packages {
+        if metadata.workspace_members.contains(&package.id) {
+            continue;
+        }
+        let (rule, level, message) = match &package.license {
+            None if package.license_file.is_some() => (
+                "license/file-only",
+                Level::Warning,
+                format!(
+                    "{} declares only a license file ({}), which must be reviewed manually",
+                    package.name,
+                    package.license_file.as_ref().unwrap()
+                ),
+            ),
+            None => (
+                "license/missing",
+                Level::Error,
+                format!("{} does not declare a license", package.name),
+            ),
+            Some(license) => {
+                let normalized = normalize_license(license);
+                let expr = normalized
+                    .spdx
+                    .as_deref()
+                    .and_then(|spdx| spdx::Expression::parse(spdx).ok());
+                match expr {
+                    None => (
+                        "license/invalid",
+                        Level::Error,
+                        format!(
+                            "{} has a license that is not a valid SPDX expression: `{}`",
+                            package.name, license
+                        ),
+                    ),
+                    Some(expr) if !expr.evaluate(|req| policy.is_allowed(&package.name, req)) => (
+                        "license/not-allowed",
+                        Level::Error,
+                        format!(
+                            "{} is licensed under `{}`, which is not allowed by the policy",
+                            package.name,
+                            normalized.spdx.as_deref().unwrap_or(license)
+                        ),
+                    ),
+                    Some(_) => continue,
+                }
+            }
+        };
+        let (file, line) = findings::lockfile_location(metadata, package);
+        set.push(Finding {
+            rule: rule.to_string(),
+            level,
+            message,
+            package: Some(format!("{}@{}", package.name, package.version)),
+            file: Some(file),
+            line,
+            dependency_path: graph.path_labels(&package.id, true),
+        });
+    }
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_normalize_license() {
+        let license = normalize_license("MIT/Apache-2.0");
+        assert_eq!(license.spdx.as_deref(), Some("MIT OR Apache-2.0"));
+        assert!(license.spdx_valid);
+        assert_eq!(license.license_ids, ["Apache-2.0", "MIT"]);
+
+        let license = normalize_license("Apache-2.0 WITH LLVM-exception OR MIT");
+        assert_eq!(
+            license.spdx.as_deref(),
+            Some("Apache-2.0 WITH LLVM-exception OR MIT")
+        );
+        assert_eq!(
+            license.license_ids,
+            ["Apache-2.0 WITH LLVM-exception", "MIT"]
+        );
+
+        assert!(!normalize_license("Some custom license").spdx_valid);
+    }
+
+    #[test]
+    fn test_license_report() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let report = license_report(&metadata);
+        assert!(report.third_party.is_empty());
+        assert!(report.unlicensed.is_empty());
+        let groups: Vec<(&str, &[String])> = report
+            .workspace_members
+            .iter()
+            .map(|g| (g.license.as_str(), g.packages.as_slice()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("MIT", &["app@0.2.0".to_string()][..]),
+                ("MIT OR Apache-2.0", &["fixture-core@0.1.0".to_string()][..])
+            ]
+        );
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let report = license_report(&metadata);
+        let group = report
+            .third_party
+            .iter()
+            .find(|g| g.packages.iter().any(|p| p.starts_with("serde@")))
+            .unwrap();
+        assert_eq!(group.license, "MIT OR Apache-2.0");
+        assert!(group.spdx_valid);
+        assert_eq!(group.count, group.packages.len());
+        assert!(
+            report
+                .third_party
+                .windows(2)
+                .all(|w| w[0].count >= w[1].count)
+        );
+    }
+
+    #[test]
+    fn test_license_policy() {
+        let policy = LicensePolicy {
+            allow: vec!["mit".to_string(), "Apache-2.0".to_string()],
+            deny: vec![],
+            exceptions: BTreeMap::from([("ring".to_string(), vec!["ISC".to_string()])]),
+            preset: None,
+        };
+        let allowed = |license: &str| {
+            spdx::Expression::parse(license)
+                .unwrap()
+                .evaluate(|req| policy.is_allowed("serde", req))
+        };
+        assert!(allowed("MIT OR GPL-3.0-only"));
+        assert!(allowed("MIT AND Apache-2.0"));
+        assert!(!allowed("MIT AND GPL-3.0-only"));
+        assert!(allowed("Apache-2.0 WITH LLVM-exception"));
+        assert!(!allowed("GPL-2.0-only WITH Classpath-exception-2.0"));
+        assert!(!allowed("ISC"));
+        let isc = spdx::Expression::parse("ISC").unwrap();
+        assert!(isc.evaluate(|req| policy.is_allowed("ring", req)));
+
+        let policy = LicensePolicy {
+            preset: Some(LicensePreset::parse("no-copyleft").unwrap()),
+            ..LicensePolicy::default()
+        };
+        let allowed = |license: &str| {
+            spdx::Expression::parse(license)
+                .unwrap()
+                .evaluate(|req| policy.is_allowed("serde", req))
+        };
+        assert!(allowed("MIT OR Apache-2.0"));
+        assert!(allowed("MIT OR GPL-3.0-only"));
+        assert!(!allowed("MPL-2.0"));
+        assert!(!allowed("MIT AND LGPL-2.1-or-later"));
+        assert!(!allowed("LicenseRef-Proprietary"));
+        assert!(LicensePreset::parse("permissive").is_err());
+    }
+}
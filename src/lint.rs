@@ -0,0 +1,117 @@
+use cargo_metadata::semver::VersionReq;
+use cargo_metadata::{Metadata, Package};
+
+use crate::findings::{self, Finding, FindingSet, Level};
+
+/// crates.io に公開可能なパッケージかどうか (`publish = false` でない)
+pub fn is_publishable(package: &Package) -> bool {
+    package.publish.as_ref().is_none_or(|p| !p.is_empty())
+}
+
+/// ワークスペースメンバーの Cargo.toml を検査する
+pub fn lint_manifests(metadata: &Metadata) -> FindingSet {
+    let mut set = FindingSet::new("manifest-lint");
+    set.rule(
+        "manifest/missing-license",
+        "Publishable packages must declare `license` or `license-file`",
+        Some("https://doc.rust-lang.org/cargo/reference/manifest.html#the-license-and-license-file-fields"),
+    );
+    set.rule(
+        "manifest/missing-description",
+        "Publishable packages must declare `description`",
+        Some("https://doc.rust-lang.org/cargo/reference/manifest.html#the-description-field"),
+    );
+    set.rule(
+        "manifest/missing-repository",
+        "Publishable packages should declare `repository`",
+        Some("https://doc.rust-lang.org/cargo/reference/manifest.html#the-repository-field"),
+    );
+    set.rule(
+        "manifest/missing-rust-version",
+        "Packages should declare the minimum supported Rust version",
+        Some("https://doc.rust-lang.org/cargo/reference/rust-version.html"),
+    );
+    set.rule(
+        "manifest/wildcard-dependency",
+        "Registry dependencies should not use the `*` version requirement",
+        Some("https://doc.rust-lang.org/cargo/faq.html#can-libraries-use--as-a-version-for-their-dependencies"),
+    );
+
+    for package in metadata.workspace_packages() {
+        let manifest = &package.manifest_path;
+        let publishable = is_publishable(package);
+        let package_line = findings::find_key_line(manifest, "[package]");
+        let mut push = |rule: &str, level, message: String, line| {
+            set.push(Finding {
+                rule: rule.to_string(),
+                level,
+                message,
+                package: Some(format!("{}@{}", package.name, package.version)),
+                file: Some(manifest.clone()),
+                line,
+                dependency_path: Vec::new(),
+            });
+        };
+
+        if publishable {
+            if package.license.is_none() && package.license_file.is_none() {
+                push(
+                    "manifest/missing-license",
+                    Level::Error,
+                    format!(
+                        "{} does not declare `license` or `license-file`",
+                        package.name
+                    ),
+                    package_line,
+                );
+            }
+            if package.description.is_none() {
+                push(
+                    "manifest/missing-description",
+                    Level::Error,
+                    format!("{} does not declare `description`", package.name),
+                    package_line,
+                );
+            }
+            if package.repository.is_none() {
+                push(
+                    "manifest/missing-repository",
+                    Level::Warning,
+                    format!("{} does not declare `repository`", package.name),
+                    package_line,
+                );
+            }
+        }
+        if package.rust_version.is_none() {
+            push(
+                "manifest/missing-rust-version",
+                Level::Note,
+                format!("{} does not declare `rust-version`", package.name),
+                package_line,
+            );
+        }
+        for dep in &package.dependencies {
+            let is_registry = dep
+                .source
+                .as_ref()
+                .is_some_and(|s| s.starts_with("registry+"));
+            if is_registry && dep.req == VersionReq::STAR {
+                let key = dep.rename.as_ref().unwrap_or(&dep.name);
+                push(
+                    "manifest/wildcard-dependency",
+                    if publishable {
+                        Level::Error
+                    } else {
+                        Level::Warning
+                    },
+                    format!(
+                        "{} depends on `{}` with the wildcard requirement `*`",
+                        package.name, dep.name
+                    ),
+                    findings::find_key_line(manifest, key),
+                );
+            }
+        }
+    }
+    set
+}
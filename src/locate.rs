@@ -0,0 +1,109 @@
+use std::path::{Component, Path, PathBuf};
+
+use mcp_attr::Result;
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// ツールに指定されたパスから Cargo.toml のパスを求める
+///
+/// `cargo locate-project` と同じように、`Cargo.toml` のパスはそのまま使い、
+/// ディレクトリやプロジェクト内のファイルのパスは、そこから親ディレクトリをたどって最も近い Cargo.toml を探す。
+pub fn locate_manifest(path: &Path) -> Result<PathBuf> {
+    if path.file_name().is_some_and(|name| name == "Cargo.toml") {
+        return Ok(path.to_path_buf());
+    }
+    let start = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    match start
+        .ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+    {
+        Some(manifest) => Ok(manifest),
+        None => bail_kind!(ErrorKind::ManifestNotFound, path.display()),
+    }
+}
+
+/// 比較に使う絶対パス (シンボリックリンクを解決できないパスは、`..` を含まない場合に限りそのまま使う)
+fn normalize(path: &Path) -> Option<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+    let path = std::path::absolute(path).ok()?;
+    if path.components().any(|c| c == Component::ParentDir) {
+        return None;
+    }
+    Some(path)
+}
+
+/// `path` が `allowed_dirs` のいずれかの中にあるかどうか (`allowed_dirs` が空の場合は常に真)
+pub fn is_allowed(path: &Path, allowed_dirs: &[PathBuf]) -> bool {
+    if allowed_dirs.is_empty() {
+        return true;
+    }
+    let Some(path) = normalize(path) else {
+        return false;
+    };
+    allowed_dirs
+        .iter()
+        .filter_map(|dir| normalize(dir))
+        .any(|dir| path.starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate_manifest() {
+        let root = std::env::temp_dir().join(format!("locate-test-{}", std::process::id()));
+        let src = root.join("member/src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[workspace]\n").unwrap();
+        std::fs::write(root.join("member/Cargo.toml"), "[package]\n").unwrap();
+        std::fs::write(src.join("lib.rs"), "").unwrap();
+
+        let manifest = |path: &Path| locate_manifest(path).unwrap();
+        assert_eq!(manifest(&root), root.join("Cargo.toml"));
+        assert_eq!(manifest(&src), root.join("member/Cargo.toml"));
+        assert_eq!(
+            manifest(&src.join("lib.rs")),
+            root.join("member/Cargo.toml")
+        );
+        assert_eq!(
+            manifest(Path::new("/missing/Cargo.toml")),
+            Path::new("/missing/Cargo.toml")
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(locate_manifest(&root).is_err());
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let root = std::env::temp_dir().join(format!("allow-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("allowed/app")).unwrap();
+        std::fs::create_dir_all(root.join("other")).unwrap();
+        let allowed = [root.join("allowed")];
+
+        assert!(is_allowed(&root.join("allowed/app/Cargo.toml"), &allowed));
+        assert!(is_allowed(
+            &root.join("allowed/missing/Cargo.toml"),
+            &allowed
+        ));
+        assert!(!is_allowed(&root.join("other/Cargo.toml"), &allowed));
+        assert!(!is_allowed(
+            &root.join("allowed/missing/../../other/Cargo.toml"),
+            &allowed
+        ));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("other"), root.join("allowed/link")).unwrap();
+            assert!(!is_allowed(&root.join("allowed/link"), &allowed));
+        }
+        assert!(is_allowed(&root.join("other/Cargo.toml"), &[]));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
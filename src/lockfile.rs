@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use cargo_metadata::semver::Version;
+use mcp_attr::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::policy::compat_key;
+
+/// Cargo.lock の内容
+#[derive(Deserialize, Serialize)]
+pub struct Lockfile {
+    /// ロックファイルの形式のバージョン (古い形式では `null`)
+    pub version: Option<u32>,
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// `registry+https://...` や `git+https://...#<commit>` (ワークスペースのメンバーとパス依存関係では `null`)
+    pub source: Option<String>,
+    pub checksum: Option<String>,
+    /// 依存先 (`name`、同じ名前で複数のバージョンがある場合は `name version`、ソースも区別する場合は `name version (source)`)
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+impl Lockfile {
+    pub fn parse(text: &str) -> Result<Self> {
+        match toml::from_str(text) {
+            Ok(lockfile) => Ok(lockfile),
+            Err(e) => bail_kind!(ErrorKind::ParseFailed, "Cargo.lock", e),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) => bail_kind!(ErrorKind::ReadFailed, path.display(), e),
+        }
+    }
+}
+
+/// ロックファイルの変更の種類
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Upgraded,
+    Downgraded,
+    /// バージョンは同じでソースやチェックサムが変わったもの
+    Changed,
+}
+
+#[derive(Serialize)]
+pub struct LockfileChange {
+    pub name: String,
+    pub kind: ChangeKind,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// semver 非互換な更新 (メジャーバージョン、0.x のマイナーバージョンの変更) かどうか
+    pub breaking: bool,
+}
+
+fn compatible(a: &str, b: &str) -> bool {
+    match (Version::parse(a), Version::parse(b)) {
+        (Ok(a), Ok(b)) => compat_key(&a) == compat_key(&b),
+        _ => false,
+    }
+}
+
+/// 2 つのロックファイルの差分を、パッケージ名ごとの変更の一覧で返す
+///
+/// 同じ名前のパッケージが 1 つずつ削除・追加された場合は、バージョンの更新として扱う。
+pub fn diff(before: &Lockfile, after: &Lockfile) -> Vec<LockfileChange> {
+    fn index(lockfile: &Lockfile) -> BTreeMap<&str, BTreeMap<&str, &LockedPackage>> {
+        let mut entries: BTreeMap<&str, BTreeMap<&str, &LockedPackage>> = BTreeMap::new();
+        for package in &lockfile.packages {
+            entries
+                .entry(&package.name)
+                .or_default()
+                .insert(&package.version, package);
+        }
+        entries
+    }
+    let (before, after) = (index(before), index(after));
+    let names: BTreeSet<&str> = before.keys().chain(after.keys()).copied().collect();
+    let empty = BTreeMap::new();
+
+    let mut changes = Vec::new();
+    for name in names {
+        let old = before.get(name).unwrap_or(&empty);
+        let new = after.get(name).unwrap_or(&empty);
+        for (version, package) in old {
+            if let Some(updated) = new.get(version)
+                && (updated.source != package.source || updated.checksum != package.checksum)
+            {
+                changes.push(LockfileChange {
+                    name: name.to_string(),
+                    kind: ChangeKind::Changed,
+                    from: Some(version.to_string()),
+                    to: Some(version.to_string()),
+                    breaking: false,
+                });
+            }
+        }
+        let removed: Vec<&str> = old
+            .keys()
+            .filter(|v| !new.contains_key(*v))
+            .copied()
+            .collect();
+        let added: Vec<&str> = new
+            .keys()
+            .filter(|v| !old.contains_key(*v))
+            .copied()
+            .collect();
+        if let ([from], [to]) = (removed.as_slice(), added.as_slice()) {
+            let upgraded = match (Version::parse(from), Version::parse(to)) {
+                (Ok(from), Ok(to)) => to > from,
+                _ => true,
+            };
+            changes.push(LockfileChange {
+                name: name.to_string(),
+                kind: if upgraded {
+                    ChangeKind::Upgraded
+                } else {
+                    ChangeKind::Downgraded
+                },
+                from: Some(from.to_string()),
+                to: Some(to.to_string()),
+                breaking: !compatible(from, to),
+            });
+            continue;
+        }
+        for version in removed {
+            changes.push(LockfileChange {
+                name: name.to_string(),
+                kind: ChangeKind::Removed,
+                from: Some(version.to_string()),
+                to: None,
+                breaking: false,
+            });
+        }
+        for version in added {
+            changes.push(LockfileChange {
+                name: name.to_string(),
+                kind: ChangeKind::Added,
+                from: None,
+                to: Some(version.to_string()),
+                breaking: false,
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let before = Lockfile::parse(
+            r#"
+version = 4
+
+[[package]]
+name = "a"
+version = "1.0.0"
+
+[[package]]
+name = "b"
+version = "0.2.0"
+
+[[package]]
+name = "c"
+version = "1.0.0"
+"#,
+        )
+        .unwrap();
+        let after = Lockfile::parse(
+            r#"
+version = 4
+
+[[package]]
+name = "a"
+version = "1.1.0"
+
+[[package]]
+name = "b"
+version = "0.3.0"
+
+[[package]]
+name = "d"
+version = "1.0.0"
+"#,
+        )
+        .unwrap();
+        let changes = diff(&before, &after);
+        let changes: Vec<(&str, ChangeKind, bool)> = changes
+            .iter()
+            .map(|c| (c.name.as_str(), c.kind, c.breaking))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("a", ChangeKind::Upgraded, false),
+                ("b", ChangeKind::Upgraded, true),
+                ("c", ChangeKind::Removed, false),
+                ("d", ChangeKind::Added, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let lockfile = Lockfile::parse(
+            r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "serde",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "ddc6f9cc94d67c0e21aaf7eda3a010fd3af78ebf6e096aa6e2e13c79749cce4f"
+"#,
+        )
+        .unwrap();
+        assert_eq!(lockfile.version, Some(4));
+        assert_eq!(lockfile.packages[0].dependencies, ["serde"]);
+        assert!(lockfile.packages[0].source.is_none());
+        assert!(lockfile.packages[1].checksum.is_some());
+    }
+}
@@ -0,0 +1,242 @@
+use std::path::Path;
+
+use cargo_metadata::semver::VersionReq;
+use mcp_attr::Result;
+use serde::Serialize;
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Value, value};
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::index::{self, validate_crate_name};
+use crate::preview::unified_diff;
+
+/// 依存関係のテーブル (`[target.*]` 以下のテーブルは対象外)
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+#[derive(Serialize)]
+pub struct ManifestEdit {
+    pub manifest_path: String,
+    /// ワークスペースルートからの相対パスによる、適用した変更の unified diff
+    pub patch: String,
+}
+
+/// マニフェストを読み込み、`edit` で変更した内容を書き込んで差分を返す
+///
+/// コメントや書式は toml_edit により保持される。
+pub fn apply(
+    manifest_path: &Path,
+    workspace_root: &Path,
+    edit: impl FnOnce(&mut DocumentMut) -> Result<()>,
+) -> Result<ManifestEdit> {
+    let old = match std::fs::read_to_string(manifest_path) {
+        Ok(text) => text,
+        Err(e) => bail_kind!(ErrorKind::ReadFailed, manifest_path.display(), e),
+    };
+    let mut document: DocumentMut = match old.parse() {
+        Ok(document) => document,
+        Err(e) => bail_kind!(ErrorKind::ParseFailed, manifest_path.display(), e),
+    };
+    edit(&mut document)?;
+    let new = document.to_string();
+    if let Err(e) = std::fs::write(manifest_path, &new) {
+        bail_kind!(ErrorKind::WriteFailed, manifest_path.display(), e);
+    }
+    let relative = manifest_path
+        .strip_prefix(workspace_root)
+        .unwrap_or(manifest_path);
+    Ok(ManifestEdit {
+        manifest_path: manifest_path.display().to_string(),
+        patch: unified_diff(&relative.to_string_lossy(), &old, &new),
+    })
+}
+
+fn table_name(dev: bool) -> &'static str {
+    if dev {
+        "dev-dependencies"
+    } else {
+        "dependencies"
+    }
+}
+
+/// 依存関係を追加する (既にある場合はバージョン要件と、指定した場合はフィーチャーを置き換える)
+///
+/// `version` を省略した場合は、crates.io の最新のバージョンを `^` 要件で使用する。
+/// 既にあるテーブル形式の依存関係は、`optional` や `path` などの他のキーを保持したまま変更する。
+/// ワークスペースから継承している依存関係 (`workspace = true`) は変更しない。
+pub fn add_dependency(
+    document: &mut DocumentMut,
+    crate_name: &str,
+    version: Option<&str>,
+    features: &[String],
+    dev: bool,
+) -> Result<()> {
+    validate_crate_name(crate_name)?;
+    let version = match version {
+        Some(version) => parse_requirement(version)?,
+        None => index::latest_version(crate_name)?.to_string(),
+    };
+    let table = document
+        .entry(table_name(dev))
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut();
+    let Some(table) = table else {
+        bail_kind!(ErrorKind::NotATable, table_name(dev));
+    };
+    let features_value = || Value::Array(features.iter().map(|f| f.as_str()).collect::<Array>());
+    if let Some(item) = table.get_mut(crate_name) {
+        if let Some(dependency) = item.as_table_like_mut() {
+            if dependency.contains_key("workspace") {
+                bail_kind!(ErrorKind::InheritedDependency, crate_name);
+            }
+            dependency.insert("version", value(&version));
+            if !features.is_empty() {
+                dependency.insert("features", Item::Value(features_value()));
+            }
+            // インラインテーブルの最後のキーの後の空白が、追加したキーの前に残らないようにする
+            if let Some(dependency) = item.as_inline_table_mut() {
+                dependency.fmt();
+            }
+            return Ok(());
+        }
+        if features.is_empty() {
+            *item = set_string(item, &version);
+            return Ok(());
+        }
+    }
+    let item = if features.is_empty() {
+        value(&version)
+    } else {
+        let mut dependency = InlineTable::new();
+        dependency.insert("version", version.as_str().into());
+        dependency.insert("features", features_value());
+        value(dependency)
+    };
+    table.insert(crate_name, item);
+    Ok(())
+}
+
+/// 依存関係を追加するマニフェストがパッケージのマニフェストであることを確かめる
+///
+/// 仮想マニフェスト (`[package]` のないワークスペースルート) に `[dependencies]` を作ると cargo が読み込めなくなるため、
+/// `members` (ワークスペースメンバーの名前) を候補として示し、package の指定を求める。
+pub fn ensure_package(document: &DocumentMut, members: &str) -> Result<()> {
+    if !document.contains_key("package") {
+        bail_kind!(ErrorKind::PackageRequired, members);
+    }
+    Ok(())
+}
+
+/// 全ての依存関係のテーブルから依存関係を削除する
+pub fn remove_dependency(document: &mut DocumentMut, crate_name: &str) -> Result<()> {
+    let mut removed = false;
+    for name in DEPENDENCY_TABLES {
+        if let Some(table) = document.get_mut(name).and_then(|t| t.as_table_like_mut()) {
+            removed |= table.remove(crate_name).is_some();
+        }
+    }
+    if !removed {
+        bail_kind!(ErrorKind::DependencyNotInManifest, crate_name);
+    }
+    Ok(())
+}
+
+/// 全ての依存関係のテーブルにある依存関係のバージョン要件を変更する
+pub fn set_dependency_version(
+    document: &mut DocumentMut,
+    crate_name: &str,
+    version: &str,
+) -> Result<()> {
+    let version = parse_requirement(version)?;
+    let mut updated = false;
+    for name in DEPENDENCY_TABLES {
+        let Some(table) = document.get_mut(name).and_then(|t| t.as_table_like_mut()) else {
+            continue;
+        };
+        let Some(item) = table.get_mut(crate_name) else {
+            continue;
+        };
+        if let Some(dependency) = item.as_table_like_mut() {
+            if dependency.contains_key("workspace") {
+                bail_kind!(ErrorKind::InheritedDependency, crate_name);
+            }
+            dependency.insert("version", value(&version));
+        } else {
+            *item = set_string(item, &version);
+        }
+        updated = true;
+    }
+    if let Some(table) = document
+        .get_mut("workspace")
+        .and_then(|w| w.get_mut("dependencies"))
+        .and_then(|t| t.as_table_like_mut())
+        && let Some(item) = table.get_mut(crate_name)
+    {
+        match item.as_table_like_mut() {
+            Some(dependency) => {
+                dependency.insert("version", value(&version));
+            }
+            None => *item = set_string(item, &version),
+        }
+        updated = true;
+    }
+    if !updated {
+        bail_kind!(ErrorKind::DependencyNotInManifest, crate_name);
+    }
+    Ok(())
+}
+
+/// 文字列の値を置き換える (前後の空白やコメントは保持する)
+fn set_string(item: &Item, version: &str) -> Item {
+    let mut new = Value::from(version);
+    if let Some(old) = item.as_value() {
+        *new.decor_mut() = old.decor().clone();
+    }
+    Item::Value(new)
+}
+
+/// バージョン要件を検証し、マニフェストに書き込む文字列を返す
+fn parse_requirement(version: &str) -> Result<String> {
+    match VersionReq::parse(version) {
+        Ok(_) => Ok(version.trim().to_string()),
+        Err(e) => bail_kind!(ErrorKind::InvalidVersionReq, version, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_dependencies() {
+        let mut document: DocumentMut = "[package]\nname = \"a\"\n\n[dependencies]\n# JSON\nserde_json = \"1.0\" # comment\nserde = { version = \"1\", features = [\"derive\"] }\n".parse().unwrap();
+        add_dependency(&mut document, "anyhow", Some("1.0.90"), &[], true).unwrap();
+        set_dependency_version(&mut document, "serde_json", "1.0.140").unwrap();
+        set_dependency_version(&mut document, "serde", "1.0.200").unwrap();
+        remove_dependency(&mut document, "serde").unwrap();
+        assert_eq!(
+            document.to_string(),
+            "[package]\nname = \"a\"\n\n[dependencies]\n# JSON\nserde_json = \"1.0.140\" # comment\n\n[dev-dependencies]\nanyhow = \"1.0.90\"\n"
+        );
+        assert!(remove_dependency(&mut document, "serde").is_err());
+
+        let mut document: DocumentMut = "[package]\nname = \"a\"\n\n[dependencies]\nlog = { version = \"0.4\", optional = true, default-features = false }\ncore = { path = \"../core\" }\nanyhow = \"1.0\" # comment\nserde = { workspace = true }\n".parse().unwrap();
+        let features = ["std".to_string()];
+        add_dependency(&mut document, "log", Some("0.4.22"), &features, false).unwrap();
+        add_dependency(&mut document, "core", Some("0.1"), &[], false).unwrap();
+        add_dependency(&mut document, "anyhow", Some("1.0.90"), &[], false).unwrap();
+        let inherited = add_dependency(&mut document, "serde", Some("1"), &[], false);
+        assert_eq!(
+            document.to_string(),
+            "[package]\nname = \"a\"\n\n[dependencies]\nlog = { version = \"0.4.22\", optional = true, default-features = false, features = [\"std\"] }\ncore = { path = \"../core\", version = \"0.1\" }\nanyhow = \"1.0.90\" # comment\nserde = { workspace = true }\n"
+        );
+        let message = inherited.err().unwrap().to_error_object(false).message;
+        assert!(message.starts_with("[inherited_dependency]"), "{message}");
+
+        let document: DocumentMut = "[workspace]\nmembers = [\"app\"]\n".parse().unwrap();
+        let message = ensure_package(&document, "app")
+            .err()
+            .unwrap()
+            .to_error_object(false)
+            .message;
+        assert!(message.starts_with("[package_required]"), "{message}");
+    }
+}
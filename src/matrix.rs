@@ -0,0 +1,479 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo_metadata::{DependencyKind, Package};
+use serde::Serialize;
+
+use crate::hakari::expand_features;
+
+/// ターゲットとフィーチャーの選択の組み合わせの表
+#[derive(Serialize)]
+pub struct TargetMatrix {
+    pub package: String,
+    pub selections: Vec<FeatureSelection>,
+    pub targets: Vec<TargetRow>,
+    /// どの選択でもビルドできないターゲット (`kind:name`)
+    pub never_buildable: Vec<String>,
+}
+
+/// フィーチャーの選択 (`default`、`no-default-features`、`all-features` または指定したフィーチャーの組み合わせ)
+#[derive(Serialize)]
+pub struct FeatureSelection {
+    pub name: String,
+    /// この選択でビルドするときの cargo の引数
+    pub cargo_args: Vec<String>,
+    /// 推移的に有効になるフィーチャー
+    pub enabled_features: Vec<String>,
+    /// 有効になる依存関係のうち、特定のプラットフォームでだけ使われるもの (`cfg(...)` またはターゲットトリプルごと)
+    pub platform_dependencies: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct TargetRow {
+    pub name: String,
+    /// `lib`、`bin`、`example`、`test`、`bench`
+    pub kind: String,
+    pub required_features: Vec<String>,
+    /// `selections` と同じ順序の、それぞれの選択でのビルドの可否
+    pub cells: Vec<MatrixCell>,
+    /// 必須フィーチャーだけを有効にしてビルドするときに使われる、プラットフォーム固有の依存関係の `cfg(...)` またはターゲットトリプル
+    pub platform_cfgs: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct MatrixCell {
+    pub selection: String,
+    pub buildable: bool,
+    /// ビルドに必要だが有効になっていないフィーチャー
+    pub missing_features: Vec<String>,
+}
+
+/// 有効なフィーチャーによって有効になる省略可能な依存関係の名前
+fn enabled_optional_dependencies(
+    package: &Package,
+    features: &BTreeSet<String>,
+) -> BTreeSet<String> {
+    let optional: BTreeSet<&str> = package
+        .dependencies
+        .iter()
+        .filter(|d| d.optional)
+        .map(|d| d.rename.as_deref().unwrap_or(&d.name))
+        .collect();
+    let mut enabled = BTreeSet::new();
+    for feature in features {
+        if !package.features.contains_key(feature) {
+            // 暗黙のフィーチャーとしての省略可能な依存関係
+            if optional.contains(feature.as_str()) {
+                enabled.insert(feature.clone());
+            }
+            continue;
+        }
+        for implied in &package.features[feature] {
+            let name = match implied.strip_prefix("dep:") {
+                Some(name) => name,
+                None => match implied.split_once('/') {
+                    Some((name, _)) if !name.ends_with('?') => name,
+                    _ => continue,
+                },
+            };
+            if optional.contains(name) {
+                enabled.insert(name.to_string());
+            }
+        }
+    }
+    enabled
+}
+
+/// 有効なフィーチャーで使われる依存関係のうち、プラットフォーム固有のものをプラットフォームごとにまとめる
+fn platform_dependencies(
+    package: &Package,
+    features: &BTreeSet<String>,
+) -> BTreeMap<String, Vec<String>> {
+    let enabled = enabled_optional_dependencies(package, features);
+    let mut platforms: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for dependency in &package.dependencies {
+        let name = dependency.rename.as_deref().unwrap_or(&dependency.name);
+        let Some(target) = &dependency.target else {
+            continue;
+        };
+        if dependency.kind == DependencyKind::Development
+            || (dependency.optional && !enabled.contains(name))
+        {
+            continue;
+        }
+        platforms
+            .entry(target.to_string())
+            .or_default()
+            .insert(name.to_string());
+    }
+    platforms
+        .into_iter()
+        .map(|(target, names)| (target, names.into_iter().collect()))
+        .collect()
+}
+
+/// パッケージのビルドターゲットとフィーチャーの選択の組み合わせごとに、ビルドできるかどうかを調べる
+///
+/// `feature_sets` の各要素はカンマ区切りのフィーチャーの一覧で、
+/// `cargo build --no-default-features --features <set>` と同じように評価する (既定のフィーチャーを含める場合は `default` を指定する)。
+pub fn target_matrix(package: &Package, feature_sets: &[String]) -> TargetMatrix {
+    let all_features: Vec<String> = package
+        .features
+        .keys()
+        .cloned()
+        .chain(
+            package
+                .dependencies
+                .iter()
+                .filter(|d| d.optional)
+                .map(|d| d.rename.clone().unwrap_or_else(|| d.name.clone())),
+        )
+        .collect();
+    let mut selections: Vec<(String, Vec<String>, Vec<String>)> = vec![
+        (
+            "default".to_string(),
+            Vec::new(),
+            vec!["default".to_string()],
+        ),
+        (
+            "no-default-features".to_string(),
+            vec!["--no-default-features".to_string()],
+            Vec::new(),
+        ),
+        (
+            "all-features".to_string(),
+            vec!["--all-features".to_string()],
+            all_features,
+        ),
+    ];
+    for set in feature_sets {
+        let features: Vec<String> = set
+            .split([',', ' '])
+            .filter(|f| !f.is_empty())
+            .map(|f| f.to_string())
+            .collect();
+        let mut args = vec!["--no-default-features".to_string()];
+        if !features.is_empty() {
+            args.push("--features".to_string());
+            args.push(features.join(","));
+        }
+        selections.push((features.join(","), args, features));
+    }
+
+    let expanded: Vec<BTreeSet<String>> = selections
+        .iter()
+        .map(|(_, _, features)| expand_features(package, features))
+        .collect();
+    let mut targets = Vec::new();
+    let mut never_buildable = Vec::new();
+    for target in &package.targets {
+        if target.is_custom_build() {
+            continue;
+        }
+        let kind = match target.kind.first().map(|k| k.as_str()) {
+            Some("bin" | "example" | "test" | "bench") => target.kind[0].clone(),
+            _ => "lib".to_string(),
+        };
+        let cells: Vec<MatrixCell> = selections
+            .iter()
+            .zip(&expanded)
+            .map(|((name, _, _), enabled)| {
+                let missing_features: Vec<String> = target
+                    .required_features
+                    .iter()
+                    .filter(|f| !enabled.contains(*f))
+                    .cloned()
+                    .collect();
+                MatrixCell {
+                    selection: name.clone(),
+                    buildable: missing_features.is_empty(),
+                    missing_features,
+                }
+            })
+            .collect();
+        if cells.iter().all(|c| !c.buildable) {
+            never_buildable.push(format!("{kind}:{}", target.name));
+        }
+        let required = expand_features(package, &target.required_features);
+        let platform_cfgs: Vec<String> = platform_dependencies(package, &required)
+            .into_keys()
+            .collect();
+        targets.push(TargetRow {
+            name: target.name.clone(),
+            kind,
+            required_features: target.required_features.clone(),
+            cells,
+            platform_cfgs,
+        });
+    }
+
+    let selections = selections
+        .into_iter()
+        .zip(expanded)
+        .map(|((name, cargo_args, _), enabled)| FeatureSelection {
+            name,
+            cargo_args,
+            platform_dependencies: platform_dependencies(package, &enabled),
+            enabled_features: enabled.into_iter().collect(),
+        })
+        .collect();
+    TargetMatrix {
+        package: package.name.clone(),
+        selections,
+        targets,
+        never_buildable,
+    }
+}
+
+/// CI でフィーチャーの組み合わせごとにビルドするための一覧
+#[derive(Serialize)]
+pub struct FeatureMatrix {
+    pub package: String,
+    pub combinations: Vec<FeatureCombination>,
+    /// 名前の接頭辞が共通で、どのフィーチャーからも同時に有効にされない (互いに排他的とみられる) フィーチャーの組
+    pub exclusive_groups: Vec<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct FeatureCombination {
+    /// `no-default-features`、`default`、`all-features`、フィーチャー名、または `all-features-with:<feature>`
+    pub name: String,
+    pub cargo_args: Vec<String>,
+    /// 実行するコマンド (`cargo check -p <package> ...`)
+    pub command: String,
+}
+
+/// 接頭辞 (最初の `-` または `_` より前) が共通のフィーチャーのうち、互いに排他的とみられる組
+///
+/// 組の中のフィーチャーが互いを有効にせず、組の外のどのフィーチャーも組の 2 つ以上を有効にしない場合に排他的とみなす
+/// (`tls-native` と `tls-rustls` など)。`default` と、組の 2 つ以上を有効にする `full` のようなフィーチャーがある組は除く。
+fn exclusive_groups(package: &Package) -> Vec<Vec<String>> {
+    let mut by_prefix: BTreeMap<&str, Vec<&String>> = BTreeMap::new();
+    for feature in package.features.keys() {
+        if let Some((prefix, _)) = feature.split_once(['-', '_'])
+            && !prefix.is_empty()
+        {
+            by_prefix.entry(prefix).or_default().push(feature);
+        }
+    }
+    let expanded: BTreeMap<&String, BTreeSet<String>> = package
+        .features
+        .keys()
+        .map(|f| (f, expand_features(package, std::slice::from_ref(f))))
+        .collect();
+    by_prefix
+        .into_values()
+        .filter(|group| {
+            group.len() >= 2
+                && expanded.iter().all(|(feature, enabled)| {
+                    let count = group
+                        .iter()
+                        .filter(|g| enabled.contains(g.as_str()))
+                        .count();
+                    if group.contains(feature) {
+                        count == 1
+                    } else {
+                        count <= 1
+                    }
+                })
+        })
+        .map(|group| group.into_iter().cloned().collect())
+        .collect()
+}
+
+/// フィーチャーの組み合わせ (なし、既定、各フィーチャー単独、すべて) と、対応する `cargo <subcommand>` のコマンドを列挙する
+///
+/// 互いに排他的とみられるフィーチャーの組がある場合は `--all-features` の代わりに、
+/// 組の中から 1 つずつ選んで残りのフィーチャーをすべて有効にする組み合わせを返す。
+pub fn feature_matrix(package: &Package, subcommand: &str) -> FeatureMatrix {
+    let exclusive_groups = exclusive_groups(package);
+    let mut combinations: Vec<(String, Vec<String>)> = vec![
+        (
+            "no-default-features".to_string(),
+            vec!["--no-default-features".to_string()],
+        ),
+        ("default".to_string(), Vec::new()),
+    ];
+    let only = |features: &[&String]| {
+        let features: Vec<&str> = features.iter().map(|f| f.as_str()).collect();
+        vec![
+            "--no-default-features".to_string(),
+            "--features".to_string(),
+            features.join(","),
+        ]
+    };
+    for feature in package.features.keys().filter(|f| *f != "default") {
+        combinations.push((feature.clone(), only(&[feature])));
+    }
+    if exclusive_groups.is_empty() {
+        combinations.push((
+            "all-features".to_string(),
+            vec!["--all-features".to_string()],
+        ));
+    } else {
+        let grouped: BTreeSet<&String> = exclusive_groups.iter().flatten().collect();
+        for (i, group) in exclusive_groups.iter().enumerate() {
+            for chosen in group {
+                // 他の組からは先頭のフィーチャーを選ぶ
+                let features: Vec<&String> = package
+                    .features
+                    .keys()
+                    .filter(|f| *f != "default" && !grouped.contains(f))
+                    .chain(
+                        exclusive_groups
+                            .iter()
+                            .enumerate()
+                            .map(|(j, other)| if i == j { chosen } else { &other[0] }),
+                    )
+                    .collect();
+                combinations.push((format!("all-features-with:{chosen}"), only(&features)));
+            }
+        }
+    }
+    FeatureMatrix {
+        package: package.name.clone(),
+        combinations: combinations
+            .into_iter()
+            .map(|(name, cargo_args)| {
+                let mut command = format!("cargo {subcommand} -p {}", package.name);
+                for arg in &cargo_args {
+                    command.push(' ');
+                    command.push_str(arg);
+                }
+                FeatureCombination {
+                    name,
+                    cargo_args,
+                    command,
+                }
+            })
+            .collect(),
+        exclusive_groups,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_target_matrix() {
+        let target = |name: &str, kind: &str, required: &[&str]| {
+            json!({
+                "name": name,
+                "kind": [kind],
+                "crate_types": [kind],
+                "required-features": required,
+                "src_path": format!("/app/src/{name}.rs"),
+            })
+        };
+        let package: Package = serde_json::from_value(json!({
+            "name": "app",
+            "version": "0.1.0",
+            "id": "path+file:///app#0.1.0",
+            "dependencies": [
+                {
+                    "name": "nix", "req": "^0.29", "kind": null, "optional": true,
+                    "uses_default_features": true, "features": [], "target": "cfg(unix)",
+                    "rename": null, "registry": null, "source": null,
+                },
+            ],
+            "targets": [
+                target("app", "lib", &[]),
+                target("cli", "bin", &["cli"]),
+                target("daemon", "example", &["unix-socket"]),
+                target("gpu", "bench", &["gpu"]),
+            ],
+            "features": {
+                "default": ["cli"],
+                "cli": [],
+                "unix-socket": ["dep:nix"],
+            },
+            "manifest_path": "/app/Cargo.toml",
+        }))
+        .unwrap();
+        let matrix = target_matrix(&package, &["unix-socket".to_string()]);
+        let names: Vec<&str> = matrix.selections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "default",
+                "no-default-features",
+                "all-features",
+                "unix-socket"
+            ]
+        );
+        let buildable = |target: usize| -> Vec<bool> {
+            matrix.targets[target]
+                .cells
+                .iter()
+                .map(|c| c.buildable)
+                .collect()
+        };
+        assert_eq!(buildable(0), [true, true, true, true]);
+        assert_eq!(buildable(1), [true, false, true, false]);
+        assert_eq!(buildable(2), [false, false, true, true]);
+        assert_eq!(matrix.targets[2].platform_cfgs, ["cfg(unix)"]);
+        assert_eq!(matrix.never_buildable, ["bench:gpu"]);
+        assert_eq!(
+            matrix.selections[3].platform_dependencies["cfg(unix)"],
+            ["nix"]
+        );
+        assert!(matrix.selections[0].platform_dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_feature_matrix() {
+        let package: Package = serde_json::from_value(json!({
+            "name": "app",
+            "version": "0.1.0",
+            "id": "path+file:///app#0.1.0",
+            "dependencies": [],
+            "targets": [],
+            "features": {
+                "default": ["tls-rustls"],
+                "cli": [],
+                "tls-native": [],
+                "tls-rustls": [],
+                "io-std": [],
+                "io-util": [],
+                "full": ["io-std", "io-util"],
+            },
+            "manifest_path": "/app/Cargo.toml",
+        }))
+        .unwrap();
+        let matrix = feature_matrix(&package, "check");
+        assert_eq!(matrix.exclusive_groups, [["tls-native", "tls-rustls"]]);
+        let names: Vec<&str> = matrix
+            .combinations
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "no-default-features",
+                "default",
+                "cli",
+                "full",
+                "io-std",
+                "io-util",
+                "tls-native",
+                "tls-rustls",
+                "all-features-with:tls-native",
+                "all-features-with:tls-rustls",
+            ]
+        );
+        assert_eq!(matrix.combinations[1].command, "cargo check -p app");
+        assert_eq!(
+            matrix.combinations[8].command,
+            "cargo check -p app --no-default-features --features cli,full,io-std,io-util,tls-native"
+        );
+
+        let mut package = package;
+        package.features.remove("tls-native");
+        let matrix = feature_matrix(&package, "test");
+        assert!(matrix.exclusive_groups.is_empty());
+        let last = matrix.combinations.last().unwrap();
+        assert_eq!(last.command, "cargo test -p app --all-features");
+    }
+}
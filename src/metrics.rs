@@ -0,0 +1,136 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use cargo_metadata::{Metadata, PackageId};
+use serde::Serialize;
+
+use crate::budget::{Depths, contributions, deepest_path};
+use crate::graph::Graph;
+
+/// 依存グラフの大きさと深さの指標
+#[derive(Serialize)]
+pub struct GraphMetrics {
+    /// ワークスペースメンバーが直接依存しているパッケージの数 (ワークスペースメンバーを除く)
+    pub direct_dependencies: usize,
+    /// ワークスペースメンバーから推移的にのみ到達できるパッケージの数
+    pub transitive_dependencies: usize,
+    /// ワークスペースメンバーから最も深い依存パッケージまでの辺の数
+    pub max_depth: usize,
+    /// 最も深い依存パッケージまでのパス (`name@version`)
+    pub deepest_path: Vec<String>,
+    /// 推移的な依存パッケージの多い順に並べた、直接依存ごとの指標
+    pub dependencies: Vec<DependencyMetrics>,
+}
+
+/// 直接依存の 1 つが依存グラフに持ち込むパッケージの数と深さ
+#[derive(Serialize)]
+pub struct DependencyMetrics {
+    pub name: String,
+    pub version: String,
+    /// このパッケージに直接依存しているワークスペースメンバー
+    pub used_by: Vec<String>,
+    /// このパッケージから推移的に到達できるパッケージの数 (このパッケージ自身とワークスペースメンバーを除く)
+    pub transitive_crates: usize,
+    /// 他の直接依存からは到達できないパッケージの数 (このパッケージ自身を含む)。この依存を外すと減るパッケージの数の目安
+    pub exclusive_crates: usize,
+    /// このパッケージから最も深い依存パッケージまでの辺の数
+    pub max_depth: usize,
+}
+
+/// 直接依存ごとに、推移的に持ち込むパッケージの数と依存の深さを求める
+pub fn dependency_metrics(metadata: &Metadata, include_dev: bool) -> GraphMetrics {
+    let graph = Graph::new(metadata);
+    let members: HashSet<&PackageId> = graph.members().collect();
+    let exclusive: HashMap<&PackageId, usize> = contributions(&graph, include_dev)
+        .into_iter()
+        .map(|c| (c.dependency, c.new_crates))
+        .collect();
+    let mut used_by: HashMap<&PackageId, BTreeSet<&str>> = HashMap::new();
+    let mut reachable: HashSet<&PackageId> = HashSet::new();
+    for &member in &members {
+        reachable.extend(graph.reachable(member, include_dev));
+        let name = graph
+            .package(member)
+            .map_or(member.repr.as_str(), |p| &p.name);
+        for dep in graph.dependencies(member, include_dev) {
+            if !members.contains(dep) {
+                used_by.entry(dep).or_default().insert(name);
+            }
+        }
+    }
+
+    let mut depths = Depths::new(&graph, include_dev);
+    let mut dependencies: Vec<DependencyMetrics> = used_by
+        .iter()
+        .filter_map(|(&id, used_by)| {
+            let package = graph.package(id)?;
+            Some(DependencyMetrics {
+                name: package.name.clone(),
+                version: package.version.to_string(),
+                used_by: used_by.iter().map(|name| name.to_string()).collect(),
+                transitive_crates: graph
+                    .reachable(id, include_dev)
+                    .iter()
+                    .filter(|dep| **dep != id && !members.contains(*dep))
+                    .count(),
+                exclusive_crates: exclusive.get(id).copied().unwrap_or_default(),
+                max_depth: depths.depth(id),
+            })
+        })
+        .collect();
+    dependencies.sort_by(|a, b| {
+        b.transitive_crates
+            .cmp(&a.transitive_crates)
+            .then_with(|| (&a.name, &a.version).cmp(&(&b.name, &b.version)))
+    });
+
+    let path = deepest_path(&graph, include_dev);
+    let dependency_count = reachable.iter().filter(|id| !members.contains(*id)).count();
+    GraphMetrics {
+        direct_dependencies: used_by.len(),
+        transitive_dependencies: dependency_count - used_by.len(),
+        max_depth: path.len().saturating_sub(1),
+        deepest_path: path.iter().map(|id| graph.label(id)).collect(),
+        dependencies,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_dependency_metrics() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let metrics = dependency_metrics(&metadata, false);
+        assert_eq!(metrics.direct_dependencies, 0);
+        assert_eq!(metrics.max_depth, 0);
+        assert!(metrics.dependencies.is_empty());
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let metrics = dependency_metrics(&metadata, false);
+        assert_eq!(metrics.direct_dependencies, metrics.dependencies.len());
+        assert!(metrics.max_depth > 1);
+        assert_eq!(metrics.deepest_path.len(), metrics.max_depth + 1);
+        let heaviest = &metrics.dependencies[0];
+        assert!(
+            metrics
+                .dependencies
+                .iter()
+                .all(|d| d.transitive_crates <= heaviest.transitive_crates)
+        );
+        assert!(heaviest.max_depth <= metrics.max_depth);
+        let serde = metrics
+            .dependencies
+            .iter()
+            .find(|d| d.name == "serde")
+            .unwrap();
+        assert!(serde.transitive_crates >= 1, "serde pulls in serde_derive");
+        assert_eq!(serde.used_by, ["mcp-attr-example-cargo-metadata"]);
+    }
+}
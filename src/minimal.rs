@@ -0,0 +1,76 @@
+use std::process::Command;
+
+use cargo_metadata::Metadata;
+use mcp_attr::Result;
+use serde::Serialize;
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::lockfile::{self, Lockfile, LockfileChange};
+use crate::sandbox::Sandbox;
+use crate::upgrade::ChangeSummary;
+
+#[derive(Serialize)]
+pub struct MinimalVersionsCheck {
+    pub toolchain: String,
+    /// `minimal-versions` または `direct-minimal-versions`
+    pub mode: &'static str,
+    /// 最小バージョンでの依存関係の解決に成功したかどうか
+    pub success: bool,
+    /// 依存関係の解決に失敗した場合の cargo のエラー出力
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 現在の Cargo.lock と比較した変更の集計 (Cargo.lock がない場合は省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ChangeSummary>,
+    /// 現在の Cargo.lock からの変更 (主にバージョンの引き下げ)
+    pub changes: Vec<LockfileChange>,
+}
+
+/// `-Z minimal-versions` で依存関係を解決できるかを、一時的なコピーで調べる
+///
+/// nightly の cargo が必要なため、`rustup run <toolchain> cargo` で実行する。
+/// `direct_only` が true の場合は、直接依存のみを最小バージョンにする `-Z direct-minimal-versions` を使用する。
+/// 依存関係の解決のみを行い、最小バージョンでのビルドは行わない。
+pub fn check_minimal_versions(
+    metadata: &Metadata,
+    toolchain: &str,
+    direct_only: bool,
+) -> Result<MinimalVersionsCheck> {
+    let sandbox = Sandbox::new(metadata)?;
+    let before = if sandbox.lockfile_path().exists() {
+        Some(Lockfile::load(&sandbox.lockfile_path())?)
+    } else {
+        None
+    };
+    let mode = if direct_only {
+        "direct-minimal-versions"
+    } else {
+        "minimal-versions"
+    };
+    let output = Command::new("rustup")
+        .args(["run", toolchain, "cargo", "generate-lockfile", "-Z", mode])
+        .current_dir(sandbox.dir())
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => bail_kind!(ErrorKind::ToolchainUnavailable, toolchain, e),
+    };
+    let mut check = MinimalVersionsCheck {
+        toolchain: toolchain.to_string(),
+        mode,
+        success: output.status.success(),
+        error: None,
+        summary: None,
+        changes: Vec::new(),
+    };
+    if !output.status.success() {
+        check.error = Some(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        return Ok(check);
+    }
+    if let Some(before) = before {
+        let after = Lockfile::load(&sandbox.lockfile_path())?;
+        check.changes = lockfile::diff(&before, &after);
+        check.summary = Some(ChangeSummary::new(&check.changes));
+    }
+    Ok(check)
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeSet;
+
+use cargo_metadata::{Metadata, Package};
+use serde::Serialize;
+
+/// `links` の値 (または `-sys` クレート名の本体) と、システムライブラリの対応表
+///
+/// (キー, pkg-config 名, apt パッケージ名, brew フォーミュラ名)
+const KNOWN_LIBRARIES: &[(&str, &str, Option<&str>, Option<&str>)] = &[
+    ("openssl", "openssl", Some("libssl-dev"), Some("openssl@3")),
+    ("z", "zlib", Some("zlib1g-dev"), Some("zlib")),
+    ("sqlite3", "sqlite3", Some("libsqlite3-dev"), Some("sqlite")),
+    ("git2", "libgit2", Some("libgit2-dev"), Some("libgit2")),
+    ("ssh2", "libssh2", Some("libssh2-1-dev"), Some("libssh2")),
+    (
+        "curl",
+        "libcurl",
+        Some("libcurl4-openssl-dev"),
+        Some("curl"),
+    ),
+    (
+        "nghttp2",
+        "libnghttp2",
+        Some("libnghttp2-dev"),
+        Some("libnghttp2"),
+    ),
+    ("pq", "libpq", Some("libpq-dev"), Some("libpq")),
+    (
+        "mysqlclient",
+        "mysqlclient",
+        Some("libmysqlclient-dev"),
+        Some("mysql-client"),
+    ),
+    ("zstd", "libzstd", Some("libzstd-dev"), Some("zstd")),
+    ("lzma", "liblzma", Some("liblzma-dev"), Some("xz")),
+    ("bzip2", "bzip2", Some("libbz2-dev"), Some("bzip2")),
+    ("ffi", "libffi", Some("libffi-dev"), Some("libffi")),
+    ("onig", "oniguruma", Some("libonig-dev"), Some("oniguruma")),
+    ("pcre2", "libpcre2-8", Some("libpcre2-dev"), Some("pcre2")),
+    (
+        "usb-1.0",
+        "libusb-1.0",
+        Some("libusb-1.0-0-dev"),
+        Some("libusb"),
+    ),
+    (
+        "hidapi",
+        "hidapi-hidraw",
+        Some("libhidapi-dev"),
+        Some("hidapi"),
+    ),
+    ("dbus", "dbus-1", Some("libdbus-1-dev"), Some("dbus")),
+    ("udev", "libudev", Some("libudev-dev"), None),
+    ("alsa", "alsa", Some("libasound2-dev"), None),
+    ("asound", "alsa", Some("libasound2-dev"), None),
+    ("x11", "x11", Some("libx11-dev"), None),
+    ("xcb", "xcb", Some("libxcb1-dev"), None),
+    (
+        "fontconfig",
+        "fontconfig",
+        Some("libfontconfig1-dev"),
+        Some("fontconfig"),
+    ),
+    (
+        "freetype",
+        "freetype2",
+        Some("libfreetype6-dev"),
+        Some("freetype"),
+    ),
+    ("glib-2.0", "glib-2.0", Some("libglib2.0-dev"), Some("glib")),
+    (
+        "gobject-2.0",
+        "gobject-2.0",
+        Some("libglib2.0-dev"),
+        Some("glib"),
+    ),
+    ("gio-2.0", "gio-2.0", Some("libglib2.0-dev"), Some("glib")),
+    ("cairo", "cairo", Some("libcairo2-dev"), Some("cairo")),
+    ("pango", "pango", Some("libpango1.0-dev"), Some("pango")),
+    (
+        "gdk_pixbuf-2.0",
+        "gdk-pixbuf-2.0",
+        Some("libgdk-pixbuf2.0-dev"),
+        Some("gdk-pixbuf"),
+    ),
+    ("gtk-3", "gtk+-3.0", Some("libgtk-3-dev"), Some("gtk+3")),
+    ("gdk-3", "gdk-3.0", Some("libgtk-3-dev"), Some("gtk+3")),
+    (
+        "webkit2gtk-4.1",
+        "webkit2gtk-4.1",
+        Some("libwebkit2gtk-4.1-dev"),
+        None,
+    ),
+    ("clang", "libclang", Some("libclang-dev"), Some("llvm")),
+];
+
+/// OS や実行環境そのものへのバインディングで、追加のシステムパッケージを必要としない `-sys` クレート
+const PLATFORM_SYS_CRATES: &[&str] = &[
+    "windows-sys",
+    "linux-raw-sys",
+    "js-sys",
+    "web-sys",
+    "core-foundation-sys",
+    "security-framework-sys",
+    "system-configuration-sys",
+    "io-kit-sys",
+    "objc-sys",
+];
+
+#[derive(Serialize)]
+pub struct NativeDependencyReport {
+    pub packages: Vec<NativePackage>,
+    pub install_hints: InstallHints,
+}
+
+#[derive(Serialize)]
+pub struct NativePackage {
+    pub name: String,
+    pub version: String,
+    pub links: Option<String>,
+    pub sys_crate: bool,
+    pub build_script: bool,
+    pub system_libraries: Vec<SystemLibrary>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SystemLibrary {
+    pub pkg_config: String,
+    pub apt: Option<String>,
+    pub brew: Option<String>,
+    /// 推定の根拠 (`system-deps` メタデータ または `known-library` 対応表)
+    pub source: &'static str,
+}
+
+#[derive(Serialize, Default)]
+pub struct InstallHints {
+    pub pkg_config: Vec<String>,
+    pub apt: Vec<String>,
+    pub brew: Vec<String>,
+    pub apt_command: Option<String>,
+    pub brew_command: Option<String>,
+    /// 対応するシステムパッケージを推定できなかった `links` の値またはクレート名
+    pub unresolved: Vec<String>,
+}
+
+/// ネイティブコードに依存するパッケージと、必要なシステムライブラリを推定する
+pub fn native_dependency_report(metadata: &Metadata) -> NativeDependencyReport {
+    let mut packages = Vec::new();
+    let mut pkg_config = BTreeSet::new();
+    let mut apt = BTreeSet::new();
+    let mut brew = BTreeSet::new();
+    let mut unresolved = BTreeSet::new();
+
+    for package in &metadata.packages {
+        if PLATFORM_SYS_CRATES.contains(&package.name.as_str()) {
+            continue;
+        }
+        let sys_crate = package.name.ends_with("-sys") || package.name.ends_with("_sys");
+        let system_deps = system_deps_libraries(package);
+        if package.links.is_none() && !sys_crate && system_deps.is_empty() {
+            continue;
+        }
+
+        let mut system_libraries = system_deps;
+        if system_libraries.is_empty() {
+            match known_library(package) {
+                Some(lib) => system_libraries.push(lib),
+                None => {
+                    unresolved.insert(
+                        package
+                            .links
+                            .clone()
+                            .unwrap_or_else(|| package.name.clone()),
+                    );
+                }
+            }
+        }
+        for lib in &system_libraries {
+            pkg_config.insert(lib.pkg_config.clone());
+            apt.extend(lib.apt.clone());
+            brew.extend(lib.brew.clone());
+        }
+
+        packages.push(NativePackage {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            links: package.links.clone(),
+            sys_crate,
+            build_script: package.targets.iter().any(|t| t.is_custom_build()),
+            system_libraries,
+        });
+    }
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let apt: Vec<String> = apt.into_iter().collect();
+    let brew: Vec<String> = brew.into_iter().collect();
+    let install_hints = InstallHints {
+        pkg_config: pkg_config.into_iter().collect(),
+        apt_command: (!apt.is_empty()).then(|| format!("sudo apt-get install {}", apt.join(" "))),
+        brew_command: (!brew.is_empty()).then(|| format!("brew install {}", brew.join(" "))),
+        apt,
+        brew,
+        unresolved: unresolved.into_iter().collect(),
+    };
+    NativeDependencyReport {
+        packages,
+        install_hints,
+    }
+}
+
+/// `[package.metadata.system-deps]` に記述された pkg-config 名を取得する
+fn system_deps_libraries(package: &Package) -> Vec<SystemLibrary> {
+    let mut names = Vec::new();
+    if let Some(table) = package.metadata.get("system-deps") {
+        collect_system_deps(table, &mut names);
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let known = KNOWN_LIBRARIES.iter().find(|(_, pc, _, _)| *pc == name);
+            SystemLibrary {
+                apt: known.and_then(|k| k.2).map(str::to_string),
+                brew: known.and_then(|k| k.3).map(str::to_string),
+                pkg_config: name,
+                source: "system-deps",
+            }
+        })
+        .collect()
+}
+
+fn collect_system_deps(table: &serde_json::Value, names: &mut Vec<String>) {
+    let Some(table) = table.as_object() else {
+        return;
+    };
+    for (key, value) in table {
+        if key.starts_with("cfg(") {
+            collect_system_deps(value, names);
+            continue;
+        }
+        let name = value
+            .get("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or(key)
+            .to_string();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+}
+
+fn known_library(package: &Package) -> Option<SystemLibrary> {
+    let stem = package
+        .name
+        .trim_end_matches("-sys")
+        .trim_end_matches("_sys")
+        .trim_end_matches("-rs");
+    let candidates = [
+        package.links.as_deref(),
+        Some(stem),
+        stem.strip_prefix("lib"),
+    ];
+    candidates.into_iter().flatten().find_map(|key| {
+        KNOWN_LIBRARIES
+            .iter()
+            .find(|(k, _, _, _)| *k == key)
+            .map(|(_, pc, apt, brew)| SystemLibrary {
+                pkg_config: pc.to_string(),
+                apt: apt.map(str::to_string),
+                brew: brew.map(str::to_string),
+                source: "known-library",
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_collect_system_deps() {
+        let table = json!({
+            "glib": { "name": "glib-2.0", "version": "2.56" },
+            "cairo": "1.14",
+            "cfg(target_os = \"linux\")": { "udev": "1.0" }
+        });
+        let mut names = Vec::new();
+        collect_system_deps(&table, &mut names);
+        names.sort();
+        assert_eq!(names, ["cairo", "glib-2.0", "udev"]);
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use cargo_metadata::semver::{Version, VersionReq};
+use cargo_metadata::{Dependency, DependencyKind, Metadata, Package};
+use serde::Serialize;
+
+use crate::graph::Graph;
+use crate::index::{self, IndexEntry};
+use crate::policy::compat_key;
+
+/// Cargo.lock のバージョンと、マニフェストの要求バージョンで選べる最新のバージョンの比較
+#[derive(Serialize)]
+pub struct DriftReport {
+    /// 調べた直接依存の数
+    pub checked: usize,
+    /// `cargo update` で更新される直接依存
+    pub drifted: Vec<LockfileDrift>,
+    /// インデックスを取得できなかったクレートとエラー
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct LockfileDrift {
+    /// 依存しているワークスペースメンバー
+    pub member: String,
+    pub name: String,
+    /// `normal`、`dev`、`build`
+    pub kind: &'static str,
+    pub req: String,
+    /// Cargo.lock でロックされているバージョン
+    pub locked: String,
+    /// 要求バージョンを満たす、yank されていない最新のバージョン
+    pub latest_matching: String,
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+/// ワークスペースメンバーの crates.io の直接依存 (メンバー、宣言、解決されたパッケージ)
+fn direct_dependencies<'a>(graph: &Graph<'a>) -> Vec<(&'a Package, &'a Dependency, &'a Package)> {
+    let mut result = Vec::new();
+    for member in graph.members() {
+        let Some(package) = graph.package(member) else {
+            continue;
+        };
+        for dependency in &package.dependencies {
+            let resolved = graph
+                .dependencies(member, true)
+                .filter_map(|id| graph.package(id))
+                .find(|p| {
+                    p.name == dependency.name
+                        && dependency.req.matches(&p.version)
+                        && p.source.as_ref().is_some_and(|s| s.is_crates_io())
+                });
+            if let Some(resolved) = resolved {
+                result.push((package, dependency, resolved));
+            }
+        }
+    }
+    result
+}
+
+/// 公開済みのバージョンのうち、yank されておらず `req` を満たす最新のもの
+fn latest_matching(entries: &[IndexEntry], req: &VersionReq) -> Option<Version> {
+    entries
+        .iter()
+        .filter(|e| !e.yanked)
+        .filter_map(|e| Version::parse(&e.vers).ok())
+        .filter(|v| req.matches(v))
+        .max()
+}
+
+/// クレートごとにインデックスを一度だけ取得する
+struct IndexCache {
+    entries: HashMap<String, Vec<IndexEntry>>,
+    errors: Vec<String>,
+}
+
+impl IndexCache {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn get(&mut self, name: &str) -> Option<&[IndexEntry]> {
+        if !self.entries.contains_key(name) {
+            match index::crate_versions(name) {
+                Ok(entries) => {
+                    self.entries.insert(name.to_string(), entries);
+                }
+                Err(e) => {
+                    self.errors
+                        .push(format!("{name}: {}", e.to_error_object(false).message));
+                    self.entries.insert(name.to_string(), Vec::new());
+                    return None;
+                }
+            }
+        }
+        self.entries
+            .get(name)
+            .map(|e| e.as_slice())
+            .filter(|e| !e.is_empty())
+    }
+}
+
+/// ワークスペースメンバーの直接依存ごとに、Cargo.lock のバージョンと要求バージョンで選べる最新のバージョンを比べる
+///
+/// ロックされたバージョンより新しいバージョンが要求バージョンを満たす場合、`cargo update` で更新される依存関係として返す。
+pub fn lockfile_drift(metadata: &Metadata) -> DriftReport {
+    let graph = Graph::new(metadata);
+    let dependencies = direct_dependencies(&graph);
+    let mut cache = IndexCache::new();
+    let mut drifted = Vec::new();
+    for (member, dependency, resolved) in &dependencies {
+        let Some(entries) = cache.get(&resolved.name) else {
+            continue;
+        };
+        if let Some(latest) = latest_matching(entries, &dependency.req)
+            && latest > resolved.version
+        {
+            drifted.push(LockfileDrift {
+                member: member.name.clone(),
+                name: dependency.name.clone(),
+                kind: kind_name(dependency.kind),
+                req: dependency.req.to_string(),
+                locked: resolved.version.to_string(),
+                latest_matching: latest.to_string(),
+            });
+        }
+    }
+    DriftReport {
+        checked: dependencies.len(),
+        drifted,
+        errors: cache.errors,
+    }
+}
+
+/// crates.io の最新版より古い直接依存
+#[derive(Serialize)]
+pub struct OutdatedReport {
+    /// 調べた直接依存の数
+    pub checked: usize,
+    /// 使用中のバージョンと semver 互換な新しいバージョンがある直接依存
+    pub compatible: Vec<OutdatedDependency>,
+    /// 最新版が使用中のバージョンと semver 非互換 (メジャーバージョン、0.x のマイナーバージョンが異なる) な直接依存
+    pub breaking: Vec<OutdatedDependency>,
+    /// インデックスを取得できなかったクレートとエラー
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct OutdatedDependency {
+    /// 依存しているワークスペースメンバー
+    pub member: String,
+    pub name: String,
+    /// `normal`、`dev`、`build`
+    pub kind: &'static str,
+    pub req: String,
+    /// 使用中のバージョン
+    pub current: String,
+    /// 使用中のバージョンと semver 互換な最新のバージョン
+    pub latest_compatible: Option<String>,
+    /// 最新のバージョン
+    pub latest: String,
+    /// 最新のバージョンを要求バージョンが許可しているかどうか (偽の場合は Cargo.toml の変更が必要)
+    pub req_allows_latest: bool,
+}
+
+/// yank されていない最新のバージョンと、`current` と semver 互換な最新のバージョン
+///
+/// プレリリース版は、使用中のバージョンがプレリリース版の場合のみ含める。
+fn latest_versions(
+    entries: &[IndexEntry],
+    current: &Version,
+) -> Option<(Version, Option<Version>)> {
+    let versions: Vec<Version> = entries
+        .iter()
+        .filter(|e| !e.yanked)
+        .filter_map(|e| Version::parse(&e.vers).ok())
+        .filter(|v| v.pre.is_empty() || !current.pre.is_empty())
+        .collect();
+    let latest = versions.iter().max()?.clone();
+    let key = compat_key(current);
+    let compatible = versions
+        .into_iter()
+        .filter(|v| compat_key(v) == key && v > current)
+        .max();
+    Some((latest, compatible))
+}
+
+/// ワークスペースメンバーの直接依存ごとに crates.io の最新版を調べ、semver 互換な更新と非互換な更新に分ける
+pub fn check_outdated(metadata: &Metadata) -> OutdatedReport {
+    let graph = Graph::new(metadata);
+    let dependencies = direct_dependencies(&graph);
+    let mut cache = IndexCache::new();
+    let mut compatible = Vec::new();
+    let mut breaking = Vec::new();
+    for (member, dependency, resolved) in &dependencies {
+        let Some(entries) = cache.get(&resolved.name) else {
+            continue;
+        };
+        let Some((latest, latest_compatible)) = latest_versions(entries, &resolved.version) else {
+            continue;
+        };
+        let outdated = || OutdatedDependency {
+            member: member.name.clone(),
+            name: dependency.name.clone(),
+            kind: kind_name(dependency.kind),
+            req: dependency.req.to_string(),
+            current: resolved.version.to_string(),
+            latest_compatible: latest_compatible.as_ref().map(|v| v.to_string()),
+            latest: latest.to_string(),
+            req_allows_latest: dependency.req.matches(&latest),
+        };
+        if latest_compatible.is_some() {
+            compatible.push(outdated());
+        }
+        if compat_key(&latest) != compat_key(&resolved.version) && latest > resolved.version {
+            breaking.push(outdated());
+        }
+    }
+    OutdatedReport {
+        checked: dependencies.len(),
+        compatible,
+        breaking,
+        errors: cache.errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(vers: &str, yanked: bool) -> IndexEntry {
+        IndexEntry {
+            name: "serde".to_string(),
+            vers: vers.to_string(),
+            yanked,
+            rust_version: None,
+            pubtime: None,
+        }
+    }
+
+    #[test]
+    fn test_latest_matching() {
+        let entries = [
+            entry("1.0.100", false),
+            entry("1.0.200", false),
+            entry("1.0.201", true),
+            entry("1.1.0-alpha.1", false),
+            entry("2.0.0", false),
+        ];
+        let latest = |req: &str| latest_matching(&entries, &req.parse().unwrap());
+        assert_eq!(latest("1.0"), Some(Version::new(1, 0, 200)));
+        assert_eq!(latest("=1.0.100"), Some(Version::new(1, 0, 100)));
+        assert_eq!(latest(">=1"), Some(Version::new(2, 0, 0)));
+        assert_eq!(latest("3"), None);
+    }
+
+    #[test]
+    fn test_latest_versions() {
+        let entries = [
+            entry("0.9.0", false),
+            entry("1.0.100", false),
+            entry("1.0.200", false),
+            entry("1.0.201", true),
+            entry("2.0.0-alpha.1", false),
+            entry("2.0.0", false),
+        ];
+        let (latest, compatible) = latest_versions(&entries, &Version::new(1, 0, 100)).unwrap();
+        assert_eq!(latest, Version::new(2, 0, 0));
+        assert_eq!(compatible, Some(Version::new(1, 0, 200)));
+        let (_, compatible) = latest_versions(&entries, &Version::new(1, 0, 200)).unwrap();
+        assert_eq!(compatible, None);
+        let (latest, compatible) =
+            latest_versions(&entries[..5], &"2.0.0-alpha.0".parse().unwrap()).unwrap();
+        assert_eq!(latest, "2.0.0-alpha.1".parse::<Version>().unwrap());
+        assert_eq!(compatible, Some(latest));
+    }
+
+    #[test]
+    fn test_direct_dependencies() {
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let graph = Graph::new(&metadata);
+        let dependencies = direct_dependencies(&graph);
+        let (_, serde, resolved) = dependencies
+            .iter()
+            .find(|(_, d, _)| d.name == "serde")
+            .unwrap();
+        assert!(serde.req.matches(&resolved.version));
+    }
+}
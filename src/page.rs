@@ -0,0 +1,157 @@
+use mcp_attr::Result;
+use serde_json::{Map, Value, json};
+
+use crate::detail::Detail;
+use crate::error::{ErrorKind, bail_kind};
+
+/// カーソルによる一覧のページ分割
+///
+/// カーソルは前のページの `_page.next_cursor` の値で、クライアントは中身を解釈せずにそのまま渡す。
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Page {
+    /// 1 ページの要素数 (`None` は分割しない)
+    size: Option<usize>,
+    /// ページの先頭の要素の位置
+    offset: usize,
+}
+
+impl Page {
+    /// ページの大きさとカーソルを解析する (どちらも省略した場合は分割しない)
+    pub fn parse(page_size: Option<usize>, cursor: Option<&str>) -> Result<Self> {
+        let offset = match cursor {
+            None => 0,
+            Some(cursor) => match cursor.parse() {
+                Ok(offset) => offset,
+                Err(_) => bail_kind!(ErrorKind::InvalidCursor, cursor),
+            },
+        };
+        Ok(Self {
+            size: page_size.map(|size| size.max(1)),
+            offset,
+        })
+    }
+
+    /// ページの要素数を、`detail` で切り詰める配列の要素数の上限までに抑える
+    ///
+    /// 詳細さによる切り詰めでページの末尾の要素が省略され、`next_cursor` がそれらを飛ばしてしまわないようにする。
+    pub fn within(self, detail: Detail) -> Self {
+        match detail.max_items() {
+            Some(max) if self.is_requested() => Self {
+                size: Some(self.size.map_or(max, |size| size.min(max))),
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// ページ分割を指定したかどうか
+    pub fn is_requested(self) -> bool {
+        self.size.is_some() || self.offset > 0
+    }
+
+    /// 配列からこのページの要素を切り出し、ページの情報 (`total`、`offset`、`next_cursor`) を返す
+    fn slice(self, items: Vec<Value>) -> (Vec<Value>, Value) {
+        let total = items.len();
+        let end = match self.size {
+            Some(size) => self.offset.saturating_add(size).min(total),
+            None => total,
+        };
+        let next_cursor = (end < total).then(|| end.to_string());
+        let items = items
+            .into_iter()
+            .skip(self.offset)
+            .take(end.saturating_sub(self.offset))
+            .collect();
+        let info = json!({
+            "total": total,
+            "offset": self.offset.min(total),
+            "next_cursor": next_cursor,
+        });
+        (items, info)
+    }
+
+    /// ツールの出力の一覧をページに分割する
+    ///
+    /// `field` を省略した場合はトップレベルの配列を分割し、`items` と `_page` を持つオブジェクトにする。
+    /// `field` を指定した場合はオブジェクトのそのフィールドの配列を分割し、`_page` を加える。
+    /// ページ分割を指定しなかった場合と、分割する配列がない場合は、値をそのまま返す。
+    pub fn apply(self, value: Value, field: Option<&str>) -> Value {
+        if !self.is_requested() {
+            return value;
+        }
+        match (value, field) {
+            (Value::Array(items), None) => {
+                let (items, info) = self.slice(items);
+                let mut map = Map::new();
+                map.insert("items".to_string(), Value::Array(items));
+                map.insert("_page".to_string(), info);
+                Value::Object(map)
+            }
+            (Value::Object(mut map), Some(field)) => {
+                if let Some(Value::Array(items)) = map.remove(field) {
+                    let (items, info) = self.slice(items);
+                    map.insert(field.to_string(), Value::Array(items));
+                    map.insert("_page".to_string(), info);
+                }
+                Value::Object(map)
+            }
+            (value, _) => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page() {
+        let items = json!([1, 2, 3, 4, 5]);
+        let page = Page::parse(Some(2), None).unwrap();
+        assert_eq!(
+            page.apply(items.clone(), None),
+            json!({
+                "items": [1, 2],
+                "_page": { "total": 5, "offset": 0, "next_cursor": "2" },
+            })
+        );
+        let last = Page::parse(Some(2), Some("4")).unwrap();
+        assert_eq!(
+            last.apply(items.clone(), None),
+            json!({
+                "items": [5],
+                "_page": { "total": 5, "offset": 4, "next_cursor": null },
+            })
+        );
+        let beyond = Page::parse(Some(2), Some("10")).unwrap();
+        assert_eq!(beyond.apply(items.clone(), None)["items"], json!([]));
+
+        let metadata = json!({ "packages": [1, 2, 3], "version": 1 });
+        assert_eq!(
+            page.apply(metadata.clone(), Some("packages")),
+            json!({
+                "packages": [1, 2],
+                "version": 1,
+                "_page": { "total": 3, "offset": 0, "next_cursor": "2" },
+            })
+        );
+
+        let none = Page::parse(None, None).unwrap();
+        assert_eq!(none.apply(items.clone(), None), items);
+        assert!(Page::parse(Some(2), Some("abc")).is_err());
+
+        let items = Value::Array((0..100).map(Value::from).collect());
+        let page = Page::parse(Some(100), None).unwrap().within(Detail::Normal);
+        let value = Detail::Normal.apply(page.apply(items.clone(), None));
+        assert_eq!(value["items"].as_array().unwrap().len(), 50);
+        assert_eq!(value["_page"]["next_cursor"], "50");
+        assert!(value.get("_omitted").is_none());
+        let rest = Page::parse(Some(100), Some("50"))
+            .unwrap()
+            .within(Detail::Summary);
+        let value = Detail::Summary.apply(rest.apply(items.clone(), None));
+        assert_eq!(value["items"][0], 50);
+        assert_eq!(value["_page"]["next_cursor"], "60");
+        assert_eq!(none.within(Detail::Summary), none);
+    }
+}
@@ -0,0 +1,383 @@
+use cargo_metadata::semver::Version;
+use cargo_metadata::{Metadata, Package};
+use mcp_attr::Result;
+use serde::Serialize;
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// cargo のパッケージ ID 仕様 (`name`、`name@version`、`registry+https://...#name@version` など)
+///
+/// 仕様の形式は `cargo help pkgid` に従う。URL のフラグメントが数字で始まる場合はバージョンとみなし、
+/// パッケージ名は URL の最後のパスセグメントとする。
+#[derive(Debug, PartialEq, Eq)]
+pub struct PackageIdSpec {
+    pub name: String,
+    pub version: Option<PartialVersion>,
+    /// ソースの URL (`registry+` などの種類の接頭辞、クエリ、フラグメントを除く)
+    pub url: Option<String>,
+}
+
+/// `1`、`1.2`、`1.2.3-alpha` のような、一部を省略できるバージョン
+#[derive(Debug, PartialEq, Eq)]
+pub struct PartialVersion {
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    /// 3 つの数値が全て指定された場合のバージョン (プレリリースとビルドメタデータを含む)
+    full: Option<Version>,
+}
+
+impl PartialVersion {
+    fn parse(text: &str) -> Option<Self> {
+        if let Ok(version) = Version::parse(text) {
+            return Some(Self {
+                major: version.major,
+                minor: Some(version.minor),
+                patch: Some(version.patch),
+                full: Some(version),
+            });
+        }
+        let mut parts = text.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = match parts.next() {
+            Some(minor) => Some(minor.parse().ok()?),
+            None => None,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            major,
+            minor,
+            patch: None,
+            full: None,
+        })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        match &self.full {
+            Some(full) => full == version,
+            None => {
+                self.major == version.major
+                    && self.minor.is_none_or(|m| m == version.minor)
+                    && self.patch.is_none_or(|p| p == version.patch)
+            }
+        }
+    }
+}
+
+/// ソースの URL から種類の接頭辞 (`registry+` など)、クエリ、フラグメントを除く
+fn normalize_url(url: &str) -> &str {
+    let url = match url.split_once('+') {
+        Some((kind, rest)) if !kind.contains(':') => rest,
+        _ => url,
+    };
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    url.trim_end_matches('/')
+}
+
+impl PackageIdSpec {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let parsed = if spec.contains("://") {
+            let (url, fragment) = match spec.split_once('#') {
+                Some((url, fragment)) => (url, Some(fragment)),
+                None => (spec, None),
+            };
+            let url = normalize_url(url);
+            let last_segment = url.rsplit('/').next().unwrap_or_default();
+            let (name, version) = match fragment {
+                Some(f) if f.starts_with(|c: char| c.is_ascii_digit()) => (last_segment, Some(f)),
+                Some(f) => match f.split_once('@') {
+                    Some((name, version)) => (name, Some(version)),
+                    None => (f, None),
+                },
+                None => (last_segment, None),
+            };
+            (name, version, Some(url.to_string()))
+        } else {
+            match spec.split_once('@') {
+                Some((name, version)) => (name, Some(version), None),
+                None => (spec, None, None),
+            }
+        };
+        let (name, version, url) = parsed;
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            bail_kind!(ErrorKind::InvalidPkgid, spec);
+        }
+        let version = match version {
+            Some(text) => match PartialVersion::parse(text) {
+                Some(version) => Some(version),
+                None => bail_kind!(ErrorKind::InvalidPkgidVersion, text, spec),
+            },
+            None => None,
+        };
+        Ok(Self {
+            name: name.to_string(),
+            version,
+            url,
+        })
+    }
+
+    /// 名前だけが指定されているかどうか
+    pub fn is_name_only(&self) -> bool {
+        self.version.is_none() && self.url.is_none()
+    }
+
+    pub fn matches(&self, package: &Package) -> bool {
+        package.name == self.name
+            && self
+                .version
+                .as_ref()
+                .is_none_or(|v| v.matches(&package.version))
+            && self
+                .url
+                .as_deref()
+                .is_none_or(|url| source_url(package) == url)
+    }
+}
+
+/// パッケージのソースの URL (パス依存関係の場合はパッケージのディレクトリの `file://` URL)
+fn source_url(package: &Package) -> String {
+    match &package.source {
+        Some(source) => normalize_url(&source.repr).to_string(),
+        None => {
+            let dir = package
+                .manifest_path
+                .parent()
+                .unwrap_or(&package.manifest_path);
+            format!("file://{dir}")
+        }
+    }
+}
+
+/// パッケージ ID 仕様に一致するパッケージを 1 つに特定する
+///
+/// 一致するパッケージが複数ある場合は、候補のパッケージ ID を含むエラーを返す。
+pub fn resolve<'a>(
+    packages: impl IntoIterator<Item = &'a Package>,
+    spec: &str,
+) -> Result<&'a Package> {
+    let parsed = PackageIdSpec::parse(spec)?;
+    let matches: Vec<&Package> = packages.into_iter().filter(|p| parsed.matches(p)).collect();
+    match matches.as_slice() {
+        [package] => Ok(package),
+        [] => bail_kind!(ErrorKind::PackageNotFound, spec),
+        _ => {
+            let ids: Vec<&str> = matches.iter().map(|p| p.id.repr.as_str()).collect();
+            bail_kind!(ErrorKind::AmbiguousPkgid, spec, ids.join(", "))
+        }
+    }
+}
+
+/// パッケージ ID 仕様に一致するパッケージを返す (名前だけの仕様に複数が一致する場合は最新のバージョン)
+pub fn resolve_latest<'a>(metadata: &'a Metadata, spec: &str) -> Result<&'a Package> {
+    let parsed = PackageIdSpec::parse(spec)?;
+    if parsed.is_name_only()
+        && let Some(package) = metadata
+            .packages
+            .iter()
+            .filter(|p| parsed.matches(p))
+            .max_by(|a, b| a.version.cmp(&b.version))
+    {
+        return Ok(package);
+    }
+    resolve(&metadata.packages, spec)
+}
+
+/// 候補として示す、ワークスペースのメンバー以外のパッケージの数の上限
+const MAX_SUGGESTIONS: usize = 10;
+
+/// 比較に使うパッケージ名 (小文字にし、`_` を `-` にそろえる)
+fn normalize_name(name: &str) -> String {
+    name.to_ascii_lowercase().replace('_', "-")
+}
+
+/// ツールの `package` パラメーターに指定されたパッケージを選ぶ
+///
+/// 省略時はルートパッケージを返す。ワークスペースのメンバーを優先し、メンバーに一致しなければ依存グラフ全体から探す
+/// (名前だけの仕様に複数のバージョンが一致する場合は最新のバージョン)。
+/// 見つからない場合は、ワークスペースのメンバーの名前と、依存グラフにある似た名前を含むエラーを返す。
+pub fn select_package<'a>(metadata: &'a Metadata, spec: Option<&str>) -> Result<&'a Package> {
+    let members = metadata.workspace_packages();
+    let member_names = || {
+        members
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let Some(spec) = spec else {
+        match metadata.root_package() {
+            Some(package) => return Ok(package),
+            None => bail_kind!(ErrorKind::PackageRequired, member_names()),
+        }
+    };
+    let parsed = PackageIdSpec::parse(spec)?;
+    if let [package] = members
+        .iter()
+        .filter(|p| parsed.matches(p))
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        return Ok(package);
+    }
+    if !metadata.packages.iter().any(|p| parsed.matches(p)) {
+        let name = normalize_name(&parsed.name);
+        let mut names: Vec<&str> = members.iter().map(|p| p.name.as_str()).collect();
+        let mut similar: Vec<&str> = metadata
+            .packages
+            .iter()
+            .map(|p| p.name.as_str())
+            .filter(|n| !names.contains(n))
+            .filter(|n| {
+                let n = normalize_name(n);
+                n.contains(&name) || name.contains(&n)
+            })
+            .collect();
+        similar.sort();
+        similar.dedup();
+        similar.truncate(MAX_SUGGESTIONS);
+        names.extend(similar);
+        bail_kind!(ErrorKind::UnknownPackage, spec, names.join(", "));
+    }
+    resolve_latest(metadata, spec)
+}
+
+/// パッケージ ID 仕様に一致する依存グラフのすべてのパッケージ (バージョンの順)
+///
+/// `serde@1` のように複数のバージョンに一致してもエラーにしない。一致しない場合は [`select_package`] と同じエラーを返す。
+pub fn matching_packages<'a>(metadata: &'a Metadata, spec: &str) -> Result<Vec<&'a Package>> {
+    let parsed = PackageIdSpec::parse(spec)?;
+    let mut packages: Vec<&Package> = metadata
+        .packages
+        .iter()
+        .filter(|p| parsed.matches(p))
+        .collect();
+    if packages.is_empty() {
+        packages.push(select_package(metadata, Some(spec))?);
+    }
+    packages.sort_by(|a, b| a.version.cmp(&b.version));
+    Ok(packages)
+}
+
+#[derive(Serialize)]
+pub struct PkgidInfo {
+    pub name: String,
+    pub version: String,
+    /// 完全なパッケージ ID
+    pub pkgid: String,
+    /// 依存グラフの中でこのパッケージを一意に特定できる、最も短いパッケージ ID 仕様
+    pub spec: String,
+    pub source: Option<String>,
+}
+
+/// 依存グラフの中でパッケージを一意に特定できる、最も短いパッケージ ID 仕様
+pub fn shortest_spec(metadata: &Metadata, package: &Package) -> String {
+    let candidates = [
+        package.name.clone(),
+        format!("{}@{}", package.name, package.version),
+    ];
+    for candidate in candidates {
+        if let Ok(spec) = PackageIdSpec::parse(&candidate)
+            && metadata.packages.iter().filter(|p| spec.matches(p)).count() == 1
+        {
+            return candidate;
+        }
+    }
+    package.id.repr.clone()
+}
+
+pub fn pkgid_info(metadata: &Metadata, package: &Package) -> PkgidInfo {
+    PkgidInfo {
+        name: package.name.clone(),
+        version: package.version.to_string(),
+        pkgid: package.id.repr.clone(),
+        spec: shortest_spec(metadata, package),
+        source: package.source.as_ref().map(|s| s.repr.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str, source: Option<&str>) -> Package {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": version,
+            "id": format!("{}#{name}@{version}", source.unwrap_or("path+file:///ws/app")),
+            "source": source,
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+            "manifest_path": format!("/ws/{name}/Cargo.toml"),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let spec = PackageIdSpec::parse("serde@1.0").unwrap();
+        assert_eq!(spec.name, "serde");
+        assert!(spec.url.is_none());
+        let spec = PackageIdSpec::parse(
+            "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.200",
+        )
+        .unwrap();
+        assert_eq!(spec.name, "serde");
+        assert_eq!(
+            spec.url.as_deref(),
+            Some("https://github.com/rust-lang/crates.io-index")
+        );
+        let spec = PackageIdSpec::parse("https://github.com/rust-lang/cargo#0.52.0").unwrap();
+        assert_eq!(spec.name, "cargo");
+        assert!(PackageIdSpec::parse("serde@x").is_err());
+        assert!(PackageIdSpec::parse("").is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let registry = "registry+https://github.com/rust-lang/crates.io-index";
+        let packages = [
+            package("syn", "1.0.109", Some(registry)),
+            package("syn", "2.0.90", Some(registry)),
+            package(
+                "syn",
+                "2.0.90",
+                Some("git+https://github.com/dtolnay/syn?branch=master#abc"),
+            ),
+        ];
+        assert_eq!(resolve(&packages, "syn@1").unwrap().version.major, 1);
+        assert!(resolve(&packages, "syn@2").is_err());
+        let git = resolve(&packages, "https://github.com/dtolnay/syn#2.0.90").unwrap();
+        assert!(git.source.as_ref().unwrap().repr.starts_with("git+"));
+        let registry = resolve(
+            &packages,
+            "https://github.com/rust-lang/crates.io-index#syn@2",
+        )
+        .unwrap();
+        assert!(
+            registry
+                .source
+                .as_ref()
+                .unwrap()
+                .repr
+                .starts_with("registry+")
+        );
+        assert!(resolve(&packages, "quote").is_err());
+    }
+
+    #[test]
+    fn test_partial_version() {
+        let version = Version::parse("1.2.3").unwrap();
+        assert!(PartialVersion::parse("1").unwrap().matches(&version));
+        assert!(PartialVersion::parse("1.2").unwrap().matches(&version));
+        assert!(!PartialVersion::parse("1.3").unwrap().matches(&version));
+        assert!(!PartialVersion::parse("1.2.4").unwrap().matches(&version));
+    }
+}
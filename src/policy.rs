@@ -0,0 +1,423 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use cargo_metadata::semver::{Version, VersionReq};
+use cargo_metadata::{Metadata, Package};
+use mcp_attr::Result;
+use serde::Deserialize;
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::findings::{self, Finding, FindingSet, Level};
+use crate::graph::Graph;
+use crate::license::{self, LicensePolicy, LicensePreset};
+
+/// ポリシーファイルを省略した場合に、ワークスペースルートから探すファイル名
+pub const DEFAULT_POLICY_FILE: &str = "cargo-policy.toml";
+
+const KNOWN_FIELDS: &[&str] = &[
+    "authors",
+    "description",
+    "documentation",
+    "homepage",
+    "keywords",
+    "categories",
+    "license",
+    "readme",
+    "repository",
+    "rust-version",
+];
+
+/// TOML で記述された依存関係ポリシー
+///
+/// ```toml
+/// max-dependencies = 200
+/// max-duplicate-majors = 1
+/// banned = ["openssl-sys"]
+/// required-fields = ["license", "description", "repository"]
+///
+/// [[crates.allow]]
+/// name = "serde"
+/// version = "1"
+/// reason = "Approved by the security team"
+///
+/// [[crates.deny]]
+/// name = "openssl"
+/// version = "<0.10.55"
+/// reason = "RUSTSEC-2023-0044"
+///
+/// [licenses]
+/// allow = ["MIT", "Apache-2.0"]
+/// deny = ["GPL-3.0-only"]
+/// preset = "no-copyleft"
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Policy {
+    /// ワークスペースメンバーを除いた依存パッケージ数の上限
+    pub max_dependencies: Option<usize>,
+    /// 1 つのクレートについて共存を許す semver 非互換なバージョンの数の上限
+    pub max_duplicate_majors: Option<usize>,
+    /// 依存グラフに含まれてはならないクレート名
+    #[serde(default)]
+    pub banned: Vec<String>,
+    /// ワークスペースメンバーの Cargo.toml に必須のフィールド
+    #[serde(default)]
+    pub required_fields: Vec<String>,
+    pub crates: Option<CrateSection>,
+    pub licenses: Option<LicenseSection>,
+}
+
+/// クレート名による許可リストと拒否リスト
+///
+/// `allow` が空でない場合、ワークスペースメンバー以外の全パッケージがいずれかの要素に一致する必要がある。
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CrateSection {
+    #[serde(default)]
+    pub allow: Vec<CrateEntry>,
+    #[serde(default)]
+    pub deny: Vec<CrateEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CrateEntry {
+    pub name: String,
+    /// 対象とするバージョンの範囲。省略時は全てのバージョン
+    pub version: Option<VersionReq>,
+    /// 許可または拒否の理由
+    pub reason: Option<String>,
+}
+
+impl CrateEntry {
+    pub(crate) fn matches(&self, package: &Package) -> bool {
+        self.name == package.name
+            && self
+                .version
+                .as_ref()
+                .is_none_or(|req| req.matches(&package.version))
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        let mut text = format!("`{}", self.name);
+        if let Some(version) = &self.version {
+            text.push_str(&format!(" {version}"));
+        }
+        text.push('`');
+        if let Some(reason) = &self.reason {
+            text.push_str(&format!(" ({reason})"));
+        }
+        text
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LicenseSection {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// `no-copyleft`、`osi-approved` または `fsf-free`
+    pub preset: Option<LicensePreset>,
+}
+
+impl Policy {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => bail_kind!(ErrorKind::ReadFailed, path.display(), e),
+        };
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let policy: Policy = match toml::from_str(text) {
+            Ok(policy) => policy,
+            Err(e) => bail_kind!(ErrorKind::InvalidPolicy, e),
+        };
+        for field in &policy.required_fields {
+            if !KNOWN_FIELDS.contains(&field.as_str()) {
+                bail_kind!(
+                    ErrorKind::UnknownPolicyField,
+                    field,
+                    KNOWN_FIELDS.join(", ")
+                );
+            }
+        }
+        Ok(policy)
+    }
+}
+
+/// 同じ値であれば semver 互換とみなされるバージョンの区分 (`1`, `0.3`, `0.0.5` など)
+pub fn compat_key(version: &Version) -> String {
+    if version.major > 0 {
+        version.major.to_string()
+    } else if version.minor > 0 {
+        format!("0.{}", version.minor)
+    } else {
+        format!("0.0.{}", version.patch)
+    }
+}
+
+fn has_field(package: &Package, field: &str) -> bool {
+    match field {
+        "authors" => !package.authors.is_empty(),
+        "description" => package.description.is_some(),
+        "documentation" => package.documentation.is_some(),
+        "homepage" => package.homepage.is_some(),
+        "keywords" => !package.keywords.is_empty(),
+        "categories" => !package.categories.is_empty(),
+        "license" => package.license.is_some() || package.license_file.is_some(),
+        "readme" => package.readme.is_some(),
+        "repository" => package.repository.is_some(),
+        "rust-version" => package.rust_version.is_some(),
+        _ => true,
+    }
+}
+
+/// ポリシーの全てのルールを 1 回で評価する
+pub fn evaluate(metadata: &Metadata, policy: &Policy) -> FindingSet {
+    let graph = Graph::new(metadata);
+    let mut set = FindingSet::new("policy");
+    let root_manifest = metadata.workspace_root.join("Cargo.toml");
+    let dependencies: Vec<&Package> = metadata
+        .packages
+        .iter()
+        .filter(|p| !metadata.workspace_members.contains(&p.id))
+        .collect();
+
+    if let Some(max) = policy.max_dependencies {
+        set.rule(
+            "policy/max-dependencies",
+            "The number of dependencies must not exceed the configured maximum",
+            None,
+        );
+        if dependencies.len() > max {
+            set.push(Finding {
+                rule: "policy/max-dependencies".to_string(),
+                level: Level::Error,
+                message: format!(
+                    "The dependency graph contains {} packages, exceeding the maximum of {} by {}",
+                    dependencies.len(),
+                    max,
+                    dependencies.len() - max
+                ),
+                package: None,
+                file: Some(root_manifest.clone()),
+                line: None,
+                dependency_path: Vec::new(),
+            });
+        }
+    }
+
+    if !policy.banned.is_empty() {
+        set.rule(
+            "policy/banned-crate",
+            "Banned crates must not appear in the dependency graph",
+            None,
+        );
+        for package in &dependencies {
+            if policy.banned.contains(&package.name) {
+                let (file, line) = findings::lockfile_location(metadata, package);
+                set.push(Finding {
+                    rule: "policy/banned-crate".to_string(),
+                    level: Level::Error,
+                    message: format!(
+                        "{}@{} is banned by the policy",
+                        package.name, package.version
+                    ),
+                    package: Some(format!("{}@{}", package.name, package.version)),
+                    file: Some(file),
+                    line,
+                    dependency_path: graph.path_labels(&package.id, true),
+                });
+            }
+        }
+    }
+
+    if let Some(max) = policy.max_duplicate_majors {
+        set.rule(
+            "policy/duplicate-versions",
+            "A crate must not appear in more semver-incompatible versions than allowed",
+            None,
+        );
+        let mut versions: BTreeMap<&str, BTreeMap<String, Vec<&Version>>> = BTreeMap::new();
+        for package in &dependencies {
+            versions
+                .entry(&package.name)
+                .or_default()
+                .entry(compat_key(&package.version))
+                .or_default()
+                .push(&package.version);
+        }
+        for (name, majors) in versions {
+            if majors.len() > max {
+                let list: Vec<String> = majors.values().flatten().map(|v| v.to_string()).collect();
+                set.push(Finding {
+                    rule: "policy/duplicate-versions".to_string(),
+                    level: Level::Error,
+                    message: format!(
+                        "{} appears in {} semver-incompatible versions ({}), exceeding the maximum of {}",
+                        name,
+                        majors.len(),
+                        list.join(", "),
+                        max
+                    ),
+                    package: Some(name.to_string()),
+                    file: Some(metadata.workspace_root.join("Cargo.lock")),
+                    line: None,
+                    dependency_path: Vec::new(),
+                });
+            }
+        }
+    }
+
+    if !policy.required_fields.is_empty() {
+        set.rule(
+            "policy/required-field",
+            "Workspace members must declare the required manifest fields",
+            None,
+        );
+        for package in metadata.workspace_packages() {
+            for field in &policy.required_fields {
+                if !has_field(package, field) {
+                    set.push(Finding {
+                        rule: "policy/required-field".to_string(),
+                        level: Level::Error,
+                        message: format!("{} does not declare `{}`", package.name, field),
+                        package: Some(format!("{}@{}", package.name, package.version)),
+                        file: Some(package.manifest_path.clone()),
+                        line: findings::find_key_line(&package.manifest_path, "[package]"),
+                        dependency_path: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(crates) = &policy.crates {
+        if !crates.allow.is_empty() {
+            set.rule(
+                "policy/crate-not-allowed",
+                "Every dependency must be explicitly listed in the crate allowlist",
+                None,
+            );
+        }
+        if !crates.deny.is_empty() {
+            set.rule(
+                "policy/crate-denied",
+                "Dependencies must not match an entry of the crate denylist",
+                None,
+            );
+        }
+        for package in &dependencies {
+            let label = format!("{}@{}", package.name, package.version);
+            let denied = crates.deny.iter().find(|entry| entry.matches(package));
+            let (rule, message) = if let Some(entry) = denied {
+                (
+                    "policy/crate-denied",
+                    format!("{label} matches the denylist entry {}", entry.describe()),
+                )
+            } else if !crates.allow.is_empty() && !crates.allow.iter().any(|e| e.matches(package)) {
+                let versions: Vec<String> = crates
+                    .allow
+                    .iter()
+                    .filter(|e| e.name == package.name)
+                    .map(|e| e.describe())
+                    .collect();
+                let message = if versions.is_empty() {
+                    format!("{label} is not in the crate allowlist")
+                } else {
+                    format!(
+                        "{label} does not match the allowed versions: {}",
+                        versions.join(", ")
+                    )
+                };
+                ("policy/crate-not-allowed", message)
+            } else {
+                continue;
+            };
+            let (file, line) = findings::lockfile_location(metadata, package);
+            set.push(Finding {
+                rule: rule.to_string(),
+                level: Level::Error,
+                message,
+                package: Some(label),
+                file: Some(file),
+                line,
+                dependency_path: graph.path_labels(&package.id, true),
+            });
+        }
+    }
+
+    if let Some(licenses) = &policy.licenses {
+        let license_policy = LicensePolicy {
+            allow: licenses.allow.clone(),
+            deny: licenses.deny.clone(),
+            preset: licenses.preset,
+            ..Default::default()
+        };
+        set.extend(license::check_license_policy(metadata, &license_policy));
+    }
+
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_metadata::MetadataCommand;
+
+    #[test]
+    fn test_evaluate() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let policy = Policy::parse(
+            r#"
+max-dependencies = 1
+banned = ["serde"]
+required-fields = ["rust-version"]
+"#,
+        )
+        .unwrap();
+        let set = evaluate(&metadata, &policy);
+        let rules: Vec<&str> = set.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert!(rules.contains(&"policy/max-dependencies"));
+        assert!(rules.contains(&"policy/banned-crate"));
+        assert!(rules.contains(&"policy/required-field"));
+
+        let policy = Policy::parse(
+            r#"
+[[crates.allow]]
+name = "serde"
+version = "<1"
+
+[[crates.deny]]
+name = "serde_json"
+reason = "testing"
+"#,
+        )
+        .unwrap();
+        let set = evaluate(&metadata, &policy);
+        let find = |package: &str| {
+            set.findings
+                .iter()
+                .find(|f| f.package.as_ref().is_some_and(|p| p.starts_with(package)))
+                .map(|f| f.rule.as_str())
+        };
+        assert_eq!(find("serde@"), Some("policy/crate-not-allowed"));
+        assert_eq!(find("serde_json@"), Some("policy/crate-denied"));
+
+        assert!(Policy::parse("unknown = 1").is_err());
+        assert!(Policy::parse(r#"required-fields = ["nope"]"#).is_err());
+        let policy = Policy::parse("[licenses]\npreset = \"no-copyleft\"").unwrap();
+        assert_eq!(
+            policy.licenses.unwrap().preset,
+            Some(LicensePreset::NoCopyleft)
+        );
+        assert!(Policy::parse("[licenses]\npreset = \"permissive\"").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use std::path::Path;
+
+use cargo_metadata::Metadata;
+use mcp_attr::Result;
+use serde::Serialize;
+use similar::TextDiff;
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::index::validate_crate_name;
+use crate::lockfile::{self, Lockfile, LockfileChange};
+use crate::sandbox::Sandbox;
+use crate::upgrade::ChangeSummary;
+
+#[derive(Serialize)]
+pub struct ManifestPreview {
+    /// 実行した cargo のコマンド
+    pub command: String,
+    pub success: bool,
+    /// cargo が失敗した場合のエラー出力
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// ワークスペースルートからの相対パスによる、マニフェストの変更の unified diff
+    pub patch: String,
+    pub summary: ChangeSummary,
+    /// Cargo.lock の変更
+    pub changes: Vec<LockfileChange>,
+}
+
+/// `cargo add` に渡す依存関係の指定
+pub struct AddDependency<'a> {
+    pub crate_name: &'a str,
+    pub version: Option<&'a str>,
+    pub features: &'a [String],
+    pub dev: bool,
+    /// 依存関係を追加するワークスペースのメンバー
+    pub package: Option<&'a str>,
+    /// `package` を省略した場合に依存関係を追加するマニフェスト
+    pub manifest_path: &'a Path,
+}
+
+/// `cargo add` を一時的なコピーで実行し、マニフェストの差分と Cargo.lock の変更を返す
+pub fn preview_add(metadata: &Metadata, dependency: &AddDependency) -> Result<ManifestPreview> {
+    validate_crate_name(dependency.crate_name)?;
+    let mut args = vec!["add".to_string()];
+    args.push(match dependency.version {
+        Some(version) => format!("{}@{version}", dependency.crate_name),
+        None => dependency.crate_name.to_string(),
+    });
+    if !dependency.features.is_empty() {
+        args.push("--features".to_string());
+        args.push(dependency.features.join(","));
+    }
+    if dependency.dev {
+        args.push("--dev".to_string());
+    }
+    args.extend(target_args(
+        metadata,
+        dependency.manifest_path,
+        dependency.package,
+    ));
+    preview(metadata, &args)
+}
+
+/// `cargo remove` を一時的なコピーで実行し、マニフェストの差分と Cargo.lock から削除されるクレートを返す
+///
+/// `package` を省略した場合は `manifest_path` のマニフェストから削除する。
+pub fn preview_remove(
+    metadata: &Metadata,
+    manifest_path: &Path,
+    crate_name: &str,
+    package: Option<&str>,
+) -> Result<ManifestPreview> {
+    validate_crate_name(crate_name)?;
+    let mut args = vec!["remove".to_string(), crate_name.to_string()];
+    args.extend(target_args(metadata, manifest_path, package));
+    preview(metadata, &args)
+}
+
+/// 変更するマニフェストを選ぶ cargo の引数
+///
+/// `package` を省略した場合は、`manifest_path` をワークスペースルートからの相対パスで `--manifest-path` に指定する
+/// (サンドボックスではワークスペースルートで cargo を実行するため)。
+fn target_args(metadata: &Metadata, manifest_path: &Path, package: Option<&str>) -> Vec<String> {
+    if let Some(package) = package {
+        return vec!["--package".to_string(), package.to_string()];
+    }
+    match manifest_path.strip_prefix(&metadata.workspace_root) {
+        Ok(relative) => vec![
+            "--manifest-path".to_string(),
+            relative.display().to_string(),
+        ],
+        Err(_) => Vec::new(),
+    }
+}
+
+fn preview(metadata: &Metadata, args: &[String]) -> Result<ManifestPreview> {
+    let sandbox = Sandbox::new(metadata)?;
+    sandbox.ensure_lockfile()?;
+    let before = Lockfile::load(&sandbox.lockfile_path())?;
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    let output = sandbox.cargo(&args)?;
+    let mut preview = ManifestPreview {
+        command: format!("cargo {}", args.join(" ")),
+        success: output.status.success(),
+        error: None,
+        patch: String::new(),
+        summary: ChangeSummary::default(),
+        changes: Vec::new(),
+    };
+    if !output.status.success() {
+        preview.error = Some(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        return Ok(preview);
+    }
+    // `cargo add` / `cargo remove` の後に依存関係を解決し直し、Cargo.lock を更新する
+    sandbox.run(&["metadata", "--format-version", "1", "--quiet"])?;
+    preview.patch = manifest_patch(metadata, &sandbox)?;
+    let after = Lockfile::load(&sandbox.lockfile_path())?;
+    preview.changes = lockfile::diff(&before, &after);
+    preview.summary = ChangeSummary::new(&preview.changes);
+    Ok(preview)
+}
+
+/// ワークスペースのマニフェストと、サンドボックス内のコピーとの unified diff
+pub fn manifest_patch(metadata: &Metadata, sandbox: &Sandbox) -> Result<String> {
+    let root = &metadata.workspace_root;
+    let mut manifests = vec![root.join("Cargo.toml")];
+    for package in metadata.packages.iter().filter(|p| p.source.is_none()) {
+        if !manifests.contains(&package.manifest_path) {
+            manifests.push(package.manifest_path.clone());
+        }
+    }
+    let mut patch = String::new();
+    for manifest in manifests {
+        let Ok(relative) = manifest.strip_prefix(root) else {
+            continue;
+        };
+        let Ok(old) = std::fs::read_to_string(&manifest) else {
+            continue;
+        };
+        let new = match std::fs::read_to_string(sandbox.dir().join(relative)) {
+            Ok(new) => new,
+            Err(e) => bail_kind!(ErrorKind::ReadFailed, relative, e),
+        };
+        patch.push_str(&unified_diff(relative.as_str(), &old, &new));
+    }
+    Ok(patch)
+}
+
+/// `git apply` で適用できる形式の unified diff (変更がなければ空文字列)
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_args() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let root = metadata.workspace_root.as_std_path();
+        assert_eq!(
+            target_args(&metadata, &root.join("app/Cargo.toml"), None),
+            ["--manifest-path", "app/Cargo.toml"]
+        );
+        assert_eq!(
+            target_args(
+                &metadata,
+                &root.join("app/Cargo.toml"),
+                Some("fixture-core")
+            ),
+            ["--package", "fixture-core"]
+        );
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "[dependencies]\nserde = \"1\"\n";
+        let new = "[dependencies]\nserde = \"1\"\nanyhow = \"1.0.100\"\n";
+        assert_eq!(unified_diff("Cargo.toml", old, old), "");
+        assert_eq!(
+            unified_diff("Cargo.toml", old, new),
+            "--- a/Cargo.toml\n+++ b/Cargo.toml\n@@ -1,2 +1,3 @@\n [dependencies]\n serde = \"1\"\n+anyhow = \"1.0.100\"\n"
+        );
+    }
+}
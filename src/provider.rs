@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use cargo_metadata::{CargoOpt, Metadata, MetadataCommand};
+use mcp_attr::Result;
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// `cargo metadata` の実行方法を変えるオプション
+///
+/// メタデータはマニフェストのパスとこのオプションの組ごとにキャッシュされる。
+#[derive(Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct MetadataOptions {
+    /// Cargo.lock を更新しない (`--locked`)
+    pub locked: bool,
+    /// 依存関係を指定したターゲットトリプルで有効なものに絞り込む (`--filter-platform`)
+    pub filter_platform: Option<String>,
+    /// 依存関係を解決せず、ワークスペースのメンバーだけを取得する (`--no-deps`)
+    pub no_deps: bool,
+    /// 有効にするフィーチャー (`--features`)
+    pub features: Vec<String>,
+    /// すべてのフィーチャーを有効にする (`--all-features`)
+    pub all_features: bool,
+    /// 既定のフィーチャーを無効にする (`--no-default-features`)
+    pub no_default_features: bool,
+}
+
+impl MetadataOptions {
+    /// 依存グラフを絞り込まず、既定のフィーチャーで取得するかどうか (`locked` は結果に影響しないため考慮しない)
+    pub fn is_complete(&self) -> bool {
+        self.filter_platform.is_none()
+            && !self.no_deps
+            && self.features.is_empty()
+            && !self.all_features
+            && !self.no_default_features
+    }
+}
+
+/// プロジェクトのメタデータの取得方法
+///
+/// サーバーはツールの呼び出しごとにこのトレイトを通してメタデータを取得するため、
+/// テストや、サーバーを組み込むアプリケーションでは、`cargo metadata` を実行しない実装に置き換えられる。
+pub trait MetadataProvider: Send + Sync {
+    /// `manifest_path` のプロジェクトのメタデータを取得する
+    ///
+    /// `options.locked` が真の場合は Cargo.lock を更新してはならない (`--locked` 相当)。
+    /// ブロッキングしてよい (サーバーは `spawn_blocking` で呼び出す)。
+    fn metadata(&self, manifest_path: &Path, options: &MetadataOptions) -> Result<Metadata>;
+}
+
+/// `cargo metadata` を実行してメタデータを取得する
+#[derive(Default)]
+pub struct CargoMetadataProvider;
+
+impl MetadataProvider for CargoMetadataProvider {
+    fn metadata(&self, manifest_path: &Path, options: &MetadataOptions) -> Result<Metadata> {
+        let mut cmd = MetadataCommand::new();
+        cmd.manifest_path(manifest_path);
+        if options.no_deps {
+            cmd.no_deps();
+        }
+        if !options.features.is_empty() {
+            cmd.features(CargoOpt::SomeFeatures(options.features.clone()));
+        }
+        if options.all_features {
+            cmd.features(CargoOpt::AllFeatures);
+        }
+        if options.no_default_features {
+            cmd.features(CargoOpt::NoDefaultFeatures);
+        }
+        let mut other_options = Vec::new();
+        if options.locked {
+            other_options.push("--locked".to_string());
+        }
+        if let Some(target) = &options.filter_platform {
+            other_options.extend(["--filter-platform".to_string(), target.clone()]);
+        }
+        cmd.other_options(other_options);
+        match cmd.exec() {
+            Ok(metadata) => Ok(metadata),
+            Err(e) if options.locked => bail_kind!(ErrorKind::MetadataLocked, e),
+            Err(e) => bail_kind!(ErrorKind::MetadataFailed, e),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use cargo_metadata::{DependencyKind, Metadata, Package};
+use mcp_attr::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// `exposed_items` に記録する公開 API の要素の数の上限
+const ITEM_LIMIT: usize = 5;
+
+#[derive(Serialize)]
+pub struct PublicDependencyReport {
+    pub members: Vec<MemberPublicDependencies>,
+    /// 宣言と公開 API の使用が食い違っている依存関係の数
+    pub flagged: usize,
+}
+
+#[derive(Serialize)]
+pub struct MemberPublicDependencies {
+    pub package: String,
+    /// 公開 API の解析に使用した rustdoc JSON のパス (見つからない場合は `None`)
+    pub rustdoc_json: Option<String>,
+    pub dependencies: Vec<PublicDependency>,
+}
+
+#[derive(Serialize)]
+pub struct PublicDependency {
+    /// Cargo.toml の依存関係のキー (名前を変更している場合は変更後の名前)
+    pub name: String,
+    pub package: String,
+    /// Cargo.toml での `public` の指定 (指定がない場合は `None`)
+    pub declared_public: Option<bool>,
+    /// 依存クレートの型などが公開 API に現れるかどうか (rustdoc JSON がない場合は `None`)
+    pub exposed: Option<bool>,
+    /// 依存クレートの要素を参照している公開 API の要素 (最大 5 件)
+    pub exposed_items: Vec<String>,
+    /// `declare-public` (公開 API に現れるのに public でない) または `declare-private` (public だが公開 API に現れない)
+    pub recommendation: Option<&'static str>,
+}
+
+/// Cargo.toml の `[dependencies]` と `[target.*.dependencies]` から、依存関係ごとの `public` の指定を読み取る
+fn declared_public(manifest_path: &Path) -> Result<BTreeMap<String, bool>> {
+    let text = match std::fs::read_to_string(manifest_path) {
+        Ok(text) => text,
+        Err(e) => bail_kind!(ErrorKind::ReadFailed, manifest_path.display(), e),
+    };
+    let manifest: toml::Table = match toml::from_str(&text) {
+        Ok(manifest) => manifest,
+        Err(e) => bail_kind!(ErrorKind::ParseFailed, manifest_path.display(), e),
+    };
+    let mut tables = vec![manifest.get("dependencies")];
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        tables.extend(targets.values().map(|t| t.get("dependencies")));
+    }
+    let mut declared = BTreeMap::new();
+    for table in tables.into_iter().flatten().filter_map(|t| t.as_table()) {
+        for (name, dependency) in table {
+            if let Some(public) = dependency.get("public").and_then(|p| p.as_bool()) {
+                declared.insert(name.clone(), public);
+            }
+        }
+    }
+    Ok(declared)
+}
+
+fn id_key(id: &Value) -> Option<String> {
+    match id {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// 値の中で参照されている要素の ID (`resolved_path` の型と `use` による再エクスポート) を集める
+fn referenced_ids(value: &Value, ids: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                if matches!(key.as_str(), "resolved_path" | "use")
+                    && let Some(id) = child.get("id").and_then(id_key)
+                {
+                    ids.insert(id);
+                }
+                referenced_ids(child, ids);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                referenced_ids(item, ids);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// rustdoc JSON を解析し、外部クレートの名前ごとに、そのクレートの要素を参照している公開 API の要素を返す
+///
+/// rustdoc JSON には既定では公開された要素だけが含まれるため、ローカルのクレート (`crate_id` が 0) の
+/// 全ての要素のシグネチャから参照されている要素を調べる。
+pub fn exposed_crates(rustdoc: &Value) -> BTreeMap<String, BTreeSet<String>> {
+    let crate_name = |crate_id: &Value| {
+        let key = id_key(crate_id)?;
+        rustdoc["external_crates"][key.as_str()]["name"]
+            .as_str()
+            .map(|s| s.to_string())
+    };
+    let mut exposed: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let Some(index) = rustdoc["index"].as_object() else {
+        return exposed;
+    };
+    for item in index.values() {
+        if item["crate_id"] != 0 {
+            continue;
+        }
+        let mut ids = BTreeSet::new();
+        referenced_ids(&item["inner"], &mut ids);
+        let item_name = item["name"].as_str().unwrap_or("<unnamed>");
+        for id in ids {
+            let path = &rustdoc["paths"][id.as_str()];
+            if path["crate_id"] == 0 || path.is_null() {
+                continue;
+            }
+            if let Some(name) = crate_name(&path["crate_id"]) {
+                exposed
+                    .entry(name)
+                    .or_default()
+                    .insert(item_name.to_string());
+            }
+        }
+    }
+    exposed
+}
+
+fn load_rustdoc(path: &Path) -> Option<Value> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// ライブラリのクレート名 (rustdoc JSON のファイル名と、外部クレートとして参照されるときの名前)
+fn lib_name(package: &Package) -> Option<String> {
+    package
+        .targets
+        .iter()
+        .find(|t| t.is_lib() || t.kind.iter().any(|k| k == "rlib" || k == "proc-macro"))
+        .map(|t| t.name.replace('-', "_"))
+}
+
+/// ワークスペースのライブラリの依存関係が公開 API に現れるかを調べ、`public` の宣言と照合する
+///
+/// `rustdoc_dir` にある `<crate>.json` (`cargo +nightly rustdoc -- -Z unstable-options --output-format json` の出力)
+/// を公開 API の解析に使用する。省略時はターゲットディレクトリの `doc`。
+pub fn public_dependency_report(
+    metadata: &Metadata,
+    rustdoc_dir: Option<PathBuf>,
+) -> Result<PublicDependencyReport> {
+    let rustdoc_dir =
+        rustdoc_dir.unwrap_or_else(|| metadata.target_directory.join("doc").into_std_path_buf());
+    let mut members = Vec::new();
+    let mut flagged = 0;
+    for package in metadata.workspace_packages() {
+        let Some(lib) = lib_name(package) else {
+            continue;
+        };
+        let declared = declared_public(package.manifest_path.as_std_path())?;
+        let rustdoc_path = rustdoc_dir.join(format!("{lib}.json"));
+        let exposed = load_rustdoc(&rustdoc_path).map(|r| exposed_crates(&r));
+
+        let mut dependencies = Vec::new();
+        for dependency in &package.dependencies {
+            if dependency.kind != DependencyKind::Normal {
+                continue;
+            }
+            let name = dependency
+                .rename
+                .clone()
+                .unwrap_or_else(|| dependency.name.clone());
+            let mut extern_names = BTreeSet::from([name.replace('-', "_")]);
+            if dependency.rename.is_none() {
+                extern_names.extend(
+                    metadata
+                        .packages
+                        .iter()
+                        .filter(|p| p.name == dependency.name)
+                        .filter_map(lib_name),
+                );
+            }
+            let items: Option<BTreeSet<String>> = exposed.as_ref().map(|exposed| {
+                extern_names
+                    .iter()
+                    .filter_map(|n| exposed.get(n))
+                    .flatten()
+                    .cloned()
+                    .collect()
+            });
+            let declared_public = declared.get(&name).copied();
+            let is_exposed = items.as_ref().map(|items| !items.is_empty());
+            let recommendation = match (declared_public.unwrap_or(false), is_exposed) {
+                (false, Some(true)) => Some("declare-public"),
+                (true, Some(false)) => Some("declare-private"),
+                _ => None,
+            };
+            if recommendation.is_some() {
+                flagged += 1;
+            }
+            dependencies.push(PublicDependency {
+                name,
+                package: dependency.name.clone(),
+                declared_public,
+                exposed: is_exposed,
+                exposed_items: items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .take(ITEM_LIMIT)
+                    .collect(),
+                recommendation,
+            });
+        }
+        dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+        members.push(MemberPublicDependencies {
+            package: package.name.clone(),
+            rustdoc_json: exposed
+                .is_some()
+                .then(|| rustdoc_path.display().to_string()),
+            dependencies,
+        });
+    }
+    Ok(PublicDependencyReport { members, flagged })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_exposed_crates() {
+        let rustdoc = json!({
+            "index": {
+                "1": {
+                    "crate_id": 0,
+                    "name": "parse",
+                    "inner": { "function": { "sig": { "output": {
+                        "resolved_path": { "path": "Value", "id": 10, "args": null }
+                    } } } }
+                },
+                "2": {
+                    "crate_id": 0,
+                    "name": "Error",
+                    "inner": { "use": { "source": "anyhow::Error", "id": 11 } }
+                },
+                "3": {
+                    "crate_id": 0,
+                    "name": "helper",
+                    "inner": { "function": { "sig": { "output": {
+                        "resolved_path": { "path": "String", "id": 12, "args": null }
+                    } } } }
+                },
+            },
+            "paths": {
+                "10": { "crate_id": 1, "path": ["serde_json", "Value"] },
+                "11": { "crate_id": 2, "path": ["anyhow", "Error"] },
+                "12": { "crate_id": 3, "path": ["alloc", "string", "String"] },
+            },
+            "external_crates": {
+                "1": { "name": "serde_json" },
+                "2": { "name": "anyhow" },
+                "3": { "name": "alloc" },
+            },
+        });
+        let exposed = exposed_crates(&rustdoc);
+        assert_eq!(
+            exposed.keys().collect::<Vec<_>>(),
+            ["alloc", "anyhow", "serde_json"]
+        );
+        assert!(exposed["serde_json"].contains("parse"));
+        assert!(exposed["anyhow"].contains("Error"));
+    }
+}
@@ -0,0 +1,623 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use mcp_attr::Result;
+use serde_json::Value;
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// JSONPath (RFC 9535) のセグメント
+#[derive(Debug, PartialEq)]
+enum Segment {
+    /// `.name`、`[...]`
+    Child(Vec<Selector>),
+    /// `..name`、`..[...]`
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Filter),
+}
+
+#[derive(Debug, PartialEq)]
+enum Filter {
+    Or(Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    /// 比較演算子のない `@.name` (存在するかどうか)
+    Exists(Operand),
+    Compare(Operand, CompareOp, Operand),
+}
+
+#[derive(Debug, PartialEq)]
+enum Operand {
+    /// `@` から始まるパス
+    Current(Vec<Segment>),
+    /// `$` から始まるパス
+    Root(Vec<Segment>),
+    Literal(Value),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+type ParseResult<T> = std::result::Result<T, String>;
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.text.len() - trimmed.len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> ParseResult<()> {
+        self.skip_whitespace();
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{token}`")))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> String {
+        match self.rest().chars().next() {
+            Some(c) => format!("expected {expected} at position {}, found `{c}`", self.pos),
+            None => format!("expected {expected} at the end of the query"),
+        }
+    }
+
+    /// 先頭の `$` または `@` に続くセグメントの列
+    fn segments(&mut self) -> ParseResult<Vec<Segment>> {
+        let mut segments = Vec::new();
+        loop {
+            if self.eat("..") {
+                let selectors = if self.rest().starts_with('[') {
+                    self.bracket()?
+                } else {
+                    vec![self.dot_selector()?]
+                };
+                segments.push(Segment::Descendant(selectors));
+            } else if self.eat(".") {
+                segments.push(Segment::Child(vec![self.dot_selector()?]));
+            } else if self.rest().starts_with('[') {
+                segments.push(Segment::Child(self.bracket()?));
+            } else {
+                return Ok(segments);
+            }
+        }
+    }
+
+    /// `.` に続く名前または `*`
+    fn dot_selector(&mut self) -> ParseResult<Selector> {
+        if self.eat("*") {
+            return Ok(Selector::Wildcard);
+        }
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.unexpected("a member name"));
+        }
+        let name = self.rest()[..len].to_string();
+        self.pos += len;
+        Ok(Selector::Name(name))
+    }
+
+    /// `[` から `]` までのセレクターの列
+    fn bracket(&mut self) -> ParseResult<Vec<Selector>> {
+        self.expect("[")?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(selectors);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn selector(&mut self) -> ParseResult<Selector> {
+        if self.eat("*") {
+            return Ok(Selector::Wildcard);
+        }
+        if self.eat("?") {
+            self.skip_whitespace();
+            return Ok(Selector::Filter(self.or()?));
+        }
+        if self.rest().starts_with(['\'', '"']) {
+            return Ok(Selector::Name(self.string()?));
+        }
+        let start = self.integer()?;
+        self.skip_whitespace();
+        if !self.eat(":") {
+            return match start {
+                Some(index) => Ok(Selector::Index(index)),
+                None => Err(self.unexpected("a selector")),
+            };
+        }
+        self.skip_whitespace();
+        let end = self.integer()?;
+        self.skip_whitespace();
+        let step = if self.eat(":") {
+            self.skip_whitespace();
+            self.integer()?
+        } else {
+            None
+        };
+        Ok(Selector::Slice(start, end, step))
+    }
+
+    fn integer(&mut self) -> ParseResult<Option<i64>> {
+        let negative = self.rest().starts_with('-');
+        let digits = self.rest()[negative as usize..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest().len() - negative as usize);
+        if digits == 0 {
+            return Ok(None);
+        }
+        let len = negative as usize + digits;
+        let text = &self.rest()[..len];
+        let value = text
+            .parse()
+            .map_err(|_| format!("integer `{text}` is out of range"))?;
+        self.pos += len;
+        Ok(Some(value))
+    }
+
+    /// `'...'` または `"..."` の文字列リテラル
+    fn string(&mut self) -> ParseResult<String> {
+        let quote = self.rest().chars().next().unwrap_or('"');
+        self.pos += 1;
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.pos += i + 1;
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, c)) => value.push(c),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        Err("unterminated string literal".to_string())
+    }
+
+    fn or(&mut self) -> ParseResult<Filter> {
+        let mut filter = self.and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("||") {
+                return Ok(filter);
+            }
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+    }
+
+    fn and(&mut self) -> ParseResult<Filter> {
+        let mut filter = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("&&") {
+                return Ok(filter);
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> ParseResult<Filter> {
+        self.skip_whitespace();
+        if self.rest().starts_with('!') && !self.rest().starts_with("!=") {
+            self.pos += 1;
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let filter = self.or()?;
+            self.expect(")")?;
+            return Ok(filter);
+        }
+        let left = self.operand()?;
+        self.skip_whitespace();
+        let op = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ]
+        .into_iter()
+        .find(|(token, _)| self.rest().starts_with(token));
+        let Some((token, op)) = op else {
+            return match left {
+                Operand::Literal(_) => Err(self.unexpected("a comparison operator")),
+                left => Ok(Filter::Exists(left)),
+            };
+        };
+        self.pos += token.len();
+        self.skip_whitespace();
+        Ok(Filter::Compare(left, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> ParseResult<Operand> {
+        if self.eat("@") {
+            return Ok(Operand::Current(self.segments()?));
+        }
+        if self.eat("$") {
+            return Ok(Operand::Root(self.segments()?));
+        }
+        if self.rest().starts_with(['\'', '"']) {
+            return Ok(Operand::Literal(Value::String(self.string()?)));
+        }
+        for (token, value) in [
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+            ("null", Value::Null),
+        ] {
+            if self.eat(token) {
+                return Ok(Operand::Literal(value));
+            }
+        }
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(self.rest().len());
+        match self.rest()[..len].parse::<serde_json::Number>() {
+            Ok(number) if len > 0 => {
+                self.pos += len;
+                Ok(Operand::Literal(Value::Number(number)))
+            }
+            _ => Err(self.unexpected("`@`, `$` or a literal")),
+        }
+    }
+}
+
+fn parse(query: &str) -> ParseResult<Vec<Segment>> {
+    let mut parser = Parser {
+        text: query.trim(),
+        pos: 0,
+    };
+    if !parser.eat("$") {
+        return Err(parser.unexpected("`$`"));
+    }
+    let segments = parser.segments()?;
+    if !parser.rest().is_empty() {
+        return Err(parser.unexpected("`.`, `..` or `[`"));
+    }
+    Ok(segments)
+}
+
+/// 配列の添え字 (負の値は末尾から数える)
+fn normalize_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+fn slice(items: &[Value], start: Option<i64>, end: Option<i64>, step: Option<i64>) -> Vec<&Value> {
+    let len = items.len() as i64;
+    let step = step.unwrap_or(1);
+    let bound = |i: i64| if i < 0 { (i + len).max(0) } else { i.min(len) };
+    let mut selected = Vec::new();
+    match step.cmp(&0) {
+        Ordering::Greater => {
+            let mut i = start.map_or(0, bound);
+            let end = end.map_or(len, bound);
+            while i < end {
+                selected.push(&items[i as usize]);
+                i += step;
+            }
+        }
+        Ordering::Less => {
+            let bound = |i: i64| {
+                if i < 0 {
+                    (i + len).max(-1)
+                } else {
+                    i.min(len - 1)
+                }
+            };
+            let mut i = start.map_or(len - 1, bound);
+            let end = end.map_or(-1, bound);
+            while i > end {
+                selected.push(&items[i as usize]);
+                i += step;
+            }
+        }
+        Ordering::Equal => {}
+    }
+    selected
+}
+
+fn select<'a>(selector: &Selector, value: &'a Value, root: &'a Value, out: &mut Vec<&'a Value>) {
+    match (selector, value) {
+        (Selector::Name(name), Value::Object(map)) => out.extend(map.get(name)),
+        (Selector::Wildcard, Value::Object(map)) => out.extend(map.values()),
+        (Selector::Wildcard, Value::Array(items)) => out.extend(items),
+        (Selector::Index(index), Value::Array(items)) => {
+            out.extend(normalize_index(*index, items.len()).map(|i| &items[i]))
+        }
+        (Selector::Slice(start, end, step), Value::Array(items)) => {
+            out.extend(slice(items, *start, *end, *step))
+        }
+        (Selector::Filter(filter), Value::Object(map)) => {
+            out.extend(map.values().filter(|v| matches(filter, v, root)))
+        }
+        (Selector::Filter(filter), Value::Array(items)) => {
+            out.extend(items.iter().filter(|v| matches(filter, v, root)))
+        }
+        _ => {}
+    }
+}
+
+fn descendants<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    out.push(value);
+    match value {
+        Value::Object(map) => map.values().for_each(|v| descendants(v, out)),
+        Value::Array(items) => items.iter().for_each(|v| descendants(v, out)),
+        _ => {}
+    }
+}
+
+fn evaluate<'a>(segments: &[Segment], value: &'a Value, root: &'a Value) -> Vec<&'a Value> {
+    let mut nodes = vec![value];
+    for segment in segments {
+        let mut next = Vec::new();
+        for node in nodes {
+            match segment {
+                Segment::Child(selectors) => {
+                    for selector in selectors {
+                        select(selector, node, root, &mut next);
+                    }
+                }
+                Segment::Descendant(selectors) => {
+                    let mut all = Vec::new();
+                    descendants(node, &mut all);
+                    for descendant in all {
+                        for selector in selectors {
+                            select(selector, descendant, root, &mut next);
+                        }
+                    }
+                }
+            }
+        }
+        nodes = next;
+    }
+    nodes
+}
+
+/// 比較に使う値 (パスが 0 個または複数の値を選んだ場合は `None`)
+fn operand_value<'a>(
+    operand: &'a Operand,
+    current: &'a Value,
+    root: &'a Value,
+) -> Option<&'a Value> {
+    let nodes = match operand {
+        Operand::Literal(value) => return Some(value),
+        Operand::Current(segments) => evaluate(segments, current, root),
+        Operand::Root(segments) => evaluate(segments, root, root),
+    };
+    match nodes.as_slice() {
+        [value] => Some(value),
+        _ => None,
+    }
+}
+
+fn compare(left: Option<&Value>, op: CompareOp, right: Option<&Value>) -> bool {
+    let ordering = match (left, right) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+        (Some(a), Some(b)) => (a == b).then_some(Ordering::Equal),
+        (None, None) => Some(Ordering::Equal),
+        _ => None,
+    };
+    match op {
+        CompareOp::Eq => ordering == Some(Ordering::Equal),
+        CompareOp::Ne => ordering != Some(Ordering::Equal),
+        CompareOp::Lt => ordering == Some(Ordering::Less) && is_ordered(left),
+        CompareOp::Le => ordering.is_some_and(|o| o != Ordering::Greater) && is_ordered(left),
+        CompareOp::Gt => ordering == Some(Ordering::Greater) && is_ordered(left),
+        CompareOp::Ge => ordering.is_some_and(|o| o != Ordering::Less) && is_ordered(left),
+    }
+}
+
+/// 大小を比較できる値 (数値と文字列) かどうか
+fn is_ordered(value: Option<&Value>) -> bool {
+    matches!(value, Some(Value::Number(_) | Value::String(_)))
+}
+
+fn matches(filter: &Filter, current: &Value, root: &Value) -> bool {
+    match filter {
+        Filter::Or(a, b) => matches(a, current, root) || matches(b, current, root),
+        Filter::And(a, b) => matches(a, current, root) && matches(b, current, root),
+        Filter::Not(filter) => !matches(filter, current, root),
+        Filter::Exists(operand) => match operand {
+            Operand::Current(segments) => !evaluate(segments, current, root).is_empty(),
+            Operand::Root(segments) => !evaluate(segments, root, root).is_empty(),
+            Operand::Literal(_) => true,
+        },
+        Filter::Compare(left, op, right) => compare(
+            operand_value(left, current, root),
+            *op,
+            operand_value(right, current, root),
+        ),
+    }
+}
+
+/// JSONPath のクエリに一致する値を、文書内の順に返す
+///
+/// RFC 9535 のうち、名前、`*`、添え字、スライス、`..`、フィルター (`?@.name == 'serde'`、`&&`、`||`、`!`、比較演算子) に対応する。
+/// フィルターは `?(...)` のように括弧で囲んでもよい。
+pub fn query(value: &Value, query: &str) -> Result<Vec<Value>> {
+    match parse(query) {
+        Ok(segments) => Ok(evaluate(&segments, value, value)
+            .into_iter()
+            .cloned()
+            .collect()),
+        Err(e) => bail_kind!(ErrorKind::InvalidQuery, query, e),
+    }
+}
+
+/// 残すフィールドのパスの木
+#[derive(Default)]
+struct FieldTree {
+    children: BTreeMap<String, FieldTree>,
+    /// 値全体を残すかどうか (`packages` と `packages.name` の両方を指定した場合は `packages` 全体を残す)
+    whole: bool,
+}
+
+impl FieldTree {
+    fn prune(&self, value: Value) -> Value {
+        if self.whole {
+            return value;
+        }
+        match value {
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .filter_map(|(key, value)| {
+                        let tree = self.children.get(&key)?;
+                        Some((key, tree.prune(value)))
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.into_iter().map(|v| self.prune(v)).collect()),
+            value => value,
+        }
+    }
+}
+
+/// `,` で区切ったフィールドのパス (`packages.name,workspace_members` など) に一致するフィールドだけを残す
+///
+/// 配列は要素ごとに同じパスを適用する (`packages.name` は各パッケージの `name` を残す)。
+/// 存在しないフィールドは無視し、パスを 1 つも指定しない場合は値全体を返す。
+pub fn select_fields(value: Value, fields: &str) -> Value {
+    let mut tree = FieldTree::default();
+    let mut any = false;
+    for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let mut node = &mut tree;
+        for key in field.split('.') {
+            node = node.children.entry(key.to_string()).or_default();
+        }
+        node.whole = true;
+        any = true;
+    }
+    if !any {
+        return value;
+    }
+    tree.prune(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let value = json!({
+            "packages": [
+                { "name": "serde", "version": "1.0.200", "edition": "2018", "features": { "std": [] } },
+                { "name": "toml", "version": "0.8.0", "edition": "2021" },
+                { "name": "app", "version": "0.2.0", "edition": "2021", "source": null },
+            ],
+            "workspace_members": ["app"],
+        });
+        let q = |text: &str| query(&value, text).unwrap();
+        assert_eq!(
+            q("$.packages[?(@.name==\"serde\")].version"),
+            [json!("1.0.200")]
+        );
+        assert_eq!(
+            q("$.packages[?@.name == 'serde'].version"),
+            q("$.packages[0].version")
+        );
+        assert_eq!(q("$.packages[-1].name"), [json!("app")]);
+        assert_eq!(q("$.packages[0:2].name"), [json!("serde"), json!("toml")]);
+        assert_eq!(q("$.packages[::-1].name")[0], json!("app"));
+        assert_eq!(q("$['workspace_members'][*]"), [json!("app")]);
+        assert_eq!(q("$..std"), [json!([])]);
+        assert_eq!(q("$.packages[?@.features].name"), [json!("serde")]);
+        assert_eq!(
+            q("$.packages[?@.edition == '2021' && !(@.name == $.workspace_members[0])].name"),
+            [json!("toml")]
+        );
+        assert_eq!(
+            q("$.packages[?@.edition < '2021' || @.version >= '0.8'].name"),
+            [json!("serde"), json!("toml")]
+        );
+        assert_eq!(q("$.packages[?@.source == null].name"), [json!("app")]);
+        assert_eq!(q("$.packages[0,2].name"), [json!("serde"), json!("app")]);
+        assert_eq!(q("$").len(), 1);
+        assert!(q("$.missing").is_empty());
+
+        for invalid in ["packages", "$.packages[", "$.packages[?@.name ==]", "$['a"] {
+            assert!(query(&value, invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_select_fields() {
+        let value = json!({
+            "packages": [
+                { "name": "serde", "version": "1.0.200", "targets": [{ "name": "serde", "kind": ["lib"] }] },
+                { "name": "app", "version": "0.2.0", "targets": [] },
+            ],
+            "workspace_members": ["app"],
+            "resolve": null,
+        });
+        assert_eq!(
+            select_fields(
+                value.clone(),
+                "packages.name, packages.targets.kind,workspace_members"
+            ),
+            json!({
+                "packages": [
+                    { "name": "serde", "targets": [{ "kind": ["lib"] }] },
+                    { "name": "app", "targets": [] },
+                ],
+                "workspace_members": ["app"],
+            })
+        );
+        assert_eq!(
+            select_fields(value.clone(), "packages.name,packages,missing.field"),
+            json!({ "packages": value["packages"] })
+        );
+        assert_eq!(select_fields(value.clone(), " , "), value);
+    }
+}
@@ -0,0 +1,234 @@
+use mcp_attr::Result;
+use serde_json::{Map, Value};
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// ツールが返す構造化データの出力形式
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ValueFormat {
+    /// 字下げした JSON
+    Json,
+    Toml,
+    /// 配列を表に、オブジェクトをフィールドと値の表にした Markdown
+    MarkdownTable,
+    /// 空白を含まない 1 行の JSON
+    Compact,
+}
+
+impl ValueFormat {
+    /// 指定できる形式の名前 (エラーメッセージ用)
+    pub const NAMES: &str = "json, toml, markdown-table, compact";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "markdown-table" => Some(Self::MarkdownTable),
+            "compact" => Some(Self::Compact),
+            _ => None,
+        }
+    }
+
+    /// 出力形式を解析する (省略時は `json`)
+    pub fn parse(format: Option<&str>) -> Result<Self> {
+        let format = format.unwrap_or("json");
+        match Self::from_name(format) {
+            Some(format) => Ok(format),
+            None => bail_kind!(ErrorKind::UnknownFormat, format, Self::NAMES),
+        }
+    }
+
+    /// JSON の値をこの形式の文字列にする (`name` はエラーメッセージ用の出力の名前)
+    pub fn render(self, value: &Value, name: &str) -> Result<String> {
+        let text = match self {
+            Self::Json => serde_json::to_string_pretty(value).map_err(|e| e.to_string()),
+            Self::Compact => serde_json::to_string(value).map_err(|e| e.to_string()),
+            Self::Toml => toml::to_string_pretty(&toml_value(value)).map_err(|e| e.to_string()),
+            Self::MarkdownTable => Ok(markdown(value)),
+        };
+        match text {
+            Ok(text) => Ok(text),
+            Err(e) => bail_kind!(ErrorKind::SerializeFailed, name, e),
+        }
+    }
+}
+
+/// TOML で表せる値にする (null のフィールドと要素を除き、トップレベルがテーブルでない場合は `items` に入れる)
+fn toml_value(value: &Value) -> Value {
+    fn strip(value: &Value) -> Option<Value> {
+        match value {
+            Value::Null => None,
+            Value::Array(items) => Some(Value::Array(items.iter().filter_map(strip).collect())),
+            Value::Object(map) => Some(Value::Object(
+                map.iter()
+                    .filter_map(|(k, v)| Some((k.clone(), strip(v)?)))
+                    .collect(),
+            )),
+            value => Some(value.clone()),
+        }
+    }
+    match strip(value) {
+        Some(value @ Value::Object(_)) => value,
+        Some(value) => Value::Object(Map::from_iter([("items".to_string(), value)])),
+        None => Value::Object(Map::new()),
+    }
+}
+
+/// 表のセルに入れる文字列 (入れ子の値は 1 行の JSON にする)
+fn cell(value: &Value) -> String {
+    fn text(value: &Value) -> String {
+        match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            Value::Array(items) if items.iter().all(is_scalar) => {
+                items.iter().map(text).collect::<Vec<_>>().join(", ")
+            }
+            Value::Bool(_) | Value::Number(_) => value.to_string(),
+            _ => format!("`{value}`"),
+        }
+    }
+    text(value)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+/// 表にできる配列 (要素がすべてオブジェクト) かどうか
+fn is_table(items: &[Value]) -> bool {
+    !items.is_empty() && items.iter().all(Value::is_object)
+}
+
+fn table(rows: &[Value]) -> String {
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        for key in row.as_object().into_iter().flat_map(|m| m.keys()) {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+    let mut out = format!("| {} |\n", columns.join(" | "));
+    out.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+    for row in rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|c| row.get(c).map(cell).unwrap_or_default())
+            .collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+/// JSON の値を Markdown の表にする
+///
+/// オブジェクトの配列は 1 要素 1 行の表に、オブジェクトはスカラーのフィールドをフィールドと値の表に、
+/// 入れ子のオブジェクトとオブジェクトの配列を見出し付きの節にする。
+fn markdown(value: &Value) -> String {
+    let mut out = String::new();
+    section(&mut out, value, 2);
+    out
+}
+
+fn section(out: &mut String, value: &Value, level: usize) {
+    match value {
+        Value::Array(items) if is_table(items) => out.push_str(&table(items)),
+        Value::Array(items) if items.iter().all(is_scalar) => {
+            for item in items {
+                out.push_str(&format!("- {}\n", cell(item)));
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                heading(out, level, &format!("[{i}]"));
+                section(out, item, level + 1);
+            }
+        }
+        Value::Object(map) => {
+            let (fields, sections): (Vec<_>, Vec<_>) = map.iter().partition(|(_, v)| match v {
+                Value::Object(_) => false,
+                Value::Array(items) => !is_table(items) && items.iter().all(is_scalar),
+                _ => true,
+            });
+            if !fields.is_empty() {
+                out.push_str("| Field | Value |\n| --- | --- |\n");
+                for (key, value) in fields {
+                    out.push_str(&format!(
+                        "| {} | {} |\n",
+                        key.replace('|', "\\|"),
+                        cell(value)
+                    ));
+                }
+            }
+            for (key, value) in sections {
+                heading(out, level, key);
+                section(out, value, level + 1);
+            }
+        }
+        value => {
+            out.push_str(&cell(value));
+            out.push('\n');
+        }
+    }
+}
+
+fn heading(out: &mut String, level: usize, title: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&format!("{} {title}\n\n", "#".repeat(level.min(6))));
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let value = json!({
+            "name": "app",
+            "license": null,
+            "keywords": ["cli", "a|b"],
+            "dependencies": [
+                { "name": "serde", "req": "^1.0" },
+                { "name": "toml", "optional": true },
+            ],
+        });
+        assert_eq!(
+            ValueFormat::Compact.render(&value, "test").unwrap(),
+            r#"{"dependencies":[{"name":"serde","req":"^1.0"},{"name":"toml","optional":true}],"keywords":["cli","a|b"],"license":null,"name":"app"}"#
+        );
+        let toml = ValueFormat::Toml.render(&value, "test").unwrap();
+        let parsed: toml::Table = toml.parse().unwrap();
+        assert_eq!(parsed["name"].as_str(), Some("app"));
+        assert!(!parsed.contains_key("license"));
+        assert_eq!(parsed["dependencies"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            ValueFormat::Toml.render(&json!([1, 2]), "test").unwrap(),
+            "items = [\n    1,\n    2,\n]\n"
+        );
+        assert_eq!(
+            ValueFormat::MarkdownTable.render(&value, "test").unwrap(),
+            "| Field | Value |\n\
+             | --- | --- |\n\
+             | keywords | cli, a\\|b |\n\
+             | license |  |\n\
+             | name | app |\n\
+             \n\
+             ## dependencies\n\
+             \n\
+             | name | req | optional |\n\
+             | --- | --- | --- |\n\
+             | serde | ^1.0 |  |\n\
+             | toml |  | true |\n"
+        );
+        assert!(ValueFormat::parse(Some("yaml")).is_err());
+        assert_eq!(ValueFormat::parse(None).unwrap(), ValueFormat::Json);
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use cargo_metadata::{Metadata, PackageId};
+
+use crate::detail::Detail;
+use crate::duplicates::find_duplicates;
+use crate::graph::Graph;
+use crate::summary::{direct_dependencies, project_name};
+
+/// Markdown の表のセル (`|` と改行をエスケープし、空の場合は `-` にする)
+fn cell(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        "-".to_string()
+    } else {
+        text.replace('|', "\\|")
+    }
+}
+
+/// 直接の依存関係として使われているパッケージの、要求、種類、依存元のメンバー
+#[derive(Default)]
+struct DirectUse<'a> {
+    reqs: BTreeSet<String>,
+    kinds: BTreeSet<&'static str>,
+    used_by: BTreeSet<&'a str>,
+}
+
+fn table(out: &mut String, header: &[&str], rows: &[Vec<String>]) {
+    out.push_str(&format!("| {} |\n", header.join(" | ")));
+    out.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+    for row in rows {
+        out.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+}
+
+/// リリースノートや設計文書にそのまま貼り付けられる、依存関係の Markdown のレポートを生成する
+///
+/// ワークスペースメンバーの一覧、直接の依存関係 (バージョン、ライセンス、説明) の表、複数のバージョンがあるクレートの表を含める。
+/// 直接の依存関係には、依存グラフで解決されたワークスペースメンバー以外のパッケージのみを含める。
+/// `detail` が `summary` の場合はワークスペースメンバーの一覧のみ、`normal` の場合は直接の依存関係の表までにする。
+pub fn markdown_report(metadata: &Metadata, include_dev: bool, detail: Detail) -> String {
+    let graph = Graph::new(metadata);
+    let members: HashSet<&PackageId> = graph.members().collect();
+    let mut out = format!("# Dependency report: {}\n\n", project_name(metadata));
+
+    let mut member_rows = Vec::new();
+    let mut direct: BTreeMap<&PackageId, DirectUse> = BTreeMap::new();
+    let mut ids: BTreeMap<&str, &PackageId> = BTreeMap::new();
+    for id in &members {
+        if let Some(package) = graph.package(id) {
+            ids.insert(&package.name, id);
+        }
+    }
+    for (name, id) in &ids {
+        let Some(package) = graph.package(id) else {
+            continue;
+        };
+        let dependencies = direct_dependencies(&graph, package);
+        let mut count = 0;
+        for dependency in &dependencies {
+            if dependency.kind == "dev" && !include_dev {
+                continue;
+            }
+            let Some(resolved) = graph.metadata.packages.iter().find(|p| {
+                dependency.pkgid.as_deref() == Some(p.id.repr.as_str()) && !members.contains(&p.id)
+            }) else {
+                continue;
+            };
+            count += 1;
+            let entry = direct.entry(&resolved.id).or_default();
+            entry.reqs.insert(dependency.req.clone());
+            entry.kinds.insert(dependency.kind);
+            entry.used_by.insert(name);
+        }
+        let kinds: Vec<&str> = package
+            .targets
+            .iter()
+            .flat_map(|t| t.kind.iter().map(|k| k.as_str()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        member_rows.push(vec![
+            format!("`{}`", name),
+            package.version.to_string(),
+            cell(package.license.as_deref().unwrap_or_default()),
+            cell(&kinds.join(", ")),
+            count.to_string(),
+            cell(package.description.as_deref().unwrap_or_default()),
+        ]);
+    }
+    out.push_str(&format!("## Workspace members ({})\n\n", member_rows.len()));
+    table(
+        &mut out,
+        &[
+            "Package",
+            "Version",
+            "License",
+            "Targets",
+            "Direct dependencies",
+            "Description",
+        ],
+        &member_rows,
+    );
+    if detail == Detail::Summary {
+        return out;
+    }
+
+    let mut direct_rows: Vec<(String, Vec<String>)> = direct
+        .iter()
+        .filter_map(|(id, usage)| {
+            let package = graph.package(id)?;
+            let reqs: Vec<&str> = usage.reqs.iter().map(String::as_str).collect();
+            let kinds: Vec<&str> = usage.kinds.iter().copied().collect();
+            let used_by: Vec<String> = usage.used_by.iter().map(|m| format!("`{m}`")).collect();
+            let name = match &package.repository {
+                Some(url) => format!("[`{}`]({url})", package.name),
+                None => format!("`{}`", package.name),
+            };
+            Some((
+                format!("{}@{}", package.name, package.version),
+                vec![
+                    name,
+                    cell(&reqs.join(", ")),
+                    package.version.to_string(),
+                    kinds.join(", "),
+                    cell(package.license.as_deref().unwrap_or_default()),
+                    used_by.join(", "),
+                    cell(package.description.as_deref().unwrap_or_default()),
+                ],
+            ))
+        })
+        .collect();
+    direct_rows.sort_by(|a, b| a.0.cmp(&b.0));
+    out.push_str(&format!(
+        "\n## Direct dependencies ({})\n\n",
+        direct_rows.len()
+    ));
+    if direct_rows.is_empty() {
+        out.push_str("No direct dependencies.\n");
+    } else {
+        let rows: Vec<Vec<String>> = direct_rows.into_iter().map(|(_, row)| row).collect();
+        table(
+            &mut out,
+            &[
+                "Crate",
+                "Requirement",
+                "Version",
+                "Kind",
+                "License",
+                "Used by",
+                "Description",
+            ],
+            &rows,
+        );
+    }
+    if detail == Detail::Normal {
+        return out;
+    }
+
+    let duplicates = find_duplicates(&graph, include_dev, 0);
+    out.push_str(&format!(
+        "\n## Duplicate versions ({})\n\n",
+        duplicates.len()
+    ));
+    if duplicates.is_empty() {
+        out.push_str("No crate is resolved to more than one version.\n");
+    } else {
+        let rows: Vec<Vec<String>> = duplicates
+            .iter()
+            .map(|duplicate| {
+                let versions: Vec<String> = duplicate
+                    .versions
+                    .iter()
+                    .map(|v| {
+                        let dependents: Vec<&str> =
+                            v.dependents.iter().map(String::as_str).collect();
+                        format!("{} (via {})", v.version, cell(&dependents.join(", ")))
+                    })
+                    .collect();
+                vec![
+                    format!("`{}`", duplicate.name),
+                    versions.join("<br>"),
+                    if duplicate.semver_incompatible {
+                        "yes".to_string()
+                    } else {
+                        "no".to_string()
+                    },
+                ]
+            })
+            .collect();
+        table(
+            &mut out,
+            &["Crate", "Versions", "Semver-incompatible"],
+            &rows,
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_markdown_report() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let report = markdown_report(&metadata, false, Detail::Full);
+        assert!(report.starts_with("# Dependency report: fixture\n\n## Workspace members (2)\n"));
+        assert!(report.contains("| `fixture-core` | 0.1.0 | MIT OR Apache-2.0 |"));
+        assert!(report.contains("## Direct dependencies (0)\n\nNo direct dependencies.\n"));
+        assert!(report.contains("## Duplicate versions (0)\n"));
+        let summary = markdown_report(&metadata, false, Detail::Summary);
+        assert!(!summary.contains("## Direct dependencies"));
+        let normal = markdown_report(&metadata, false, Detail::Normal);
+        assert!(normal.contains("## Direct dependencies"));
+        assert!(!normal.contains("## Duplicate versions"));
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let report = markdown_report(&metadata, false, Detail::Full);
+        let serde = report
+            .lines()
+            .find(|l| l.starts_with("| [`serde`]("))
+            .unwrap();
+        assert!(serde.contains("| MIT OR Apache-2.0 |"));
+        assert!(serde.contains("| `mcp-attr-example-cargo-metadata` |"));
+    }
+}
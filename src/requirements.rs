@@ -0,0 +1,482 @@
+use std::collections::HashSet;
+
+use cargo_metadata::semver::{Comparator, Op, Version, VersionReq};
+use cargo_metadata::{Dependency, DependencyKind, Metadata, Package, PackageId};
+use serde::Serialize;
+
+use crate::findings::{self, Finding, FindingSet, Level};
+use crate::graph::Graph;
+use crate::lint::is_publishable;
+use crate::policy::compat_key;
+
+/// プレリリース版の依存関係
+#[derive(Serialize)]
+pub struct PrereleaseReport {
+    /// プレリリース版に解決されたパッケージ
+    pub resolved: Vec<PrereleasePackage>,
+    /// プレリリース版を明示的に許可している、ワークスペースメンバーの要求バージョン
+    pub requirements: Vec<PrereleaseRequirement>,
+}
+
+#[derive(Serialize)]
+pub struct PrereleasePackage {
+    /// `name@version`
+    pub package: String,
+    pub pkgid: String,
+    /// ワークスペースメンバーが直接依存しているかどうか
+    pub direct: bool,
+    /// このパッケージに直接依存しているパッケージ (`name@version`)
+    pub dependents: Vec<String>,
+    /// ワークスペースメンバーからこのパッケージに至る経路の例 (`name@version` の列)
+    pub path: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PrereleaseRequirement {
+    /// 依存しているワークスペースメンバー
+    pub member: String,
+    pub name: String,
+    /// `normal`、`dev`、`build`
+    pub kind: &'static str,
+    pub req: String,
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+/// 要求バージョンにプレリリース版を含む比較子があるかどうか (semver ではその場合のみプレリリース版が選ばれる)
+fn allows_prerelease(req: &VersionReq) -> bool {
+    req.comparators.iter().any(|c| !c.pre.is_empty())
+}
+
+/// ワークスペースメンバーから到達できるパッケージのうちプレリリース版に解決されたものと、
+/// プレリリース版を許可しているワークスペースメンバーの要求バージョンを返す
+pub fn prerelease_report(metadata: &Metadata) -> PrereleaseReport {
+    let graph = Graph::new(metadata);
+    let members: HashSet<&PackageId> = graph.members().collect();
+    let mut reachable: HashSet<&PackageId> = HashSet::new();
+    for member in &members {
+        reachable.extend(graph.reachable(member, true));
+    }
+
+    let mut resolved: Vec<PrereleasePackage> = reachable
+        .iter()
+        .filter(|id| !members.contains(*id))
+        .filter_map(|id| graph.package(id))
+        .filter(|p| !p.version.pre.is_empty())
+        .map(|package| {
+            let mut dependents: Vec<String> = reachable
+                .iter()
+                .filter(|id| graph.dependencies(id, true).any(|d| *d == package.id))
+                .map(|id| graph.label(id))
+                .collect();
+            dependents.sort();
+            PrereleasePackage {
+                package: graph.label(&package.id),
+                pkgid: package.id.repr.clone(),
+                direct: members
+                    .iter()
+                    .any(|m| graph.dependencies(m, true).any(|d| *d == package.id)),
+                dependents,
+                path: graph.path_labels(&package.id, true),
+            }
+        })
+        .collect();
+    resolved.sort_by(|a, b| a.package.cmp(&b.package));
+
+    let mut requirements = Vec::new();
+    for package in metadata.workspace_packages() {
+        for dependency in &package.dependencies {
+            if allows_prerelease(&dependency.req) {
+                requirements.push(PrereleaseRequirement {
+                    member: package.name.clone(),
+                    name: dependency.name.clone(),
+                    kind: kind_name(dependency.kind),
+                    req: dependency.req.to_string(),
+                });
+            }
+        }
+    }
+    PrereleaseReport {
+        resolved,
+        requirements,
+    }
+}
+
+/// 緩すぎる要求バージョンの種類
+#[derive(Debug, PartialEq)]
+enum Looseness {
+    /// `*`
+    Wildcard,
+    /// 上限のない `>=` や `>`
+    Unbounded,
+    /// 複数の semver 互換な範囲にまたがる (`0`、`0.*`、`>=1, <3` など)
+    Broad,
+}
+
+/// 比較子のバージョン (省略された部分は 0 とする)
+fn comparator_version(comparator: &Comparator) -> Version {
+    Version {
+        major: comparator.major,
+        minor: comparator.minor.unwrap_or(0),
+        patch: comparator.patch.unwrap_or(0),
+        pre: comparator.pre.clone(),
+        build: Default::default(),
+    }
+}
+
+/// `version` と semver 互換な範囲より後の、最初のバージョン
+fn next_incompatible(version: &Version) -> Version {
+    if version.major > 0 {
+        Version::new(version.major + 1, 0, 0)
+    } else if version.minor > 0 {
+        Version::new(0, version.minor + 1, 0)
+    } else {
+        Version::new(0, 0, version.patch + 1)
+    }
+}
+
+/// 要求バージョンが緩すぎるかどうか
+fn looseness(req: &VersionReq) -> Option<Looseness> {
+    if req.comparators.is_empty() {
+        return Some(Looseness::Wildcard);
+    }
+    let lower = req
+        .comparators
+        .iter()
+        .filter(|c| matches!(c.op, Op::Greater | Op::GreaterEq))
+        .map(comparator_version)
+        .max();
+    let upper = req
+        .comparators
+        .iter()
+        .filter(|c| matches!(c.op, Op::Less | Op::LessEq))
+        .map(|c| (comparator_version(c), c.op == Op::LessEq))
+        .min();
+    let bounded = req
+        .comparators
+        .iter()
+        .any(|c| !matches!(c.op, Op::Greater | Op::GreaterEq | Op::Less | Op::LessEq));
+    if lower.is_some() && upper.is_none() && !bounded {
+        return Some(Looseness::Unbounded);
+    }
+    // `0`、`^0`、`~0`、`0.*`、`=0` は 0.x の全ての範囲にまたがる
+    let major_zero_only = req.comparators.iter().any(|c| {
+        c.major == 0
+            && c.minor.is_none()
+            && matches!(c.op, Op::Caret | Op::Tilde | Op::Wildcard | Op::Exact)
+    });
+    if major_zero_only {
+        return Some(Looseness::Broad);
+    }
+    if let (Some(lower), Some((upper, inclusive))) = (lower, upper)
+        && !bounded
+    {
+        let limit = next_incompatible(&lower);
+        if upper > limit || (inclusive && upper == limit) {
+            return Some(Looseness::Broad);
+        }
+    }
+    None
+}
+
+/// ワークスペースメンバーの crates.io などのレジストリの依存関係のうち、要求バージョンが緩すぎるものを検査する
+///
+/// `*` は crates.io への公開時に拒否されるため、公開可能なパッケージではエラーにする。
+pub fn check_requirements(metadata: &Metadata) -> FindingSet {
+    let mut set = FindingSet::new("requirement-check");
+    set.rule(
+        "requirement/wildcard",
+        "Registry dependencies should not use the `*` version requirement",
+        Some("https://doc.rust-lang.org/cargo/faq.html#can-libraries-use--as-a-version-for-their-dependencies"),
+    );
+    set.rule(
+        "requirement/unbounded",
+        "Version requirements should have an upper bound",
+        Some("https://doc.rust-lang.org/cargo/reference/specifying-dependencies.html#comparison-requirements"),
+    );
+    set.rule(
+        "requirement/broad",
+        "Version requirements should not span multiple semver-incompatible ranges",
+        Some("https://doc.rust-lang.org/cargo/reference/semver.html"),
+    );
+
+    for package in metadata.workspace_packages() {
+        let manifest = &package.manifest_path;
+        let publishable = is_publishable(package);
+        for dep in &package.dependencies {
+            let is_registry = dep
+                .source
+                .as_ref()
+                .is_some_and(|s| s.starts_with("registry+") || s.starts_with("sparse+"));
+            if !is_registry {
+                continue;
+            }
+            let Some(looseness) = looseness(&dep.req) else {
+                continue;
+            };
+            let (rule, level, description) = match looseness {
+                Looseness::Wildcard => (
+                    "requirement/wildcard",
+                    if publishable {
+                        Level::Error
+                    } else {
+                        Level::Warning
+                    },
+                    "the wildcard requirement",
+                ),
+                Looseness::Unbounded => (
+                    "requirement/unbounded",
+                    Level::Warning,
+                    "the unbounded requirement",
+                ),
+                Looseness::Broad => ("requirement/broad", Level::Warning, "the broad requirement"),
+            };
+            let key = dep.rename.as_ref().unwrap_or(&dep.name);
+            set.push(Finding {
+                rule: rule.to_string(),
+                level,
+                message: format!(
+                    "{} depends on `{}` ({}) with {description} `{}`",
+                    package.name,
+                    dep.name,
+                    kind_name(dep.kind),
+                    dep.req
+                ),
+                package: Some(format!("{}@{}", package.name, package.version)),
+                file: Some(manifest.clone()),
+                line: findings::find_key_line(manifest, key),
+                dependency_path: Vec::new(),
+            });
+        }
+    }
+    set
+}
+
+/// `=` で固定された依存関係
+#[derive(Serialize)]
+pub struct PinnedDependency {
+    /// 依存しているパッケージ (`name@version`)
+    pub dependent: String,
+    pub workspace_member: bool,
+    pub name: String,
+    /// `normal`、`dev`、`build`
+    pub kind: &'static str,
+    pub req: String,
+    /// 固定されたバージョン (解決されていない場合は要求バージョンから求めたもの)
+    pub pinned: String,
+    /// 他の要求バージョンと衝突しているかどうか
+    pub conflicting: bool,
+    /// 依存グラフ内の、同じクレートに対する他の要求バージョン
+    pub other_requirements: Vec<OtherRequirement>,
+}
+
+#[derive(Serialize)]
+pub struct OtherRequirement {
+    /// `name@version`
+    pub dependent: String,
+    pub req: String,
+    /// 固定されたバージョンを満たすかどうか
+    pub accepts_pin: bool,
+    /// 固定されたバージョンと semver 互換な範囲を要求しているのに、固定されたバージョンを満たさないかどうか
+    ///
+    /// cargo は semver 互換な範囲ごとに 1 つのバージョンしか選べないため、この組み合わせは解決に失敗する。
+    pub conflict: bool,
+}
+
+/// 要求バージョンの下限 (下限のない `<` や `*` だけの場合は `None`)
+fn lower_bound(req: &VersionReq) -> Option<Version> {
+    req.comparators
+        .iter()
+        .filter(|c| !matches!(c.op, Op::Less | Op::LessEq))
+        .map(comparator_version)
+        .max()
+}
+
+/// `dependency` で解決されたパッケージ
+fn resolved_dependency<'a>(
+    graph: &Graph<'a>,
+    dependent: &Package,
+    dependency: &Dependency,
+) -> Option<&'a Package> {
+    graph
+        .dependencies(&dependent.id, true)
+        .filter_map(|id| graph.package(id))
+        .find(|p| p.name == dependency.name && dependency.req.matches(&p.version))
+}
+
+/// 依存グラフ内のパッケージの依存関係のうち `=` で固定されたものと、同じクレートに対する他の要求バージョンとの衝突を返す
+pub fn pinned_dependencies(metadata: &Metadata) -> Vec<PinnedDependency> {
+    let graph = Graph::new(metadata);
+    let members: HashSet<&PackageId> = graph.members().collect();
+    let declarations: Vec<(&Package, &Dependency)> = metadata
+        .packages
+        .iter()
+        .flat_map(|p| p.dependencies.iter().map(move |d| (p, d)))
+        .collect();
+
+    let mut pins = Vec::new();
+    for (package, dependency) in &declarations {
+        let Some(exact) = dependency
+            .req
+            .comparators
+            .iter()
+            .find(|c| c.op == Op::Exact)
+        else {
+            continue;
+        };
+        let pinned = resolved_dependency(&graph, package, dependency)
+            .map(|p| p.version.clone())
+            .unwrap_or_else(|| comparator_version(exact));
+        let other_requirements: Vec<OtherRequirement> = declarations
+            .iter()
+            .filter(|(_, d)| d.name == dependency.name && !std::ptr::eq(*d, *dependency))
+            .map(|(p, d)| {
+                let accepts_pin = d.req.matches(&pinned);
+                OtherRequirement {
+                    dependent: graph.label(&p.id),
+                    req: d.req.to_string(),
+                    accepts_pin,
+                    conflict: !accepts_pin
+                        && lower_bound(&d.req)
+                            .is_some_and(|v| compat_key(&v) == compat_key(&pinned)),
+                }
+            })
+            .collect();
+        pins.push(PinnedDependency {
+            dependent: graph.label(&package.id),
+            workspace_member: members.contains(&package.id),
+            name: dependency.name.clone(),
+            kind: kind_name(dependency.kind),
+            req: dependency.req.to_string(),
+            pinned: pinned.to_string(),
+            conflicting: other_requirements.iter().any(|r| r.conflict),
+            other_requirements,
+        });
+    }
+    pins.sort_by(|a, b| {
+        b.workspace_member
+            .cmp(&a.workspace_member)
+            .then_with(|| a.dependent.cmp(&b.dependent))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    pins
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    /// fixture-core をワークスペース外のプレリリース版の依存にしたメタデータ
+    fn prerelease_fixture(req: &str) -> Metadata {
+        let mut metadata: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        metadata["workspace_members"] = serde_json::json!([metadata["packages"][0]["id"]]);
+        metadata["packages"][1]["version"] = "0.1.0-alpha.3".into();
+        metadata["packages"][0]["dependencies"][0]["req"] = req.into();
+        serde_json::from_value(metadata).unwrap()
+    }
+
+    #[test]
+    fn test_prerelease_report() {
+        let report = prerelease_report(&prerelease_fixture("^0.1.0-alpha.1"));
+        assert_eq!(report.resolved.len(), 1);
+        let core = &report.resolved[0];
+        assert_eq!(core.package, "fixture-core@0.1.0-alpha.3");
+        assert!(core.direct);
+        assert_eq!(core.dependents, ["app@0.2.0"]);
+        assert_eq!(core.path, ["app@0.2.0", "fixture-core@0.1.0-alpha.3"]);
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.requirements[0].req, "^0.1.0-alpha.1");
+
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let report = prerelease_report(&metadata);
+        assert!(report.resolved.is_empty());
+        assert!(report.requirements.is_empty());
+    }
+
+    #[test]
+    fn test_looseness() {
+        let looseness = |req: &str| looseness(&req.parse().unwrap());
+        assert_eq!(looseness("*"), Some(Looseness::Wildcard));
+        assert_eq!(looseness(">=1.0"), Some(Looseness::Unbounded));
+        assert_eq!(looseness(">0.3"), Some(Looseness::Unbounded));
+        assert_eq!(looseness("0"), Some(Looseness::Broad));
+        assert_eq!(looseness("0.*"), Some(Looseness::Broad));
+        assert_eq!(looseness(">=1, <3"), Some(Looseness::Broad));
+        assert_eq!(looseness(">=0.2, <=0.3.0"), Some(Looseness::Broad));
+        assert_eq!(looseness("1"), None);
+        assert_eq!(looseness("1.*"), None);
+        assert_eq!(looseness("0.3"), None);
+        assert_eq!(looseness("=1.2.3"), None);
+        assert_eq!(looseness(">=1.2, <2"), None);
+        assert_eq!(looseness(">=1.2, ^1.3"), None);
+    }
+
+    #[test]
+    fn test_check_requirements() {
+        let mut metadata: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let dependency = &mut metadata["packages"][0]["dependencies"][0];
+        dependency["source"] = "registry+https://github.com/rust-lang/crates.io-index".into();
+        dependency["req"] = "*".into();
+        let metadata: Metadata = serde_json::from_value(metadata).unwrap();
+        let set = check_requirements(&metadata);
+        assert_eq!(set.findings.len(), 1);
+        assert_eq!(set.findings[0].rule, "requirement/wildcard");
+        assert_eq!(set.findings[0].package.as_deref(), Some("app@0.2.0"));
+
+        // パス依存関係は要求バージョンを検査しない
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        assert!(check_requirements(&metadata).findings.is_empty());
+    }
+
+    #[test]
+    fn test_pinned_dependencies() {
+        let mut metadata: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        metadata["packages"][0]["dependencies"][0]["req"] = "=0.1.0".into();
+        let other = |req: &str| {
+            let mut dependency = metadata["packages"][0]["dependencies"][0].clone();
+            dependency["req"] = req.into();
+            dependency["kind"] = "dev".into();
+            dependency
+        };
+        let others = [other("^0.1.2"), other("^0.2"), other("<0.1")];
+        metadata["packages"][0]["dependencies"]
+            .as_array_mut()
+            .unwrap()
+            .extend(others);
+        let metadata: Metadata = serde_json::from_value(metadata).unwrap();
+
+        let pins = pinned_dependencies(&metadata);
+        assert_eq!(pins.len(), 1);
+        let pin = &pins[0];
+        assert_eq!(pin.dependent, "app@0.2.0");
+        assert!(pin.workspace_member);
+        assert_eq!(pin.pinned, "0.1.0");
+        assert!(pin.conflicting);
+        let conflicts: Vec<(&str, bool, bool)> = pin
+            .other_requirements
+            .iter()
+            .map(|r| (r.req.as_str(), r.accepts_pin, r.conflict))
+            .collect();
+        assert_eq!(
+            conflicts,
+            [
+                ("^0.1.2", false, true),
+                ("^0.2", false, false),
+                ("<0.1", false, false)
+            ]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+
+use cargo_metadata::{DependencyKind, Metadata, Node, NodeDep, Package, PackageId};
+use serde::Serialize;
+
+/// `cargo metadata` の resolve グラフ
+#[derive(Serialize)]
+pub struct ResolveGraph {
+    /// ルートパッケージの ID (仮想ワークスペースでは `null`)
+    pub root: Option<String>,
+    pub nodes: Vec<ResolveNode>,
+}
+
+#[derive(Serialize)]
+pub struct ResolveNode {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// 解決後に有効になったフィーチャー
+    pub features: Vec<String>,
+    pub deps: Vec<ResolveEdge>,
+}
+
+#[derive(Serialize)]
+pub struct ResolveEdge {
+    /// コードから参照するときのクレート名 (`rename` した場合は変更後の名前)
+    pub name: String,
+    /// 依存先のパッケージ ID
+    pub pkg: String,
+    pub kinds: Vec<ResolveEdgeKind>,
+}
+
+#[derive(Serialize)]
+pub struct ResolveEdgeKind {
+    /// `normal`、`build` または `dev`
+    pub kind: &'static str,
+    /// プラットフォーム固有の依存関係の場合の `cfg(...)` またはターゲットトリプル
+    pub target: Option<String>,
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+/// 辺を含めるかどうか (`include_dev` が `false` の場合、dev-dependencies としてのみの辺は含めない)
+fn is_included(dep: &NodeDep, include_dev: bool) -> bool {
+    include_dev
+        || dep.dep_kinds.is_empty()
+        || dep
+            .dep_kinds
+            .iter()
+            .any(|k| k.kind != DependencyKind::Development)
+}
+
+fn edge(dep: &NodeDep, include_dev: bool) -> ResolveEdge {
+    ResolveEdge {
+        name: dep.name.clone(),
+        pkg: dep.pkg.repr.clone(),
+        kinds: dep
+            .dep_kinds
+            .iter()
+            .filter(|k| include_dev || k.kind != DependencyKind::Development)
+            .map(|k| ResolveEdgeKind {
+                kind: kind_name(k.kind),
+                target: k.target.as_ref().map(|t| t.to_string()),
+            })
+            .collect(),
+    }
+}
+
+/// resolve グラフを返す
+///
+/// `roots` を指定した場合は、そこから到達できるノードだけを含める。
+/// `include_dev` が `false` の場合、dev-dependencies としてのみ依存している辺は含めない。
+pub fn resolve_graph(
+    metadata: &Metadata,
+    roots: Option<&[&PackageId]>,
+    include_dev: bool,
+) -> ResolveGraph {
+    let Some(resolve) = &metadata.resolve else {
+        return ResolveGraph {
+            root: None,
+            nodes: Vec::new(),
+        };
+    };
+    let nodes: HashMap<&PackageId, &Node> = resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+    let packages: HashMap<&PackageId, &Package> =
+        metadata.packages.iter().map(|p| (&p.id, p)).collect();
+
+    let included: Option<HashSet<&PackageId>> = roots.map(|roots| {
+        let mut visited: HashSet<&PackageId> = roots.iter().copied().collect();
+        let mut stack: Vec<&PackageId> = roots.to_vec();
+        while let Some(id) = stack.pop() {
+            for dep in nodes.get(id).into_iter().flat_map(|n| &n.deps) {
+                if is_included(dep, include_dev) && visited.insert(&dep.pkg) {
+                    stack.push(&dep.pkg);
+                }
+            }
+        }
+        visited
+    });
+
+    let mut result: Vec<ResolveNode> = resolve
+        .nodes
+        .iter()
+        .filter(|n| included.as_ref().is_none_or(|set| set.contains(&n.id)))
+        .map(|node| {
+            let package = packages.get(&node.id);
+            ResolveNode {
+                id: node.id.repr.clone(),
+                name: package.map(|p| p.name.clone()).unwrap_or_default(),
+                version: package.map(|p| p.version.to_string()).unwrap_or_default(),
+                features: node.features.clone(),
+                deps: node
+                    .deps
+                    .iter()
+                    .filter(|dep| is_included(dep, include_dev))
+                    .map(|dep| edge(dep, include_dev))
+                    .collect(),
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    ResolveGraph {
+        root: resolve.root.as_ref().map(|id| id.repr.clone()),
+        nodes: result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_graph() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let graph = resolve_graph(&metadata, None, false);
+        assert_eq!(graph.root, None);
+        let names: Vec<&str> = graph.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["app", "fixture-core"]);
+        let app = &graph.nodes[0];
+        assert_eq!(app.features, ["cli", "default"]);
+        assert_eq!(app.deps[0].name, "fixture_core");
+        assert_eq!(app.deps[0].kinds[0].kind, "normal");
+
+        let core = &metadata.packages[1].id;
+        let graph = resolve_graph(&metadata, Some(&[core]), false);
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.nodes[0].name, "fixture-core");
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use cargo_metadata::{Metadata, MetadataCommand, Package};
+use mcp_attr::Result;
+use serde::Serialize;
+
+use crate::advisory::{self, AdvisoryFinding, DatabaseInfo};
+use crate::error::{ErrorKind, bail_kind};
+use crate::index::validate_crate_name;
+use crate::license::{self, NormalizedLicense};
+use crate::policy::compat_key;
+
+/// 一時プロジェクトのパッケージ名 (依存グラフの根として使用する)
+const REVIEW_PACKAGE: &str = "cargo-metadata-candidate-review";
+
+#[derive(Serialize)]
+pub struct CandidateReview {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    #[serde(flatten)]
+    pub normalized_license: NormalizedLicense,
+    pub maintenance: MaintenanceSignals,
+    /// 候補のクレート自身を含む、依存関係の閉包に含まれるパッケージ
+    pub closure: Vec<String>,
+    /// 閉包内のパッケージに該当するアドバイザリ
+    pub advisories: Vec<AdvisoryFinding>,
+    /// アドバイザリデータベースを読み込めなかった場合のエラー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advisories_error: Option<String>,
+    /// 検査に使用したアドバイザリデータベース
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advisory_database: Option<DatabaseInfo>,
+    pub impact: GraphImpact,
+}
+
+/// Cargo.toml から読み取れる保守状況の手がかり
+#[derive(Serialize)]
+pub struct MaintenanceSignals {
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub documentation: Option<String>,
+    pub rust_version: Option<String>,
+    pub edition: String,
+    /// 1.0 未満のバージョンかどうか
+    pub pre_1_0: bool,
+    /// unmaintained のアドバイザリが出ているかどうか
+    pub unmaintained: bool,
+}
+
+/// 候補を追加した場合の現在の依存グラフへの影響
+#[derive(Serialize)]
+pub struct GraphImpact {
+    /// 現在の依存グラフに含まれておらず、新たに追加されるパッケージ
+    pub new_crates: Vec<String>,
+    /// 現在の依存グラフの semver 互換なバージョンと統合されるパッケージ
+    pub shared_crates: Vec<String>,
+    /// 現在の依存グラフに semver 非互換なバージョンとして重複するパッケージ
+    pub duplicate_majors: Vec<DuplicateMajor>,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateMajor {
+    pub name: String,
+    pub candidate_version: String,
+    pub existing_versions: Vec<String>,
+}
+
+/// プロジェクトを変更せずに、依存関係の追加候補を評価する
+///
+/// 候補だけに依存する一時プロジェクトを作成して `cargo metadata` を実行し、その結果を現在の依存グラフと比較する。
+pub fn review_candidate(
+    metadata: &Metadata,
+    crate_name: &str,
+    version: Option<&str>,
+) -> Result<CandidateReview> {
+    let candidate_metadata = candidate_metadata(crate_name, version)?;
+    let Some(candidate) = candidate_metadata
+        .packages
+        .iter()
+        .find(|p| p.name == crate_name)
+    else {
+        bail_kind!(ErrorKind::PackageNotFound, crate_name);
+    };
+    let closure: Vec<&Package> = candidate_metadata
+        .packages
+        .iter()
+        .filter(|p| p.name != REVIEW_PACKAGE)
+        .collect();
+
+    let (advisories, advisories_error, advisory_database) = match advisory::load_database() {
+        Ok(database) => (
+            advisory::find_advisories(&candidate_metadata, &database.db),
+            None,
+            Some(database.info),
+        ),
+        Err(e) => (Vec::new(), Some(e.to_error_object(false).message), None),
+    };
+
+    let mut new_crates = Vec::new();
+    let mut shared_crates = Vec::new();
+    let mut duplicate_majors = Vec::new();
+    for package in &closure {
+        let label = format!("{}@{}", package.name, package.version);
+        let existing: BTreeSet<_> = metadata
+            .packages
+            .iter()
+            .filter(|p| p.name == package.name)
+            .map(|p| &p.version)
+            .collect();
+        let key = compat_key(&package.version);
+        if existing.iter().any(|v| compat_key(v) == key) {
+            shared_crates.push(label);
+        } else {
+            if !existing.is_empty() {
+                duplicate_majors.push(DuplicateMajor {
+                    name: package.name.clone(),
+                    candidate_version: package.version.to_string(),
+                    existing_versions: existing.iter().map(|v| v.to_string()).collect(),
+                });
+            }
+            new_crates.push(label);
+        }
+    }
+    new_crates.sort();
+    shared_crates.sort();
+
+    let mut closure: Vec<String> = closure
+        .iter()
+        .map(|p| format!("{}@{}", p.name, p.version))
+        .collect();
+    closure.sort();
+
+    Ok(CandidateReview {
+        name: candidate.name.clone(),
+        version: candidate.version.to_string(),
+        description: candidate.description.clone(),
+        license: candidate.license.clone(),
+        normalized_license: candidate
+            .license
+            .as_deref()
+            .map(license::normalize_license)
+            .unwrap_or_default(),
+        maintenance: MaintenanceSignals {
+            repository: candidate.repository.clone(),
+            homepage: candidate.homepage.clone(),
+            documentation: candidate.documentation.clone(),
+            rust_version: candidate.rust_version.as_ref().map(|v| v.to_string()),
+            edition: candidate.edition.to_string(),
+            pre_1_0: candidate.version.major == 0,
+            unmaintained: advisories
+                .iter()
+                .any(|a| a.package == candidate.name && a.kind == "unmaintained"),
+        },
+        closure,
+        advisories,
+        advisories_error,
+        advisory_database,
+        impact: GraphImpact {
+            new_crates,
+            shared_crates,
+            duplicate_majors,
+        },
+    })
+}
+
+/// 一時プロジェクトのディレクトリ
+///
+/// 同じクレートを同時に調べても衝突しないよう、プロセス ID と作成時刻を名前に含める。破棄時に削除する。
+struct TempProject {
+    dir: PathBuf,
+}
+
+impl TempProject {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "{REVIEW_PACKAGE}-{}-{}",
+            std::process::id(),
+            jiff::Timestamp::now().as_nanosecond()
+        ));
+        Self { dir }
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// 候補のクレートだけに依存する一時プロジェクトのメタデータを取得する
+fn candidate_metadata(crate_name: &str, version: Option<&str>) -> Result<Metadata> {
+    validate_crate_name(crate_name)?;
+    let project = TempProject::new();
+    let result = write_project(&project.dir, crate_name, version).and_then(|manifest| {
+        MetadataCommand::new()
+            .manifest_path(manifest)
+            .exec()
+            .map_err(|e| e.to_string())
+    });
+    match result {
+        Ok(metadata) => Ok(metadata),
+        Err(e) => bail_kind!(ErrorKind::ResolveFailed, crate_name, e),
+    }
+}
+
+fn write_project(
+    dir: &Path,
+    crate_name: &str,
+    version: Option<&str>,
+) -> std::result::Result<PathBuf, String> {
+    let manifest = format!(
+        "[package]\nname = \"{REVIEW_PACKAGE}\"\nversion = \"0.0.0\"\nedition = \"2021\"\npublish = false\n\n\
+         [dependencies]\n{crate_name} = {:?}\n\n[workspace]\n",
+        version.unwrap_or("*")
+    );
+    let src = dir.join("src");
+    std::fs::create_dir_all(&src).map_err(|e| e.to_string())?;
+    std::fs::write(src.join("lib.rs"), "").map_err(|e| e.to_string())?;
+    let path = dir.join("Cargo.toml");
+    std::fs::write(&path, manifest).map_err(|e| e.to_string())?;
+    Ok(path)
+}
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use mcp_attr::jsoncall::{
+    Handler, Hook, NotificationContext, Params, RequestContext, Response, SessionContext,
+};
+use mcp_attr::schema::Root;
+use serde::Deserialize;
+
+use crate::ServerData;
+
+/// ルートの中で Cargo.toml を探すディレクトリの深さの上限
+const MAX_DEPTH: usize = 3;
+
+/// ルートの中で Cargo.toml を探さないディレクトリ
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// クライアントのルートの一覧が変更されたときに送られる通知の名前
+const ROOTS_LIST_CHANGED: &str = "notifications/roots/list_changed";
+
+/// `file://` のルートの URI をパスに変換する (パーセントエンコードを復元する)
+pub fn root_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = path
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    Some(PathBuf::from(String::from_utf8(decoded).ok()?))
+}
+
+/// ルートにある Cargo プロジェクトのマニフェストを探す
+///
+/// ルート自体が Cargo.toml を持つ場合はそれを使う。そうでなければ、`MAX_DEPTH` までのサブディレクトリを探し、
+/// Cargo.toml が見つかったディレクトリの中 (ワークスペースのメンバー) はそれ以上探さない。
+pub fn discover_manifests(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut manifests = Vec::new();
+    for root in roots {
+        let mut dirs = vec![(root.clone(), 0)];
+        while let Some((dir, depth)) = dirs.pop() {
+            let manifest = dir.join("Cargo.toml");
+            if manifest.is_file() {
+                manifests.push(manifest);
+                continue;
+            }
+            if depth == MAX_DEPTH {
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()) {
+                    continue;
+                }
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    dirs.push((entry.path(), depth + 1));
+                }
+            }
+        }
+    }
+    manifests.sort();
+    manifests.dedup();
+    manifests
+}
+
+/// `roots/list` の結果
+///
+/// 仕様では `{ "roots": [...] }` だが、ルートの配列だけを返すクライアント (mcp-attr 0.0.2 の `McpClient` など) もある。
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ListRootsResponse {
+    Result { roots: Vec<Root> },
+    Roots(Vec<Root>),
+}
+
+/// クライアントにルートの一覧を問い合わせ、その中にあるプロジェクトのマニフェストを返す
+///
+/// クライアントがルートに対応していない場合は空を返す。
+pub(crate) async fn list_root_manifests(session: &SessionContext) -> Vec<PathBuf> {
+    let roots = match session
+        .request::<ListRootsResponse>("roots/list", None::<&()>)
+        .await
+    {
+        Ok(ListRootsResponse::Result { roots } | ListRootsResponse::Roots(roots)) => roots,
+        Err(_) => return Vec::new(),
+    };
+    let roots: Vec<PathBuf> = roots
+        .iter()
+        .filter_map(|root| root_path(&root.uri))
+        .collect();
+    tokio::task::spawn_blocking(move || discover_manifests(&roots))
+        .await
+        .unwrap_or_default()
+}
+
+/// サーバーのハンドラーを包み、クライアントのルートを問い合わせるためのセッションを記録する
+///
+/// `notifications/roots/list_changed` を受け取ると、見つけたプロジェクトの一覧を破棄する。
+pub(crate) struct RootsHandler<H> {
+    pub inner: H,
+    pub state: Arc<ServerData>,
+}
+
+impl<H: Handler> Handler for RootsHandler<H> {
+    fn hook(&self) -> Arc<dyn Hook> {
+        self.inner.hook()
+    }
+
+    fn request(
+        &mut self,
+        method: &str,
+        params: Params,
+        cx: RequestContext,
+    ) -> mcp_attr::Result<Response> {
+        self.state.session.get_or_init(|| cx.session());
+        self.inner.request(method, params, cx)
+    }
+
+    fn notification(
+        &mut self,
+        method: &str,
+        params: Params,
+        cx: NotificationContext,
+    ) -> mcp_attr::Result<Response> {
+        self.state.session.get_or_init(|| cx.session());
+        if method == ROOTS_LIST_CHANGED {
+            *self.state.root_manifests.lock().unwrap() = None;
+            return cx.handle(Ok(()));
+        }
+        self.inner.notification(method, params, cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_path() {
+        assert_eq!(
+            root_path("file:///home/user/My%20Project"),
+            Some(PathBuf::from("/home/user/My Project"))
+        );
+        assert_eq!(
+            root_path("file://localhost/src/app"),
+            Some(PathBuf::from("/src/app"))
+        );
+        assert_eq!(root_path("https://example.com/app"), None);
+    }
+
+    #[test]
+    fn test_discover_manifests() {
+        let root = std::env::temp_dir().join(format!("roots-test-{}", std::process::id()));
+        for dir in ["app/crates/core", "tools/cli", "target/debug", ".git/x"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for manifest in [
+            "app/Cargo.toml",
+            "app/crates/core/Cargo.toml",
+            "tools/cli/Cargo.toml",
+            "target/debug/Cargo.toml",
+            ".git/x/Cargo.toml",
+        ] {
+            std::fs::write(root.join(manifest), "").unwrap();
+        }
+        assert_eq!(
+            discover_manifests(std::slice::from_ref(&root)),
+            [
+                root.join("app/Cargo.toml"),
+                root.join("tools/cli/Cargo.toml")
+            ]
+        );
+        assert_eq!(
+            discover_manifests(&[root.join("app")]),
+            [root.join("app/Cargo.toml")]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use cargo_metadata::Metadata;
+use cargo_metadata::camino::Utf8Path;
+use mcp_attr::Result;
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// ワークスペースルートからコピーする、依存関係の解決に影響するファイル
+const WORKSPACE_FILES: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    ".cargo/config.toml",
+    ".cargo/config",
+    "rust-toolchain.toml",
+    "rust-toolchain",
+];
+
+/// 実際のプロジェクトを変更せずに cargo を実行するための、ワークスペースの一時的なコピー
+///
+/// マニフェストとロックファイルのみをコピーし、ターゲットのソースファイルは空のファイルで置き換える。
+/// 一時ディレクトリは破棄時に削除される。
+pub struct Sandbox {
+    dir: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new(metadata: &Metadata) -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "{}-sandbox-{}-{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id(),
+            jiff::Timestamp::now().as_nanosecond()
+        ));
+        let sandbox = Self { dir };
+        if let Err(e) = sandbox.populate(metadata) {
+            bail_kind!(ErrorKind::SandboxFailed, e);
+        }
+        Ok(sandbox)
+    }
+
+    /// 一時ディレクトリのワークスペースルート
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn lockfile_path(&self) -> PathBuf {
+        self.dir.join("Cargo.lock")
+    }
+
+    fn populate(&self, metadata: &Metadata) -> std::result::Result<(), String> {
+        let root = &metadata.workspace_root;
+        for file in WORKSPACE_FILES {
+            let source = root.join(file);
+            if source.is_file() {
+                self.copy(root, &source)?;
+            }
+        }
+        for package in metadata.packages.iter().filter(|p| p.source.is_none()) {
+            if package.manifest_path.strip_prefix(root).is_err() {
+                return Err(format!(
+                    "path dependency `{}` at {} is outside the workspace root",
+                    package.name, package.manifest_path
+                ));
+            }
+            self.copy(root, &package.manifest_path)?;
+            for target in &package.targets {
+                self.stub(root, &target.src_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn destination(
+        &self,
+        root: &Utf8Path,
+        path: &Utf8Path,
+    ) -> std::result::Result<PathBuf, String> {
+        match path.strip_prefix(root) {
+            Ok(relative) => Ok(self.dir.join(relative)),
+            Err(_) => Err(format!("{path} is outside the workspace root")),
+        }
+    }
+
+    fn copy(&self, root: &Utf8Path, path: &Utf8Path) -> std::result::Result<(), String> {
+        let destination = self.destination(root, path)?;
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        std::fs::copy(path, &destination).map_err(|e| format!("{path}: {e}"))?;
+        Ok(())
+    }
+
+    fn stub(&self, root: &Utf8Path, path: &Utf8Path) -> std::result::Result<(), String> {
+        let destination = self.destination(root, path)?;
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        if !destination.exists() {
+            std::fs::write(&destination, "").map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// 一時ディレクトリのワークスペースルートで cargo を実行する
+    pub fn cargo(&self, args: &[&str]) -> Result<Output> {
+        match cargo_command(&self.dir).args(args).output() {
+            Ok(output) => Ok(output),
+            Err(e) => bail_kind!(ErrorKind::CargoSpawnFailed, e),
+        }
+    }
+
+    /// 一時ディレクトリのワークスペースルートで cargo を実行し、失敗した場合はエラーを返す
+    pub fn run(&self, args: &[&str]) -> Result<()> {
+        let output = self.cargo(args)?;
+        if !output.status.success() {
+            bail_kind!(
+                ErrorKind::CargoCommandFailed,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// Cargo.lock がなければ生成する
+    pub fn ensure_lockfile(&self) -> Result<()> {
+        if !self.lockfile_path().exists() {
+            self.run(&["generate-lockfile"])?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// 指定したディレクトリで実行する cargo のコマンド (`CARGO` 環境変数が設定されていればそれを使用する)
+pub fn cargo_command(dir: &Path) -> Command {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = Command::new(cargo);
+    command.current_dir(dir);
+    command
+}
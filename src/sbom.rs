@@ -0,0 +1,291 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use cargo_metadata::{DependencyKind, Metadata, Package, PackageId};
+use mcp_attr::Result;
+use serde_json::{Value, json};
+
+use crate::cyclonedx::purl;
+use crate::error::{ErrorKind, bail_kind};
+use crate::graph::Graph;
+use crate::license::normalize_license;
+use crate::lockfile::Lockfile;
+
+/// SBOM の形式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SbomFormat {
+    Spdx,
+    CycloneDx,
+}
+
+impl SbomFormat {
+    pub fn parse(format: Option<&str>) -> Result<Self> {
+        match format.unwrap_or("spdx") {
+            "spdx" => Ok(Self::Spdx),
+            "cyclonedx" => Ok(Self::CycloneDx),
+            other => bail_kind!(ErrorKind::UnknownFormat, other, "spdx, cyclonedx"),
+        }
+    }
+}
+
+/// ワークスペースメンバーと、それらから到達できるパッケージ (名前とバージョンの順)
+pub(crate) fn sbom_packages<'a>(graph: &Graph<'a>, include_dev: bool) -> Vec<&'a Package> {
+    let mut reachable: HashSet<&PackageId> = HashSet::new();
+    for member in graph.members() {
+        reachable.extend(graph.reachable(member, include_dev));
+    }
+    let mut packages: Vec<&Package> = reachable
+        .into_iter()
+        .filter_map(|id| graph.package(id))
+        .collect();
+    packages.sort_by(|a, b| (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id)));
+    packages
+}
+
+/// Cargo.lock に記録された、パッケージの SHA-256 チェックサム (`(name, version, source)` ごと)
+pub(crate) fn lockfile_checksums(metadata: &Metadata) -> HashMap<(String, String, String), String> {
+    let Ok(lockfile) = Lockfile::load(metadata.workspace_root.join("Cargo.lock").as_std_path())
+    else {
+        return HashMap::new();
+    };
+    lockfile
+        .packages
+        .into_iter()
+        .filter_map(|p| Some(((p.name, p.version, p.source?), p.checksum?)))
+        .collect()
+}
+
+pub(crate) fn checksum<'a>(
+    checksums: &'a HashMap<(String, String, String), String>,
+    package: &Package,
+) -> Option<&'a String> {
+    let source = package.source.as_ref()?;
+    checksums.get(&(
+        package.name.clone(),
+        package.version.to_string(),
+        source.repr.clone(),
+    ))
+}
+
+/// SPDX の `downloadLocation` (crates.io のダウンロード URL、または `git+<url>@<commit>`)
+fn download_location(package: &Package) -> String {
+    let Some(source) = &package.source else {
+        return "NOASSERTION".to_string();
+    };
+    if source.is_crates_io() {
+        return format!(
+            "https://crates.io/api/v1/crates/{}/{}/download",
+            package.name, package.version
+        );
+    }
+    if let Some(url) = source.repr.strip_prefix("git+") {
+        let (url, commit) = url.split_once('#').unwrap_or((url, ""));
+        let url = url.split('?').next().unwrap_or(url);
+        return if commit.is_empty() {
+            format!("git+{url}")
+        } else {
+            format!("git+{url}@{commit}")
+        };
+    }
+    "NOASSERTION".to_string()
+}
+
+/// SPDX の識別子に使える文字 (英数字、`.`、`-`) 以外を `-` に置き換える
+fn spdx_id(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// resolve グラフから SPDX 2.3 の JSON ドキュメントを生成する
+///
+/// ワークスペースメンバーと、それらから到達できるパッケージを含める。
+/// チェックサムは Cargo.lock から、ライセンスは正規化した SPDX ライセンス式 (正規化できない場合は `NOASSERTION`) を使う。
+pub fn spdx_document(metadata: &Metadata, include_dev: bool) -> Value {
+    let graph = Graph::new(metadata);
+    let packages = sbom_packages(&graph, include_dev);
+    let checksums = lockfile_checksums(metadata);
+
+    let mut ids: HashMap<&PackageId, String> = HashMap::new();
+    let mut used: BTreeSet<String> = BTreeSet::new();
+    for package in &packages {
+        let base = spdx_id(&format!(
+            "SPDXRef-Package-{}-{}",
+            package.name, package.version
+        ));
+        let mut id = base.clone();
+        let mut n = 2;
+        while !used.insert(id.clone()) {
+            id = format!("{base}-{n}");
+            n += 1;
+        }
+        ids.insert(&package.id, id);
+    }
+
+    let spdx_packages: Vec<Value> = packages
+        .iter()
+        .map(|package| {
+            let license = package
+                .license
+                .as_deref()
+                .map(normalize_license)
+                .filter(|l| l.spdx_valid)
+                .and_then(|l| l.spdx)
+                .unwrap_or_else(|| "NOASSERTION".to_string());
+            let mut value = json!({
+                "name": package.name,
+                "SPDXID": ids[&package.id],
+                "versionInfo": package.version.to_string(),
+                "downloadLocation": download_location(package),
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": license,
+                "copyrightText": "NOASSERTION",
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": purl(package),
+                }],
+            });
+            if let Some(checksum) = checksum(&checksums, package) {
+                value["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": checksum }]);
+            }
+            if let Some(description) = &package.description {
+                value["summary"] = json!(description.trim());
+            }
+            if let Some(homepage) = package.homepage.as_ref().or(package.repository.as_ref()) {
+                value["homepage"] = json!(homepage);
+            }
+            value
+        })
+        .collect();
+
+    let mut relationships: Vec<Value> = graph
+        .members()
+        .filter_map(|id| ids.get(id))
+        .map(|id| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": id,
+            })
+        })
+        .collect();
+    for package in &packages {
+        let from = &ids[&package.id];
+        for edge in graph.edges(&package.id, include_dev) {
+            let Some(to) = ids.get(&edge.pkg) else {
+                continue;
+            };
+            let mut kinds: Vec<DependencyKind> = edge.dep_kinds.iter().map(|k| k.kind).collect();
+            if kinds.is_empty() {
+                kinds.push(DependencyKind::Normal);
+            }
+            kinds.sort_by_key(|k| k.to_string());
+            kinds.dedup();
+            for kind in kinds {
+                // 開発時とビルド時の依存関係は、依存先から依存元への関係として表す
+                let relationship = match kind {
+                    DependencyKind::Development if include_dev => {
+                        json!({ "spdxElementId": to, "relationshipType": "DEV_DEPENDENCY_OF", "relatedSpdxElement": from })
+                    }
+                    DependencyKind::Development => continue,
+                    DependencyKind::Build => {
+                        json!({ "spdxElementId": to, "relationshipType": "BUILD_DEPENDENCY_OF", "relatedSpdxElement": from })
+                    }
+                    _ => {
+                        json!({ "spdxElementId": from, "relationshipType": "DEPENDS_ON", "relatedSpdxElement": to })
+                    }
+                };
+                relationships.push(relationship);
+            }
+        }
+    }
+
+    let name = metadata
+        .root_package()
+        .map(|p| format!("{}-{}", p.name, p.version))
+        .or_else(|| metadata.workspace_root.file_name().map(str::to_string))
+        .unwrap_or_else(|| "workspace".to_string());
+    let now = jiff::Timestamp::now();
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!(
+            "https://spdx.org/spdxdocs/{}-{}",
+            spdx_id(&name),
+            now.as_nanosecond()
+        ),
+        "creationInfo": {
+            "created": now.strftime("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            "creators": [format!("Tool: {}-{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": spdx_packages,
+        "relationships": relationships,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_spdx_document() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let document = spdx_document(&metadata, false);
+        assert_eq!(document["spdxVersion"], "SPDX-2.3");
+        assert_eq!(document["name"], "fixture");
+        let packages = document["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0]["SPDXID"], "SPDXRef-Package-app-0.2.0");
+        assert_eq!(packages[0]["licenseDeclared"], "MIT");
+        assert_eq!(packages[0]["downloadLocation"], "NOASSERTION");
+        assert_eq!(
+            packages[1]["externalRefs"][0]["referenceLocator"],
+            "pkg:cargo/fixture-core@0.1.0"
+        );
+        let relationships = document["relationships"].as_array().unwrap();
+        assert!(relationships.contains(&json!({
+            "spdxElementId": "SPDXRef-Package-app-0.2.0",
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": "SPDXRef-Package-fixture-core-0.1.0",
+        })));
+        assert_eq!(
+            relationships
+                .iter()
+                .filter(|r| r["relationshipType"] == "DESCRIBES")
+                .count(),
+            2
+        );
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let document = spdx_document(&metadata, false);
+        let serde = document["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["name"] == "serde")
+            .unwrap();
+        assert!(
+            serde["downloadLocation"]
+                .as_str()
+                .unwrap()
+                .starts_with("https://crates.io/api/v1/crates/serde/")
+        );
+        assert_eq!(serde["checksums"][0]["algorithm"], "SHA256");
+        assert_eq!(serde["licenseDeclared"], "MIT OR Apache-2.0");
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use cargo_metadata::{Metadata, PackageId};
+use mcp_attr::Result;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::graph::Graph;
+use crate::workspace::glob_match;
+
+/// パッケージ名のパターン
+pub enum NamePattern {
+    /// `*` は任意の文字列、`?` は任意の 1 文字 (名前全体と照合する)
+    Glob(String),
+    /// 正規表現 (名前の一部に一致すればよい)
+    Regex(Regex),
+}
+
+impl NamePattern {
+    pub fn parse(pattern: &str, regex: bool) -> Result<Self> {
+        if !regex {
+            return Ok(Self::Glob(pattern.to_string()));
+        }
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(Self::Regex(regex)),
+            Err(e) => bail_kind!(ErrorKind::InvalidPattern, pattern, e),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Glob(pattern) => glob_match(pattern, name),
+            Self::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// 名前がパターンに一致したパッケージ
+#[derive(Serialize)]
+pub struct PackageMatch {
+    pub name: String,
+    pub version: String,
+    /// `workspace` (ワークスペースメンバー)、`direct` (ワークスペースメンバーが直接依存している)、`transitive` (それ以外) のいずれか
+    pub relation: &'static str,
+}
+
+/// 依存グラフのすべてのパッケージから、名前がパターンに一致するものを探す (名前とバージョンの順)
+///
+/// dev-dependencies としてのみ依存しているパッケージも、直接の依存関係として扱う。
+pub fn search_packages(metadata: &Metadata, pattern: &NamePattern) -> Vec<PackageMatch> {
+    let graph = Graph::new(metadata);
+    let members: HashSet<&PackageId> = graph.members().collect();
+    let direct: HashSet<&PackageId> = members
+        .iter()
+        .flat_map(|id| graph.dependencies(id, true))
+        .collect();
+    let mut packages: Vec<_> = metadata
+        .packages
+        .iter()
+        .filter(|p| pattern.matches(&p.name))
+        .collect();
+    packages.sort_by(|a, b| (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id)));
+    packages
+        .into_iter()
+        .map(|package| PackageMatch {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            relation: if members.contains(&package.id) {
+                "workspace"
+            } else if direct.contains(&package.id) {
+                "direct"
+            } else {
+                "transitive"
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_search_packages() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let pattern = NamePattern::parse("fixture-*", false).unwrap();
+        let matches = search_packages(&metadata, &pattern);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].name, "fixture-core");
+        assert_eq!(matches[0].relation, "workspace");
+        assert!(NamePattern::parse("(", true).is_err());
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let relation = |pattern: &str| {
+            let pattern = NamePattern::parse(pattern, true).unwrap();
+            let matches = search_packages(&metadata, &pattern);
+            matches.first().map(|m| m.relation)
+        };
+        assert_eq!(relation("^serde$"), Some("direct"));
+        assert_eq!(relation("^serde_derive$"), Some("transitive"));
+        assert_eq!(relation("^mcp-attr-example"), Some("workspace"));
+        assert_eq!(relation("^no-such-crate$"), None);
+    }
+}
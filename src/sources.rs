@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo_metadata::camino::Utf8Path;
+use cargo_metadata::{Metadata, Package, PackageId};
+use serde::Serialize;
+
+use crate::graph::Graph;
+
+/// ソースの種類 (監査で確認したい順)
+const KINDS: &[&str] = &["git", "path", "vendored", "registry", "crates-io"];
+
+/// 依存関係のソースの種類ごとの内訳
+#[derive(Serialize)]
+pub struct SourceBreakdown {
+    /// ソースの種類ごとのパッケージ数
+    pub counts: BTreeMap<&'static str, usize>,
+    /// ソースごとのパッケージ (git、path、vendored、registry、crates-io の順)
+    pub sources: Vec<SourceGroup>,
+}
+
+#[derive(Serialize)]
+pub struct SourceGroup {
+    /// `crates-io`、`registry` (crates.io 以外のレジストリ)、`git`、`path`、`vendored` のいずれか
+    pub kind: &'static str,
+    /// レジストリや git リポジトリの URL、またはディレクトリ (ワークスペースのルートからの相対パス)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    pub packages: Vec<SourcePackage>,
+}
+
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourcePackage {
+    pub name: String,
+    pub version: String,
+    /// git の依存関係の場合の、Cargo.lock に記録されたコミット
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+}
+
+/// git のソースの ID を、リポジトリの URL (`?branch=` などを含む) とコミットに分ける
+fn git_location(repr: &str) -> (String, Option<String>) {
+    let url = repr.strip_prefix("git+").unwrap_or(repr);
+    match url.split_once('#') {
+        Some((url, rev)) => (url.to_string(), Some(rev.to_string())),
+        None => (url.to_string(), None),
+    }
+}
+
+/// ワークスペースのルートの中のパスは相対パスにする
+fn display_path(metadata: &Metadata, path: &Utf8Path) -> String {
+    path.strip_prefix(&metadata.workspace_root)
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// パッケージのソースの種類と場所、git のコミット
+///
+/// ソースを持ち、ディレクトリに `cargo vendor` が作る `.cargo-checksum.json` があるパッケージは vendored とし、
+/// 場所は vendor ディレクトリにする。
+fn classify(
+    metadata: &Metadata,
+    package: &Package,
+) -> (&'static str, Option<String>, Option<String>) {
+    let dir = package
+        .manifest_path
+        .parent()
+        .unwrap_or(&package.manifest_path);
+    let Some(source) = &package.source else {
+        return ("path", Some(display_path(metadata, dir)), None);
+    };
+    if dir.join(".cargo-checksum.json").is_file() {
+        let vendor = dir.parent().unwrap_or(dir);
+        return ("vendored", Some(display_path(metadata, vendor)), None);
+    }
+    if source.is_crates_io() {
+        ("crates-io", None, None)
+    } else if source.repr.starts_with("git+") {
+        let (url, rev) = git_location(&source.repr);
+        ("git", Some(url), rev)
+    } else {
+        let url = source
+            .repr
+            .strip_prefix("registry+")
+            .unwrap_or(&source.repr);
+        ("registry", Some(url.to_string()), None)
+    }
+}
+
+/// ワークスペースメンバーから到達できる依存関係 (ワークスペースメンバーを除く) を、ソースの種類と場所ごとにまとめる
+pub fn source_breakdown(metadata: &Metadata, include_dev: bool) -> SourceBreakdown {
+    let graph = Graph::new(metadata);
+    let mut ids: BTreeSet<&PackageId> = BTreeSet::new();
+    for member in graph.members() {
+        ids.extend(graph.reachable(member, include_dev));
+    }
+    let mut groups: BTreeMap<(usize, &'static str, Option<String>), Vec<SourcePackage>> =
+        BTreeMap::new();
+    let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for package in ids
+        .iter()
+        .filter(|id| !metadata.workspace_members.contains(*id))
+        .filter_map(|id| graph.package(id))
+    {
+        let (kind, location, rev) = classify(metadata, package);
+        *counts.entry(kind).or_default() += 1;
+        let order = KINDS.iter().position(|k| *k == kind).unwrap_or(KINDS.len());
+        groups
+            .entry((order, kind, location))
+            .or_default()
+            .push(SourcePackage {
+                name: package.name.clone(),
+                version: package.version.to_string(),
+                rev,
+            });
+    }
+    let sources = groups
+        .into_iter()
+        .map(|((_, kind, location), mut packages)| {
+            packages.sort();
+            SourceGroup {
+                kind,
+                location,
+                packages,
+            }
+        })
+        .collect();
+    SourceBreakdown { counts, sources }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_source_breakdown() {
+        assert_eq!(
+            git_location("git+https://github.com/a/b?branch=main#0123abc"),
+            (
+                "https://github.com/a/b?branch=main".to_string(),
+                Some("0123abc".to_string())
+            )
+        );
+
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let breakdown = source_breakdown(&metadata, true);
+        assert!(breakdown.counts.is_empty());
+        assert!(breakdown.sources.is_empty());
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let breakdown = source_breakdown(&metadata, false);
+        let crates_io = breakdown
+            .sources
+            .iter()
+            .find(|g| g.kind == "crates-io")
+            .unwrap();
+        assert_eq!(crates_io.location, None);
+        assert!(crates_io.packages.iter().any(|p| p.name == "serde"));
+        assert_eq!(
+            breakdown.counts.values().sum::<usize>(),
+            breakdown
+                .sources
+                .iter()
+                .map(|g| g.packages.len())
+                .sum::<usize>()
+        );
+    }
+}
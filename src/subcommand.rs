@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::index::cargo_home;
+
+/// サーバーのツールが連携できる cargo のサブコマンドと、その用途
+const KNOWN_SUBCOMMANDS: &[(&str, &str)] = &[
+    ("audit", "security advisories for Cargo.lock"),
+    ("deny", "license, ban, advisory and source checks"),
+    ("vet", "supply-chain audits"),
+    ("outdated", "outdated dependency detection"),
+    ("udeps", "unused dependency detection (nightly)"),
+    ("machete", "unused dependency detection"),
+    ("bloat", "binary size breakdown"),
+    ("geiger", "unsafe code statistics"),
+    ("hakari", "workspace-hack management"),
+    ("semver-checks", "semver violation detection"),
+    ("msrv", "minimum supported Rust version search"),
+    ("nextest", "test runner"),
+    ("llvm-cov", "code coverage"),
+    ("expand", "macro expansion"),
+    ("about", "third-party license reports"),
+    ("cyclonedx", "CycloneDX SBOM generation"),
+];
+
+#[derive(Serialize)]
+pub struct SubcommandReport {
+    pub subcommands: Vec<Subcommand>,
+    /// 連携できる既知のサブコマンドが使用可能かどうか
+    pub capabilities: Vec<Capability>,
+}
+
+#[derive(Serialize)]
+pub struct Subcommand {
+    /// `cargo <name>` として実行できる名前
+    pub name: String,
+    pub path: String,
+    /// `cargo install` でインストールされた場合のクレート名とバージョン
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Capability {
+    pub subcommand: &'static str,
+    pub purpose: &'static str,
+    pub available: bool,
+}
+
+/// PATH と `$CARGO_HOME/bin` にある `cargo-*` の実行ファイルを、cargo と同じ順序で探す
+///
+/// バージョンは `cargo install` の記録 (`$CARGO_HOME/.crates2.json`) から取得し、サブコマンドは実行しない。
+pub fn installed_subcommands() -> SubcommandReport {
+    let home = cargo_home();
+    let mut dirs = vec![home.join("bin")];
+    if let Some(path) = std::env::var_os("PATH") {
+        dirs.extend(std::env::split_paths(&path));
+    }
+    let installs = read_installs(&home);
+
+    let mut found: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for dir in dirs {
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(name) = file_name
+                .strip_suffix(std::env::consts::EXE_SUFFIX)
+                .and_then(|name| name.strip_prefix("cargo-"))
+            else {
+                continue;
+            };
+            if !name.is_empty() && is_executable(&entry.path()) {
+                found.entry(name.to_string()).or_insert(entry.path());
+            }
+        }
+    }
+
+    let subcommands: Vec<Subcommand> = found
+        .into_iter()
+        .map(|(name, path)| {
+            let install = installs.get(&format!("cargo-{name}"));
+            Subcommand {
+                name,
+                path: path.display().to_string(),
+                package: install.map(|(krate, _)| krate.clone()),
+                version: install.map(|(_, version)| version.clone()),
+            }
+        })
+        .collect();
+    let capabilities = KNOWN_SUBCOMMANDS
+        .iter()
+        .map(|(subcommand, purpose)| Capability {
+            subcommand,
+            purpose,
+            available: subcommands.iter().any(|s| s.name == *subcommand),
+        })
+        .collect();
+    SubcommandReport {
+        subcommands,
+        capabilities,
+    }
+}
+
+/// `cargo install` でインストールされた実行ファイル名から (クレート名, バージョン) への対応
+fn read_installs(home: &Path) -> BTreeMap<String, (String, String)> {
+    let Some(json) = std::fs::read_to_string(home.join(".crates2.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+    else {
+        return BTreeMap::new();
+    };
+    parse_installs(&json)
+}
+
+fn parse_installs(json: &Value) -> BTreeMap<String, (String, String)> {
+    let mut installs = BTreeMap::new();
+    for (key, install) in json["installs"].as_object().into_iter().flatten() {
+        // キーは `<crate> <version> (<source>)` の形式
+        let mut parts = key.split(' ');
+        let (Some(krate), Some(version)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        for bin in install["bins"].as_array().into_iter().flatten() {
+            if let Some(bin) = bin.as_str() {
+                let bin = bin
+                    .strip_suffix(std::env::consts::EXE_SUFFIX)
+                    .unwrap_or(bin);
+                installs.insert(bin.to_string(), (krate.to_string(), version.to_string()));
+            }
+        }
+    }
+    installs
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_installs() {
+        let json = serde_json::json!({
+            "installs": {
+                "cargo-audit 0.21.2 (registry+https://github.com/rust-lang/crates.io-index)": {
+                    "bins": ["cargo-audit"]
+                },
+                "cargo-llvm-cov 0.6.16 (registry+https://github.com/rust-lang/crates.io-index)": {
+                    "bins": ["cargo-llvm-cov"]
+                }
+            }
+        });
+        let installs = parse_installs(&json);
+        assert_eq!(
+            installs.get("cargo-audit"),
+            Some(&("cargo-audit".to_string(), "0.21.2".to_string()))
+        );
+        assert_eq!(installs.len(), 2);
+    }
+}
@@ -0,0 +1,642 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::Arc;
+
+use cargo_metadata::{DependencyKind, Metadata, Package, PackageId};
+use serde::Serialize;
+
+use crate::artifact::{ArtifactDependencies, ArtifactSpec};
+use crate::category;
+use crate::detail::Detail;
+use crate::duplicates::find_duplicates;
+use crate::findings::Level;
+use crate::graph::Graph;
+use crate::license::normalize_license;
+use crate::lint;
+use crate::policy::compat_key;
+
+/// ワークスペースメンバーの概要 (`cargo://{project}/packages/{name}/summary` リソースの内容)
+#[derive(Serialize)]
+pub struct PackageSummary {
+    pub name: String,
+    pub version: String,
+    pub pkgid: String,
+    /// パッケージの目的、ターゲット、フィーチャー、依存関係を説明する文章
+    pub summary: String,
+    pub description: Option<String>,
+    pub targets: Vec<TargetSummary>,
+    pub default_features: Vec<String>,
+    /// フィーチャー名と、そのフィーチャーが有効にするもの
+    pub features: BTreeMap<String, Vec<String>>,
+    pub dependencies: Vec<DirectDependency>,
+}
+
+#[derive(Serialize)]
+pub struct TargetSummary {
+    pub name: String,
+    pub kind: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DirectDependency {
+    pub name: String,
+    pub req: String,
+    /// `normal`、`dev`、`build` のいずれか
+    pub kind: &'static str,
+    pub optional: bool,
+    /// 依存グラフで解決されたバージョン
+    pub resolved: Option<String>,
+    /// 依存グラフで解決されたパッケージの ID
+    pub pkgid: Option<String>,
+    /// 成果物依存関係 (`artifact = "bin"` など) の場合の成果物の種類とターゲット
+    pub artifact: Option<ArtifactSpec>,
+}
+
+/// プロジェクト名 (ワークスペースルートのディレクトリ名)
+pub fn project_name(metadata: &Metadata) -> String {
+    metadata
+        .workspace_root
+        .file_name()
+        .unwrap_or(metadata.workspace_root.as_str())
+        .to_string()
+}
+
+/// リソースの URI で使用するプロジェクトのキー
+///
+/// プロジェクト名をパーセントエンコードしたもの。`projects` に同じ名前の別のワークスペースがある場合は、
+/// 個別に指定できるよう、ワークスペースルートのパスのハッシュを付ける (`app-1a2b3c4d`)。
+pub fn project_key(metadata: &Metadata, projects: &[Arc<Metadata>]) -> String {
+    let name = project_name(metadata);
+    let key = percent_encode(&name);
+    let collides = projects
+        .iter()
+        .any(|p| p.workspace_root != metadata.workspace_root && project_name(p) == name);
+    if collides {
+        format!("{key}-{:08x}", fnv1a(metadata.workspace_root.as_str()))
+    } else {
+        key
+    }
+}
+
+/// URI の非予約文字以外をパーセントエンコードする
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// 実行ごとに変わらない、文字列の 32 ビットの FNV-1a ハッシュ
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// パッケージの概要リソースの URI
+pub fn summary_uri(project: &str, package: &str) -> String {
+    format!("cargo://{project}/packages/{package}/summary")
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev",
+        DependencyKind::Build => "build",
+        _ => "normal",
+    }
+}
+
+/// パッケージの直接の依存関係と、依存グラフで解決されたバージョン
+pub fn direct_dependencies(graph: &Graph, package: &Package) -> Vec<DirectDependency> {
+    let resolved: Vec<&Package> = graph
+        .dependencies(&package.id, true)
+        .filter_map(|id| graph.package(id))
+        .collect();
+    let artifacts = ArtifactDependencies::load(package);
+    package
+        .dependencies
+        .iter()
+        .map(|dep| {
+            let package = resolved
+                .iter()
+                .find(|p| p.name == dep.name && dep.req.matches(&p.version));
+            DirectDependency {
+                name: dep.name.clone(),
+                req: dep.req.to_string(),
+                kind: kind_name(dep.kind),
+                optional: dep.optional,
+                resolved: package.map(|p| p.version.to_string()),
+                pkgid: package.map(|p| p.id.repr.clone()),
+                artifact: artifacts.get(dep.kind, &dep.name).cloned(),
+            }
+        })
+        .collect()
+}
+
+/// ワークスペースメンバーの概要を作成する
+pub fn package_summary(metadata: &Metadata, package: &Package) -> PackageSummary {
+    let dependencies = direct_dependencies(&Graph::new(metadata), package);
+    let targets: Vec<TargetSummary> = package
+        .targets
+        .iter()
+        .map(|t| TargetSummary {
+            name: t.name.clone(),
+            kind: t.kind.iter().map(|k| k.to_string()).collect(),
+        })
+        .collect();
+    let default_features = package.features.get("default").cloned().unwrap_or_default();
+    let features: BTreeMap<String, Vec<String>> = package
+        .features
+        .iter()
+        .filter(|(name, _)| *name != "default")
+        .map(|(name, values)| (name.clone(), values.clone()))
+        .collect();
+
+    PackageSummary {
+        name: package.name.clone(),
+        version: package.version.to_string(),
+        pkgid: package.id.repr.clone(),
+        summary: describe(
+            package,
+            &targets,
+            &default_features,
+            &features,
+            &dependencies,
+        ),
+        description: package.description.clone(),
+        targets,
+        default_features,
+        features,
+        dependencies,
+    }
+}
+
+/// ターゲットの種類から、パッケージの種類を表す語句 (`library and binary crate` など) を作る
+fn crate_kind<'a>(target_kinds: impl IntoIterator<Item = &'a str>) -> String {
+    let mut kinds: Vec<&str> = Vec::new();
+    for kind in target_kinds {
+        let kind = match kind {
+            "lib" | "rlib" | "dylib" | "cdylib" | "staticlib" => "library",
+            "proc-macro" => "proc-macro",
+            "bin" => "binary",
+            _ => continue,
+        };
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    if kinds.is_empty() {
+        "package".to_string()
+    } else {
+        format!("{} crate", kinds.join(" and "))
+    }
+}
+
+fn describe(
+    package: &Package,
+    targets: &[TargetSummary],
+    default_features: &[String],
+    features: &BTreeMap<String, Vec<String>>,
+    dependencies: &[DirectDependency],
+) -> String {
+    let kinds = crate_kind(targets.iter().flat_map(|t| &t.kind).map(String::as_str));
+    let mut text = format!("`{}` {} is a {kinds}", package.name, package.version);
+    match package.description.as_deref().map(str::trim) {
+        Some(description) if !description.is_empty() => {
+            text.push_str(&format!(": {}", description.trim_end_matches('.')));
+        }
+        _ => {}
+    }
+    text.push_str(". ");
+
+    let names: Vec<String> = targets
+        .iter()
+        .map(|t| format!("{} {}", t.kind.join("/"), t.name))
+        .collect();
+    text.push_str(&format!(
+        "It has {} target(s) ({})",
+        targets.len(),
+        names.join(", ")
+    ));
+    if features.is_empty() {
+        text.push_str(", no features");
+    } else {
+        text.push_str(&format!(", {} feature(s)", features.len()));
+        if !default_features.is_empty() {
+            text.push_str(&format!(" (default: {})", default_features.join(", ")));
+        }
+    }
+    let count = |kind| dependencies.iter().filter(|d| d.kind == kind).count();
+    let optional = dependencies.iter().filter(|d| d.optional).count();
+    text.push_str(&format!(
+        " and {} direct dependencies ({} normal, {} dev, {} build; {} optional).",
+        dependencies.len(),
+        count("normal"),
+        count("dev"),
+        count("build"),
+        optional
+    ));
+    text
+}
+
+#[derive(Serialize)]
+pub struct ProjectOverview {
+    pub workspace: WorkspaceShape,
+    /// 他のメンバーから多く依存されている順に並べたワークスペースメンバー
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<MemberOverview>,
+    /// 直接依存の機能分類と、分類ごとのクレート数
+    pub categories: Vec<CategoryOverview>,
+    pub risks: Vec<Risk>,
+    /// 詳細さの指定により省略した要素の数
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub omitted: BTreeMap<&'static str, usize>,
+}
+
+#[derive(Serialize)]
+pub struct WorkspaceShape {
+    pub name: String,
+    pub root: String,
+    pub members: usize,
+    /// ワークスペースメンバーを除くパッケージの数
+    pub dependencies: usize,
+    /// ワークスペースメンバーが直接依存しているパッケージの数
+    pub direct_dependencies: usize,
+    pub summary: String,
+}
+
+#[derive(Serialize)]
+pub struct MemberOverview {
+    pub name: String,
+    pub version: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// このメンバーに依存している他のメンバーの数
+    pub dependents: usize,
+    pub dependencies: usize,
+    pub features: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct CategoryOverview {
+    pub name: String,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub crates: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Risk {
+    /// `duplicate-versions`、`git-source`、`unknown-license`、`manifest` のいずれか
+    pub kind: &'static str,
+    pub message: String,
+}
+
+/// 一覧を詳細さに応じた数に切り詰め、省略した数を記録する
+fn truncate<T>(
+    items: &mut Vec<T>,
+    limit: Option<usize>,
+    name: &'static str,
+    omitted: &mut BTreeMap<&'static str, usize>,
+) {
+    if let Some(limit) = limit
+        && items.len() > limit
+    {
+        omitted.insert(name, items.len() - limit);
+        items.truncate(limit);
+    }
+}
+
+/// プロジェクトの構成、主要なメンバー、依存関係の分類、フィーチャー、注意すべき点を簡潔にまとめる
+pub fn project_overview(metadata: &Metadata, detail: Detail) -> ProjectOverview {
+    let graph = Graph::new(metadata);
+    let member_ids: Vec<&PackageId> = graph.members().collect();
+    let direct = category::direct_dependencies(&graph);
+    let dependency_count = metadata
+        .packages
+        .iter()
+        .filter(|p| !metadata.workspace_members.contains(&p.id))
+        .count();
+    let mut omitted = BTreeMap::new();
+
+    let mut members: Vec<MemberOverview> = member_ids
+        .iter()
+        .filter_map(|id| graph.package(id))
+        .map(|package| MemberOverview {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            kind: crate_kind(
+                package
+                    .targets
+                    .iter()
+                    .flat_map(|t| &t.kind)
+                    .map(|k| k.as_str()),
+            ),
+            description: package.description.clone(),
+            dependents: member_ids
+                .iter()
+                .filter(|m| graph.dependencies(m, true).any(|d| *d == package.id))
+                .count(),
+            dependencies: package.dependencies.len(),
+            features: package
+                .features
+                .keys()
+                .filter(|f| *f != "default")
+                .cloned()
+                .collect(),
+        })
+        .collect();
+    members.sort_by(|a, b| b.dependents.cmp(&a.dependents).then(a.name.cmp(&b.name)));
+    if detail == Detail::Summary {
+        omitted.insert("members", members.len());
+        members.clear();
+    } else {
+        truncate(&mut members, detail.limit(0, 10), "members", &mut omitted);
+    }
+
+    let mut categories: Vec<CategoryOverview> = category::category_map(metadata)
+        .clusters
+        .into_iter()
+        .map(|cluster| CategoryOverview {
+            name: cluster.name,
+            count: cluster.crates.len(),
+            crates: match detail {
+                Detail::Full => cluster.crates.into_iter().map(|c| c.name).collect(),
+                _ => Vec::new(),
+            },
+        })
+        .collect();
+    categories.sort_by(|a, b| b.count.cmp(&a.count).then(a.name.cmp(&b.name)));
+    truncate(
+        &mut categories,
+        detail.limit(5, 15),
+        "categories",
+        &mut omitted,
+    );
+
+    let mut risks = project_risks(metadata);
+    truncate(&mut risks, detail.limit(5, 15), "risks", &mut omitted);
+
+    let name = project_name(metadata);
+    let summary = format!(
+        "Workspace `{name}` has {} member(s) and {} dependencies ({} direct).",
+        member_ids.len(),
+        dependency_count,
+        direct.len()
+    );
+    ProjectOverview {
+        workspace: WorkspaceShape {
+            name,
+            root: metadata.workspace_root.to_string(),
+            members: member_ids.len(),
+            dependencies: dependency_count,
+            direct_dependencies: direct.len(),
+            summary,
+        },
+        members,
+        categories,
+        risks,
+        omitted,
+    }
+}
+
+/// 概要で報告する、依存関係とマニフェストの注意すべき点
+fn project_risks(metadata: &Metadata) -> Vec<Risk> {
+    let mut risks = Vec::new();
+    let mut versions: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    for package in &metadata.packages {
+        versions
+            .entry(package.name.as_str())
+            .or_default()
+            .insert(compat_key(&package.version));
+    }
+    for (name, keys) in versions.iter().filter(|(_, keys)| keys.len() > 1) {
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        risks.push(Risk {
+            kind: "duplicate-versions",
+            message: format!(
+                "`{name}` is used in {} semver-incompatible versions ({})",
+                keys.len(),
+                keys.join(", ")
+            ),
+        });
+    }
+    for package in metadata
+        .packages
+        .iter()
+        .filter(|p| !metadata.workspace_members.contains(&p.id))
+    {
+        if package
+            .source
+            .as_ref()
+            .is_some_and(|s| s.repr.starts_with("git+"))
+        {
+            risks.push(Risk {
+                kind: "git-source",
+                message: format!("`{}@{}` is fetched from git", package.name, package.version),
+            });
+        }
+        let license_known = match &package.license {
+            Some(license) => normalize_license(license).spdx_valid,
+            None => package.license_file.is_some(),
+        };
+        if !license_known {
+            risks.push(Risk {
+                kind: "unknown-license",
+                message: format!(
+                    "`{}@{}` has no valid SPDX license expression",
+                    package.name, package.version
+                ),
+            });
+        }
+    }
+    for finding in lint::lint_manifests(metadata).findings {
+        if finding.level == Level::Error {
+            risks.push(Risk {
+                kind: "manifest",
+                message: finding.message,
+            });
+        }
+    }
+    risks
+}
+
+/// 依存関係の統計
+#[derive(Serialize)]
+pub struct DependencyStatistics {
+    pub workspace_members: usize,
+    /// ワークスペースメンバーが直接依存しているパッケージの数 (ワークスペースメンバーを除く)
+    pub direct_dependencies: usize,
+    /// ワークスペースメンバーから推移的にのみ到達できるパッケージの数
+    pub transitive_dependencies: usize,
+    /// 依存関係のクレート名の数 (複数のバージョンがあるクレートは 1 つと数える)
+    pub unique_crates: usize,
+    /// 複数のバージョンに解決されたクレートの数
+    pub duplicate_versions: usize,
+    /// ワークスペースメンバーのターゲットの種類ごとの数
+    pub targets: BTreeMap<String, usize>,
+    /// 依存関係の正規化されたライセンス式ごとのパッケージ数
+    pub licenses: BTreeMap<String, usize>,
+    /// `license` を宣言していない依存関係の数
+    pub unlicensed: usize,
+}
+
+/// ワークスペースメンバーと、そこから到達できる依存関係の数を数える
+pub fn dependency_statistics(metadata: &Metadata, include_dev: bool) -> DependencyStatistics {
+    let graph = Graph::new(metadata);
+    let members: HashSet<&PackageId> = graph.members().collect();
+    let mut reachable: HashSet<&PackageId> = HashSet::new();
+    let mut direct: HashSet<&PackageId> = HashSet::new();
+    for &member in &members {
+        reachable.extend(graph.reachable(member, include_dev));
+        direct.extend(graph.dependencies(member, include_dev));
+    }
+    let dependencies: Vec<&Package> = reachable
+        .iter()
+        .filter(|id| !members.contains(*id))
+        .filter_map(|id| graph.package(id))
+        .collect();
+    let direct_count = dependencies
+        .iter()
+        .filter(|p| direct.contains(&p.id))
+        .count();
+
+    let mut targets: BTreeMap<String, usize> = BTreeMap::new();
+    for package in members.iter().filter_map(|id| graph.package(id)) {
+        for kind in package.targets.iter().flat_map(|t| &t.kind) {
+            *targets.entry(kind.to_string()).or_default() += 1;
+        }
+    }
+    let mut licenses: BTreeMap<String, usize> = BTreeMap::new();
+    let mut unlicensed = 0;
+    for package in &dependencies {
+        match &package.license {
+            Some(license) => {
+                let license = normalize_license(license).spdx.unwrap_or(license.clone());
+                *licenses.entry(license).or_default() += 1;
+            }
+            None => unlicensed += 1,
+        }
+    }
+
+    DependencyStatistics {
+        workspace_members: members.len(),
+        direct_dependencies: direct_count,
+        transitive_dependencies: dependencies.len() - direct_count,
+        unique_crates: dependencies
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<HashSet<_>>()
+            .len(),
+        duplicate_versions: find_duplicates(&graph, include_dev, 0).len(),
+        targets,
+        licenses,
+        unlicensed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_project_key() {
+        let project = |root: &str| {
+            let mut metadata: Metadata =
+                serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+            metadata.workspace_root = root.into();
+            Arc::new(metadata)
+        };
+        let (a, b, c) = (project("/a/app"), project("/b/app"), project("/c/my app"));
+        let projects = [a.clone(), b.clone(), c.clone()];
+        let (key_a, key_b) = (project_key(&a, &projects), project_key(&b, &projects));
+        assert_ne!(key_a, key_b);
+        assert!(key_a.starts_with("app-") && key_b.starts_with("app-"));
+        assert_eq!(key_a, project_key(&a, &[b.clone(), a.clone()]));
+        assert_eq!(project_key(&a, std::slice::from_ref(&a)), "app");
+        assert_eq!(project_key(&c, &projects), "my%20app");
+    }
+
+    #[test]
+    fn test_crate_kind() {
+        assert_eq!(
+            crate_kind(["lib", "bin", "test"]),
+            "library and binary crate"
+        );
+        assert_eq!(crate_kind(["cdylib", "rlib"]), "library crate");
+        assert_eq!(crate_kind(["example"]), "package");
+    }
+
+    #[test]
+    fn test_describe() {
+        let package: Package = serde_json::from_value(serde_json::json!({
+            "name": "foo",
+            "version": "1.2.3",
+            "id": "path+file:///foo#1.2.3",
+            "description": "Does foo things.",
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+            "manifest_path": "/foo/Cargo.toml",
+        }))
+        .unwrap();
+        let targets = [TargetSummary {
+            name: "foo".to_string(),
+            kind: vec!["lib".to_string()],
+        }];
+        let features = BTreeMap::from([("std".to_string(), Vec::new())]);
+        let dependencies = [DirectDependency {
+            name: "bar".to_string(),
+            req: "^1".to_string(),
+            kind: "dev",
+            optional: false,
+            resolved: None,
+            pkgid: None,
+            artifact: None,
+        }];
+        assert_eq!(
+            describe(
+                &package,
+                &targets,
+                &["std".to_string()],
+                &features,
+                &dependencies
+            ),
+            "`foo` 1.2.3 is a library crate: Does foo things. It has 1 target(s) (lib foo), 1 feature(s) (default: std) and 1 direct dependencies (0 normal, 1 dev, 0 build; 0 optional)."
+        );
+    }
+
+    #[test]
+    fn test_dependency_statistics() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let statistics = dependency_statistics(&metadata, false);
+        assert_eq!(statistics.workspace_members, 2);
+        assert_eq!(statistics.direct_dependencies, 0);
+        assert_eq!(statistics.transitive_dependencies, 0);
+        assert_eq!(statistics.unique_crates, 0);
+        assert!(statistics.licenses.is_empty());
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let statistics = dependency_statistics(&metadata, false);
+        assert_eq!(statistics.workspace_members, 1);
+        assert_eq!(statistics.targets.get("bin"), Some(&1));
+        assert!(statistics.direct_dependencies >= 10);
+        assert!(statistics.transitive_dependencies > statistics.direct_dependencies);
+        assert!(
+            statistics.unique_crates
+                <= statistics.direct_dependencies + statistics.transitive_dependencies
+        );
+        assert!(statistics.licenses.contains_key("MIT OR Apache-2.0"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use cargo_metadata::{Metadata, Target};
+use jiff::Timestamp;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct TargetDirReport {
+    /// cargo が解決したターゲットディレクトリ (`CARGO_TARGET_DIR` と設定ファイルの `build.target-dir` を反映したもの)
+    pub target_directory: String,
+    pub exists: bool,
+    pub total_size: u64,
+    pub profiles: Vec<ProfileDir>,
+}
+
+/// プロファイルごとの出力ディレクトリ (`debug`、`release`、`<target-triple>/debug` など)
+#[derive(Serialize)]
+pub struct ProfileDir {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub artifacts: Vec<Artifact>,
+}
+
+/// ワークスペースのメンバーのターゲットのビルド済みの成果物
+#[derive(Serialize)]
+pub struct Artifact {
+    pub package: String,
+    pub target: String,
+    pub kind: String,
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<String>,
+}
+
+/// 成果物のファイル名の拡張子 (ライブラリのクレートの種類ごと)
+const LIBRARY_EXTENSIONS: &[&str] = &["rlib", "so", "dylib", "dll", "a", "lib", "wasm"];
+
+/// ターゲットディレクトリのプロファイルごとのサイズと、ワークスペースのメンバーのビルド済みの成果物を返す
+pub fn target_dir_report(metadata: &Metadata) -> TargetDirReport {
+    let root = metadata.target_directory.as_std_path();
+    let mut profiles = Vec::new();
+    for dir in profile_dirs(root) {
+        let name = dir
+            .strip_prefix(root)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let mut artifacts = Vec::new();
+        for package in metadata.workspace_packages() {
+            for target in &package.targets {
+                for path in artifact_paths(&dir, target) {
+                    let Ok(file) = std::fs::metadata(&path) else {
+                        continue;
+                    };
+                    artifacts.push(Artifact {
+                        package: package.name.clone(),
+                        target: target.name.clone(),
+                        kind: target.kind.join(","),
+                        path: path.display().to_string(),
+                        size: file.len(),
+                        modified: file
+                            .modified()
+                            .ok()
+                            .and_then(|t| Timestamp::try_from(t).ok())
+                            .map(|t| t.to_string()),
+                    });
+                }
+            }
+        }
+        profiles.push(ProfileDir {
+            name,
+            path: dir.display().to_string(),
+            size: dir_size(&dir),
+            artifacts,
+        });
+    }
+    TargetDirReport {
+        target_directory: metadata.target_directory.to_string(),
+        exists: root.is_dir(),
+        total_size: dir_size(root),
+        profiles,
+    }
+}
+
+/// `deps` ディレクトリを含む、ターゲットディレクトリ直下またはターゲットトリプルのディレクトリ直下のディレクトリ
+fn profile_dirs(root: &Path) -> Vec<std::path::PathBuf> {
+    let mut dirs = Vec::new();
+    for entry in read_dirs(root) {
+        if entry.join("deps").is_dir() {
+            dirs.push(entry);
+        } else {
+            dirs.extend(read_dirs(&entry).filter(|d| d.join("deps").is_dir()));
+        }
+    }
+    dirs.sort();
+    dirs
+}
+
+fn read_dirs(dir: &Path) -> impl Iterator<Item = std::path::PathBuf> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+}
+
+/// ターゲットの成果物が置かれるパスの候補
+///
+/// テストとベンチマークは `deps` 以下にハッシュ付きのファイル名で出力されるため、一致するものを全て返す。
+fn artifact_paths(profile: &Path, target: &Target) -> Vec<std::path::PathBuf> {
+    let kinds: Vec<&str> = target.kind.iter().map(|k| k.as_str()).collect();
+    let crate_name = target.name.replace('-', "_");
+    let exe = std::env::consts::EXE_SUFFIX;
+    let mut paths = Vec::new();
+    if kinds.contains(&"bin") {
+        paths.push(profile.join(format!("{}{exe}", target.name)));
+    }
+    if kinds.contains(&"example") {
+        paths.push(
+            profile
+                .join("examples")
+                .join(format!("{}{exe}", target.name)),
+        );
+    }
+    if kinds
+        .iter()
+        .any(|k| matches!(*k, "lib" | "rlib" | "dylib" | "cdylib" | "staticlib"))
+    {
+        for extension in LIBRARY_EXTENSIONS {
+            paths.push(profile.join(format!("lib{crate_name}.{extension}")));
+            paths.push(profile.join(format!("{crate_name}.{extension}")));
+        }
+    }
+    if kinds.contains(&"test") || kinds.contains(&"bench") {
+        let prefix = format!("{crate_name}-");
+        for entry in std::fs::read_dir(profile.join("deps"))
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let executable = match exe {
+                "" => !file_name.contains('.'),
+                exe => file_name.ends_with(exe),
+            };
+            if file_name.starts_with(&prefix) && executable {
+                paths.push(entry.path());
+            }
+        }
+    }
+    paths
+}
+
+/// ディレクトリ以下のファイルの合計サイズ (シンボリックリンクはたどらない)
+fn dir_size(dir: &Path) -> u64 {
+    let mut size = 0;
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => stack.push(entry.path()),
+                Ok(t) if t.is_file() => size += entry.metadata().map(|m| m.len()).unwrap_or(0),
+                _ => {}
+            }
+        }
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_dirs() {
+        let root = std::env::temp_dir().join(format!("target-dir-test-{}", std::process::id()));
+        for dir in [
+            "debug/deps",
+            "release/deps",
+            "x86_64-unknown-linux-gnu/debug/deps",
+            "tmp",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("debug/deps/a"), "1234").unwrap();
+        let dirs = profile_dirs(&root);
+        let size = dir_size(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        let names: Vec<_> = dirs
+            .iter()
+            .map(|d| d.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                Path::new("debug"),
+                Path::new("release"),
+                Path::new("x86_64-unknown-linux-gnu/debug")
+            ]
+        );
+        assert_eq!(size, 4);
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+/// テスト用の一時的なワークスペース
+///
+/// 破棄時に削除するため、テストが失敗した場合もディレクトリが残らない。
+pub(crate) struct TempWorkspace {
+    root: PathBuf,
+}
+
+impl TempWorkspace {
+    /// `{name}-test-{プロセス ID}` という名前で、空のディレクトリを作成する
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("{name}-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+
+    /// ルートの Cargo.toml のパス
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root.join("Cargo.toml")
+    }
+
+    /// ルートからの相対パスにファイルを書き込む (親ディレクトリも作成する)
+    pub fn write(&self, path: &str, text: &str) {
+        let path = self.root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+}
+
+impl Drop for TempWorkspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
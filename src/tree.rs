@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+
+use cargo_metadata::{DependencyKind, NodeDep, PackageId};
+
+use crate::graph::Graph;
+
+/// 依存関係の木の表示方法
+#[derive(Default)]
+pub struct TreeOptions {
+    /// 表示する深さの上限 (`cargo tree --depth` 相当、`None` は無制限)
+    pub max_depth: Option<usize>,
+    /// dev-dependencies を含めるかどうか
+    pub include_dev: bool,
+    /// 依存先ではなく依存元を辿る (`cargo tree --invert` 相当)
+    pub invert: bool,
+}
+
+/// 依存関係の種類ごとの見出し (`cargo tree` と同じく、通常の依存関係には見出しを付けない)
+const SECTIONS: [(DependencyKind, Option<&str>); 3] = [
+    (DependencyKind::Normal, None),
+    (DependencyKind::Build, Some("[build-dependencies]")),
+    (DependencyKind::Development, Some("[dev-dependencies]")),
+];
+
+fn has_kind(dep: &NodeDep, kind: DependencyKind) -> bool {
+    if dep.dep_kinds.is_empty() {
+        return kind == DependencyKind::Normal;
+    }
+    dep.dep_kinds.iter().any(|k| k.kind == kind)
+}
+
+/// `cargo tree` と同じ形式のパッケージの表示 (`serde v1.0.200`、パス依存関係はディレクトリ、proc-macro は `(proc-macro)` 付き)
+fn label(graph: &Graph, id: &PackageId) -> String {
+    let Some(package) = graph.package(id) else {
+        return id.repr.clone();
+    };
+    let mut label = format!("{} v{}", package.name, package.version);
+    if package.source.is_none()
+        && let Some(dir) = package.manifest_path.parent()
+    {
+        label.push_str(&format!(" ({dir})"));
+    }
+    if package
+        .targets
+        .iter()
+        .any(|t| t.kind.iter().any(|k| k == "proc-macro"))
+    {
+        label.push_str(" (proc-macro)");
+    }
+    label
+}
+
+/// 木の子として辿る辺 (反転した木では依存元のパッケージと、依存元からの辺)
+type Adjacency<'a> = HashMap<&'a PackageId, Vec<(&'a PackageId, &'a NodeDep)>>;
+
+fn adjacency<'a>(graph: &Graph<'a>, invert: bool) -> Adjacency<'a> {
+    let mut adjacency: Adjacency = HashMap::new();
+    for package in &graph.metadata.packages {
+        for dep in graph.edges(&package.id, true) {
+            if invert {
+                adjacency
+                    .entry(&dep.pkg)
+                    .or_default()
+                    .push((&package.id, dep));
+            } else {
+                adjacency
+                    .entry(&package.id)
+                    .or_default()
+                    .push((&dep.pkg, dep));
+            }
+        }
+    }
+    adjacency
+}
+
+/// `roots` から始まる依存関係の木を `cargo tree` のような字下げした文字列で表す
+///
+/// 同じ木の中で一度表示したパッケージの子は繰り返さず、`(*)` を付ける。
+/// `options.invert` が真の場合は `cargo tree --invert` と同じく、依存元のパッケージを子として辿る。
+pub fn render(graph: &Graph, roots: &[&PackageId], options: &TreeOptions) -> String {
+    let tree = Tree {
+        graph,
+        adjacency: adjacency(graph, options.invert),
+        options,
+    };
+    let mut out = String::new();
+    for (i, root) in roots.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let mut visited = HashSet::new();
+        tree.write_package(root, "", 0, &mut visited, &mut out);
+    }
+    out
+}
+
+struct Tree<'a, 'g> {
+    graph: &'g Graph<'a>,
+    adjacency: Adjacency<'a>,
+    options: &'g TreeOptions,
+}
+
+impl<'a> Tree<'a, '_> {
+    /// 種類が `kind` の辺で辿る子 (表示名の順)
+    fn children(&self, id: &PackageId, kind: DependencyKind) -> Vec<&'a PackageId> {
+        let mut children: Vec<&PackageId> = self
+            .adjacency
+            .get(id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, dep)| has_kind(dep, kind))
+            .map(|(child, _)| *child)
+            .collect();
+        children.sort_by_key(|id| label(self.graph, id));
+        children.dedup();
+        children
+    }
+
+    /// 表示する種類の依存関係の見出しと子
+    fn sections(&self, id: &PackageId) -> Vec<(Option<&'static str>, Vec<&'a PackageId>)> {
+        SECTIONS
+            .into_iter()
+            .filter(|(kind, _)| self.options.include_dev || *kind != DependencyKind::Development)
+            .map(|(kind, header)| (header, self.children(id, kind)))
+            .filter(|(_, children)| !children.is_empty())
+            .collect()
+    }
+
+    fn write_package(
+        &self,
+        id: &'a PackageId,
+        prefix: &str,
+        depth: usize,
+        visited: &mut HashSet<&'a PackageId>,
+        out: &mut String,
+    ) {
+        out.push_str(&label(self.graph, id));
+        let expanded = !visited.insert(id);
+        let sections = self.sections(id);
+        if expanded && !sections.is_empty() {
+            out.push_str(" (*)");
+        }
+        out.push('\n');
+        if expanded || self.options.max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+        for (header, children) in sections {
+            if let Some(header) = header {
+                out.push_str(prefix);
+                out.push_str(header);
+                out.push('\n');
+            }
+            for (i, child) in children.iter().enumerate() {
+                let last = i + 1 == children.len();
+                out.push_str(prefix);
+                out.push_str(if last { "└── " } else { "├── " });
+                let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+                self.write_package(child, &prefix, depth + 1, visited, out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let graph = Graph::new(&metadata);
+        let root = &metadata.root_package().unwrap().id;
+
+        let tree = render(&graph, &[root], &TreeOptions::default());
+        let mut lines = tree.lines();
+        assert!(
+            lines
+                .next()
+                .unwrap()
+                .starts_with("mcp-attr-example-cargo-metadata v0.1.0 (")
+        );
+        assert!(tree.contains("├── serde v1."));
+        assert!(tree.contains("│   "));
+        assert!(tree.contains("(proc-macro)"));
+        assert!(tree.contains(" (*)\n"));
+        assert!(!tree.contains("[dev-dependencies]"));
+
+        let options = TreeOptions {
+            max_depth: Some(1),
+            include_dev: true,
+            ..TreeOptions::default()
+        };
+        let tree = render(&graph, &[root], &options);
+        assert!(tree.lines().skip(1).all(|line| {
+            line.starts_with("├── ") || line.starts_with("└── ") || line.starts_with('[')
+        }));
+
+        // serde_derive には serde 経由でこのクレートまで辿れる依存元がある
+        let serde_derive = &metadata
+            .packages
+            .iter()
+            .find(|p| p.name == "serde_derive")
+            .unwrap()
+            .id;
+        let options = TreeOptions {
+            invert: true,
+            ..TreeOptions::default()
+        };
+        let tree = render(&graph, &[serde_derive], &options);
+        assert!(tree.starts_with("serde_derive v1."));
+        assert!(tree.contains("── serde v1."));
+        assert!(tree.contains("── mcp-attr-example-cargo-metadata v0.1.0 ("));
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+
+use cargo_metadata::{Metadata, Package, PackageId};
+use serde::Serialize;
+
+use crate::features::source_files;
+use crate::graph::Graph;
+
+/// パッケージごとの `unsafe` の使用状況 (cargo-geiger 相当)
+#[derive(Serialize, Default)]
+pub struct UnsafeUsage {
+    /// `name@version`
+    pub package: String,
+    pub workspace_member: bool,
+    /// クレートのルートに `#![forbid(unsafe_code)]` があるかどうか
+    pub forbids_unsafe: bool,
+    pub files_scanned: usize,
+    #[serde(flatten)]
+    pub counts: UnsafeCounts,
+}
+
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct UnsafeCounts {
+    /// `unsafe fn` (`unsafe extern "C" fn` を含む)
+    pub functions: usize,
+    /// `unsafe { ... }`
+    pub blocks: usize,
+    /// `unsafe impl`
+    pub impls: usize,
+    /// `unsafe trait`
+    pub traits: usize,
+    pub total: usize,
+}
+
+impl UnsafeCounts {
+    fn add(&mut self, other: &UnsafeCounts) {
+        self.functions += other.functions;
+        self.blocks += other.blocks;
+        self.impls += other.impls;
+        self.traits += other.traits;
+        self.total += other.total;
+    }
+}
+
+/// コメントを取り除き、文字列と文字のリテラルを `""` に置き換える
+fn strip_comments_and_literals(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            // ブロックコメントは入れ子にできる
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            out.push(' ');
+        } else if c == 'r' && matches!(next, Some('"' | '#')) && is_raw_prefix(&chars, i) {
+            // 生文字列リテラル (`r"..."`、`r#"..."#`)
+            let hashes = chars[i + 1..].iter().take_while(|c| **c == '#').count();
+            if chars.get(i + 1 + hashes) != Some(&'"') {
+                out.push(c);
+                i += 1;
+                continue;
+            }
+            i += 2 + hashes;
+            while i < chars.len() {
+                if chars[i] == '"'
+                    && chars[i + 1..].iter().take_while(|c| **c == '#').count() >= hashes
+                {
+                    i += 1 + hashes;
+                    break;
+                }
+                i += 1;
+            }
+            out.push_str("\"\"");
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i += 1;
+            out.push_str("\"\"");
+        } else if c == '\'' && next == Some('\\') {
+            i += 2;
+            while i < chars.len() && chars[i] != '\'' {
+                i += 1;
+            }
+            i += 1;
+            out.push_str("\"\"");
+        } else if c == '\'' && chars.get(i + 2) == Some(&'\'') {
+            i += 3;
+            out.push_str("\"\"");
+        } else {
+            // ライフタイムはそのまま残す
+            out.push(c);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// `i` の `r` が生文字列リテラルの接頭辞 (`r`、`br`、`cr`) かどうか
+fn is_raw_prefix(chars: &[char], i: usize) -> bool {
+    let is_ident = |j: usize| chars[j].is_alphanumeric() || chars[j] == '_';
+    match i {
+        0 => true,
+        1 => !is_ident(0) || matches!(chars[0], 'b' | 'c'),
+        _ => !is_ident(i - 1) || (matches!(chars[i - 1], 'b' | 'c') && !is_ident(i - 2)),
+    }
+}
+
+/// ソースコード中の `unsafe` の数を、続くトークンの種類ごとに数える
+fn count_unsafe(text: &str) -> UnsafeCounts {
+    let text = strip_comments_and_literals(text);
+    let tokens: Vec<&str> = tokens(&text);
+    let mut counts = UnsafeCounts::default();
+    for (i, token) in tokens.iter().enumerate() {
+        if *token != "unsafe" {
+            continue;
+        }
+        let mut next = tokens[i + 1..].iter().copied();
+        let counted = match next.next() {
+            Some("{") => &mut counts.blocks,
+            Some("fn") => &mut counts.functions,
+            Some("impl") => &mut counts.impls,
+            Some("trait") | Some("auto") => &mut counts.traits,
+            Some("extern") => match next.find(|t| *t != "\"\"") {
+                Some("fn") => &mut counts.functions,
+                _ => continue,
+            },
+            // `unsafe(no_mangle)` などの属性
+            _ => continue,
+        };
+        *counted += 1;
+        counts.total += 1;
+    }
+    counts
+}
+
+/// 識別子、`""`、その他の 1 文字の記号に分ける (空白は捨てる)
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_alphanumeric() || c == '_' {
+            rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len())
+        } else if rest.starts_with("\"\"") {
+            2
+        } else {
+            c.len_utf8()
+        };
+        if !c.is_whitespace() {
+            tokens.push(&rest[..len]);
+        }
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// パッケージのソースファイルの `unsafe` の使用状況
+pub fn unsafe_usage(package: &Package, workspace_member: bool) -> UnsafeUsage {
+    let files = source_files(package);
+    let mut usage = UnsafeUsage {
+        package: format!("{}@{}", package.name, package.version),
+        workspace_member,
+        files_scanned: files.len(),
+        ..UnsafeUsage::default()
+    };
+    for file in &files {
+        let Ok(text) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        usage.counts.add(&count_unsafe(&text));
+        let is_root = package.targets.iter().any(|t| &t.src_path == file);
+        if is_root {
+            let text: String = strip_comments_and_literals(&text)
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            usage.forbids_unsafe |= text.contains("#![forbid(unsafe_code");
+        }
+    }
+    usage
+}
+
+/// ワークスペースメンバーとそれらから到達できるパッケージの `unsafe` の使用状況 (使用数の多い順)
+pub fn unsafe_report(metadata: &Metadata, include_dev: bool) -> Vec<UnsafeUsage> {
+    let graph = Graph::new(metadata);
+    let members: HashSet<&PackageId> = graph.members().collect();
+    let mut reachable: HashSet<&PackageId> = HashSet::new();
+    for member in &members {
+        reachable.extend(graph.reachable(member, include_dev));
+    }
+    let mut report: Vec<UnsafeUsage> = reachable
+        .iter()
+        .filter_map(|id| graph.package(id))
+        .map(|package| unsafe_usage(package, members.contains(&package.id)))
+        .collect();
+    report.sort_by(|a, b| {
+        b.counts
+            .total
+            .cmp(&a.counts.total)
+            .then_with(|| a.package.cmp(&b.package))
+    });
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::MetadataCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_count_unsafe() {
+        let source = r##"
+#![forbid(unsafe_code)]
+// unsafe { commented }
+/* unsafe fn /* nested */ unsafe impl */
+unsafe fn f<'a>(x: &'a u8) -> char {
+    let s = "unsafe { in a string }";
+    let r = r#"unsafe " fn"#;
+    let b = br"unsafe {";
+    let c = '"';
+    unsafe { g() };
+    'x'
+}
+pub unsafe extern "C" fn h() {}
+unsafe impl Send for S {}
+pub unsafe trait T {}
+#[unsafe(no_mangle)]
+fn unsafe_name() {}
+unsafe extern "C" {
+    fn k();
+}
+"##;
+        assert_eq!(
+            count_unsafe(source),
+            UnsafeCounts {
+                functions: 2,
+                blocks: 1,
+                impls: 1,
+                traits: 1,
+                total: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unsafe_report() {
+        let metadata = MetadataCommand::new()
+            .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .exec()
+            .unwrap();
+        let report = unsafe_report(&metadata, false);
+        let root = report
+            .iter()
+            .find(|u| u.package.starts_with("mcp-attr-example-cargo-metadata@"))
+            .unwrap();
+        assert!(root.workspace_member);
+        assert!(root.files_scanned > 0);
+        assert_eq!(root.counts.total, 0);
+        assert!(
+            report
+                .windows(2)
+                .all(|w| w[0].counts.total >= w[1].counts.total)
+        );
+    }
+}
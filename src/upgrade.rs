@@ -0,0 +1,273 @@
+use cargo_metadata::semver::Version;
+use cargo_metadata::{Metadata, Package};
+use mcp_attr::Result;
+use serde::Serialize;
+
+use crate::error::{ErrorKind, bail_kind};
+use crate::lockfile::{self, ChangeKind, Lockfile, LockfileChange};
+use crate::pkgid::PackageIdSpec;
+use crate::policy::compat_key;
+use crate::sandbox::Sandbox;
+
+#[derive(Serialize)]
+pub struct UpgradeSimulation {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    /// 依存関係の解決に成功したかどうか
+    pub success: bool,
+    /// 依存関係の解決に失敗した場合の cargo のエラー出力
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub summary: ChangeSummary,
+    /// 指定したクレート以外も含む、ロックファイルの全ての変更
+    pub changes: Vec<LockfileChange>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ChangeSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub upgraded: usize,
+    pub downgraded: usize,
+    pub changed: usize,
+    /// semver 非互換な更新の数
+    pub breaking: usize,
+}
+
+impl ChangeSummary {
+    pub fn new<'a>(changes: impl IntoIterator<Item = &'a LockfileChange>) -> Self {
+        let mut summary = Self::default();
+        for change in changes {
+            *match change.kind {
+                ChangeKind::Added => &mut summary.added,
+                ChangeKind::Removed => &mut summary.removed,
+                ChangeKind::Upgraded => &mut summary.upgraded,
+                ChangeKind::Downgraded => &mut summary.downgraded,
+                ChangeKind::Changed => &mut summary.changed,
+            } += 1;
+            if change.breaking {
+                summary.breaking += 1;
+            }
+        }
+        summary
+    }
+}
+
+/// 1 つの依存関係を指定したバージョンに更新した場合のロックファイルの変更を、一時的なコピーで調べる
+///
+/// `cargo update -p <crate> --precise <version>` を実行し、実行前後のロックファイルを比較する。
+/// `crate_name` にはパッケージ ID 仕様 (`name@version` など) も指定できる。
+/// 依存グラフに一致するパッケージが複数ある場合は、更新先と semver 互換なもの (なければ最新のもの) を対象とする。
+pub fn simulate_upgrade(
+    metadata: &Metadata,
+    crate_name: &str,
+    version: &str,
+) -> Result<UpgradeSimulation> {
+    let spec = PackageIdSpec::parse(crate_name)?;
+    let target = match Version::parse(version) {
+        Ok(target) => target,
+        Err(e) => bail_kind!(ErrorKind::InvalidVersion, version, e),
+    };
+    let mut candidates: Vec<&Package> = metadata
+        .packages
+        .iter()
+        .filter(|p| spec.matches(p) && p.source.is_some())
+        .collect();
+    candidates.sort_by(|a, b| a.version.cmp(&b.version));
+    let Some(from) = candidates
+        .iter()
+        .rev()
+        .find(|p| compat_key(&p.version) == compat_key(&target))
+        .or(candidates.last())
+        .copied()
+    else {
+        bail_kind!(ErrorKind::NotRegistryDependency, crate_name);
+    };
+
+    let sandbox = Sandbox::new(metadata)?;
+    sandbox.ensure_lockfile()?;
+    let before = Lockfile::load(&sandbox.lockfile_path())?;
+    // 同じ名前とバージョンのパッケージが複数のソースにある場合も区別できるように、パッケージ ID で指定する
+    let output = sandbox.cargo(&["update", "--package", &from.id.repr, "--precise", version])?;
+    let mut simulation = UpgradeSimulation {
+        name: from.name.clone(),
+        from: from.version.to_string(),
+        to: target.to_string(),
+        success: output.status.success(),
+        error: None,
+        summary: ChangeSummary::default(),
+        changes: Vec::new(),
+    };
+    if !output.status.success() {
+        simulation.error = Some(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        return Ok(simulation);
+    }
+    let after = Lockfile::load(&sandbox.lockfile_path())?;
+    simulation.changes = lockfile::diff(&before, &after);
+    simulation.summary = ChangeSummary::new(&simulation.changes);
+    Ok(simulation)
+}
+
+/// `cargo update --dry-run` が報告する 1 つのパッケージの変更
+#[derive(Serialize)]
+pub struct UpdateChange {
+    #[serde(flatten)]
+    pub change: LockfileChange,
+    /// 依存関係の要件により選ばれなかった、より新しいバージョン
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available: Option<String>,
+}
+
+/// 更新されないが、より新しいバージョンが公開されているパッケージ
+#[derive(Serialize)]
+pub struct UnchangedPackage {
+    pub name: String,
+    pub version: String,
+    pub available: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UpdatePreview {
+    pub success: bool,
+    /// cargo が失敗した場合のエラー出力
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub summary: ChangeSummary,
+    pub changes: Vec<UpdateChange>,
+    pub unchanged: Vec<UnchangedPackage>,
+}
+
+/// 解析した `cargo update` の出力の行
+enum UpdateLine {
+    Change(UpdateChange),
+    Unchanged(UnchangedPackage),
+}
+
+/// `cargo update --dry-run` を実行し、ロックファイルに加えられる変更を返す
+///
+/// `packages` を指定した場合は、それらのパッケージ (`name` または `name@version`) のみを更新の対象とする。
+/// dry run ではロックファイルが書き込まれないため、サンドボックスを使わずに実際のワークスペースで実行する。
+pub fn update_dry_run(metadata: &Metadata, packages: &[String]) -> Result<UpdatePreview> {
+    let manifest = metadata.workspace_root.join("Cargo.toml");
+    let mut command = crate::sandbox::cargo_command(metadata.workspace_root.as_std_path());
+    command
+        .args(["update", "--dry-run", "--verbose", "--color", "never"])
+        .arg("--manifest-path")
+        .arg(&manifest);
+    for package in packages {
+        command.arg("--package").arg(package);
+    }
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(e) => bail_kind!(ErrorKind::CargoSpawnFailed, e),
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut preview = UpdatePreview {
+        success: output.status.success(),
+        error: None,
+        summary: ChangeSummary::default(),
+        changes: Vec::new(),
+        unchanged: Vec::new(),
+    };
+    if !output.status.success() {
+        preview.error = Some(stderr.trim().to_string());
+        return Ok(preview);
+    }
+    for line in stderr.lines().filter_map(parse_update_line) {
+        match line {
+            UpdateLine::Change(change) => preview.changes.push(change),
+            UpdateLine::Unchanged(package) => preview.unchanged.push(package),
+        }
+    }
+    preview.summary = ChangeSummary::new(preview.changes.iter().map(|c| &c.change));
+    Ok(preview)
+}
+
+/// `cargo update` の出力の 1 行を解析する
+///
+/// 例: `    Updating foo v1.0.0 -> v1.0.1 (available: v2.0.0)`、`      Adding bar v0.1.0`
+fn parse_update_line(line: &str) -> Option<UpdateLine> {
+    let (verb, rest) = line.trim_start().split_once(' ')?;
+    let (body, note) = match rest.split_once(" (") {
+        Some((body, note)) => (body, Some(note.trim_end_matches(')'))),
+        None => (rest, None),
+    };
+    let available = note
+        .and_then(|n| n.split(", ").find_map(|p| p.strip_prefix("available: ")))
+        .map(|v| v.trim_start_matches('v').to_string());
+    let words: Vec<&str> = body.split_whitespace().collect();
+    let version = |word: &str| word.strip_prefix('v').map(|v| v.to_string());
+    let (kind, name, from, to) = match (verb, words.as_slice()) {
+        ("Unchanged", [name, v]) => {
+            return Some(UpdateLine::Unchanged(UnchangedPackage {
+                name: name.to_string(),
+                version: version(v)?,
+                available,
+            }));
+        }
+        ("Adding", [name, v]) => (ChangeKind::Added, name, None, Some(version(v)?)),
+        ("Removing", [name, v]) => (ChangeKind::Removed, name, Some(version(v)?), None),
+        ("Updating" | "Upgrading", [name, from, "->", to]) => (
+            ChangeKind::Upgraded,
+            name,
+            Some(version(from)?),
+            Some(version(to)?),
+        ),
+        ("Downgrading", [name, from, "->", to]) => (
+            ChangeKind::Downgraded,
+            name,
+            Some(version(from)?),
+            Some(version(to)?),
+        ),
+        _ => return None,
+    };
+    let breaking = match (&from, &to) {
+        (Some(from), Some(to)) => match (Version::parse(from), Version::parse(to)) {
+            (Ok(from), Ok(to)) => compat_key(&from) != compat_key(&to),
+            _ => false,
+        },
+        _ => false,
+    };
+    Some(UpdateLine::Change(UpdateChange {
+        change: LockfileChange {
+            name: name.to_string(),
+            kind,
+            from,
+            to,
+            breaking,
+        },
+        available,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_update_line() {
+        assert!(parse_update_line("    Updating crates.io index").is_none());
+        let Some(UpdateLine::Change(update)) =
+            parse_update_line("    Updating foo v1.0.0 -> v2.0.1 (available: v2.1.0)")
+        else {
+            panic!("expected a change");
+        };
+        assert_eq!(update.change.kind, ChangeKind::Upgraded);
+        assert_eq!(update.change.from.as_deref(), Some("1.0.0"));
+        assert_eq!(update.change.to.as_deref(), Some("2.0.1"));
+        assert!(update.change.breaking);
+        assert_eq!(update.available.as_deref(), Some("2.1.0"));
+
+        let Some(UpdateLine::Change(update)) = parse_update_line("      Adding bar v0.1.0") else {
+            panic!("expected a change");
+        };
+        assert_eq!(update.change.kind, ChangeKind::Added);
+        let Some(UpdateLine::Unchanged(package)) =
+            parse_update_line("   Unchanged base64 v0.22.1 (available: v0.23.1)")
+        else {
+            panic!("expected an unchanged package");
+        };
+        assert_eq!(package.available.as_deref(), Some("0.23.1"));
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo_metadata::Metadata;
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use cargo_metadata::semver::{Version, VersionReq};
+use mcp_attr::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorKind, bail_kind};
+
+/// cargo-vet のストアの既定のディレクトリ (ワークスペースルートからの相対パス)
+const DEFAULT_STORE: &str = "supply-chain";
+
+/// 1 つまたは複数の監査基準 (`"safe-to-deploy"` または `["safe-to-run", "crypto-reviewed"]`)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Criteria {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Criteria {
+    fn names(&self) -> Vec<String> {
+        match self {
+            Criteria::One(name) => vec![name.clone()],
+            Criteria::Many(names) => names.clone(),
+        }
+    }
+}
+
+/// `audits.toml` と `imports.lock` の監査の記録
+#[derive(Deserialize)]
+struct AuditEntry {
+    criteria: Criteria,
+    /// 全体を監査したバージョン
+    version: Option<String>,
+    /// 差分を監査したバージョン (`1.0.0 -> 1.0.1`)
+    delta: Option<String>,
+    /// 基準に違反するバージョンの範囲
+    violation: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExemptionEntry {
+    version: String,
+    criteria: Criteria,
+    notes: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct AuditsFile {
+    #[serde(default)]
+    audits: BTreeMap<String, Vec<AuditEntry>>,
+}
+
+#[derive(Deserialize, Default)]
+struct ConfigFile {
+    #[serde(default)]
+    exemptions: BTreeMap<String, Vec<ExemptionEntry>>,
+}
+
+#[derive(Deserialize, Default)]
+struct ImportsFile {
+    /// インポート元の名前ごとの監査
+    #[serde(default)]
+    audits: BTreeMap<String, AuditsFile>,
+}
+
+/// cargo-vet による監査の状況
+#[derive(Serialize)]
+pub struct VetReport {
+    /// cargo-vet のストアのディレクトリ
+    pub store: Utf8PathBuf,
+    /// 調べた crates.io のパッケージの数
+    pub checked: usize,
+    /// 監査済みのパッケージの割合 (0.0 〜 1.0)
+    pub coverage: f64,
+    pub audited: Vec<AuditedPackage>,
+    pub exempted: Vec<ExemptedPackage>,
+    /// 監査も免除もされていないパッケージ (`name@version`)
+    pub unaudited: Vec<String>,
+    /// 監査で基準に違反するとされたバージョンのパッケージ (`name@version`)
+    pub violations: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct AuditedPackage {
+    /// `name@version`
+    pub package: String,
+    /// 満たしている監査基準
+    pub criteria: Vec<String>,
+    /// 差分の監査を辿って監査済みとみなしたかどうか
+    pub delta: bool,
+    /// `imports.lock` にある、他のプロジェクトの監査を使ったかどうか
+    pub imported: bool,
+}
+
+#[derive(Serialize)]
+pub struct ExemptedPackage {
+    /// `name@version`
+    pub package: String,
+    pub criteria: Vec<String>,
+    pub notes: Option<String>,
+}
+
+fn read_toml<T: DeserializeOwned + Default>(path: &Utf8Path, required: bool) -> Result<T> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(T::default());
+        }
+        Err(e) => bail_kind!(ErrorKind::ReadFailed, path, e),
+    };
+    match toml::from_str(&text) {
+        Ok(value) => Ok(value),
+        Err(e) => bail_kind!(ErrorKind::ParseFailed, path, e),
+    }
+}
+
+/// cargo-vet のストアのディレクトリ (`[workspace.metadata.vet] store.path`、または `supply-chain`)
+pub fn store_dir(metadata: &Metadata) -> Utf8PathBuf {
+    let path = metadata.workspace_metadata["vet"]["store"]["path"]
+        .as_str()
+        .unwrap_or(DEFAULT_STORE);
+    metadata.workspace_root.join(path)
+}
+
+/// 監査済みのバージョンごとの監査基準
+///
+/// 全体の監査と、監査済みまたは免除されたバージョンからの差分の監査を辿って求める。
+/// 差分の監査で得られる基準は、差分の元のバージョンの基準と差分の監査の基準の両方に含まれるものとする。
+fn audited_versions(
+    audits: &[(&AuditEntry, bool)],
+    exempted: &BTreeMap<Version, Vec<String>>,
+) -> BTreeMap<Version, (BTreeSet<String>, bool, bool)> {
+    let mut audited: BTreeMap<Version, (BTreeSet<String>, bool, bool)> = BTreeMap::new();
+    for (entry, imported) in audits {
+        if let Some(version) = entry.version.as_deref().and_then(|v| v.parse().ok()) {
+            let (criteria, _, from_import) = audited.entry(version).or_default();
+            criteria.extend(entry.criteria.names());
+            *from_import |= *imported;
+        }
+    }
+    let deltas: Vec<(Version, Version, BTreeSet<String>, bool)> = audits
+        .iter()
+        .filter_map(|(entry, imported)| {
+            let (from, to) = entry.delta.as_deref()?.split_once("->")?;
+            Some((
+                from.trim().parse().ok()?,
+                to.trim().parse().ok()?,
+                entry.criteria.names().into_iter().collect(),
+                *imported,
+            ))
+        })
+        .collect();
+    // 差分を適用しても基準が増えなくなるまで繰り返す
+    loop {
+        let mut changed = false;
+        for (from, to, criteria, imported) in &deltas {
+            let base: BTreeSet<String> = match audited.get(from) {
+                Some((base, _, _)) => base.clone(),
+                None => match exempted.get(from) {
+                    Some(base) => base.iter().cloned().collect(),
+                    None => continue,
+                },
+            };
+            let gained: BTreeSet<String> = base.intersection(criteria).cloned().collect();
+            let is_new = !audited.contains_key(to);
+            let (existing, delta, from_import) = audited.entry(to.clone()).or_default();
+            if is_new {
+                *delta = true;
+            }
+            for name in gained {
+                if existing.insert(name) {
+                    changed = true;
+                    *from_import |= *imported;
+                }
+            }
+            if existing.is_empty() && is_new {
+                audited.remove(to);
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    audited
+}
+
+/// cargo-vet のストア (`config.toml`、`audits.toml`、`imports.lock`) を読み、
+/// 依存グラフ内の crates.io のパッケージが監査済みか、免除されているか、監査されていないかを返す
+///
+/// 公開者の信頼 (`trusted`) や監査基準の包含関係は考慮しない。
+pub fn vet_report(metadata: &Metadata) -> Result<VetReport> {
+    let store = store_dir(metadata);
+    let config: ConfigFile = read_toml(&store.join("config.toml"), true)?;
+    let audits: AuditsFile = read_toml(&store.join("audits.toml"), true)?;
+    let imports: ImportsFile = read_toml(&store.join("imports.lock"), false)?;
+
+    let mut packages: Vec<_> = metadata
+        .packages
+        .iter()
+        .filter(|p| !metadata.workspace_members.contains(&p.id))
+        .filter(|p| p.source.as_ref().is_some_and(|s| s.is_crates_io()))
+        .collect();
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+    let mut report = VetReport {
+        store,
+        checked: packages.len(),
+        coverage: 1.0,
+        audited: Vec::new(),
+        exempted: Vec::new(),
+        unaudited: Vec::new(),
+        violations: Vec::new(),
+    };
+    for package in packages {
+        let label = format!("{}@{}", package.name, package.version);
+        let entries: Vec<(&AuditEntry, bool)> = audits
+            .audits
+            .get(&package.name)
+            .into_iter()
+            .flatten()
+            .map(|e| (e, false))
+            .chain(
+                imports
+                    .audits
+                    .values()
+                    .filter_map(|i| i.audits.get(&package.name))
+                    .flatten()
+                    .map(|e| (e, true)),
+            )
+            .collect();
+        let violated = entries.iter().any(|(e, _)| {
+            e.violation
+                .as_deref()
+                .and_then(|v| v.parse::<VersionReq>().ok())
+                .is_some_and(|req| req.matches(&package.version))
+        });
+        if violated {
+            report.violations.push(label);
+            continue;
+        }
+        let exemptions: Vec<&ExemptionEntry> = config
+            .exemptions
+            .get(&package.name)
+            .into_iter()
+            .flatten()
+            .collect();
+        let exempted: BTreeMap<Version, Vec<String>> = exemptions
+            .iter()
+            .filter_map(|e| Some((e.version.parse().ok()?, e.criteria.names())))
+            .collect();
+        if let Some((criteria, delta, imported)) =
+            audited_versions(&entries, &exempted).remove(&package.version)
+        {
+            report.audited.push(AuditedPackage {
+                package: label,
+                criteria: criteria.into_iter().collect(),
+                delta,
+                imported,
+            });
+        } else if let Some(exemption) = exemptions
+            .iter()
+            .find(|e| e.version.parse::<Version>().ok().as_ref() == Some(&package.version))
+        {
+            report.exempted.push(ExemptedPackage {
+                package: label,
+                criteria: exemption.criteria.names(),
+                notes: exemption.notes.clone(),
+            });
+        } else {
+            report.unaudited.push(label);
+        }
+    }
+    if report.checked > 0 {
+        report.coverage = report.audited.len() as f64 / report.checked as f64;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vet_report() {
+        let root = std::env::temp_dir().join(format!("vet-test-{}", std::process::id()));
+        let store = root.join("supply-chain");
+        std::fs::create_dir_all(&store).unwrap();
+        let write = |name: &str, text: &str| std::fs::write(store.join(name), text).unwrap();
+        write(
+            "config.toml",
+            r#"
+[cargo-vet]
+version = "0.10"
+
+[[exemptions.fixture-util]]
+version = "1.0.0"
+criteria = "safe-to-deploy"
+notes = "legacy"
+"#,
+        );
+        write(
+            "audits.toml",
+            r#"
+[[audits.fixture-core]]
+who = "someone"
+criteria = "safe-to-deploy"
+version = "0.0.9"
+
+[[audits.fixture-core]]
+who = "someone"
+criteria = ["safe-to-deploy", "safe-to-run"]
+delta = "0.0.9 -> 0.1.0"
+
+[[audits.fixture-util]]
+who = "someone"
+criteria = "safe-to-run"
+violation = "<0.5"
+"#,
+        );
+        write(
+            "imports.lock",
+            r#"
+[[audits.mozilla.audits.fixture-log]]
+who = "other"
+criteria = "safe-to-deploy"
+version = "0.4.0"
+"#,
+        );
+
+        let mut metadata: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        metadata["workspace_root"] = root.to_str().unwrap().into();
+        metadata["workspace_members"] = serde_json::json!([metadata["packages"][0]["id"]]);
+        let crates_io = "registry+https://github.com/rust-lang/crates.io-index";
+        metadata["packages"][1]["source"] = crates_io.into();
+        let packages = metadata["packages"].as_array_mut().unwrap();
+        for (name, version) in [
+            ("fixture-util", "1.0.0"),
+            ("fixture-util", "0.4.0"),
+            ("fixture-log", "0.4.0"),
+            ("fixture-new", "0.1.0"),
+        ] {
+            let mut package = packages[1].clone();
+            package["name"] = name.into();
+            package["version"] = version.into();
+            package["id"] = format!("{crates_io}#{name}@{version}").into();
+            packages.push(package);
+        }
+        let metadata: Metadata = serde_json::from_value(metadata).unwrap();
+        let report = vet_report(&metadata).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.checked, 5);
+        let audited: Vec<(&str, bool, bool)> = report
+            .audited
+            .iter()
+            .map(|a| (a.package.as_str(), a.delta, a.imported))
+            .collect();
+        assert_eq!(
+            audited,
+            [
+                ("fixture-core@0.1.0", true, false),
+                ("fixture-log@0.4.0", false, true)
+            ]
+        );
+        assert_eq!(report.audited[0].criteria, ["safe-to-deploy"]);
+        assert_eq!(report.exempted[0].package, "fixture-util@1.0.0");
+        assert_eq!(report.exempted[0].notes.as_deref(), Some("legacy"));
+        assert_eq!(report.violations, ["fixture-util@0.4.0"]);
+        assert_eq!(report.unaudited, ["fixture-new@0.1.0"]);
+        assert_eq!(report.coverage, 0.4);
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use mcp_attr::jsoncall::SessionContext;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::lockfile::{self, ChangeKind, Lockfile, LockfileChange};
+use crate::upgrade::ChangeSummary;
+use crate::{ServerData, summary};
+
+/// ロックファイルの変更を調べる間隔
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// ロックファイルが変更されたときに送る通知の名前
+pub const LOCKFILE_CHANGED: &str = "notifications/cargo/lockfile_changed";
+
+/// 通知の `message` に列挙する変更の数の上限
+const MESSAGE_LIMIT: usize = 5;
+
+/// `notifications/cargo/lockfile_changed` の内容
+#[derive(Serialize)]
+pub struct LockfileChanged {
+    pub project: String,
+    pub lockfile: String,
+    /// 変更の概要 (`3 packages changed: serde 1.0.200 -> 1.0.210, ...`)
+    pub message: String,
+    pub summary: ChangeSummary,
+    pub changes: Vec<LockfileChange>,
+}
+
+/// 監視している 1 つのロックファイルの最後の状態
+struct Snapshot {
+    modified: Option<SystemTime>,
+    lockfile: Option<Lockfile>,
+}
+
+/// 監視しているロックファイルの最後の状態
+#[derive(Default)]
+struct Snapshots(BTreeMap<PathBuf, Snapshot>);
+
+impl Snapshots {
+    /// `targets` とこれまでに監視したロックファイルを調べ、変更されたロックファイルと変更の一覧を返す
+    ///
+    /// 初めて見つけたロックファイルは、状態を記録するだけで変更としては扱わない。
+    fn poll(
+        &mut self,
+        targets: impl IntoIterator<Item = PathBuf>,
+    ) -> Vec<(PathBuf, Vec<LockfileChange>)> {
+        for path in targets {
+            self.0.entry(path).or_insert_with_key(|path| Snapshot {
+                modified: modified(path),
+                lockfile: Lockfile::load(path).ok(),
+            });
+        }
+        let mut changed = Vec::new();
+        for (path, snapshot) in &mut self.0 {
+            let modified = modified(path);
+            if snapshot.modified == modified {
+                continue;
+            }
+            snapshot.modified = modified;
+            let Ok(after) = Lockfile::load(path) else {
+                continue;
+            };
+            let changes = match &snapshot.lockfile {
+                Some(before) => lockfile::diff(before, &after),
+                None => Vec::new(),
+            };
+            snapshot.lockfile = Some(after);
+            if !changes.is_empty() {
+                changed.push((path.clone(), changes));
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// マニフェストのディレクトリから親へ、最も近い Cargo.lock を探す
+fn find_lockfile(manifest_path: &Path) -> Option<PathBuf> {
+    manifest_path
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+/// 1 つの変更を `serde 1.0.200 -> 1.0.210` のような短い文字列にする
+fn describe_change(change: &LockfileChange) -> String {
+    let from = change.from.as_deref().unwrap_or_default();
+    let to = change.to.as_deref().unwrap_or_default();
+    match change.kind {
+        ChangeKind::Added => format!("+{} {to}", change.name),
+        ChangeKind::Removed => format!("-{} {from}", change.name),
+        ChangeKind::Changed => format!("{} {to} (source changed)", change.name),
+        ChangeKind::Upgraded | ChangeKind::Downgraded => {
+            let breaking = if change.breaking { " (breaking)" } else { "" };
+            format!("{} {from} -> {to}{breaking}", change.name)
+        }
+    }
+}
+
+/// 変更の一覧の概要を作成する
+pub fn describe(changes: &[LockfileChange]) -> String {
+    let mut items: Vec<String> = changes
+        .iter()
+        .take(MESSAGE_LIMIT)
+        .map(describe_change)
+        .collect();
+    if changes.len() > MESSAGE_LIMIT {
+        items.push(format!("and {} more", changes.len() - MESSAGE_LIMIT));
+    }
+    let noun = if changes.len() == 1 {
+        "package"
+    } else {
+        "packages"
+    };
+    format!("{} {noun} changed: {}", changes.len(), items.join(", "))
+}
+
+/// プロジェクトの Cargo.lock を監視し、変更されたらバージョンの変更の概要を通知する
+///
+/// 監視の対象は、キャッシュしているメタデータのワークスペースと `--workspace` で設定したプロジェクトで、
+/// 一度監視したロックファイルはキャッシュから外れても監視を続ける。
+/// 変更を検出すると、`notifications/cargo/lockfile_changed` と、影響を受ける概要リソースの `notifications/resources/updated` を送る。
+/// キャッシュしているメタデータは、ロックファイルの更新日時が変わったため次に取得するときに読み込み直される。
+pub async fn watch_lockfiles(state: Arc<ServerData>, session: SessionContext) {
+    let mut snapshots = Snapshots::default();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let cached = state
+            .metadata
+            .cached()
+            .into_iter()
+            .map(|m| m.workspace_root.join("Cargo.lock").into_std_path_buf());
+        let configured = state
+            .options
+            .workspaces
+            .iter()
+            .filter_map(|path| find_lockfile(path));
+        let targets: Vec<PathBuf> = cached.chain(configured).collect();
+        for (path, changes) in snapshots.poll(targets) {
+            let root = path.parent().unwrap_or(&path);
+            let uris = summary_uris(&state, root).await;
+            let project = root
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| root.display().to_string());
+            let params = LockfileChanged {
+                message: describe(&changes),
+                project,
+                lockfile: path.display().to_string(),
+                summary: ChangeSummary::new(&changes),
+                changes,
+            };
+            if session
+                .notification(LOCKFILE_CHANGED, Some(&params))
+                .is_err()
+            {
+                return;
+            }
+            for uri in uris {
+                let params = json!({ "uri": uri });
+                if session
+                    .notification("notifications/resources/updated", Some(&params))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// ワークスペースの概要リソースの URI
+async fn summary_uris(state: &ServerData, root: &Path) -> Vec<String> {
+    let projects = state.projects().await;
+    let Some(metadata) = projects
+        .iter()
+        .find(|m| m.workspace_root.as_std_path() == root)
+    else {
+        return Vec::new();
+    };
+    let project = summary::project_key(metadata, &projects);
+    metadata
+        .workspace_packages()
+        .iter()
+        .map(|p| summary::summary_uri(&project, &p.name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let before = Lockfile::parse(
+            r#"
+[[package]]
+name = "serde"
+version = "1.0.200"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+
+[[package]]
+name = "log"
+version = "0.4.20"
+"#,
+        )
+        .unwrap();
+        let after = Lockfile::parse(
+            r#"
+[[package]]
+name = "serde"
+version = "1.0.210"
+
+[[package]]
+name = "rand"
+version = "0.9.0"
+
+[[package]]
+name = "itoa"
+version = "1.0.11"
+"#,
+        )
+        .unwrap();
+        let changes = lockfile::diff(&before, &after);
+        assert_eq!(
+            describe(&changes),
+            "4 packages changed: +itoa 1.0.11, -log 0.4.20, rand 0.8.5 -> 0.9.0 (breaking), serde 1.0.200 -> 1.0.210"
+        );
+        assert_eq!(describe(&changes[..1]), "1 package changed: +itoa 1.0.11");
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let root = std::env::temp_dir().join(format!("watch-test-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("Cargo.lock");
+        let write = |version: &str, seconds: u64| {
+            let text = format!("[[package]]\nname = \"serde\"\nversion = \"{version}\"\n");
+            std::fs::write(&path, text).unwrap();
+            let time = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(time))
+                .unwrap();
+        };
+        let mut snapshots = Snapshots::default();
+        write("1.0.200", 1);
+        let first = snapshots.poll([path.clone()]);
+        write("1.0.210", 2);
+        let second = snapshots.poll([path.clone()]);
+        // キャッシュから外れて対象に含まれなくなっても、監視を続ける
+        write("1.0.220", 3);
+        let third = snapshots.poll([]);
+        let unchanged = snapshots.poll([]);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(first.is_empty());
+        assert_eq!(
+            describe(&second[0].1),
+            "1 package changed: serde 1.0.200 -> 1.0.210"
+        );
+        assert_eq!(third[0].0, path);
+        assert_eq!(
+            describe(&third[0].1),
+            "1 package changed: serde 1.0.210 -> 1.0.220"
+        );
+        assert!(unchanged.is_empty());
+    }
+}
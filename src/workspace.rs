@@ -0,0 +1,635 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use cargo_metadata::{DependencyKind, Metadata, Package, workspace_default_members_is_missing};
+use mcp_attr::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::inheritance::read_manifest;
+use crate::lint::is_publishable;
+
+/// ワークスペースメンバーの絞り込みの条件
+#[derive(Default)]
+pub struct MemberFilter<'a> {
+    /// パッケージ名のパターン (`*` は任意の文字列、`?` は任意の 1 文字)
+    pub name: Option<&'a str>,
+    /// パッケージのディレクトリの接頭辞 (ワークスペースのルートからの相対パスまたは絶対パス)
+    pub path_prefix: Option<&'a str>,
+    /// crates.io に公開可能なパッケージ (`publish = false` でない) だけにするかどうか
+    pub publish_only: bool,
+}
+
+/// ワークスペースメンバーの要約
+#[derive(Serialize)]
+pub struct MemberSummary {
+    pub name: String,
+    pub version: String,
+    /// ワークスペースのルートからのパッケージのディレクトリ
+    pub path: String,
+    /// ターゲットの種類 (`lib`、`bin`、`proc-macro` など)
+    pub target_kinds: Vec<String>,
+}
+
+/// `*` と `?` を使ったパターンに `text` が一致するかどうか
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // 最後に現れた `*` の位置と、そこから照合し直す `text` の位置
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// ワークスペースのルートからのパッケージのディレクトリ
+fn member_path<'a>(metadata: &Metadata, package: &'a Package) -> &'a Utf8Path {
+    let dir = package
+        .manifest_path
+        .parent()
+        .unwrap_or(&package.manifest_path);
+    dir.strip_prefix(&metadata.workspace_root).unwrap_or(dir)
+}
+
+/// 条件に一致するワークスペースメンバー (`workspace_members` の順)
+pub fn filter_members<'a>(metadata: &'a Metadata, filter: &MemberFilter) -> Vec<&'a Package> {
+    metadata
+        .workspace_packages()
+        .into_iter()
+        .filter(|package| {
+            filter
+                .name
+                .is_none_or(|pattern| glob_match(pattern, &package.name))
+        })
+        .filter(|package| {
+            filter.path_prefix.is_none_or(|prefix| {
+                let prefix = Utf8Path::new(prefix);
+                let dir = package
+                    .manifest_path
+                    .parent()
+                    .unwrap_or(&package.manifest_path);
+                if prefix.is_absolute() {
+                    dir.starts_with(prefix)
+                } else {
+                    member_path(metadata, package).starts_with(prefix)
+                }
+            })
+        })
+        .filter(|package| !filter.publish_only || is_publishable(package))
+        .collect()
+}
+
+pub fn member_summary(metadata: &Metadata, package: &Package) -> MemberSummary {
+    let target_kinds: BTreeSet<&String> = package.targets.iter().flat_map(|t| &t.kind).collect();
+    MemberSummary {
+        name: package.name.clone(),
+        version: package.version.to_string(),
+        path: member_path(metadata, package).to_string(),
+        target_kinds: target_kinds.into_iter().cloned().collect(),
+    }
+}
+
+/// `[package.metadata]` と `[workspace.metadata]` のテーブル
+#[derive(Serialize)]
+pub struct CustomMetadata {
+    /// `[package.metadata]` を返したパッケージ (仮想ワークスペースでパッケージを指定しない場合は `null`)
+    pub package: Option<String>,
+    pub package_metadata: Value,
+    pub workspace_metadata: Value,
+}
+
+/// `key` を指定した場合はその名前のテーブル (`docs.rs`、`release` など) だけを取り出す
+fn select_key(metadata: &Value, key: Option<&str>) -> Value {
+    match key {
+        Some(key) => metadata.get(key).cloned().unwrap_or(Value::Null),
+        None => metadata.clone(),
+    }
+}
+
+/// パッケージの `[package.metadata]` とワークスペースの `[workspace.metadata]` を返す
+pub fn custom_metadata(
+    metadata: &Metadata,
+    package: Option<&Package>,
+    key: Option<&str>,
+) -> CustomMetadata {
+    CustomMetadata {
+        package: package.map(|p| p.name.clone()),
+        package_metadata: package.map_or(Value::Null, |p| select_key(&p.metadata, key)),
+        workspace_metadata: select_key(&metadata.workspace_metadata, key),
+    }
+}
+
+/// ワークスペースのメンバー、既定のメンバー、除外されたパスと、ワークスペースに含まれないパッケージ
+#[derive(Serialize)]
+pub struct WorkspaceLayout {
+    pub members: Vec<String>,
+    /// 引数なしの `cargo build` などの対象になるメンバー (cargo 1.71 より前では `null`)
+    pub default_members: Option<Vec<String>>,
+    /// 既定のメンバーではないメンバー
+    pub non_default_members: Vec<String>,
+    /// `[workspace] members` のパターン
+    pub members_patterns: Vec<String>,
+    /// `[workspace] default-members` のパターン
+    pub default_members_patterns: Vec<String>,
+    /// `[workspace] exclude` のパス
+    pub exclude: Vec<String>,
+    /// ワークスペースのディレクトリ以下にあるが、メンバーではないパッケージ
+    pub unlisted_packages: Vec<UnlistedPackage>,
+}
+
+#[derive(Serialize)]
+pub struct UnlistedPackage {
+    pub name: Option<String>,
+    /// ワークスペースのルートからのパッケージのディレクトリ
+    pub path: String,
+    /// `excluded` (`exclude` に含まれる)、`own_workspace` (独自の `[workspace]` を持つ)、`not_in_members` (`members` のどのパターンにも一致しない)
+    pub reason: &'static str,
+}
+
+fn strings(table: Option<&toml::Table>, key: &str) -> Vec<String> {
+    table
+        .and_then(|t| t.get(key))
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+        .collect()
+}
+
+/// `dir` 以下の Cargo.toml (隠しディレクトリ、`target` ディレクトリ、`skip` は辿らない)
+fn manifests(dir: &Utf8Path, skip: &Utf8Path, found: &mut Vec<Utf8PathBuf>) {
+    let Ok(entries) = dir.read_dir_utf8() else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !name.starts_with('.') && name != "target" && entry.path() != skip {
+                manifests(entry.path(), skip, found);
+            }
+        } else if name == "Cargo.toml" {
+            found.push(entry.path().to_path_buf());
+        }
+    }
+}
+
+/// ワークスペースのメンバーと既定のメンバー、`[workspace]` のパターンと、ディレクトリ以下にあるメンバーでないパッケージを調べる
+pub fn workspace_layout(metadata: &Metadata) -> Result<WorkspaceLayout> {
+    let root_manifest = metadata.workspace_root.join("Cargo.toml");
+    let root = read_manifest(&root_manifest)?;
+    let workspace = root.get("workspace").and_then(|w| w.as_table());
+    let exclude = strings(workspace, "exclude");
+
+    let members: Vec<&Package> = metadata.workspace_packages();
+    let default_members =
+        (!workspace_default_members_is_missing(&metadata.workspace_default_members))
+            .then(|| metadata.workspace_default_packages());
+    let non_default_members = match &default_members {
+        Some(defaults) => members
+            .iter()
+            .filter(|p| !defaults.iter().any(|d| d.id == p.id))
+            .map(|p| p.name.clone())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut found = Vec::new();
+    manifests(
+        &metadata.workspace_root,
+        &metadata.target_directory,
+        &mut found,
+    );
+    found.sort();
+    let mut unlisted_packages = Vec::new();
+    for manifest_path in found {
+        if manifest_path == root_manifest
+            || members.iter().any(|p| p.manifest_path == manifest_path)
+        {
+            continue;
+        }
+        let Ok(manifest) = read_manifest(&manifest_path) else {
+            continue;
+        };
+        let Some(package) = manifest.get("package").and_then(|p| p.as_table()) else {
+            continue;
+        };
+        let dir = manifest_path.parent().unwrap_or(&manifest_path);
+        let path = dir.strip_prefix(&metadata.workspace_root).unwrap_or(dir);
+        let reason = if manifest.contains_key("workspace") {
+            "own_workspace"
+        } else if exclude.iter().any(|e| path.starts_with(e)) {
+            "excluded"
+        } else {
+            "not_in_members"
+        };
+        unlisted_packages.push(UnlistedPackage {
+            name: package
+                .get("name")
+                .and_then(|n| n.as_str())
+                .map(|n| n.to_string()),
+            path: path.to_string(),
+            reason,
+        });
+    }
+
+    Ok(WorkspaceLayout {
+        members: members.iter().map(|p| p.name.clone()).collect(),
+        default_members: default_members
+            .map(|defaults| defaults.iter().map(|p| p.name.clone()).collect()),
+        non_default_members,
+        members_patterns: strings(workspace, "members"),
+        default_members_patterns: strings(workspace, "default-members"),
+        exclude,
+        unlisted_packages,
+    })
+}
+
+/// crates.io に公開する順序
+#[derive(Serialize)]
+pub struct PublishOrder {
+    /// 公開する順序 (依存先のメンバーが先)
+    pub order: Vec<String>,
+    /// 同時に公開できるメンバーの組 (前の組をすべて公開したあとに公開できる)
+    pub groups: Vec<Vec<String>>,
+    /// 公開しないメンバー (`publish = false`)
+    pub skipped: Vec<String>,
+    /// 循環する依存関係のため順序を決められないメンバー
+    pub cycles: Vec<String>,
+    /// 公開しないメンバーに依存しているため公開できないメンバー
+    pub blocked: Vec<BlockedMember>,
+}
+
+#[derive(Serialize)]
+pub struct BlockedMember {
+    pub package: String,
+    /// 依存している公開しないメンバー
+    pub unpublishable_dependencies: Vec<String>,
+}
+
+/// メンバーがパスで依存しているワークスペースメンバーの名前 (`include_dev` が偽の場合は dev-dependencies を除く)
+fn workspace_dependencies<'a>(
+    package: &'a Package,
+    members: &BTreeSet<&str>,
+    include_dev: bool,
+) -> BTreeSet<&'a str> {
+    package
+        .dependencies
+        .iter()
+        .filter(|d| (include_dev || d.kind != DependencyKind::Development) && d.path.is_some())
+        .map(|d| d.name.as_str())
+        .filter(|name| members.contains(name) && *name != package.name)
+        .collect()
+}
+
+/// ワークスペースメンバーを、ワークスペース内の依存関係でトポロジカルソートした公開の順序
+///
+/// dev-dependencies は公開時にバージョンのないものが取り除かれ、循環することも多いため順序には含めない。
+pub fn publish_order(metadata: &Metadata) -> PublishOrder {
+    let members = metadata.workspace_packages();
+    let names: BTreeSet<&str> = members.iter().map(|p| p.name.as_str()).collect();
+    let (publishable, unpublishable): (Vec<&Package>, Vec<&Package>) =
+        members.iter().partition(|p| is_publishable(p));
+    let unpublishable: BTreeSet<&str> = unpublishable.iter().map(|p| p.name.as_str()).collect();
+
+    let mut blocked = Vec::new();
+    let mut pending: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for package in &publishable {
+        let dependencies = workspace_dependencies(package, &names, false);
+        let unpublishable_dependencies: Vec<String> = dependencies
+            .iter()
+            .filter(|d| unpublishable.contains(*d))
+            .map(|d| d.to_string())
+            .collect();
+        if unpublishable_dependencies.is_empty() {
+            pending.insert(&package.name, dependencies);
+        } else {
+            blocked.push(BlockedMember {
+                package: package.name.clone(),
+                unpublishable_dependencies,
+            });
+        }
+    }
+    // 公開できないメンバーに依存しているメンバーも公開できない
+    loop {
+        let blocked_names: BTreeSet<String> = blocked.iter().map(|b| b.package.clone()).collect();
+        let newly: Vec<(&str, Vec<String>)> = pending
+            .iter()
+            .map(|(name, deps)| {
+                let deps: Vec<String> = deps
+                    .iter()
+                    .filter(|d| blocked_names.contains(**d))
+                    .map(|d| d.to_string())
+                    .collect();
+                (*name, deps)
+            })
+            .filter(|(_, deps)| !deps.is_empty())
+            .collect();
+        if newly.is_empty() {
+            break;
+        }
+        for (name, unpublishable_dependencies) in newly {
+            pending.remove(name);
+            blocked.push(BlockedMember {
+                package: name.to_string(),
+                unpublishable_dependencies,
+            });
+        }
+    }
+
+    let mut order = Vec::new();
+    let mut groups = Vec::new();
+    loop {
+        let group: Vec<&str> = pending
+            .iter()
+            .filter(|(_, deps)| deps.iter().all(|d| !pending.contains_key(d)))
+            .map(|(name, _)| *name)
+            .collect();
+        if group.is_empty() {
+            break;
+        }
+        for name in &group {
+            pending.remove(name);
+        }
+        order.extend(group.iter().map(|n| n.to_string()));
+        groups.push(group.into_iter().map(|n| n.to_string()).collect());
+    }
+    PublishOrder {
+        order,
+        groups,
+        skipped: unpublishable.into_iter().map(|n| n.to_string()).collect(),
+        cycles: pending.into_keys().map(|n| n.to_string()).collect(),
+        blocked,
+    }
+}
+
+/// 変更されたファイルの影響を受けるワークスペースメンバー
+#[derive(Serialize)]
+pub struct AffectedMembers {
+    /// 変更されたファイルを含むメンバーと、そのメンバーのファイル
+    pub changed: BTreeMap<String, Vec<String>>,
+    /// 変更されたメンバーと、それらに推移的に依存しているメンバー (dev-dependencies を含む)
+    pub affected: Vec<String>,
+    /// すべてのメンバーに影響するファイル (Cargo.lock、ワークスペースルートの Cargo.toml、`.cargo/config.toml` など)
+    pub global_files: Vec<String>,
+    /// どのメンバーにも含まれないファイル
+    pub unowned_files: Vec<String>,
+}
+
+/// ワークスペースのルートにあり、すべてのメンバーのビルドに影響するファイル
+const GLOBAL_FILES: [&str; 6] = [
+    "Cargo.lock",
+    "Cargo.toml",
+    "rust-toolchain",
+    "rust-toolchain.toml",
+    ".cargo/config",
+    ".cargo/config.toml",
+];
+
+/// 変更されたファイル (`git diff --name-only` の出力など) を含むメンバーと、その影響を受けるメンバーを調べる
+///
+/// 相対パスはワークスペースのルートからのパスとみなす。ファイルは、ディレクトリが最も深いメンバーに属するものとする。
+pub fn affected_members(metadata: &Metadata, changed_files: &[String]) -> AffectedMembers {
+    let members = metadata.workspace_packages();
+    let names: BTreeSet<&str> = members.iter().map(|p| p.name.as_str()).collect();
+    let mut changed: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut global_files = Vec::new();
+    let mut unowned_files = Vec::new();
+    for file in changed_files {
+        let path = metadata.workspace_root.join(file);
+        let relative = path.strip_prefix(&metadata.workspace_root).ok();
+        if relative.is_some_and(|r| GLOBAL_FILES.iter().any(|g| r == Utf8Path::new(g))) {
+            global_files.push(file.clone());
+            continue;
+        }
+        let owner = members
+            .iter()
+            .filter_map(|p| Some((p, p.manifest_path.parent()?)))
+            .filter(|(_, dir)| path.starts_with(dir))
+            .max_by_key(|(_, dir)| dir.components().count());
+        match owner {
+            Some((package, _)) => changed
+                .entry(package.name.clone())
+                .or_default()
+                .push(file.clone()),
+            None => unowned_files.push(file.clone()),
+        }
+    }
+
+    let mut affected: BTreeSet<&str> = if global_files.is_empty() {
+        names
+            .iter()
+            .filter(|n| changed.contains_key(**n))
+            .copied()
+            .collect()
+    } else {
+        names.clone()
+    };
+    let dependencies: Vec<(&str, BTreeSet<&str>)> = members
+        .iter()
+        .map(|p| (p.name.as_str(), workspace_dependencies(p, &names, true)))
+        .collect();
+    loop {
+        let dependents: Vec<&str> = dependencies
+            .iter()
+            .filter(|(name, deps)| {
+                !affected.contains(name) && deps.iter().any(|d| affected.contains(d))
+            })
+            .map(|(name, _)| *name)
+            .collect();
+        if dependents.is_empty() {
+            break;
+        }
+        affected.extend(dependents);
+    }
+    AffectedMembers {
+        changed,
+        affected: affected.into_iter().map(|n| n.to_string()).collect(),
+        global_files,
+        unowned_files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempWorkspace;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("fixture-*", "fixture-core"));
+        assert!(glob_match("*core", "fixture-core"));
+        assert!(glob_match("a?p", "app"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("fixture-*", "app"));
+        assert!(!glob_match("a?", "app"));
+    }
+
+    #[test]
+    fn test_filter_members() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let names = |filter: &MemberFilter| -> Vec<String> {
+            filter_members(&metadata, filter)
+                .into_iter()
+                .map(|p| p.name.clone())
+                .collect()
+        };
+        assert_eq!(names(&MemberFilter::default()).len(), 2);
+        let filter = MemberFilter {
+            name: Some("fixture-*"),
+            ..MemberFilter::default()
+        };
+        assert_eq!(names(&filter), ["fixture-core"]);
+        let filter = MemberFilter {
+            path_prefix: Some("app"),
+            ..MemberFilter::default()
+        };
+        assert_eq!(names(&filter), ["app"]);
+        let filter = MemberFilter {
+            path_prefix: Some("/fixture/core"),
+            ..MemberFilter::default()
+        };
+        assert_eq!(names(&filter), ["fixture-core"]);
+
+        let summary = member_summary(&metadata, filter_members(&metadata, &filter)[0]);
+        assert_eq!(summary.path, "core");
+        assert_eq!(summary.version, "0.1.0");
+    }
+
+    #[test]
+    fn test_custom_metadata() {
+        let mut metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        metadata.workspace_metadata = serde_json::json!({ "release": { "shared-version": true } });
+        metadata.packages[0].metadata =
+            serde_json::json!({ "docs.rs": { "all-features": true }, "release": {} });
+        let app = &metadata.packages[0];
+
+        let all = custom_metadata(&metadata, Some(app), None);
+        assert_eq!(all.package.as_deref(), Some("app"));
+        assert_eq!(all.package_metadata["docs.rs"]["all-features"], true);
+        let docs = custom_metadata(&metadata, Some(app), Some("docs.rs"));
+        assert_eq!(
+            docs.package_metadata,
+            serde_json::json!({ "all-features": true })
+        );
+        assert_eq!(docs.workspace_metadata, Value::Null);
+        let release = custom_metadata(&metadata, None, Some("release"));
+        assert_eq!(release.package_metadata, Value::Null);
+        assert_eq!(release.workspace_metadata["shared-version"], true);
+    }
+
+    #[test]
+    fn test_workspace_layout() {
+        let workspace = TempWorkspace::new("layout");
+        let package = |name: &str| {
+            format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n")
+        };
+        workspace.write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\ndefault-members = [\"crates/a\"]\nexclude = [\"vendor\"]\nresolver = \"2\"\n",
+        );
+        for name in ["a", "b"] {
+            workspace.write(&format!("crates/{name}/Cargo.toml"), &package(name));
+            workspace.write(&format!("crates/{name}/src/lib.rs"), "");
+        }
+        workspace.write("vendor/c/Cargo.toml", &package("c"));
+        workspace.write(
+            "tools/d/Cargo.toml",
+            &format!("{}\n[workspace]\n", package("d")),
+        );
+        workspace.write("examples/e/Cargo.toml", &package("e"));
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(workspace.manifest_path())
+            .no_deps()
+            .exec()
+            .unwrap();
+        let layout = workspace_layout(&metadata).unwrap();
+
+        assert_eq!(layout.members, ["a", "b"]);
+        assert_eq!(layout.default_members, Some(vec!["a".to_string()]));
+        assert_eq!(layout.non_default_members, ["b"]);
+        assert_eq!(layout.members_patterns, ["crates/*"]);
+        assert_eq!(layout.default_members_patterns, ["crates/a"]);
+        assert_eq!(layout.exclude, ["vendor"]);
+        let unlisted: Vec<(&str, &str)> = layout
+            .unlisted_packages
+            .iter()
+            .map(|p| (p.path.as_str(), p.reason))
+            .collect();
+        assert_eq!(
+            unlisted,
+            [
+                ("examples/e", "not_in_members"),
+                ("tools/d", "own_workspace"),
+                ("vendor/c", "excluded"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_publish_order() {
+        let mut metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let order = publish_order(&metadata);
+        assert_eq!(order.order, ["fixture-core", "app"]);
+        assert_eq!(
+            order.groups,
+            [vec!["fixture-core".to_string()], vec!["app".to_string()]]
+        );
+        assert!(order.skipped.is_empty() && order.cycles.is_empty() && order.blocked.is_empty());
+
+        metadata.packages[1].publish = Some(Vec::new());
+        let order = publish_order(&metadata);
+        assert!(order.order.is_empty());
+        assert_eq!(order.skipped, ["fixture-core"]);
+        assert_eq!(order.blocked[0].package, "app");
+        assert_eq!(
+            order.blocked[0].unpublishable_dependencies,
+            ["fixture-core"]
+        );
+    }
+
+    #[test]
+    fn test_affected_members() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("../tests/fixtures/workspace.json")).unwrap();
+        let files =
+            |files: &[&str]| -> Vec<String> { files.iter().map(|f| f.to_string()).collect() };
+
+        let affected = affected_members(&metadata, &files(&["core/src/lib.rs", "README.md"]));
+        assert_eq!(affected.changed["fixture-core"], ["core/src/lib.rs"]);
+        assert_eq!(affected.affected, ["app", "fixture-core"]);
+        assert_eq!(affected.unowned_files, ["README.md"]);
+
+        let affected = affected_members(&metadata, &files(&["/fixture/app/src/main.rs"]));
+        assert_eq!(affected.affected, ["app"]);
+
+        let affected = affected_members(&metadata, &files(&["Cargo.lock"]));
+        assert!(affected.changed.is_empty());
+        assert_eq!(affected.global_files, ["Cargo.lock"]);
+        assert_eq!(affected.affected, ["app", "fixture-core"]);
+    }
+}
@@ -0,0 +1,443 @@
+use std::path::Path;
+
+use cargo_metadata::Metadata;
+use mcp_attr::Result;
+use mcp_attr::client::{McpClient, McpClientBuilder};
+use mcp_attr::jsoncall::Session;
+use mcp_attr::schema::{CallToolRequestParams, Root};
+use mcp_attr_example_cargo_metadata::error::Language;
+use mcp_attr_example_cargo_metadata::provider::{MetadataOptions, MetadataProvider};
+use mcp_attr_example_cargo_metadata::{CargoMetadataServer, ServerOptions};
+use serde_json::{Value, json};
+
+/// `tests/fixtures/workspace.json` のメタデータを返す
+///
+/// フィクスチャは `app` (バイナリとライブラリ) と `fixture-core` (ライブラリ) からなる
+/// `/fixture` の仮想ワークスペース。
+struct FixtureProvider;
+
+impl MetadataProvider for FixtureProvider {
+    fn metadata(&self, _manifest_path: &Path, _options: &MetadataOptions) -> Result<Metadata> {
+        Ok(serde_json::from_str(include_str!("fixtures/workspace.json")).unwrap())
+    }
+}
+
+async fn client() -> McpClient {
+    let server = CargoMetadataServer::with_provider(ServerOptions::default(), FixtureProvider);
+    McpClient::from_server(server).await.unwrap()
+}
+
+/// ツールを呼び出し、成功した場合は出力の JSON を、失敗した場合はエラーメッセージを返す
+async fn call(tool: &str, arguments: Value) -> std::result::Result<Value, String> {
+    let mut params = CallToolRequestParams::new(tool);
+    for (name, value) in arguments.as_object().unwrap() {
+        params = params.with_argument(name, value).unwrap();
+    }
+    match client().await.tools_call(params).await {
+        Ok(result) => {
+            let result = serde_json::to_value(result).unwrap();
+            let text = result["content"][0]["text"].as_str().unwrap();
+            Ok(serde_json::from_str(text).unwrap())
+        }
+        Err(e) => Err(e.error_object().unwrap().message.clone()),
+    }
+}
+
+#[tokio::test]
+async fn test_get_pkgid() {
+    let pkgid = call(
+        "get_pkgid",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "package": "fixture-core" }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        pkgid["pkgid"],
+        "path+file:///fixture/core#fixture-core@0.1.0"
+    );
+    assert_eq!(pkgid["spec"], "fixture-core");
+}
+
+#[tokio::test]
+async fn test_package_not_found() {
+    let error = call(
+        "get_pkgid",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "package": "serde" }),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.starts_with("[package_not_found]"), "{error}");
+}
+
+#[tokio::test]
+async fn test_get_workspace_info() {
+    let members = call(
+        "get_workspace_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml" }),
+    )
+    .await
+    .unwrap();
+    let names: Vec<&str> = members
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["app", "fixture-core"]);
+
+    let members = call(
+        "get_workspace_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "name": "fixture-*", "compact": true }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        members,
+        json!([{
+            "name": "fixture-core",
+            "version": "0.1.0",
+            "path": "core",
+            "target_kinds": ["lib"],
+        }])
+    );
+}
+
+#[tokio::test]
+async fn test_get_target_matrix() {
+    let matrices = call(
+        "get_target_matrix",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "package": "app" }),
+    )
+    .await
+    .unwrap();
+    let bin = matrices[0]["targets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["kind"] == "bin")
+        .unwrap();
+    assert_eq!(bin["required_features"], json!(["cli"]));
+    let buildable: Vec<bool> = bin["cells"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["buildable"].as_bool().unwrap())
+        .collect();
+    assert_eq!(buildable, [true, false, true]);
+}
+
+#[tokio::test]
+async fn test_query_metadata() {
+    let versions = call(
+        "query_metadata",
+        json!({
+            "manifest_path": "/fixture/Cargo.toml",
+            "query": "$.packages[?(@.name==\"fixture-core\")].version",
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(versions, json!(["0.1.0"]));
+
+    let error = call(
+        "query_metadata",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "query": "packages" }),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.starts_with("[invalid_query] "));
+}
+
+#[tokio::test]
+async fn test_pagination() {
+    let page = call(
+        "get_workspace_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "compact": true, "page_size": 1 }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(page["items"][0]["name"], "app");
+    assert_eq!(
+        page["_page"],
+        json!({ "total": 2, "offset": 0, "next_cursor": "1" })
+    );
+
+    let page = call(
+        "get_workspace_info",
+        json!({
+            "manifest_path": "/fixture/Cargo.toml",
+            "compact": true,
+            "page_size": 1,
+            "cursor": page["_page"]["next_cursor"],
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(page["items"][0]["name"], "fixture-core");
+    assert_eq!(page["_page"]["next_cursor"], Value::Null);
+
+    let metadata = call(
+        "get_metadata",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "fields": "packages.name", "page_size": 1 }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(metadata["packages"], json!([{ "name": "app" }]));
+    assert_eq!(metadata["_page"]["total"], 2);
+
+    let error = call(
+        "get_workspace_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "cursor": "next" }),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.starts_with("[invalid_cursor] "));
+}
+
+#[tokio::test]
+async fn test_output_format() {
+    let members = call(
+        "get_workspace_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "compact": true, "format": "compact" }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(members.as_array().unwrap().len(), 2);
+
+    let params = CallToolRequestParams::new("get_workspace_info")
+        .with_argument("manifest_path", "/fixture/Cargo.toml")
+        .unwrap()
+        .with_argument("compact", true)
+        .unwrap()
+        .with_argument("format", "markdown-table")
+        .unwrap();
+    let result = serde_json::to_value(client().await.tools_call(params).await.unwrap()).unwrap();
+    let text = result["content"][0]["text"].as_str().unwrap();
+    assert!(
+        text.starts_with("| name | path | target_kinds | version |\n| --- | --- | --- | --- |\n")
+    );
+    assert!(text.contains("| fixture-core | core | lib | 0.1.0 |\n"));
+
+    let error = call(
+        "get_workspace_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "format": "yaml" }),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.contains("yaml"));
+}
+
+#[tokio::test]
+async fn test_resources_list() {
+    let client = client().await;
+    // メタデータはツールの呼び出しで読み込まれる
+    client
+        .tools_call(
+            CallToolRequestParams::new("get_workspace_info")
+                .with_argument("manifest_path", "/fixture/Cargo.toml")
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let resources = client.resources_list(None).await.unwrap();
+    let uris: Vec<&str> = resources.resources.iter().map(|r| r.uri.as_str()).collect();
+    assert_eq!(
+        uris,
+        [
+            "cargo://fixture/packages/app/summary",
+            "cargo://fixture/packages/fixture-core/summary",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_refresh_and_clear_cache() {
+    let client = client().await;
+    let call = async |tool: &str, manifest_path: Option<&str>| -> Value {
+        let mut params = CallToolRequestParams::new(tool);
+        if let Some(manifest_path) = manifest_path {
+            params = params
+                .with_argument("manifest_path", manifest_path)
+                .unwrap();
+        }
+        let result = serde_json::to_value(client.tools_call(params).await.unwrap()).unwrap();
+        serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap()
+    };
+    let refresh = call("refresh_metadata", Some("/fixture/Cargo.toml")).await;
+    assert_eq!(refresh["was_cached"], false);
+    assert_eq!(refresh["workspace_members"], json!(["app", "fixture-core"]));
+    let refresh = call("refresh_metadata", Some("/fixture/Cargo.toml")).await;
+    assert_eq!(refresh["was_cached"], true);
+    let cleared = call("clear_cache", None).await;
+    assert_eq!(cleared["cleared"], json!(["/fixture/Cargo.toml"]));
+    let cleared = call("clear_cache", None).await;
+    assert_eq!(cleared["cleared"], json!([]));
+}
+
+#[tokio::test]
+async fn test_manifest_path_from_roots() {
+    let root = std::env::temp_dir().join(format!("server-roots-test-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("fixture")).unwrap();
+    std::fs::write(root.join("fixture/Cargo.toml"), "[workspace]\n").unwrap();
+
+    let server = CargoMetadataServer::with_provider(ServerOptions::default(), FixtureProvider);
+    let (handler, params) = McpClientBuilder::new()
+        .with_roots(vec![Root {
+            name: None,
+            uri: format!("file://{}", root.display()),
+        }])
+        .into_handler();
+    let (session, _server) = Session::new_channel(handler, server.into_session_handler());
+    let client = McpClient::initialize(session, params).await.unwrap();
+    let result = client
+        .tools_call(CallToolRequestParams::new("refresh_metadata"))
+        .await
+        .unwrap();
+    let result = serde_json::to_value(result).unwrap();
+    let refresh: Value =
+        serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        refresh["manifest_path"],
+        root.join("fixture/Cargo.toml").display().to_string()
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+
+    // ルートに対応していないクライアントでは manifest_path が必要
+    let error = call("get_workspace_info", json!({})).await.unwrap_err();
+    assert!(error.starts_with("[manifest_path_required]"), "{error}");
+}
+
+#[tokio::test]
+async fn test_default_manifest_path() {
+    let options = ServerOptions {
+        manifest_path: Some("/fixture/Cargo.toml".into()),
+        ..ServerOptions::default()
+    };
+    let client =
+        McpClient::from_server(CargoMetadataServer::with_provider(options, FixtureProvider))
+            .await
+            .unwrap();
+    let result = client
+        .tools_call(CallToolRequestParams::new("refresh_metadata"))
+        .await
+        .unwrap();
+    let result = serde_json::to_value(result).unwrap();
+    let refresh: Value =
+        serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(refresh["manifest_path"], "/fixture/Cargo.toml");
+}
+
+#[tokio::test]
+async fn test_path_not_allowed() {
+    let options = ServerOptions {
+        allowed_dirs: vec!["/allowed".into()],
+        ..ServerOptions::default()
+    };
+    let client =
+        McpClient::from_server(CargoMetadataServer::with_provider(options, FixtureProvider))
+            .await
+            .unwrap();
+    let params = CallToolRequestParams::new("get_workspace_info")
+        .with_argument("manifest_path", "/fixture/Cargo.toml")
+        .unwrap();
+    let error = client.tools_call(params).await.unwrap_err();
+    let error = error.error_object().unwrap();
+    assert_eq!(error.code, mcp_attr::ErrorCode::INVALID_PARAMS);
+    assert!(
+        error.message.starts_with("[path_not_allowed]"),
+        "{}",
+        error.message
+    );
+}
+
+#[tokio::test]
+async fn test_language_per_server() {
+    let message = async |language| {
+        let options = ServerOptions {
+            language,
+            ..ServerOptions::default()
+        };
+        let client =
+            McpClient::from_server(CargoMetadataServer::with_provider(options, FixtureProvider))
+                .await
+                .unwrap();
+        let params = CallToolRequestParams::new("get_package_info")
+            .with_argument("manifest_path", "/fixture/Cargo.toml")
+            .unwrap()
+            .with_argument("package", "serde")
+            .unwrap();
+        let error = client.tools_call(params).await.unwrap_err();
+        error.error_object().unwrap().message.clone()
+    };
+    let (ja, en) = tokio::join!(message(Language::Ja), message(Language::En));
+    assert!(ja.contains("は依存グラフにありません"), "{ja}");
+    assert!(en.contains("is not in the dependency graph"), "{en}");
+}
+
+#[tokio::test]
+async fn test_get_package_info() {
+    let info = call(
+        "get_package_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "package": "fixture-core" }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        info["pkgid"],
+        "path+file:///fixture/core#fixture-core@0.1.0"
+    );
+
+    // 仮想ワークスペースでは package が必要
+    let error = call(
+        "get_package_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml" }),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.starts_with("[package_required]"), "{error}");
+    assert!(error.ends_with("app, fixture-core"), "{error}");
+
+    let error = call(
+        "get_package_info",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "package": "fixture_cor" }),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.starts_with("[unknown_package]"), "{error}");
+    assert!(error.ends_with("app, fixture-core"), "{error}");
+}
+
+#[tokio::test]
+async fn test_get_dependency_tree() {
+    let client = client().await;
+    let params = CallToolRequestParams::new("get_dependency_tree")
+        .with_argument("manifest_path", "/fixture/Cargo.toml")
+        .unwrap();
+    let result = serde_json::to_value(client.tools_call(params).await.unwrap()).unwrap();
+    assert_eq!(
+        result["content"][0]["text"],
+        "app v0.2.0 (/fixture/app)\n└── fixture-core v0.1.0 (/fixture/core)\n\nfixture-core v0.1.0 (/fixture/core)\n"
+    );
+}
+
+#[tokio::test]
+async fn test_get_reverse_dependencies() {
+    let client = client().await;
+    let params = CallToolRequestParams::new("get_reverse_dependencies")
+        .with_argument("manifest_path", "/fixture/Cargo.toml")
+        .unwrap()
+        .with_argument("package", "fixture-core")
+        .unwrap();
+    let result = serde_json::to_value(client.tools_call(params).await.unwrap()).unwrap();
+    assert_eq!(
+        result["content"][0]["text"],
+        "fixture-core v0.1.0 (/fixture/core)\n└── app v0.2.0 (/fixture/app)\n"
+    );
+    let error = call(
+        "get_reverse_dependencies",
+        json!({ "manifest_path": "/fixture/Cargo.toml", "package": "serde" }),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.starts_with("[unknown_package]"), "{error}");
+}